@@ -137,6 +137,9 @@ class GeoEngineClient:
                                 or parsed.get("detail")
                                 or parsed
                             )
+                            # {"code": ..., "message": ...}; older releases sent a plain string
+                            if isinstance(error_detail, dict) and "message" in error_detail:
+                                error_detail = f"{error_detail['message']} [{error_detail.get('code')}]"
                         else:
                             error_detail = parsed
                     except json.JSONDecodeError:
@@ -151,6 +154,30 @@ class GeoEngineClient:
                 process.stderr.close()
 
 
+def visible_when_met(expr: str, values: Dict[str, Any]) -> bool:
+    """Evaluate a worker input's visible_when expression against current values.
+
+    Comparisons (INPUT=VALUE, INPUT==VALUE, INPUT!=VALUE, alternatives separated
+    by '|') are joined with '&&'. Booleans compare as 'true'/'false'.
+    """
+    for part in expr.split('&&'):
+        negated = '!=' in part
+        if negated:
+            name, expected = part.split('!=', 1)
+        elif '==' in part:
+            name, expected = part.split('==', 1)
+        else:
+            name, expected = part.split('=', 1)
+        options = [v.strip().strip('"\'') for v in expected.split('|')]
+        value = values.get(name.strip())
+        if isinstance(value, bool):
+            value = 'true' if value else 'false'
+        matched = value is not None and str(value) in options
+        if matched == negated:
+            return False
+    return True
+
+
 # Convenience function for standalone use
 def run_tool(
     worker: str,
@@ -20,6 +20,7 @@ from qgis.core import (
     QgsProcessingParameterBoolean,
     QgsProcessingParameterFile,
     QgsProcessingParameterEnum,
+    QgsProcessingParameterDefinition,
     QgsProcessingProvider,
     QgsSettings
 )
@@ -43,6 +44,30 @@ def set_dev_mode_enabled(enabled: bool) -> None:
     s.setValue(DEV_MODE_SETTING_KEY, enabled)
     s.sync()
 
+
+def visible_when_met(expr: str, values: Dict[str, Any]) -> bool:
+    """Evaluate a worker input's visible_when expression against current values.
+
+    Comparisons (INPUT=VALUE, INPUT==VALUE, INPUT!=VALUE, alternatives separated
+    by '|') are joined with '&&'. Booleans compare as 'true'/'false'.
+    """
+    for part in expr.split('&&'):
+        negated = '!=' in part
+        if negated:
+            name, expected = part.split('!=', 1)
+        elif '==' in part:
+            name, expected = part.split('==', 1)
+        else:
+            name, expected = part.split('=', 1)
+        options = [v.strip().strip('"\'') for v in expected.split('|')]
+        value = values.get(name.strip())
+        if isinstance(value, bool):
+            value = 'true' if value else 'false'
+        matched = value is not None and str(value) in options
+        if matched == negated:
+            return False
+    return True
+
 class GeoEngineCLIClient:
     """Client that invokes the geoengine CLI binary via subprocess."""
 
@@ -161,9 +186,12 @@ class GeoEngineCLIClient:
                         error_detail = json.loads(stdout_data)
                     except json.JSONDecodeError:
                         pass
+                error = error_detail.get('error', 'unknown error')
+                # {"code": ..., "message": ...}; older releases sent a plain string
+                if isinstance(error, dict):
+                    error = f"{error.get('message')} [{error.get('code')}]"
                 raise Exception(
-                    f"Tool exited with code {process.returncode}: "
-                    f"{error_detail.get('error', 'unknown error')}"
+                    f"Tool exited with code {process.returncode}: {error}"
                 )
             else:
                 return {'status': 'completed', 'exit_code': 0, 'files': []}
@@ -320,6 +348,14 @@ class GeoEngineAlgorithm(QgsProcessingAlgorithm):
         else:
             param = QgsProcessingParameterString(name, label, defaultValue=default, optional=not required)
 
+        # Processing dialogs only know one collapsible "Advanced" section; other
+        # groups are shown as a label prefix
+        group = param_info.get('group')
+        if group and group.lower() == 'advanced':
+            param.setFlags(param.flags() | QgsProcessingParameterDefinition.FlagAdvanced)
+        elif group:
+            param.setDescription(f"{group}: {label}")
+
         return param
 
     def processAlgorithm(
@@ -342,6 +378,13 @@ class GeoEngineAlgorithm(QgsProcessingAlgorithm):
                     value = value.dataProvider().dataSourceUri()
                 inputs[name] = str(value) if value is not None else None
 
+        # Processing dialogs can't hide parameters, so drop the ones whose
+        # visible_when condition doesn't hold instead of passing them on
+        values = {inp['name']: parameters.get(inp['name']) for inp in self._inputs}
+        for inp in self._inputs:
+            if inp.get('visible_when') and not visible_when_met(inp['visible_when'], values):
+                inputs.pop(inp['name'], None)
+
         feedback.pushInfo(f"Running worker '{self._worker}'...")
 
         result = client.run_tool(
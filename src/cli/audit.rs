@@ -0,0 +1,160 @@
+use anyhow::Result;
+use clap::Subcommand;
+use colored::Colorize;
+
+use crate::config::audit::{self, AuditRecord};
+
+#[derive(Subcommand)]
+pub enum AuditCommands {
+    /// List audit records, most recent first
+    List {
+        /// Only show events of this worker
+        #[arg(short, long)]
+        worker: Option<String>,
+
+        /// Only show events whose name starts with this, e.g. "run" or "auth.failed"
+        #[arg(short, long)]
+        event: Option<String>,
+
+        /// Only show events at or after this time (RFC 3339 or YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Maximum number of records to show
+        #[arg(short = 'n', long, default_value_t = 50)]
+        limit: usize,
+
+        /// Output as JSON (for programmatic use)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check that no record was modified or removed (needs `audit.hash_chain`)
+    Verify,
+}
+
+impl AuditCommands {
+    pub async fn execute(self) -> Result<()> {
+        match self {
+            Self::List {
+                worker,
+                event,
+                since,
+                limit,
+                json,
+            } => list(worker.as_deref(), event.as_deref(), since.as_deref(), limit, json),
+            Self::Verify => verify(),
+        }
+    }
+}
+
+fn list(worker: Option<&str>, event: Option<&str>, since: Option<&str>, limit: usize, json: bool) -> Result<()> {
+    let since = since.map(parse_since).transpose()?;
+    let records: Vec<AuditRecord> = audit::load()?
+        .into_iter()
+        .rev()
+        .filter(|r| worker.is_none_or(|w| r.worker.as_deref() == Some(w)))
+        .filter(|r| event.is_none_or(|e| r.event.starts_with(e)))
+        .filter(|r| {
+            since.is_none_or(|since| {
+                chrono::DateTime::parse_from_rfc3339(&r.time).is_ok_and(|t| t.with_timezone(&chrono::Utc) >= since)
+            })
+        })
+        .take(limit)
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string(&records)?);
+        return Ok(());
+    }
+    if records.is_empty() {
+        if audit::settings().is_none() {
+            println!("{}", "Audit logging is off. Add an `audit:` section to the settings to turn it on.".yellow());
+        } else {
+            println!("{}", "No audit records".yellow());
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{:<20} {:<16} {:<24} {:<20} {}",
+        "TIME".bold(),
+        "EVENT".bold(),
+        "ACTOR".bold(),
+        "WORKER".bold(),
+        "DETAILS".bold()
+    );
+    println!("{}", "-".repeat(100));
+    for record in &records {
+        let details: Vec<String> = record
+            .details
+            .iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(s) => format!("{}={}", key, s),
+                other => format!("{}={}", key, other),
+            })
+            .collect();
+        let event = if record.event == "auth.failed" || record.details.get("outcome").is_some_and(|o| o == "error") {
+            format!("{:<16}", record.event).red().to_string()
+        } else {
+            format!("{:<16}", record.event)
+        };
+        println!(
+            "{:<20} {} {:<24} {:<20} {}",
+            format_time(&record.time),
+            event,
+            record.actor,
+            record.worker.as_deref().unwrap_or("-"),
+            details.join(" ")
+        );
+    }
+
+    Ok(())
+}
+
+fn parse_since(since: &str) -> Result<chrono::DateTime<chrono::Utc>> {
+    if let Ok(time) = chrono::DateTime::parse_from_rfc3339(since) {
+        return Ok(time.with_timezone(&chrono::Utc));
+    }
+    let date = chrono::NaiveDate::parse_from_str(since, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("Invalid --since '{}'. Expected RFC 3339 or YYYY-MM-DD", since))?;
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+        .map(|t| t.with_timezone(&chrono::Utc))
+        .ok_or_else(|| anyhow::anyhow!("Invalid --since '{}'", since))
+}
+
+fn verify() -> Result<()> {
+    let report = audit::verify()?;
+    if report.records == report.unchained {
+        anyhow::bail!(
+            "No hash-chained records to verify ({} record(s)). Set `audit.hash_chain: true` in the settings.",
+            report.records
+        );
+    }
+    if !report.failures.is_empty() {
+        for failure in &report.failures {
+            eprintln!("  {} {}", "✗".red().bold(), failure);
+        }
+        anyhow::bail!("Audit log failed verification: {} problem(s)", report.failures.len());
+    }
+    println!(
+        "{} {} record(s) verified",
+        "✓".green().bold(),
+        report.records - report.unchained
+    );
+    if report.unchained > 0 {
+        println!(
+            "{} {} record(s) were written while hash chaining was off and can't be verified",
+            "!".yellow().bold(),
+            report.unchained
+        );
+    }
+    Ok(())
+}
+
+fn format_time(time: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| time.to_string())
+}
@@ -0,0 +1,401 @@
+use anyhow::Result;
+use bollard::auth::DockerCredentials;
+use clap::Subcommand;
+use colored::Colorize;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::cli::deploy::remote_command;
+use crate::cli::worker::resolve_worker;
+use crate::config::settings::{AwsSettings, Settings};
+use crate::config::state;
+use crate::config::worker::{self as worker_config, Accelerator, WorkerConfig};
+use crate::docker::client::{self, DockerClient};
+use crate::docker::registry::{self, PushRetry};
+use crate::error::GeoEngineError;
+use crate::utils::aws;
+
+/// Memory given to a job when the worker sets no `runtime.memory`
+const DEFAULT_MEMORY_MIB: i64 = 2048;
+
+/// How often a watched job is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// CloudWatch log group AWS Batch writes container output to by default
+const LOG_GROUP: &str = "/aws/batch/job";
+
+#[derive(Subcommand)]
+pub enum AwsBatchCommands {
+    /// Register a job definition from the worker's runtime config (vCPU, memory, GPU)
+    Register {
+        /// Worker name (defaults to the worker in the current directory)
+        worker: Option<String>,
+
+        /// Push the local image to ECR first, creating the repository if needed
+        #[arg(long)]
+        push: bool,
+
+        /// GPUs per job (defaults to 1 if `runtime.accelerator` is cuda, otherwise 0)
+        #[arg(long)]
+        gpus: Option<u32>,
+
+        /// AWS region (defaults to `aws.region` in settings, then the AWS CLI's region)
+        #[arg(long, env = "AWS_REGION")]
+        region: Option<String>,
+
+        /// ECR repository prefix; images go to <prefix>/<worker> (defaults to `aws.repository` or "geoengine")
+        #[arg(long)]
+        repository: Option<String>,
+
+        /// IAM role the job's container assumes (defaults to `aws.job_role_arn` in settings)
+        #[arg(long)]
+        job_role_arn: Option<String>,
+    },
+
+    /// Submit a job running the worker's command with the given inputs
+    Submit {
+        /// Worker name (defaults to the worker in the current directory)
+        worker: Option<String>,
+
+        /// Input parameters (KEY=VALUE); file and folder inputs should be S3 URIs
+        #[arg(short, long = "input")]
+        inputs: Vec<String>,
+
+        /// Job queue (defaults to `aws.job_queue` in settings)
+        #[arg(long)]
+        job_queue: Option<String>,
+
+        /// Wait for the job to finish, printing its state as it changes
+        #[arg(long)]
+        wait: bool,
+
+        /// AWS region (defaults to `aws.region` in settings, then the AWS CLI's region)
+        #[arg(long, env = "AWS_REGION")]
+        region: Option<String>,
+    },
+
+    /// Show the state of a submitted job
+    Status {
+        /// Job ID printed by `submit`
+        job_id: String,
+
+        /// Wait for the job to finish, printing its state as it changes
+        #[arg(long)]
+        wait: bool,
+
+        /// Print the full job description as JSON
+        #[arg(long, conflicts_with = "wait")]
+        json: bool,
+
+        /// AWS region (defaults to `aws.region` in settings, then the AWS CLI's region)
+        #[arg(long, env = "AWS_REGION")]
+        region: Option<String>,
+    },
+}
+
+impl AwsBatchCommands {
+    pub async fn execute(self) -> Result<()> {
+        let settings = Settings::load()?.aws.unwrap_or_default();
+        match self {
+            Self::Register {
+                worker,
+                push,
+                gpus,
+                region,
+                repository,
+                job_role_arn,
+            } => {
+                let region = resolve_region(region, &settings)?;
+                let repository = repository
+                    .or(settings.repository.clone())
+                    .unwrap_or_else(|| "geoengine".to_string());
+                let job_role_arn = job_role_arn.or(settings.job_role_arn.clone());
+                register(worker.as_deref(), push, gpus, &region, &repository, job_role_arn.as_deref()).await
+            }
+            Self::Submit {
+                worker,
+                inputs,
+                job_queue,
+                wait,
+                region,
+            } => {
+                let region = resolve_region(region, &settings)?;
+                let job_queue = job_queue.or(settings.job_queue.clone()).ok_or_else(|| {
+                    GeoEngineError::ConfigInvalid(
+                        "No job queue given. Pass --job-queue or set aws.job_queue in settings.".to_string(),
+                    )
+                })?;
+                submit(worker.as_deref(), &inputs, &job_queue, wait, &region).await
+            }
+            Self::Status {
+                job_id,
+                wait,
+                json,
+                region,
+            } => {
+                let region = resolve_region(region, &settings)?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&aws::describe_job(&region, &job_id)?)?);
+                    return Ok(());
+                }
+                watch(&region, &job_id, wait).await
+            }
+        }
+    }
+}
+
+/// Region from the flag (or AWS_REGION), then settings, then the AWS CLI's configuration
+fn resolve_region(region: Option<String>, settings: &AwsSettings) -> Result<String> {
+    region
+        .filter(|r| !r.is_empty())
+        .or(settings.region.clone())
+        .or_else(aws::configured_region)
+        .ok_or_else(|| {
+            GeoEngineError::ConfigInvalid(
+                "No AWS region given. Pass --region, set AWS_REGION or aws.region in settings.".to_string(),
+            )
+            .into()
+        })
+}
+
+/// Job definitions are named after the worker so each `register` adds a revision
+fn job_definition_name(worker: &str) -> String {
+    format!("geoengine-{}", worker)
+}
+
+async fn register(
+    worker: Option<&str>,
+    push: bool,
+    gpus: Option<u32>,
+    region: &str,
+    repository: &str,
+    job_role_arn: Option<&str>,
+) -> Result<()> {
+    let (name, path) = resolve_worker(worker)?;
+    let config = WorkerConfig::load(&worker_config::config_file(&path))?;
+    let cmd_config = config
+        .command
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No command defined for worker '{}'", name))?;
+    let version = config.version.clone().unwrap_or("latest".to_string());
+
+    let account = aws::account_id(region)?;
+    let registry = aws::ecr_registry(&account, region);
+    let ecr_repository = format!("{}/{}", repository, config.name);
+    let image = format!("{}/{}:{}", registry, ecr_repository, version);
+
+    if push {
+        push_to_ecr(&config, &version, region, &registry, &ecr_repository, &image).await?;
+    }
+
+    // Resources: Batch wants whole vCPUs and memory in MiB
+    let runtime = config.runtime.as_ref();
+    let vcpus = runtime.and_then(|r| r.cpus).map(|c| c.ceil().max(1.0) as u32).unwrap_or(1);
+    let memory_mib = match runtime.map(|r| r.memory_bytes()).transpose()?.flatten() {
+        Some(bytes) => (bytes / (1024 * 1024)).max(4),
+        None => DEFAULT_MEMORY_MIB,
+    };
+    let gpus = gpus.unwrap_or(if config.accelerator() == Accelerator::Cuda { 1 } else { 0 });
+    let mut resources = vec![
+        json!({ "type": "VCPU", "value": vcpus.to_string() }),
+        json!({ "type": "MEMORY", "value": memory_mib.to_string() }),
+    ];
+    if gpus > 0 {
+        resources.push(json!({ "type": "GPU", "value": gpus.to_string() }));
+    }
+
+    let environment: Vec<Value> = runtime
+        .and_then(|r| r.env.as_ref())
+        .map(|env| env.iter().map(|(k, v)| json!({ "name": k, "value": v })).collect())
+        .unwrap_or_default();
+
+    let mut container = json!({
+        "image": image,
+        "command": ["/bin/sh", "-c", format!("{} {}", cmd_config.program, cmd_config.script)],
+        "resourceRequirements": resources,
+        "environment": environment,
+    });
+    if let Some(arn) = job_role_arn {
+        container["jobRoleArn"] = json!(arn);
+    }
+    let definition = json!({
+        "jobDefinitionName": job_definition_name(&config.name),
+        "type": "container",
+        "platformCapabilities": ["EC2"],
+        "containerProperties": container,
+        "tags": { "geoengine:worker": config.name, "geoengine:version": version },
+    });
+
+    println!(
+        "{} Registering job definition {}...",
+        "=>".blue().bold(),
+        job_definition_name(&config.name).cyan()
+    );
+    let arn = aws::register_job_definition(region, &definition)?;
+    println!("{} Registered: {}", "✓".green().bold(), arn.cyan());
+    println!(
+        "  {} {} vCPU, {} MiB memory, {} GPU",
+        "•".cyan(),
+        vcpus,
+        memory_mib,
+        gpus
+    );
+    println!("  {} Image: {}", "•".cyan(), image);
+    if !push {
+        println!(
+            "  {} The image must already be in ECR; pass {} to push it",
+            "!".yellow().bold(),
+            "--push".cyan()
+        );
+    }
+    if runtime.and_then(|r| r.env_files.as_ref()).is_some_and(|f| !f.is_empty()) {
+        println!(
+            "  {} runtime.env_files are not sent to AWS Batch; only runtime.env is",
+            "!".yellow().bold()
+        );
+    }
+    Ok(())
+}
+
+/// Tag the worker's local image for ECR and push it with a short-lived ECR login
+async fn push_to_ecr(
+    config: &WorkerConfig,
+    version: &str,
+    region: &str,
+    registry: &str,
+    ecr_repository: &str,
+    image: &str,
+) -> Result<()> {
+    let client = DockerClient::new().await?;
+    let local = state::load_state(&config.name)
+        .ok()
+        .flatten()
+        .and_then(|s| s.image_tag)
+        .unwrap_or_else(|| format!("geoengine-local/{}:{}", config.name, version));
+    if client.image_id(&local).await.is_err() {
+        return Err(GeoEngineError::ImageNotFound(format!(
+            "Image '{}' not found. Run 'geoengine build' first.",
+            local
+        ))
+        .into());
+    }
+
+    if aws::ensure_ecr_repository(region, ecr_repository)? {
+        println!("{} Created ECR repository {}", "✓".green().bold(), ecr_repository.cyan());
+    }
+
+    println!("{} Pushing {} to {}...", "=>".blue().bold(), local.cyan(), image.cyan());
+    client.tag_image(&local, image).await?;
+    let credentials = DockerCredentials {
+        username: Some("AWS".to_string()),
+        password: Some(aws::ecr_password(region)?),
+        serveraddress: Some(registry.to_string()),
+        ..Default::default()
+    };
+
+    registry::push(&client, image, Some(credentials), PushRetry::default()).await?;
+
+    println!("{} Successfully pushed: {}", "✓".green().bold(), image.cyan());
+    Ok(())
+}
+
+async fn submit(worker: Option<&str>, inputs: &[String], job_queue: &str, wait: bool, region: &str) -> Result<()> {
+    let (name, path) = resolve_worker(worker)?;
+    let config = WorkerConfig::load(&worker_config::config_file(&path))?;
+    let cmd_config = config
+        .command
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No command defined for worker '{}'", name))?;
+
+    // The job runs remotely: local paths mean nothing there
+    let command = remote_command(cmd_config, inputs, "Upload it and pass an S3 URI instead.")?;
+
+    let job_name: String = format!("{}-{}", config.name, chrono::Utc::now().format("%Y%m%d-%H%M%S"))
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .take(128)
+        .collect();
+    let request = json!({
+        "jobName": job_name,
+        "jobQueue": job_queue,
+        "jobDefinition": job_definition_name(&config.name),
+        "containerOverrides": { "command": ["/bin/sh", "-c", command] },
+        "tags": { "geoengine:worker": config.name },
+    });
+
+    println!("{} Submitting {} to {}...", "=>".blue().bold(), job_name.cyan(), job_queue.cyan());
+    let job_id = aws::submit_job(region, &request)?;
+    println!("{} Submitted job {}", "✓".green().bold(), job_id.cyan());
+
+    if wait {
+        watch(region, &job_id, true).await
+    } else {
+        println!("\nFollow it with:");
+        println!("  {}", format!("geoengine deploy aws-batch status {} --wait", job_id).cyan());
+        Ok(())
+    }
+}
+
+/// Print a job's state, polling until it finishes if `wait` is set. Ctrl-C stops
+/// watching; the job keeps running.
+async fn watch(region: &str, job_id: &str, wait: bool) -> Result<()> {
+    let mut last_status = String::new();
+    let mut shown_logs = false;
+    loop {
+        let job = aws::describe_job(region, job_id)?;
+        let status = job["status"].as_str().unwrap_or("UNKNOWN").to_string();
+        let job_name = job["jobName"].as_str().unwrap_or(job_id).to_string();
+
+        if status != last_status {
+            let reason = job["statusReason"].as_str().unwrap_or_default();
+            let symbol = match status.as_str() {
+                "SUCCEEDED" => "✓".green().bold(),
+                "FAILED" => "✗".red().bold(),
+                _ => "•".cyan(),
+            };
+            if reason.is_empty() {
+                println!("{} {} {}", symbol, job_name, status);
+            } else {
+                println!("{} {} {} ({})", symbol, job_name, status, reason);
+            }
+            last_status = status.clone();
+        }
+
+        if !shown_logs {
+            if let Some(stream) = job["container"]["logStreamName"].as_str() {
+                println!(
+                    "  {} Logs: {}",
+                    "•".cyan(),
+                    format!("aws logs tail {} --log-stream-names {} --follow", LOG_GROUP, stream).cyan()
+                );
+                shown_logs = true;
+            }
+        }
+
+        match status.as_str() {
+            "SUCCEEDED" => return Ok(()),
+            "FAILED" => {
+                let exit_code = job["container"]["exitCode"].as_i64().unwrap_or(1);
+                return Err(GeoEngineError::ContainerFailed {
+                    worker: job_name,
+                    exit_code,
+                }
+                .into());
+            }
+            _ if !wait => return Ok(()),
+            _ => {}
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = client::shutdown_signal() => {
+                println!(
+                    "\n{} Stopped watching; the job keeps running. Check on it with {}",
+                    "!".yellow().bold(),
+                    format!("geoengine deploy aws-batch status {}", job_id).cyan()
+                );
+                return Ok(());
+            }
+        }
+    }
+}
@@ -0,0 +1,417 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use futures::StreamExt;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::Instrument;
+
+use crate::cli::output_rules::validate_outputs;
+use crate::cli::worker::{self, RunArgs, RunOutput};
+use crate::config::worker::InputParameter;
+use crate::config::yaml_store;
+use crate::docker::client::{self, DockerClient};
+use crate::docker::usage::ResourceUsage;
+use crate::error::{self, GeoEngineError};
+
+/// Summary of a batch run, written next to the manifest as `<manifest>.results.json`
+/// and rewritten after every item so an interrupted batch can be resumed.
+#[derive(Serialize, Deserialize)]
+pub struct BatchSummary {
+    pub manifest: String,
+    pub worker: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub total: usize,
+    pub completed: usize,
+    pub failed: usize,
+    pub items: Vec<BatchItem>,
+}
+
+/// Result of one parameter set in the manifest
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BatchItem {
+    pub index: usize,
+    pub parameters: BTreeMap<String, String>,
+    pub status: ItemStatus,
+    pub exit_code: Option<i64>,
+    pub error: Option<String>,
+    /// Machine-readable class of `error`, as reported in `error.code` of `--json` output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<String>,
+    pub log: Option<String>,
+    pub duration_secs: Option<f64>,
+    /// CPU, memory and GPU use of the item's container
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ResourceUsage>,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ItemStatus {
+    Pending,
+    Completed,
+    Failed,
+}
+
+/// Run the worker once per row of a CSV/JSONL manifest, up to `--parallel` at a time
+pub async fn run_batch(run: &RunArgs, manifest: &Path) -> Result<()> {
+    let (worker_name, _) = worker::resolve_worker(run.worker.as_deref())?;
+    let config = yaml_store::load_saved_config(&worker_name)?;
+    let definitions = config.input_definitions();
+    let rows = read_manifest(manifest, definitions)?;
+    if rows.is_empty() {
+        anyhow::bail!("Batch manifest is empty: {}", manifest.display());
+    }
+
+    // --param-file / --input values are shared defaults; manifest columns override them
+    let shared = worker::collect_inputs(run, definitions)?;
+    let parameters: Vec<BTreeMap<String, String>> = rows
+        .into_iter()
+        .map(|row| {
+            let mut params: BTreeMap<String, String> = shared.clone().into_iter().collect();
+            params.extend(row);
+            params
+        })
+        .collect();
+
+    let summary_path = manifest.with_extension("results.json");
+    let logs_dir = manifest.with_extension("logs");
+    std::fs::create_dir_all(&logs_dir)
+        .with_context(|| format!("Failed to create log directory: {}", logs_dir.display()))?;
+
+    let client = DockerClient::new().await?;
+    let prepared = worker::prepare_run(run, &client).await?;
+
+    let mut summary = if run.resume && summary_path.exists() {
+        resume_summary(&summary_path, &parameters)?
+    } else {
+        BatchSummary {
+            manifest: manifest.display().to_string(),
+            worker: prepared.worker_name.clone(),
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+            total: parameters.len(),
+            completed: 0,
+            failed: 0,
+            items: parameters
+                .into_iter()
+                .enumerate()
+                .map(|(index, parameters)| BatchItem {
+                    index,
+                    parameters,
+                    status: ItemStatus::Pending,
+                    exit_code: None,
+                    error: None,
+                    error_code: None,
+                    log: None,
+                    duration_secs: None,
+                    usage: None,
+                })
+                .collect(),
+        }
+    };
+    summary.finished_at = None;
+
+    let pending: Vec<usize> = summary
+        .items
+        .iter()
+        .filter(|item| item.status != ItemStatus::Completed)
+        .map(|item| item.index)
+        .collect();
+    let skipped = summary.items.len() - pending.len();
+    if skipped > 0 {
+        eprintln!(
+            "{} Resuming batch: {} of {} items already completed",
+            "•".cyan(),
+            skipped,
+            summary.items.len()
+        );
+    }
+
+    // With NVIDIA GPUs, each concurrent item gets a GPU of its own
+    let mut parallel = run.parallel.max(1);
+    let gpu_slots: Option<Mutex<Vec<usize>>> = match &prepared.gpu_config {
+        Some(gpu) if gpu.is_nvidia() => {
+            if parallel > gpu.count {
+                eprintln!(
+                    "{} Limiting --parallel to {} (one item per GPU)",
+                    "!".yellow().bold(),
+                    gpu.count
+                );
+                parallel = gpu.count;
+            }
+            Some(Mutex::new((0..gpu.count).rev().collect()))
+        }
+        _ => None,
+    };
+
+    eprintln!(
+        "{} Running {} batch item(s) of worker '{}' ({} at a time)...",
+        "=>".blue().bold(),
+        pending.len(),
+        prepared.worker_name.cyan(),
+        parallel
+    );
+
+    // Stop scheduling new items on Ctrl-C; running containers are stopped by the client
+    let interrupted = Arc::new(AtomicBool::new(false));
+    {
+        let interrupted = interrupted.clone();
+        tokio::spawn(async move {
+            client::shutdown_signal().await;
+            interrupted.store(true, Ordering::SeqCst);
+        });
+    }
+
+    let pb = ProgressBar::new(pending.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {msg}")?
+            .progress_chars("=> "),
+    );
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+
+    let summary = Mutex::new(summary);
+    futures::stream::iter(pending)
+        .map(|index| {
+            let (client, prepared, summary, gpu_slots, interrupted, pb, logs_dir, summary_path) = (
+                &client, &prepared, &summary, &gpu_slots, &interrupted, &pb, &logs_dir, &summary_path,
+            );
+            async move {
+                if interrupted.load(Ordering::SeqCst) {
+                    return;
+                }
+
+                let inputs: HashMap<String, String> = summary.lock().unwrap().items[index]
+                    .parameters
+                    .clone()
+                    .into_iter()
+                    .collect();
+                let log_path = logs_dir.join(format!("item-{}.log", index));
+
+                let slot = gpu_slots.as_ref().and_then(|s| s.lock().unwrap().pop());
+                let gpu_config = match (slot, &prepared.gpu_config) {
+                    (Some(i), Some(gpu)) => Some(gpu.pinned(i)),
+                    (_, gpu) => gpu.clone(),
+                };
+
+                let started = std::time::Instant::now();
+                let result = worker::execute_run(
+                    client,
+                    prepared,
+                    &inputs,
+                    run,
+                    gpu_config,
+                    &RunOutput::LogFile(log_path.clone()),
+                )
+                .instrument(tracing::info_span!("batch_item", index))
+                .await
+                .map(|outcome| (outcome.exit_code, outcome.oom_killed, outcome.usage, outcome.files));
+                let invalid_outputs = match &result {
+                    Ok((0, false, _, files)) => validate_outputs(client, &prepared.config, &inputs, files, &HashMap::new()).await,
+                    _ => Vec::new(),
+                };
+
+                if let (Some(i), Some(slots)) = (slot, gpu_slots) {
+                    slots.lock().unwrap().push(i);
+                }
+
+                let mut summary = summary.lock().unwrap();
+                let item = &mut summary.items[index];
+                item.log = Some(log_path.display().to_string());
+                item.duration_secs = Some(started.elapsed().as_secs_f64());
+                item.usage = result.as_ref().ok().and_then(|(_, _, usage, _)| usage.clone());
+                match result.map(|(code, oom_killed, _, _)| (code, oom_killed)) {
+                    Ok((code, true)) => {
+                        let detail = worker::oom_detail(&prepared.config, item.usage.as_ref());
+                        item.status = ItemStatus::Failed;
+                        item.exit_code = Some(code);
+                        item.error = Some(format!("Killed: {}", detail));
+                        item.error_code = Some("oom_killed".to_string());
+                        pb.println(format!(
+                            "{} Item {} killed: {} (log: {})",
+                            "✗".red().bold(),
+                            index,
+                            detail,
+                            log_path.display()
+                        ));
+                    }
+                    Ok((0, _)) if !invalid_outputs.is_empty() => {
+                        item.status = ItemStatus::Failed;
+                        item.exit_code = Some(0);
+                        item.error = Some(format!("Invalid outputs: {}", invalid_outputs.join("; ")));
+                        item.error_code = Some("outputs_invalid".to_string());
+                        pb.println(format!(
+                            "{} Item {} wrote invalid outputs: {} (log: {})",
+                            "✗".red().bold(),
+                            index,
+                            invalid_outputs.join("; "),
+                            log_path.display()
+                        ));
+                    }
+                    Ok((0, _)) => {
+                        item.status = ItemStatus::Completed;
+                        item.exit_code = Some(0);
+                        item.error = None;
+                        item.error_code = None;
+                        pb.println(format!("{} Item {} completed", "✓".green().bold(), index));
+                    }
+                    Ok((code, _)) => {
+                        item.status = ItemStatus::Failed;
+                        item.exit_code = Some(code);
+                        item.error = Some(format!("Container exited with code {}", code));
+                        item.error_code = Some("container_failed".to_string());
+                        pb.println(format!(
+                            "{} Item {} failed with exit code {} (log: {})",
+                            "✗".red().bold(),
+                            index,
+                            code,
+                            log_path.display()
+                        ));
+                    }
+                    Err(e) => {
+                        item.status = if interrupted.load(Ordering::SeqCst) {
+                            ItemStatus::Pending
+                        } else {
+                            ItemStatus::Failed
+                        };
+                        item.exit_code = None;
+                        item.error = Some(format!("{:#}", e));
+                        item.error_code = Some(error::classify(&e).map_or("error", |e| e.code()).to_string());
+                        pb.println(format!("{} Item {}: {:#}", "✗".red().bold(), index, e));
+                    }
+                }
+                if let Err(e) = write_summary(summary_path, &mut summary) {
+                    pb.println(format!("{} {:#}", "!".yellow().bold(), e));
+                }
+                pb.inc(1);
+            }
+        })
+        .buffer_unordered(parallel)
+        .collect::<Vec<()>>()
+        .await;
+    pb.finish_and_clear();
+
+    let mut summary = summary.into_inner().unwrap();
+    summary.finished_at = Some(chrono::Utc::now().to_rfc3339());
+    write_summary(&summary_path, &mut summary)?;
+
+    let interrupted = interrupted.load(Ordering::SeqCst);
+    if run.json {
+        // An interrupted batch is reported as an error; the summary file has the details
+        if !interrupted {
+            println!("{}", serde_json::to_string(&summary)?);
+        }
+    } else {
+        eprintln!(
+            "{} Batch finished: {} completed, {} failed, {} pending",
+            if summary.failed == 0 { "✓".green().bold() } else { "✗".red().bold() },
+            summary.completed,
+            summary.failed,
+            summary.total - summary.completed - summary.failed
+        );
+        eprintln!("  Results: {}", summary_path.display());
+    }
+
+    if interrupted {
+        return Err(GeoEngineError::Interrupted).context("Batch interrupted. Rerun with --resume to continue.");
+    }
+    if summary.failed > 0 {
+        return Err(GeoEngineError::BatchFailed {
+            failed: summary.failed,
+            total: summary.total,
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Read parameter sets from a CSV (header row = input names) or JSONL manifest.
+/// Empty CSV cells are left out so the worker's defaults apply.
+fn read_manifest(path: &Path, definitions: &[InputParameter]) -> Result<Vec<HashMap<String, String>>> {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        "csv" => {
+            let mut reader = csv::Reader::from_path(path)
+                .with_context(|| format!("Failed to read batch manifest: {}", path.display()))?;
+            let headers = reader.headers()?.clone();
+            let mut rows = Vec::new();
+            for (line, record) in reader.records().enumerate() {
+                let record = record.with_context(|| {
+                    format!("Failed to parse row {} of {}", line + 2, path.display())
+                })?;
+                let row = headers
+                    .iter()
+                    .zip(record.iter())
+                    .filter(|(_, value)| !value.is_empty())
+                    .map(|(key, value)| (key.trim().to_string(), value.to_string()))
+                    .collect();
+                rows.push(row);
+            }
+            Ok(rows)
+        }
+        "jsonl" | "ndjson" => {
+            let content = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read batch manifest: {}", path.display()))?;
+            content
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| !line.trim().is_empty())
+                .map(|(line, text)| {
+                    let params: serde_yaml::Mapping = serde_yaml::from_str(text).with_context(|| {
+                        format!("Failed to parse line {} of {}", line + 1, path.display())
+                    })?;
+                    worker::mapping_to_inputs(params, path, definitions)
+                })
+                .collect()
+        }
+        _ => anyhow::bail!(
+            "Unsupported batch manifest '{}'. Expected a .csv or .jsonl file.",
+            path.display()
+        ),
+    }
+}
+
+/// Load a previous summary for --resume, checking it belongs to the same manifest contents
+fn resume_summary(path: &Path, parameters: &[BTreeMap<String, String>]) -> Result<BatchSummary> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read batch results: {}", path.display()))?;
+    let mut summary: BatchSummary = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse batch results: {}", path.display()))?;
+
+    let unchanged = summary.items.len() == parameters.len()
+        && summary.items.iter().zip(parameters).all(|(item, params)| &item.parameters == params);
+    if !unchanged {
+        anyhow::bail!(
+            "The manifest no longer matches {}. Run without --resume to start a new batch.",
+            path.display()
+        );
+    }
+
+    for item in &mut summary.items {
+        if item.status == ItemStatus::Failed {
+            item.status = ItemStatus::Pending;
+        }
+    }
+    Ok(summary)
+}
+
+/// Recount and write the summary file
+fn write_summary(path: &PathBuf, summary: &mut BatchSummary) -> Result<()> {
+    summary.completed = summary.items.iter().filter(|i| i.status == ItemStatus::Completed).count();
+    summary.failed = summary.items.iter().filter(|i| i.status == ItemStatus::Failed).count();
+    let json = serde_json::to_string_pretty(summary).context("Failed to serialize batch results")?;
+    std::fs::write(path, json)
+        .with_context(|| format!("Failed to write batch results: {}", path.display()))?;
+    Ok(())
+}
@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::cli::worker::resolve_worker;
+use crate::docker::client::DockerClient;
+use crate::docker::config::ContainerConfig;
+use crate::docker::dockerfile::get_base_images;
+use crate::docker::registry;
+
+/// Directory (relative to the worker) that receives the vendored dependencies
+const VENDOR_DIR: &str = "vendor";
+
+const REQUIREMENTS_FILES: &[&str] = &["requirements.txt"];
+const CONDA_ENV_FILES: &[&str] = &["environment.yml", "environment.yaml"];
+
+/// Download a worker's pip wheels and/or a packed conda environment into `vendor/`,
+/// using the Dockerfile's base image so the artifacts match the build platform.
+/// The Dockerfile can then install from `vendor/` and build without network access.
+pub async fn bundle_deps(worker: Option<&str>, image: Option<&str>) -> Result<()> {
+    let (worker_name, worker_path) = resolve_worker(worker)?;
+
+    let requirements = find_file(&worker_path, REQUIREMENTS_FILES);
+    let conda_env = find_file(&worker_path, CONDA_ENV_FILES);
+    if requirements.is_none() && conda_env.is_none() {
+        anyhow::bail!(
+            "No requirements.txt or environment.yml found in {}. Nothing to bundle.",
+            worker_path.display()
+        );
+    }
+
+    let dockerfile = worker_path.join("Dockerfile");
+    let base_image = match image {
+        Some(image) => image.to_string(),
+        // The last external base image is the one the final stage builds on
+        None => get_base_images(&dockerfile)
+            .with_context(|| format!("Failed to read {}", dockerfile.display()))?
+            .pop()
+            .context("Could not determine the base image from the Dockerfile. Pass --image.")?,
+    };
+
+    let client = DockerClient::new().await?;
+    if client.image_id(&base_image).await.is_err() {
+        println!("{} Pulling base image {}...", "=>".blue().bold(), base_image.cyan());
+        registry::pull(&client, &base_image).await?;
+    }
+
+    let vendor = worker_path.join(VENDOR_DIR);
+    let mut steps: Vec<String> = Vec::new();
+    if let Some(requirements) = &requirements {
+        reset_dir(&vendor.join("wheels"))?;
+        // setuptools/wheel let source-only packages build without an index
+        steps.push(format!(
+            "python -m pip download --dest /vendor/wheels -r /worker/{} setuptools wheel",
+            requirements
+        ));
+    }
+    if let Some(conda_env) = &conda_env {
+        let archive = vendor.join("conda-env.tar.gz");
+        if archive.exists() {
+            std::fs::remove_file(&archive)?;
+        }
+        std::fs::create_dir_all(&vendor)?;
+        steps.push(format!(
+            "conda install -y -n base -c conda-forge conda-pack && \
+             conda env create -p /tmp/env -f /worker/{} && \
+             conda pack -p /tmp/env -o /vendor/conda-env.tar.gz",
+            conda_env
+        ));
+    }
+    // Files written by the container belong to root; hand them back to the worker's owner
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let meta = std::fs::metadata(&worker_path)?;
+        steps.push(format!("chown -R {}:{} /vendor", meta.uid(), meta.gid()));
+    }
+
+    println!(
+        "{} Bundling dependencies of '{}' using {}...",
+        "=>".blue().bold(),
+        worker_name.cyan(),
+        base_image.cyan()
+    );
+    let container_config = ContainerConfig {
+        image: base_image.clone(),
+        entrypoint: None,
+        command: Some(vec!["/bin/sh".to_string(), "-c".to_string(), steps.join(" && ")]),
+        env_vars: HashMap::new(),
+        mounts: vec![
+            (worker_path.display().to_string(), "/worker".to_string(), true),
+            (vendor.display().to_string(), "/vendor".to_string(), false),
+        ],
+        volumes: Vec::new(),
+        gpu_config: None,
+        user: None,
+        group_add: Vec::new(),
+        workdir: Some("/worker".to_string()),
+        name: None,
+        labels: HashMap::new(),
+        ports: Vec::new(),
+        restart: None,
+        ulimits: Vec::new(),
+        cap_add: Vec::new(),
+        cap_drop: Vec::new(),
+        devices: Vec::new(),
+        read_only: false,
+        security_opt: Vec::new(),
+        privileged: false,
+        memory: None,
+        nano_cpus: None,
+        network: None,
+        network_aliases: Vec::new(),
+        remove_on_exit: true,
+        detach: false,
+        tty: false,
+    };
+    let exit_code = client.run_container_attached(&container_config).await?.exit_code;
+    if exit_code != 0 {
+        anyhow::bail!("Dependency download failed with exit code {}", exit_code);
+    }
+
+    println!(
+        "{} Dependencies vendored in {}",
+        "✓".green().bold(),
+        vendor.display()
+    );
+
+    let dockerfile_content = std::fs::read_to_string(&dockerfile).unwrap_or_default();
+    if dockerfile_content.contains(&format!("{}/", VENDOR_DIR)) {
+        println!("{} The Dockerfile already installs from {}/", "✓".green().bold(), VENDOR_DIR);
+        return Ok(());
+    }
+    println!("\nInstall from the vendored files in the Dockerfile to build offline:");
+    if let Some(requirements) = &requirements {
+        println!("  {}", "COPY vendor/wheels /tmp/wheels".cyan());
+        println!(
+            "  {}",
+            format!("RUN pip install --no-index --find-links /tmp/wheels -r {}", requirements).cyan()
+        );
+    }
+    if conda_env.is_some() {
+        println!("  {}", "COPY vendor/conda-env.tar.gz /tmp/conda-env.tar.gz".cyan());
+        println!(
+            "  {}",
+            "RUN mkdir -p /opt/env && tar -xzf /tmp/conda-env.tar.gz -C /opt/env && /opt/env/bin/conda-unpack".cyan()
+        );
+        println!("  {}", "ENV PATH=/opt/env/bin:$PATH".cyan());
+    }
+
+    Ok(())
+}
+
+fn find_file(dir: &Path, candidates: &[&str]) -> Option<String> {
+    candidates
+        .iter()
+        .find(|name| dir.join(name).is_file())
+        .map(|name| name.to_string())
+}
+
+/// Empty a vendor subdirectory so stale artifacts from an earlier bundle don't linger
+fn reset_dir(dir: &Path) -> Result<()> {
+    if dir.exists() {
+        std::fs::remove_dir_all(dir).with_context(|| format!("Failed to clear {}", dir.display()))?;
+    }
+    std::fs::create_dir_all(dir)?;
+    Ok(())
+}
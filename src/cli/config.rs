@@ -0,0 +1,136 @@
+use anyhow::Result;
+use clap::Subcommand;
+use colored::Colorize;
+
+use crate::config::settings::Settings;
+use crate::error::GeoEngineError;
+use crate::utils::paths::{self, DEFAULT_PROFILE};
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Manage settings profiles (separate settings for work, personal or air-gapped setups)
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    /// Create a profile, starting from a copy of the active one
+    Create {
+        /// Profile name (letters, digits, '-' and '_')
+        name: String,
+
+        /// Start from empty settings instead (no registered workers)
+        #[arg(long)]
+        empty: bool,
+
+        /// Make the new profile the active one
+        #[arg(long)]
+        switch: bool,
+    },
+
+    /// List profiles, marking the active one
+    List,
+
+    /// Make a profile the active one for later commands ("default" for settings.yaml)
+    Switch {
+        /// Profile name
+        name: String,
+    },
+}
+
+impl ConfigCommands {
+    pub async fn execute(self) -> Result<()> {
+        match self {
+            Self::Profile { command } => match command {
+                ProfileCommands::Create { name, empty, switch } => create_profile(&name, empty, switch),
+                ProfileCommands::List => list_profiles(),
+                ProfileCommands::Switch { name } => switch_profile(&name),
+            },
+        }
+    }
+}
+
+fn create_profile(name: &str, empty: bool, switch: bool) -> Result<()> {
+    let path = paths::get_profile_file(name)?;
+    if name == DEFAULT_PROFILE || path.exists() {
+        return Err(GeoEngineError::ConfigInvalid(format!("Profile '{}' already exists", name)).into());
+    }
+
+    let settings = if empty { Settings::default() } else { Settings::load()? };
+    settings.save_to(&path)?;
+    println!(
+        "{} Created profile {} ({})",
+        "✓".green().bold(),
+        name.cyan(),
+        path.display()
+    );
+    if !empty {
+        println!("  {} Copied from the {} profile", "•".cyan(), paths::active_profile()?.cyan());
+    }
+
+    if switch {
+        switch_profile(name)?;
+    } else {
+        println!(
+            "\nUse it with {} or {}",
+            format!("--profile {}", name).cyan(),
+            format!("geoengine config profile switch {}", name).cyan()
+        );
+    }
+    Ok(())
+}
+
+fn list_profiles() -> Result<()> {
+    let active = paths::active_profile()?;
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    let mut named: Vec<String> = std::fs::read_dir(paths::get_profiles_dir()?)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            (path.extension().and_then(|e| e.to_str()) == Some("yaml"))
+                .then(|| path.file_stem()?.to_str().map(String::from))
+                .flatten()
+        })
+        .collect();
+    named.sort();
+    names.extend(named);
+
+    for name in &names {
+        let path = paths::get_profile_file(name)?;
+        if *name == active {
+            println!("{} {}  {}", "*".green().bold(), name.green().bold(), path.display().to_string().dimmed());
+        } else {
+            println!("  {}  {}", name, path.display().to_string().dimmed());
+        }
+    }
+    if !names.contains(&active) {
+        println!(
+            "{} The active profile {} does not exist",
+            "!".yellow().bold(),
+            active.cyan()
+        );
+    }
+    Ok(())
+}
+
+fn switch_profile(name: &str) -> Result<()> {
+    if !paths::get_profile_file(name)?.exists() && name != DEFAULT_PROFILE {
+        return Err(GeoEngineError::ConfigInvalid(format!(
+            "Profile '{}' does not exist. Create it with 'geoengine config profile create {}'.",
+            name, name
+        ))
+        .into());
+    }
+    paths::switch_profile(name)?;
+    println!("{} Switched to profile {}", "✓".green().bold(), name.cyan());
+    if std::env::var("GEOENGINE_PROFILE").is_ok_and(|p| !p.is_empty()) {
+        println!(
+            "  {} GEOENGINE_PROFILE is set and takes precedence in this shell",
+            "!".yellow().bold()
+        );
+    }
+    Ok(())
+}
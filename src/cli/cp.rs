@@ -0,0 +1,258 @@
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use colored::Colorize;
+use futures::StreamExt;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::io::{BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::docker::client::DockerClient;
+use crate::docker::usage::format_bytes;
+use crate::utils::paths;
+
+/// One side of a copy: a host path or `<container>:<path>`
+enum Location {
+    Host(PathBuf),
+    Container { name: String, path: String },
+}
+
+impl Location {
+    fn parse(arg: &str) -> Self {
+        match arg.split_once(':') {
+            Some((name, path)) if is_container_name(name) => {
+                Location::Container {
+                    name: name.to_string(),
+                    path: path.to_string(),
+                }
+            }
+            _ => Location::Host(PathBuf::from(arg)),
+        }
+    }
+}
+
+/// `./a:b` and (on Windows) `C:\...` are host paths, not container references
+fn is_container_name(name: &str) -> bool {
+    if cfg!(windows) && name.len() == 1 {
+        return false;
+    }
+    !name.is_empty() && !name.contains(['/', '\\'])
+}
+
+/// Copy files or directories between a container and the host, like `docker cp`.
+/// A destination that is an existing directory receives the source under its own
+/// name; any other destination is the new name of the source.
+pub async fn cp(source: &str, dest: &str) -> Result<()> {
+    match (Location::parse(source), Location::parse(dest)) {
+        (Location::Container { name, path }, Location::Host(dest)) => {
+            let client = DockerClient::new().await?;
+            ensure_container(&client, &name).await?;
+            copy_out(&client, &name, &path, &dest).await
+        }
+        (Location::Host(source), Location::Container { name, path }) => {
+            let client = DockerClient::new().await?;
+            ensure_container(&client, &name).await?;
+            copy_in(&client, &source, &name, &path).await
+        }
+        (Location::Container { .. }, Location::Container { .. }) => {
+            anyhow::bail!("Copying between containers is not supported; copy through the host instead")
+        }
+        (Location::Host(_), Location::Host(_)) => {
+            anyhow::bail!("One of source and destination must be a container path (<container>:<path>)")
+        }
+    }
+}
+
+/// Stopped containers are fine: like `docker cp`, the archive API works on them
+async fn ensure_container(client: &DockerClient, name: &str) -> Result<()> {
+    if client.container_running(name).await?.is_none() {
+        anyhow::bail!("Container not found: {}", name);
+    }
+    Ok(())
+}
+
+async fn copy_out(client: &DockerClient, container: &str, path: &str, dest: &Path) -> Result<()> {
+    if !path.starts_with('/') {
+        anyhow::bail!("Container paths must be absolute: {}", path);
+    }
+    // Copy into an existing directory, or to a new name next to it
+    let (target_dir, rename) = if dest.is_dir() {
+        (dest.to_path_buf(), None)
+    } else {
+        let parent = dest.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        if !parent.is_dir() {
+            anyhow::bail!("Destination directory does not exist: {}", parent.display());
+        }
+        (parent.to_path_buf(), dest.file_name().map(|n| n.to_os_string()))
+    };
+
+    let archive = paths::get_temp_dir()?.join(format!(
+        "cp-{}-{}.tar",
+        std::process::id(),
+        chrono::Utc::now().timestamp_millis()
+    ));
+    let pb = transfer_bar(&format!("Copying {}:{}", container, path))?;
+    let downloaded = client.download_path(container, path, &archive, &pb).await;
+    pb.finish_and_clear();
+    let extracted = downloaded.and_then(|_| extract(&archive, &target_dir, rename.as_deref()));
+    std::fs::remove_file(&archive).ok();
+    let (files, bytes) = extracted?;
+
+    println!(
+        "{} Copied {}:{} to {} ({} file(s), {})",
+        "✓".green().bold(),
+        container.cyan(),
+        path,
+        dest.display(),
+        files,
+        format_bytes(bytes)
+    );
+    Ok(())
+}
+
+/// Unpack an archive from the Docker API into `dir`, renaming its top-level entry
+/// if asked. Returns the number of files and their total size.
+///
+/// Entries go through `unpack_in`, which refuses any whose path leaves the
+/// directory, including through a symlink the archive created earlier. A rename
+/// unpacks into a staging directory next to the destination first, so links
+/// inside the archive keep pointing at the names they were made with.
+fn extract(archive: &Path, dir: &Path, rename: Option<&std::ffi::OsStr>) -> Result<(usize, u64)> {
+    let staging = match rename {
+        Some(_) => {
+            let staging = dir.join(format!(".geoengine-cp-{}", std::process::id()));
+            std::fs::create_dir(&staging).with_context(|| format!("Failed to create {}", staging.display()))?;
+            staging
+        }
+        None => dir.to_path_buf(),
+    };
+    let unpacked = unpack(archive, &staging);
+    let result = match (unpacked, rename) {
+        (Ok((top, counts)), Some(name)) => {
+            let from = staging.join(&top);
+            let to = dir.join(name);
+            std::fs::rename(&from, &to)
+                .with_context(|| format!("Failed to move {} to {}", from.display(), to.display()))
+                .map(|_| counts)
+        }
+        (unpacked, _) => unpacked.map(|(_, counts)| counts),
+    };
+    if rename.is_some() {
+        std::fs::remove_dir_all(&staging).ok();
+    }
+    result
+}
+
+/// Unpack every entry of `archive` inside `dir`; returns the top-level entry
+/// name with the number of files and their total size
+fn unpack(archive: &Path, dir: &Path) -> Result<(PathBuf, (usize, u64))> {
+    let file = std::fs::File::open(archive).with_context(|| format!("Failed to read {}", archive.display()))?;
+    let mut archive = tar::Archive::new(file);
+    let mut top: Option<PathBuf> = None;
+    let (mut files, mut bytes) = (0, 0);
+    for entry in archive.entries().context("Failed to read the copied archive")? {
+        let mut entry = entry.context("Failed to read the copied archive")?;
+        let path = entry.path()?.into_owned();
+        if top.is_none() {
+            top = path.components().find_map(|c| match c {
+                Component::Normal(name) => Some(PathBuf::from(name)),
+                _ => None,
+            });
+        }
+        if entry.header().entry_type().is_file() {
+            files += 1;
+            bytes += entry.size();
+        }
+        let inside = entry
+            .unpack_in(dir)
+            .with_context(|| format!("Refusing to extract {} outside the destination", path.display()))?;
+        if !inside {
+            anyhow::bail!("Refusing to extract {} outside the destination", path.display());
+        }
+    }
+    let top = top.context("The copied archive is empty")?;
+    Ok((top, (files, bytes)))
+}
+
+async fn copy_in(client: &DockerClient, source: &Path, container: &str, path: &str) -> Result<()> {
+    if !source.exists() {
+        anyhow::bail!("No such file or directory: {}", source.display());
+    }
+    if !path.starts_with('/') {
+        anyhow::bail!("Container paths must be absolute: {}", path);
+    }
+    // Copy into an existing directory, or to a new name next to it
+    let (dir, name) = if path.ends_with('/') || client.is_container_dir(container, path).await {
+        let name = source
+            .canonicalize()?
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .context("Cannot copy the filesystem root")?;
+        (path.to_string(), name)
+    } else {
+        let (parent, name) = path.rsplit_once('/').unwrap_or(("", path));
+        (if parent.is_empty() { "/" } else { parent }.to_string(), name.to_string())
+    };
+
+    // Pack on a blocking thread and stream the archive to Docker as it is written
+    let pb = transfer_bar(&format!("Copying {} to {}:{}", source.display(), container, path))?;
+    let (tx, rx) = tokio::sync::mpsc::channel::<Bytes>(8);
+    let packer = {
+        let source = source.to_path_buf();
+        tokio::task::spawn_blocking(move || pack(&source, &name, ChannelWriter(tx)))
+    };
+    let sent = pb.clone();
+    let body = futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) })
+        .inspect(move |chunk| sent.inc(chunk.len() as u64));
+    let uploaded = client.upload_archive(container, &dir, body).await;
+    let packed = packer.await?;
+    pb.finish_and_clear();
+    let size = pb.position();
+    uploaded?;
+    packed.with_context(|| format!("Failed to read {}", source.display()))?;
+
+    println!(
+        "{} Copied {} to {}:{} ({})",
+        "✓".green().bold(),
+        source.display(),
+        container.cyan(),
+        path,
+        format_bytes(size)
+    );
+    Ok(())
+}
+
+/// Write `source` as a tar archive whose top-level entry is `name`
+fn pack(source: &Path, name: &str, out: impl Write) -> std::io::Result<()> {
+    let mut builder = tar::Builder::new(BufWriter::with_capacity(1024 * 1024, out));
+    builder.follow_symlinks(false);
+    if source.is_dir() {
+        builder.append_dir_all(name, source)?;
+    } else {
+        builder.append_path_with_name(source, name)?;
+    }
+    builder.into_inner()?.flush()
+}
+
+/// Hands what the tar builder writes to the upload stream
+struct ChannelWriter(tokio::sync::mpsc::Sender<Bytes>);
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0
+            .blocking_send(Bytes::copy_from_slice(buf))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "the upload stopped"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn transfer_bar(message: &str) -> Result<ProgressBar> {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(ProgressStyle::default_spinner().template("{spinner:.green} {msg} {bytes} ({bytes_per_sec})")?);
+    pb.set_message(message.to_string());
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    Ok(pb)
+}
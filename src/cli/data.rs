@@ -0,0 +1,131 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
+
+use crate::cli::worker::resolve_worker;
+use crate::config::state::compute_file_hash;
+use crate::config::worker::{self as worker_config, WorkerConfig};
+use crate::utils::{checksums, datasets};
+
+#[derive(Subcommand)]
+pub enum DataCommands {
+    /// Print the SHA-256 of a file, or write a manifest of every file in a directory
+    /// for `--checksum KEY=manifest:FILE`
+    Hash {
+        /// File or directory to hash
+        path: PathBuf,
+
+        /// Write the manifest to this file instead of stdout
+        #[arg(short, long, value_name = "FILE")]
+        output: Option<PathBuf>,
+    },
+
+    /// Download the datasets in a worker's `data` section into ~/.geoengine/data,
+    /// skipping those already fetched
+    Fetch {
+        /// Worker name (defaults to current directory's worker)
+        worker: Option<String>,
+
+        /// Only fetch these datasets
+        #[arg(long, value_name = "NAME")]
+        only: Vec<String>,
+
+        /// Download again even if the dataset is up to date
+        #[arg(long)]
+        force: bool,
+    },
+}
+
+impl DataCommands {
+    pub async fn execute(self) -> Result<()> {
+        match self {
+            Self::Hash { path, output } => hash(&path, output.as_deref()),
+            Self::Fetch { worker, only, force } => fetch(worker.as_deref(), &only, force).await,
+        }
+    }
+}
+
+fn hash(path: &Path, output: Option<&Path>) -> Result<()> {
+    if path.is_file() {
+        let hex = compute_file_hash(path)?;
+        let manifest = checksums::format_manifest(&[(path.display().to_string(), hex.clone())]);
+        return match output {
+            Some(output) => write_manifest(output, &manifest, 1),
+            None => {
+                println!("sha256:{}  {}", hex, path.display());
+                Ok(())
+            }
+        };
+    }
+    if !path.is_dir() {
+        anyhow::bail!("No such file or directory: {}", path.display());
+    }
+
+    let pb = ProgressBar::new(checksums::tree_size(path));
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} Hashing [{bar:30.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?
+            .progress_chars("=> "),
+    );
+    let entries = checksums::hash_tree(path, output, &mut |size| pb.inc(size));
+    pb.finish_and_clear();
+    let entries = entries?;
+
+    let manifest = checksums::format_manifest(&entries);
+    match output {
+        Some(output) => write_manifest(output, &manifest, entries.len()),
+        None => {
+            print!("{}", manifest);
+            Ok(())
+        }
+    }
+}
+
+async fn fetch(worker: Option<&str>, only: &[String], force: bool) -> Result<()> {
+    let (name, path) = resolve_worker(worker)?;
+    let config = WorkerConfig::load(&worker_config::config_file(&path))?;
+    let data = config.data.unwrap_or_default();
+    if data.is_empty() {
+        println!("{}", format!("Worker '{}' has no data section", name).yellow());
+        return Ok(());
+    }
+    for dataset in only {
+        if !data.contains_key(dataset) {
+            anyhow::bail!("Unknown dataset '{}'. Defined datasets: {}", dataset, data.keys().cloned().collect::<Vec<_>>().join(", "));
+        }
+    }
+
+    let mut failed = 0;
+    for (dataset_name, dataset) in data.iter().filter(|(n, _)| only.is_empty() || only.contains(n)) {
+        let dir = datasets::dataset_dir(&name, dataset_name)?;
+        if !force && datasets::is_current(&dir, dataset) {
+            println!("{} {} is up to date", "✓".green().bold(), dataset_name.cyan());
+            continue;
+        }
+        println!("{} Fetching {} from {}...", "=>".blue().bold(), dataset_name.cyan(), dataset.url);
+        match datasets::fetch(&name, dataset_name, dataset, force).await {
+            Ok(_) => println!("{} {} -> {}", "✓".green().bold(), dataset_name.cyan(), dir.display()),
+            Err(e) => {
+                eprintln!("{} {}: {:#}", "✗".red().bold(), dataset_name, e);
+                failed += 1;
+            }
+        }
+    }
+    if failed > 0 {
+        anyhow::bail!("{} dataset(s) failed to download; run the fetch again to resume", failed);
+    }
+    Ok(())
+}
+
+fn write_manifest(output: &Path, manifest: &str, files: usize) -> Result<()> {
+    std::fs::write(output, manifest).with_context(|| format!("Failed to write {}", output.display()))?;
+    eprintln!(
+        "{} Wrote checksums of {} file(s) to {}",
+        "✓".green().bold(),
+        files,
+        output.display()
+    );
+    Ok(())
+}
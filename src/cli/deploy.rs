@@ -1,9 +1,25 @@
 use anyhow::{Context, Result};
-use clap::Subcommand;
+use bollard::auth::DockerCredentials;
+use clap::{Args, Subcommand};
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
-
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cli::aws_batch::AwsBatchCommands;
+use crate::cli::image::PullOutput;
+use crate::cli::k8s::{self, K8sArgs};
+use crate::cli::vertex::VertexCommands;
+use crate::cli::worker::{fill_missing_inputs, shell_escape};
+use crate::config::settings::{Settings, SigningSettings};
+use crate::config::worker::CommandConfig;
 use crate::docker::client::DockerClient;
+use crate::docker::registry::{self, PushRetry};
+use crate::error::GeoEngineError;
+use crate::utils::artifact_registry::ArtifactRegistry;
+use crate::utils::ghcr::{self, Ghcr};
+use crate::utils::sbom::{self, SbomFormat};
+use crate::utils::{cosign, crane, paths};
 
 #[derive(Subcommand)]
 pub enum DeployCommands {
@@ -14,60 +30,233 @@ pub enum DeployCommands {
         project: Option<String>,
     },
 
-    /// Push an image to GCP Artifact Registry
+    /// Push an image to GCP Artifact Registry or GitHub Container Registry
     Push {
         /// Local image name/tag
         image: String,
 
-        /// GCP project ID
-        #[arg(long, env = "GCP_PROJECT")]
-        project: String,
-
-        /// GCP region (e.g., us-central1)
-        #[arg(long, default_value = "us-central1")]
-        region: String,
+        #[command(flatten)]
+        registry: RegistryArgs,
 
-        /// Repository name in Artifact Registry
-        #[arg(long, default_value = "geoengine")]
-        repository: String,
-
-        /// Remote image tag (defaults to local tag)
+        /// Remote image tag (defaults to local tag); repeat to push several tags at once
         #[arg(long)]
-        tag: Option<String>,
+        tag: Vec<String>,
+
+        /// Retry a failed or stalled push this many times; layers already uploaded are skipped
+        #[arg(long, default_value_t = 3)]
+        retries: u32,
+
+        /// Retry when Docker reports no progress for this many seconds
+        #[arg(long, default_value_t = 300, value_name = "SECS")]
+        stall_timeout: u64,
+
+        /// Generate an SBOM of the image with syft and attach it to the pushed image with oras;
+        /// pick the format with --with-sbom=FORMAT
+        #[arg(
+            long,
+            value_enum,
+            value_name = "FORMAT",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "spdx"
+        )]
+        with_sbom: Option<SbomFormat>,
     },
 
-    /// Pull an image from GCP Artifact Registry
+    /// Pull an image from GCP Artifact Registry or GitHub Container Registry
     Pull {
         /// Remote image name
         image: String,
 
-        /// GCP project ID
-        #[arg(long, env = "GCP_PROJECT")]
-        project: String,
+        #[command(flatten)]
+        registry: RegistryArgs,
 
-        /// GCP region
-        #[arg(long, default_value = "us-central1")]
-        region: String,
+        /// Verify the image signature before pulling (always on if `signing.enforce` is set)
+        #[arg(long)]
+        verify: bool,
 
-        /// Repository name
-        #[arg(long, default_value = "geoengine")]
-        repository: String,
+        /// Progress output: per-layer bars, or one JSON event per line for wrapping UIs
+        #[arg(long, value_enum, default_value_t = PullOutput::Text)]
+        output: PullOutput,
+    },
+
+    /// Sign an image in GCP Artifact Registry or GitHub Container Registry (or an exported tar file) with cosign
+    Sign {
+        /// Remote image name (e.g. my-worker:1.0.0)
+        #[arg(required_unless_present = "tarfile")]
+        image: Option<String>,
+
+        /// Sign an exported image tar instead, writing <tarfile>.bundle next to it
+        #[arg(long, conflicts_with = "image")]
+        tarfile: Option<PathBuf>,
+
+        /// Cosign private key (keyless OIDC signing is used if omitted)
+        #[arg(long)]
+        key: Option<PathBuf>,
+
+        #[command(flatten)]
+        registry: RegistryArgs,
+    },
+
+    /// Verify the cosign signature of an image in GCP Artifact Registry or GitHub Container Registry
+    /// (or an exported tar file)
+    Verify {
+        /// Remote image name (e.g. my-worker:1.0.0)
+        #[arg(required_unless_present = "tarfile")]
+        image: Option<String>,
+
+        /// Verify an exported image tar against its <tarfile>.bundle instead
+        #[arg(long, conflicts_with = "image")]
+        tarfile: Option<PathBuf>,
+
+        /// Cosign public key (overrides `signing.public_key` in settings)
+        #[arg(long)]
+        key: Option<PathBuf>,
+
+        #[command(flatten)]
+        registry: RegistryArgs,
+    },
+
+    /// Copy an image by digest from one environment's registry to another's
+    Promote {
+        /// Image name in the source registry (e.g. my-worker:1.0.0)
+        image: String,
+
+        /// Source environment (from `environments` in settings)
+        #[arg(long)]
+        from: String,
+
+        /// Target environment (from `environments` in settings)
+        #[arg(long)]
+        to: String,
+
+        /// Verify the source image signature before promoting (always on if required by the target)
+        #[arg(long)]
+        verify: bool,
+
+        /// Sign the promoted image, recording where and when it was promoted as signature annotations
+        #[arg(long)]
+        annotate: bool,
+
+        /// Cosign private key for --annotate (keyless OIDC signing is used if omitted)
+        #[arg(long, requires = "annotate")]
+        key: Option<PathBuf>,
     },
 
-    /// List images in GCP Artifact Registry
+    /// Register, submit and monitor worker jobs on AWS Batch (images in Amazon ECR)
+    AwsBatch {
+        #[command(subcommand)]
+        command: AwsBatchCommands,
+    },
+
+    /// Generate a Kubernetes Job for a worker run, optionally submitting it and streaming its logs
+    K8s(Box<K8sArgs>),
+
+    /// Submit and monitor worker runs as Vertex AI custom jobs (images in Artifact Registry)
+    Vertex {
+        #[command(subcommand)]
+        command: VertexCommands,
+    },
+
+    /// List images in GCP Artifact Registry or GitHub Container Registry
     List {
-        /// GCP project ID
-        #[arg(long, env = "GCP_PROJECT")]
-        project: String,
+        #[command(flatten)]
+        registry: RegistryArgs,
+    },
 
-        /// GCP region
-        #[arg(long, default_value = "us-central1")]
-        region: String,
+    /// Delete a tag from GCP Artifact Registry (the image stays available by digest)
+    Delete {
+        /// Remote image tag (e.g. my-worker:1.0.0)
+        image: String,
+
+        #[command(flatten)]
+        registry: RegistryArgs,
+    },
+}
+
+/// Container registry to deploy to
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum Provider {
+    /// GCP Artifact Registry
+    Gcp,
+    /// GitHub Container Registry (ghcr.io)
+    Ghcr,
+}
+
+/// Registry selection shared by push, pull, sign, verify and list
+#[derive(Args)]
+pub struct RegistryArgs {
+    /// Registry provider
+    #[arg(long, value_enum, default_value_t = Provider::Gcp)]
+    provider: Provider,
 
-        /// Repository name
-        #[arg(long, default_value = "geoengine")]
+    /// GCP project ID
+    #[arg(long, env = "GCP_PROJECT")]
+    project: Option<String>,
+
+    /// GCP region (e.g., us-central1)
+    #[arg(long, default_value = "us-central1")]
+    region: String,
+
+    /// Repository name in Artifact Registry
+    #[arg(long, default_value = "geoengine")]
+    repository: String,
+
+    /// GHCR owner (GitHub user or organization; defaults to `ghcr.owner` in settings)
+    #[arg(long)]
+    owner: Option<String>,
+}
+
+/// A resolved registry: where images live and how to authenticate
+enum Registry {
+    Gcp {
+        prefix: String,
+        project: String,
+        region: String,
         repository: String,
     },
+    Ghcr(Ghcr),
+}
+
+impl RegistryArgs {
+    fn resolve(&self) -> Result<Registry> {
+        match self.provider {
+            Provider::Gcp => Ok(Registry::Gcp {
+                prefix: remote_image_ref("", self.project.as_deref(), &self.region, &self.repository)?
+                    .trim_end_matches('/')
+                    .to_string(),
+                project: self.project.clone().unwrap_or_default(),
+                region: self.region.clone(),
+                repository: self.repository.clone(),
+            }),
+            Provider::Ghcr => Ok(Registry::Ghcr(Ghcr::resolve(self.owner.as_deref(), &Settings::load()?)?)),
+        }
+    }
+}
+
+impl Registry {
+    /// Full remote reference for an image name
+    fn image_ref(&self, image: &str) -> String {
+        match self {
+            Registry::Gcp { prefix, .. } => format!("{}/{}", prefix, image),
+            Registry::Ghcr(ghcr) => ghcr.image_ref(image),
+        }
+    }
+
+    /// Credentials passed to the Docker daemon (GCP relies on `deploy auth`)
+    fn credentials(&self) -> Option<DockerCredentials> {
+        match self {
+            Registry::Gcp { .. } => None,
+            Registry::Ghcr(ghcr) => Some(ghcr.credentials()),
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Registry::Gcp { .. } => "Artifact Registry",
+            Registry::Ghcr(_) => "GitHub Container Registry",
+        }
+    }
 }
 
 impl DeployCommands {
@@ -76,22 +265,49 @@ impl DeployCommands {
             Self::Auth { project } => configure_auth(project.as_deref()).await,
             Self::Push {
                 image,
-                project,
-                region,
-                repository,
+                registry,
                 tag,
-            } => push_image(&image, &project, &region, &repository, tag.as_deref()).await,
+                retries,
+                stall_timeout,
+                with_sbom,
+            } => {
+                let retry = PushRetry {
+                    retries,
+                    stall_timeout: Duration::from_secs(stall_timeout),
+                };
+                push_image(&image, &registry, &tag, retry, with_sbom).await
+            }
             Self::Pull {
                 image,
-                project,
-                region,
-                repository,
-            } => pull_image(&image, &project, &region, &repository).await,
-            Self::List {
-                project,
-                region,
-                repository,
-            } => list_images(&project, &region, &repository).await,
+                registry,
+                verify,
+                output,
+            } => pull_image(&image, &registry, verify, output).await,
+            Self::Sign {
+                image,
+                tarfile,
+                key,
+                registry,
+            } => sign(image.as_deref(), tarfile.as_ref(), key.as_ref(), &registry),
+            Self::Verify {
+                image,
+                tarfile,
+                key,
+                registry,
+            } => verify(image.as_deref(), tarfile.as_ref(), key, &registry),
+            Self::Promote {
+                image,
+                from,
+                to,
+                verify,
+                annotate,
+                key,
+            } => promote(&image, &from, &to, verify, annotate, key.as_ref()),
+            Self::AwsBatch { command } => command.execute().await,
+            Self::K8s(args) => k8s::export_k8s(*args).await,
+            Self::Vertex { command } => command.execute().await,
+            Self::List { registry } => list_images(&registry).await,
+            Self::Delete { image, registry } => delete_tag(&image, &registry).await,
         }
     }
 }
@@ -150,48 +366,165 @@ async fn configure_auth(project: Option<&str>) -> Result<()> {
 
 async fn push_image(
     image: &str,
-    project: &str,
-    region: &str,
-    repository: &str,
-    tag: Option<&str>,
+    registry: &RegistryArgs,
+    tags: &[String],
+    retry: PushRetry,
+    sbom_format: Option<SbomFormat>,
 ) -> Result<()> {
+    let registry = registry.resolve()?;
     let client = DockerClient::new().await?;
 
-    // Build the full GCP Artifact Registry path
-    let remote_tag = tag.unwrap_or_else(|| {
-        image.split(':').last().unwrap_or("latest")
-    });
-    let image_name = image.split(':').next().unwrap_or(image);
-    let remote_image = format!(
-        "{}-docker.pkg.dev/{}/{}/{}:{}",
-        region, project, repository, image_name, remote_tag
-    );
+    // Build the full remote paths, one per tag
+    let (image_name, local_tag) = split_tag(image);
+    let remote_tags: Vec<&str> = if tags.is_empty() {
+        vec![local_tag.unwrap_or("latest")]
+    } else {
+        tags.iter().map(String::as_str).collect()
+    };
+    let remote_name = match registry {
+        // GHCR packages live directly under the owner, so drop any local namespace
+        Registry::Ghcr(_) => image_name.rsplit('/').next().unwrap_or(image_name),
+        Registry::Gcp { .. } => image_name,
+    };
+    let remote_images: Vec<String> = remote_tags
+        .iter()
+        .map(|tag| registry.image_ref(&format!("{}:{}", remote_name, tag)))
+        .collect();
 
-    println!(
-        "{} Pushing {} to {}...",
-        "=>".blue().bold(),
-        image.cyan(),
-        remote_image.cyan()
-    );
+    let credentials = registry.credentials();
+    for remote_image in &remote_images {
+        println!(
+            "{} Pushing {} to {}...",
+            "=>".blue().bold(),
+            image.cyan(),
+            remote_image.cyan()
+        );
+
+        // Tag the image
+        client.tag_image(image, remote_image).await?;
+
+        // Push; layers uploaded for an earlier tag are skipped by the registry
+        registry::push(&client, remote_image, credentials.clone(), retry).await?;
+        println!(
+            "{} Successfully pushed: {}",
+            "✓".green().bold(),
+            remote_image.cyan()
+        );
+    }
 
-    // Tag the image
-    client.tag_image(image, &remote_image).await?;
+    // The tags share one manifest, so one referrer covers them all
+    if let (Some(format), Some(remote_image)) = (sbom_format, remote_images.first()) {
+        println!("{} Generating SBOM of {}...", "=>".blue().bold(), image.cyan());
+        let path = paths::get_temp_dir()?.join(format!("sbom-{}.{}", std::process::id(), format.extension()));
+        let attached = sbom::generate(image, format, Some(&path))
+            .and_then(|_| sbom::attach(remote_image, &path, format, credentials.as_ref()));
+        std::fs::remove_file(&path).ok();
+        attached?;
+        println!("{} Attached SBOM to {}", "✓".green().bold(), remote_image.cyan());
+    }
 
-    // Push
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
-    );
-    pb.set_message("Pushing to Artifact Registry...");
-    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    Ok(())
+}
+
+/// Shell command running the worker's script with `--key value` inputs on a remote
+/// runner. Unset required inputs get their defaults, as in a local run without a
+/// terminal. Warns about file and folder inputs that are local paths, with `hint`
+/// saying where the data should live instead.
+pub fn remote_command(cmd_config: &CommandConfig, inputs: &[String], hint: &str) -> Result<String> {
+    let mut given: HashMap<String, String> = HashMap::new();
+    for arg in inputs {
+        let (key, value) = arg.split_once('=').ok_or_else(|| {
+            GeoEngineError::InputInvalid(format!("Invalid input format: '{}'. Expected KEY=VALUE", arg))
+        })?;
+        given.insert(key.to_string(), value.to_string());
+    }
+    fill_missing_inputs(cmd_config.inputs.as_deref().unwrap_or_default(), &mut given)?;
+    let values: BTreeMap<String, String> = given.into_iter().collect();
+
+    let path_inputs: Vec<&str> = cmd_config
+        .inputs
+        .iter()
+        .flatten()
+        .filter(|p| p.param_type == "file" || p.param_type == "folder")
+        .map(|p| p.name.as_str())
+        .collect();
+    for (key, value) in &values {
+        if path_inputs.contains(&key.as_str()) && !value.contains("://") && Path::new(value).exists() {
+            eprintln!(
+                "{} Input '{}' is a local path; the job can't read it. {}",
+                "!".yellow().bold(),
+                key,
+                hint
+            );
+        }
+    }
+
+    let mut command = format!("{} {}", cmd_config.program, cmd_config.script);
+    for (key, value) in &values {
+        command.push_str(&format!(" --{} {}", key, shell_escape(value)));
+    }
+    Ok(command)
+}
+
+/// Split `name[:tag]`, ignoring the port of a registry host (`host:5000/name`)
+fn split_tag(image: &str) -> (&str, Option<&str>) {
+    match image.rsplit_once(':') {
+        Some((name, tag)) if !tag.contains('/') => (name, Some(tag)),
+        _ => (image, None),
+    }
+}
+
+async fn pull_image(image: &str, registry: &RegistryArgs, verify: bool, output: PullOutput) -> Result<()> {
+    let registry = registry.resolve()?;
+    let client = DockerClient::new().await?;
+    let text = output == PullOutput::Text;
+
+    let remote_image = registry.image_ref(image);
+
+    // Pin the tag to a digest once, so the image verified is the one pulled even
+    // if the tag moves in between. A digest reference is pinned already.
+    let (pinned, digest) = match remote_image.rsplit_once('@') {
+        Some((_, digest)) => (remote_image.clone(), digest.to_string()),
+        None => {
+            let digest = client.remote_digest_with(&remote_image, registry.credentials()).await?;
+            (format!("{}@{}", repository_of(&remote_image), digest), digest)
+        }
+    };
+
+    let signing = Settings::load()?.signing.unwrap_or_default();
+    if verify || signing.enforce {
+        // stdout carries only events in ndjson mode
+        let note = |message: String| if text { println!("{}", message) } else { eprintln!("{}", message) };
+        note(format!("{} Verifying signature of {}...", "=>".blue().bold(), pinned.cyan()));
+        cosign::verify_image(&pinned, &signing)?;
+        note(format!("{} Signature verified", "✓".green().bold()));
+    }
 
-    client.push_image(&remote_image).await?;
+    if text {
+        println!(
+            "{} Pulling {} from {}...",
+            "=>".blue().bold(),
+            remote_image.cyan(),
+            registry.name()
+        );
+    }
 
-    pb.finish_and_clear();
+    let mut progress = output.progress(&remote_image);
+    let pulled = client.pull_image(&pinned, registry.credentials(), &mut progress).await;
+    progress.finish();
+    pulled?;
+    // A pull by digest leaves the image untagged; digest references can't be tagged
+    if pinned != remote_image && repository_of(&remote_image) != remote_image {
+        client.tag_image(&pinned, &remote_image).await?;
+    }
+
+    if !text {
+        let event = serde_json::json!({ "type": "pulled", "image": remote_image, "digest": digest });
+        println!("{}", event);
+        return Ok(());
+    }
     println!(
-        "{} Successfully pushed: {}",
+        "{} Successfully pulled: {}",
         "✓".green().bold(),
         remote_image.cyan()
     );
@@ -199,34 +532,58 @@ async fn push_image(
     Ok(())
 }
 
-async fn pull_image(image: &str, project: &str, region: &str, repository: &str) -> Result<()> {
-    let client = DockerClient::new().await?;
-
-    let remote_image = format!(
-        "{}-docker.pkg.dev/{}/{}/{}",
-        region, project, repository, image
-    );
+fn sign(image: Option<&str>, tarfile: Option<&PathBuf>, key: Option<&PathBuf>, registry: &RegistryArgs) -> Result<()> {
+    if let Some(tarfile) = tarfile {
+        println!(
+            "{} Signing {}...",
+            "=>".blue().bold(),
+            tarfile.display()
+        );
+        let bundle = cosign::sign_blob(tarfile, key.map(|k| k.as_path()))?;
+        println!(
+            "{} Signature bundle written to {}",
+            "✓".green().bold(),
+            bundle.display()
+        );
+        return Ok(());
+    }
 
+    let remote_image = registry.resolve()?.image_ref(image.unwrap_or_default());
     println!(
-        "{} Pulling {}...",
+        "{} Signing {}...",
         "=>".blue().bold(),
         remote_image.cyan()
     );
-
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
+    cosign::sign_image(&remote_image, key.map(|k| k.as_path()), &[])?;
+    println!(
+        "{} Successfully signed: {}",
+        "✓".green().bold(),
+        remote_image.cyan()
     );
-    pb.set_message("Downloading from Artifact Registry...");
-    pb.enable_steady_tick(std::time::Duration::from_millis(100));
 
-    client.pull_image(&remote_image).await?;
+    Ok(())
+}
 
-    pb.finish_and_clear();
+fn verify(image: Option<&str>, tarfile: Option<&PathBuf>, key: Option<PathBuf>, registry: &RegistryArgs) -> Result<()> {
+    let mut signing: SigningSettings = Settings::load()?.signing.unwrap_or_default();
+    if key.is_some() {
+        signing.public_key = key;
+    }
+
+    if let Some(tarfile) = tarfile {
+        cosign::verify_blob(tarfile, &signing)?;
+        println!(
+            "{} Signature verified: {}",
+            "✓".green().bold(),
+            tarfile.display()
+        );
+        return Ok(());
+    }
+
+    let remote_image = registry.resolve()?.image_ref(image.unwrap_or_default());
+    cosign::verify_image(&remote_image, &signing)?;
     println!(
-        "{} Successfully pulled: {}",
+        "{} Signature verified: {}",
         "✓".green().bold(),
         remote_image.cyan()
     );
@@ -234,35 +591,164 @@ async fn pull_image(image: &str, project: &str, region: &str, repository: &str)
     Ok(())
 }
 
-async fn list_images(project: &str, region: &str, repository: &str) -> Result<()> {
+fn promote(
+    image: &str,
+    from: &str,
+    to: &str,
+    verify: bool,
+    annotate: bool,
+    key: Option<&PathBuf>,
+) -> Result<()> {
+    let settings = Settings::load()?;
+    let source_env = settings.get_environment(from)?;
+    let target_env = settings.get_environment(to)?;
+
+    let source = format!("{}/{}", source_env.registry.trim_end_matches('/'), image);
+    let target = format!("{}/{}", target_env.registry.trim_end_matches('/'), image);
+
+    // Pin the source by digest so the exact image that was verified is the one copied
+    let digest = crane::digest(&source)?;
+    let pinned_source = format!("{}@{}", repository_of(&source), digest);
     println!(
-        "{} Listing images in {}-docker.pkg.dev/{}/{}...",
+        "{} Promoting {} ({}) from {} to {}...",
         "=>".blue().bold(),
-        region,
-        project,
-        repository
+        image.cyan(),
+        digest,
+        from.cyan(),
+        to.cyan()
     );
 
-    // Use gcloud to list images
-    let output = std::process::Command::new("gcloud")
-        .args([
-            "artifacts",
-            "docker",
-            "images",
-            "list",
-            &format!("{}-docker.pkg.dev/{}/{}", region, project, repository),
-            "--format=table(package,version,createTime)",
-        ])
-        .output()
-        .context("Failed to run gcloud artifacts command")?;
+    let signing = settings.signing.clone().unwrap_or_default();
+    if verify || signing.enforce || target_env.require_signature {
+        println!("{} Verifying signature of {}...", "=>".blue().bold(), pinned_source.cyan());
+        cosign::verify_image(&pinned_source, &signing)?;
+        println!("{} Signature verified", "✓".green().bold());
+    }
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        anyhow::bail!("Failed to list images: {}", stderr);
+    crane::copy(&pinned_source, &target)?;
+
+    let copied = crane::digest(&target)?;
+    if copied != digest {
+        anyhow::bail!(
+            "Digest mismatch after promotion: expected {}, found {} at {}",
+            digest,
+            copied,
+            target
+        );
+    }
+    println!("{} Promoted: {}", "✓".green().bold(), target.cyan());
+
+    if annotate {
+        let pinned_target = format!("{}@{}", repository_of(&target), digest);
+        let annotations = vec![
+            ("geoengine.promoted-from".to_string(), from.to_string()),
+            ("geoengine.promoted-to".to_string(), to.to_string()),
+            ("geoengine.promoted-at".to_string(), chrono::Utc::now().to_rfc3339()),
+        ];
+        cosign::sign_image(&pinned_target, key.map(|k| k.as_path()), &annotations)?;
+        println!("{} Promotion recorded in signature of {}", "✓".green().bold(), pinned_target.cyan());
     }
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    println!("{}", stdout);
+    Ok(())
+}
+
+/// Strip the tag or digest from an image reference
+fn repository_of(image: &str) -> &str {
+    if let Some((name, _)) = image.rsplit_once('@') {
+        return name;
+    }
+    match image.rsplit_once(':') {
+        // A ':' after the last '/' is a tag, otherwise it is a registry port
+        Some((name, tag)) if !tag.contains('/') => name,
+        _ => image,
+    }
+}
+
+/// Build the full Artifact Registry reference for an image
+fn remote_image_ref(image: &str, project: Option<&str>, region: &str, repository: &str) -> Result<String> {
+    let project = project.context("GCP project is required. Pass --project or set GCP_PROJECT.")?;
+    Ok(format!(
+        "{}-docker.pkg.dev/{}/{}/{}",
+        region, project, repository, image
+    ))
+}
+
+async fn list_images(registry: &RegistryArgs) -> Result<()> {
+    let registry = registry.resolve()?;
+    let prefix = match &registry {
+        Registry::Gcp { prefix, .. } => prefix.clone(),
+        Registry::Ghcr(ghcr) => format!("{}/{}", ghcr::REGISTRY, ghcr.owner),
+    };
+    println!("{} Listing images in {}...", "=>".blue().bold(), prefix);
+
+    // (package, tag, digest, created)
+    let rows: Vec<(String, String, String, String)> = match &registry {
+        Registry::Ghcr(ghcr) => ghcr
+            .list_images()
+            .await?
+            .into_iter()
+            .map(|t| (t.package, t.tag, t.digest, t.created_at))
+            .collect(),
+        Registry::Gcp {
+            project,
+            region,
+            repository,
+            ..
+        } => ArtifactRegistry::connect(project, region, repository)
+            .await?
+            .list_images()
+            .await?
+            .into_iter()
+            .map(|t| (t.package, t.tag.unwrap_or_else(|| "<none>".to_string()), t.digest, t.uploaded_at))
+            .collect(),
+    };
+    if rows.is_empty() {
+        println!("No images found");
+        return Ok(());
+    }
+
+    let width = rows.iter().map(|r| r.0.len()).max().unwrap_or(0).max(7);
+    println!("{:<width$}  {:<20}  {:<19}  CREATED", "PACKAGE", "TAG", "DIGEST", width = width);
+    for (package, tag, digest, created) in rows {
+        let digest = digest.trim_start_matches("sha256:");
+        println!(
+            "{:<width$}  {:<20}  {:<19}  {}",
+            package,
+            tag,
+            format!("sha256:{}", &digest[..digest.len().min(12)]),
+            created,
+            width = width
+        );
+    }
+
+    Ok(())
+}
+
+async fn delete_tag(image: &str, registry: &RegistryArgs) -> Result<()> {
+    let Registry::Gcp {
+        prefix,
+        project,
+        region,
+        repository,
+    } = registry.resolve()?
+    else {
+        anyhow::bail!("Deleting tags is only supported for Artifact Registry");
+    };
+    let (package, tag) = split_tag(image);
+    let tag = tag.context("Specify the tag to delete, e.g. my-worker:1.0.0")?;
+
+    println!(
+        "{} Deleting {}/{}:{}...",
+        "=>".blue().bold(),
+        prefix,
+        package,
+        tag
+    );
+    ArtifactRegistry::connect(&project, &region, &repository)
+        .await?
+        .delete_tag(package, tag)
+        .await?;
+    println!("{} Deleted tag {}", "✓".green().bold(), format!("{}:{}", package, tag).cyan());
 
     Ok(())
 }
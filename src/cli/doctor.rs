@@ -0,0 +1,324 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::cli::gpu;
+use crate::cli::image::format_size;
+use crate::config::settings::Settings;
+use crate::config::{state, worker as worker_config, yaml_store};
+use crate::docker::client::DockerClient;
+use crate::docker::user::UserNamespace;
+use crate::docker::host;
+use crate::error::GeoEngineError;
+use crate::utils::checks::{Check, Report};
+use crate::utils::{artifact_registry, disk, ghcr, paths};
+
+/// Oldest Docker Engine with device requests (`--gpus`) and the API bollard speaks
+const MIN_DOCKER_MAJOR: u32 = 20;
+
+/// Free space below which runs and builds are likely to fail
+const DISK_FAIL_BYTES: u64 = 1024 * 1024 * 1024;
+const DISK_WARN_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+
+/// Token requests to Google Cloud give up after this long
+const AUTH_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Check the whole environment GeoEngine depends on and print pass/warn/fail per check
+pub async fn run_doctor(json: bool) -> Result<()> {
+    let mut checks = Vec::new();
+
+    let (client, info) = docker_checks(&mut checks).await;
+    checks.push(buildkit_check());
+
+    checks.extend(gpu::driver_checks().await);
+    match &info {
+        Some(info) => checks.extend(gpu::docker_gpu_checks(info)),
+        None => checks.push(Check::skip("Docker GPU support", "Docker daemon not reachable")),
+    }
+
+    checks.extend(disk_checks(client.as_ref()).await);
+
+    match Settings::load() {
+        Ok(settings) => {
+            checks.push(Check::pass(
+                "Settings",
+                paths::get_settings_file().map(|p| p.display().to_string()).unwrap_or_default(),
+            ));
+            checks.extend(worker_checks(&settings, client.as_ref()).await);
+            checks.extend(registry_checks(&settings).await);
+        }
+        Err(e) => checks.push(
+            Check::fail("Settings", format!("{:#}", e))
+                .fix("Fix or remove ~/.geoengine/settings.yaml (registered workers will need `geoengine apply` again)"),
+        ),
+    }
+
+    let report = Report::new(checks);
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+    } else {
+        eprintln!("{} Checking the GeoEngine environment", "=>".blue().bold());
+        report.print();
+    }
+
+    if report.failed > 0 {
+        return Err(GeoEngineError::ChecksFailed { failed: report.failed }.into());
+    }
+    Ok(())
+}
+
+/// Connect to the Docker daemon and check its version, pushing the "Docker daemon"
+/// check. Shared with `geoengine gpu doctor`.
+pub async fn docker_checks(checks: &mut Vec<Check>) -> (Option<DockerClient>, Option<bollard::models::SystemInfo>) {
+    let client = match DockerClient::new().await {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::debug!("Docker connection failed: {:#}", e);
+            if let Some(GeoEngineError::ConfigInvalid(_)) = e.downcast_ref::<GeoEngineError>() {
+                checks.push(Check::fail("Docker daemon", format!("{:#}", e)).fix("Fix the docker_context setting"));
+                return (None, None);
+            }
+            let check = Check::fail("Docker daemon", host::connect_hint());
+            checks.push(if host::is_wsl() {
+                check.fix("Start Docker Desktop on Windows").fix(format!(
+                    "In Docker Desktop, open Settings > Resources > WSL Integration and enable integration for '{}'",
+                    gpu::wsl_distro()
+                ))
+            } else {
+                check.fix("Start the Docker daemon (e.g. `sudo systemctl start docker`)")
+            });
+            return (None, None);
+        }
+    };
+    match client.system_info().await {
+        Ok(info) => {
+            checks.push(docker_version_check(&info));
+            (Some(client), Some(info))
+        }
+        Err(e) => {
+            checks.push(Check::fail("Docker daemon", format!("{:#}", e)));
+            (Some(client), None)
+        }
+    }
+}
+
+fn docker_version_check(info: &bollard::models::SystemInfo) -> Check {
+    let version = info.server_version.clone().unwrap_or_default();
+    let mut detail = format!(
+        "Docker {} ({})",
+        version,
+        info.operating_system.as_deref().unwrap_or("unknown OS")
+    );
+    match UserNamespace::from_security_options(info.security_options.as_deref().unwrap_or_default()) {
+        UserNamespace::Host => {}
+        UserNamespace::Rootless => detail.push_str(", rootless"),
+        UserNamespace::Remapped => detail.push_str(", userns-remap"),
+    }
+    let major: Option<u32> = version.split('.').next().and_then(|m| m.parse().ok());
+    match major {
+        Some(major) if major < MIN_DOCKER_MAJOR => Check::warn("Docker daemon", format!("{} is older than 20.10", detail))
+            .fix("Upgrade Docker to 20.10 or later; GPU passthrough needs device requests"),
+        _ => Check::pass("Docker daemon", detail),
+    }
+}
+
+/// BuildKit is only needed for Dockerfiles using its syntax (`RUN --mount`, heredocs);
+/// `geoengine build` itself talks to the daemon's build API
+fn buildkit_check() -> Check {
+    let output = std::process::Command::new("docker").args(["buildx", "version"]).output();
+    match output {
+        Ok(output) if output.status.success() => {
+            Check::pass("BuildKit", String::from_utf8_lossy(&output.stdout).trim().to_string())
+        }
+        Ok(_) => Check::warn("BuildKit", "docker buildx is not installed")
+            .fix("Install the buildx plugin (docker-buildx-plugin) for Dockerfiles that use BuildKit syntax"),
+        Err(_) => Check::warn("BuildKit", "docker CLI not found").fix("Install the Docker CLI"),
+    }
+}
+
+/// Free space where job workspaces, build contexts and (when local) Docker's data root live
+async fn disk_checks(client: Option<&DockerClient>) -> Vec<Check> {
+    let mut locations: Vec<(String, PathBuf)> = Vec::new();
+    if let Ok(dir) = paths::get_config_dir() {
+        locations.push(("Disk (~/.geoengine)".to_string(), dir));
+    }
+    locations.push(("Disk (temp)".to_string(), std::env::temp_dir()));
+    // Docker Desktop keeps its data root inside a VM; only check it when it's on this host
+    if let Some(client) = client {
+        if let Ok(Some(root)) = client.docker_root_dir().await {
+            if root.exists() {
+                locations.push(("Disk (Docker data root)".to_string(), root));
+            }
+        }
+    }
+
+    let mut checks = Vec::new();
+    let mut seen = Vec::new();
+    for (name, path) in locations {
+        let device = disk::device_id(&path);
+        if device.is_some() && seen.contains(&device) {
+            continue;
+        }
+        seen.push(device);
+        let Some(available) = disk::available_space(&path) else {
+            checks.push(Check::skip(&name, "free space can't be determined on this platform"));
+            continue;
+        };
+        let detail = format!("{} free on {}", format_size(available as i64), path.display());
+        checks.push(if available < DISK_FAIL_BYTES {
+            Check::fail(&name, detail).fix(format!("Free up space on {} (e.g. `geoengine service reap`, `docker system prune`)", path.display()))
+        } else if available < DISK_WARN_BYTES {
+            Check::warn(&name, detail).fix(format!("Free up space on {} before large runs", path.display()))
+        } else {
+            Check::pass(&name, detail)
+        });
+    }
+    checks
+}
+
+/// One check per registered worker (directory, config, apply and build state),
+/// plus one for the services of each worker that declares any
+async fn worker_checks(settings: &Settings, client: Option<&DockerClient>) -> Vec<Check> {
+    let mut workers = settings.list_workers();
+    workers.sort();
+    if workers.is_empty() {
+        return vec![Check::skip("Workers", "No workers registered")];
+    }
+
+    let mut checks = Vec::new();
+    for (name, path) in workers {
+        let check_name = format!("Worker {}", name);
+        checks.push(worker_check(&check_name, name, path, client).await);
+        if let Some(check) = service_check(name, client).await {
+            checks.push(check);
+        }
+    }
+    checks
+}
+
+async fn worker_check(check_name: &str, name: &str, path: &Path, client: Option<&DockerClient>) -> Check {
+    if !path.is_dir() {
+        return Check::fail(check_name, format!("{} no longer exists", path.display())).fix(format!(
+            "Unregister it with `geoengine delete --name {}`, or restore the directory and run `geoengine apply {}`",
+            name, name
+        ));
+    }
+    if !worker_config::has_config_file(path) {
+        return Check::fail(check_name, format!("No geoengine.yaml in {}", path.display()))
+            .fix(format!("Restore the worker's config file or run `geoengine delete --name {}`", name));
+    }
+    if let Err(e) = worker_config::WorkerConfig::load(&worker_config::config_file(path)) {
+        return Check::fail(check_name, format!("{:#}", e)).fix(format!("Fix the config file in {}", path.display()));
+    }
+    if yaml_store::load_saved_config(name).is_err() {
+        return Check::warn(check_name, "Not applied").fix(format!("Run `geoengine apply {}`", name));
+    }
+
+    let mut problems = Vec::new();
+    let mut fixes = Vec::new();
+    if yaml_store::check_changed_config(name, &path.to_path_buf()).unwrap_or(false) {
+        problems.push("config changed since last apply".to_string());
+        fixes.push(format!("Run `geoengine apply {}`", name));
+    }
+    let image = state::load_state(name).ok().flatten().and_then(|s| s.image_tag);
+    match (image, client) {
+        (Some(image), Some(client)) if client.image_id(&image).await.is_err() => {
+            problems.push(format!("image {} is missing", image));
+            fixes.push(format!("Rebuild it with `geoengine build` in {}", path.display()));
+        }
+        (None, _) => {
+            problems.push("never built".to_string());
+            fixes.push(format!("Run `geoengine build` in {}", path.display()));
+        }
+        _ => {}
+    }
+
+    if problems.is_empty() {
+        return Check::pass(check_name, path.display().to_string());
+    }
+    fixes.into_iter().fold(Check::warn(check_name, problems.join("; ")), Check::fix)
+}
+
+/// Status of the worker's declared services; None when it declares none
+async fn service_check(name: &str, client: Option<&DockerClient>) -> Option<Check> {
+    let config = yaml_store::load_saved_config(name).ok()?;
+    let services: Vec<String> = config.services?.into_keys().collect();
+    if services.is_empty() {
+        return None;
+    }
+    let check_name = format!("Services of {}", name);
+    let Some(client) = client else {
+        return Some(Check::skip(&check_name, "Docker daemon not reachable"));
+    };
+    let containers = match client.list_service_containers(name).await {
+        Ok(containers) => containers,
+        Err(e) => return Some(Check::fail(&check_name, format!("{:#}", e))),
+    };
+
+    let mut running = 0;
+    let mut down = Vec::new();
+    for service in &services {
+        match containers.iter().find(|c| c.service.as_deref() == Some(service.as_str())) {
+            Some(c) if c.status == "running" => running += 1,
+            Some(c) => down.push(format!("{} {}", service, c.status)),
+            // Services that were never started aren't a problem
+            None => {}
+        }
+    }
+    let detail = format!("{} of {} running", running, services.len());
+    Some(if down.is_empty() {
+        Check::pass(&check_name, detail)
+    } else {
+        Check::warn(&check_name, format!("{}; {}", detail, down.join(", ")))
+            .fix(format!("Check `geoengine logs {}-<service>` and restart with `geoengine up {}`", name, name))
+    })
+}
+
+/// Whether the credentials configured for registries are usable. Nothing is
+/// pushed or pulled; GCP credentials are checked by requesting a token.
+async fn registry_checks(settings: &Settings) -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    let mut hosts: Vec<_> = settings
+        .registries
+        .iter()
+        .flat_map(|r| r.credentials.iter())
+        .collect();
+    hosts.sort_by_key(|(host, _)| host.as_str());
+    for (host, creds) in hosts {
+        let name = format!("Registry {}", host);
+        checks.push(match (&creds.password_env, &creds.password) {
+            (Some(var), _) if std::env::var(var).is_err() => Check::fail(&name, format!("{} is not set", var))
+                .fix(format!("Export {} with the password or token for {}", var, host)),
+            (None, None) => Check::warn(&name, "No password or password_env configured")
+                .fix(format!("Set `password_env` for {} under registries.credentials in settings.yaml", host)),
+            _ => Check::pass(&name, format!("credentials for {}", creds.username.as_deref().unwrap_or("(no user)"))),
+        });
+    }
+
+    if settings.ghcr.is_some() {
+        checks.push(match ghcr::Ghcr::resolve(None, settings) {
+            Ok(ghcr) => Check::pass("Registry ghcr.io", format!("token for {}", ghcr.owner)),
+            Err(e) => Check::fail("Registry ghcr.io", format!("{:#}", e))
+                .fix("Set ghcr.owner and GITHUB_TOKEN (or ghcr.token) in ~/.geoengine/settings.yaml"),
+        });
+    }
+
+    if let Some(project) = &settings.gcp_project {
+        let name = "Registry GCP Artifact Registry";
+        let client = reqwest::Client::new();
+        checks.push(
+            match tokio::time::timeout(AUTH_TIMEOUT, artifact_registry::access_token(&client)).await {
+                Ok(Ok(_)) => Check::pass(name, format!("credentials for project {}", project)),
+                Ok(Err(e)) => Check::fail(name, format!("{:#}", e)).fix("Run `geoengine deploy auth`"),
+                Err(_) => Check::warn(name, "Timed out requesting a token").fix("Check network access to Google Cloud"),
+            },
+        );
+    }
+
+    if checks.is_empty() {
+        checks.push(Check::skip("Registries", "No registry credentials configured"));
+    }
+    checks
+}
@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::settings::Settings;
+use crate::config::worker as worker_config;
+use crate::error::GeoEngineError;
+use crate::utils::paths;
+
+/// Programs named `<PREFIX><command>` on PATH are run for unknown subcommands
+const PREFIX: &str = "geoengine-";
+
+/// Environment variable carrying the JSON context to an extension
+const CONTEXT_ENV: &str = "GEOENGINE_CONTEXT";
+
+/// What an extension is told about the invocation, as JSON in GEOENGINE_CONTEXT
+#[derive(Serialize)]
+struct ExtensionContext {
+    version: &'static str,
+    /// geoengine binary, for extensions that call back into the CLI
+    executable: Option<PathBuf>,
+    profile: String,
+    config_dir: PathBuf,
+    settings_file: PathBuf,
+    /// Worker of the current directory, if it is one
+    worker: Option<WorkerContext>,
+    verbose: bool,
+}
+
+#[derive(Serialize)]
+struct WorkerContext {
+    name: String,
+    path: PathBuf,
+    config_file: PathBuf,
+}
+
+/// Run the extension for `geoengine <name> [args...]`, passing its exit code on
+pub async fn run(args: &[String], verbose: bool) -> Result<()> {
+    let (name, args) = args.split_first().context("Missing command")?;
+    let program = find(name)?.ok_or_else(|| {
+        GeoEngineError::InputInvalid(format!(
+            "Unknown command '{}'. Run 'geoengine --help' for the built-in commands, or install an extension as {}{} on PATH.",
+            name, PREFIX, name
+        ))
+    })?;
+    tracing::debug!("Running extension {} ({})", name, program.display());
+
+    let worker = current_worker().map(|(name, path)| WorkerContext {
+        config_file: worker_config::config_file(&path),
+        name,
+        path,
+    });
+    let context = ExtensionContext {
+        version: env!("CARGO_PKG_VERSION"),
+        executable: std::env::current_exe().ok(),
+        profile: paths::active_profile()?,
+        config_dir: paths::get_config_dir()?,
+        settings_file: paths::get_settings_file()?,
+        worker,
+        verbose,
+    };
+
+    let status = tokio::process::Command::new(&program)
+        .args(args)
+        .env(CONTEXT_ENV, serde_json::to_string(&context)?)
+        .status()
+        .await
+        .with_context(|| format!("Failed to run {}", program.display()))?;
+    if status.success() {
+        return Ok(());
+    }
+    Err(GeoEngineError::ExtensionFailed {
+        name: name.clone(),
+        // No code when killed by a signal
+        exit_code: status.code().unwrap_or(1),
+    }
+    .into())
+}
+
+/// The registered worker of the current directory, if it is one. Unlike
+/// `resolve_worker`, other directories aren't an error.
+pub fn current_worker() -> Option<(String, PathBuf)> {
+    let cwd = std::env::current_dir().ok()?;
+    Settings::load().ok()?.find_worker_by_path(&cwd)
+}
+
+/// Executable of an extension: from the settings' `extensions`, else `geoengine-<name>` on PATH
+fn find(name: &str) -> Result<Option<PathBuf>> {
+    if let Some(path) = Settings::load()?.extensions.get(name) {
+        if !path.is_file() {
+            return Err(GeoEngineError::ConfigInvalid(format!(
+                "Extension '{}' in the settings points to {}, which doesn't exist",
+                name,
+                path.display()
+            ))
+            .into());
+        }
+        return Ok(Some(path.clone()));
+    }
+    Ok(which::which(format!("{}{}", PREFIX, name)).ok())
+}
+
+/// Every extension by name: those on PATH (the first of a name wins), overridden by the settings
+pub fn discover() -> Result<BTreeMap<String, PathBuf>> {
+    let mut found = BTreeMap::new();
+    let dirs = std::env::var_os("PATH").map(|p| std::env::split_paths(&p).collect::<Vec<_>>());
+    for dir in dirs.unwrap_or_default() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+            let Some(name) = extension_name(&path) else {
+                continue;
+            };
+            if is_executable(&path) {
+                found.entry(name).or_insert(path);
+            }
+        }
+    }
+    found.extend(Settings::load()?.extensions);
+    Ok(found)
+}
+
+/// `<name>` of a `geoengine-<name>[.exe]` file
+fn extension_name(path: &Path) -> Option<String> {
+    let file_name = path.file_name()?.to_str()?;
+    let name = file_name.strip_prefix(PREFIX)?;
+    let name = if cfg!(windows) {
+        name.strip_suffix(".exe").or_else(|| name.strip_suffix(".cmd")).or_else(|| name.strip_suffix(".bat"))?
+    } else {
+        name
+    };
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::metadata(path).is_ok_and(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// `geoengine extensions`: list the extension commands
+pub fn list(json: bool) -> Result<()> {
+    let extensions = discover()?;
+    if json {
+        println!("{}", serde_json::to_string(&extensions)?);
+        return Ok(());
+    }
+    if extensions.is_empty() {
+        println!(
+            "{}",
+            format!("No extensions found. Put a {}<command> program on PATH or add it to the settings.", PREFIX).yellow()
+        );
+        return Ok(());
+    }
+    println!("{:<20} {}", "COMMAND".bold(), "PROGRAM".bold());
+    println!("{}", "-".repeat(60));
+    for (name, path) in &extensions {
+        println!("{:<20} {}", name.cyan(), path.display());
+    }
+    Ok(())
+}
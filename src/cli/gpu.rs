@@ -0,0 +1,412 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use colored::Colorize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::cli::doctor;
+use crate::config::settings::Settings;
+use crate::docker::client::DockerClient;
+use crate::docker::config::ContainerConfig;
+use crate::docker::gpu::{self, GpuConfig, GpuRequest, GpuType};
+use crate::docker::{host, registry};
+use crate::error::GeoEngineError;
+use crate::utils::checks::{Check, Report};
+use crate::utils::paths;
+
+/// CUDA driver library the Windows NVIDIA driver exposes to WSL 2 distros
+const WSL_LIBCUDA: &str = "/usr/lib/wsl/lib/libcuda.so";
+
+/// Where distro packages install a Linux libcuda, which shadows the WSL one
+const LINUX_LIBCUDA_DIRS: &[&str] = &["/usr/lib/x86_64-linux-gnu", "/usr/lib64", "/usr/lib/aarch64-linux-gnu"];
+
+/// Image `gpu test` runs nvidia-smi in by default
+const TEST_IMAGE: &str = "ubuntu:24.04";
+
+const TOOLKIT_GUIDE: &str = "https://docs.nvidia.com/datacenter/cloud-native/container-toolkit/install-guide.html";
+
+#[derive(Subcommand)]
+pub enum GpuCommands {
+    /// Show the GPUs GeoEngine would hand to containers
+    Info,
+
+    /// Check the GPU driver and Docker GPU support, with steps to fix what's broken
+    Doctor {
+        /// Output the checks as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Write a Container Device Interface spec for the NVIDIA GPUs, for Podman,
+    /// Apptainer and Docker with CDI enabled
+    CdiGenerate {
+        /// Spec file to write ('-' prints it)
+        #[arg(short, long, default_value = "/etc/cdi/nvidia.yaml")]
+        output: PathBuf,
+    },
+
+    /// Run `nvidia-smi -L` in a container and check it sees the requested GPUs
+    Test {
+        /// GPUs to request, as with `run --gpus`: all, a count, or device=INDEX|UUID[,...]
+        #[arg(long, value_name = "GPUS", default_value = "all")]
+        gpus: GpuRequest,
+
+        /// Image to run nvidia-smi in (the NVIDIA runtime mounts it into any image)
+        #[arg(long, default_value = TEST_IMAGE)]
+        image: String,
+    },
+}
+
+impl GpuCommands {
+    pub async fn execute(self) -> Result<()> {
+        match self {
+            Self::Info => gpu::print_gpu_info().await,
+            Self::Doctor { json } => doctor(json).await,
+            Self::CdiGenerate { output } => cdi_generate(&output).await,
+            Self::Test { gpus, image } => test(&gpus, &image).await,
+        }
+    }
+}
+
+/// Hand the requested GPUs to a container as `geoengine run --gpus` would and
+/// compare what `nvidia-smi -L` lists inside it with the request
+async fn test(request: &GpuRequest, image: &str) -> Result<()> {
+    let client = DockerClient::new().await?;
+    let detected = GpuConfig::detect().await?;
+    if !detected.is_nvidia() {
+        return Err(GeoEngineError::GpuUnavailable(
+            "No NVIDIA GPU detected. Run 'geoengine gpu doctor' to find out why.".into(),
+        )
+        .into());
+    }
+    let busy = client.busy_gpu_devices().await?;
+    let gpu = detected.requested(request, &busy)?;
+    if client.image_id(image).await.is_err() {
+        registry::pull(&client, image).await?;
+    }
+
+    println!(
+        "{} Running nvidia-smi -L in {} with --gpus {}",
+        "=>".blue().bold(),
+        image.cyan(),
+        request
+    );
+    let log = paths::get_temp_dir()?.join(format!("gpu-test-{}.log", std::process::id()));
+    let config = ContainerConfig {
+        image: image.to_string(),
+        entrypoint: Some(vec![String::new()]),
+        command: Some(vec!["nvidia-smi".to_string(), "-L".to_string()]),
+        env_vars: HashMap::new(),
+        mounts: Vec::new(),
+        volumes: Vec::new(),
+        gpu_config: Some(gpu.clone()),
+        user: None,
+        group_add: Vec::new(),
+        workdir: None,
+        name: None,
+        labels: HashMap::new(),
+        ports: Vec::new(),
+        restart: None,
+        ulimits: Vec::new(),
+        cap_add: Vec::new(),
+        cap_drop: Vec::new(),
+        devices: Vec::new(),
+        read_only: false,
+        security_opt: Vec::new(),
+        privileged: false,
+        memory: None,
+        nano_cpus: None,
+        network: None,
+        network_aliases: Vec::new(),
+        remove_on_exit: true,
+        detach: false,
+        tty: false,
+    };
+    let result = client.run_container_attached_to_file(&config, &log).await;
+    let output = std::fs::read_to_string(&log).unwrap_or_default();
+    let _ = std::fs::remove_file(&log);
+    let exit = result.context("Failed to run the GPU test container")?;
+    for line in output.lines().filter(|l| !l.trim().is_empty()) {
+        println!("  {}", line);
+    }
+    if exit.exit_code != 0 {
+        return Err(GeoEngineError::GpuUnavailable(format!(
+            "nvidia-smi failed in the container (exit code {}). Run 'geoengine gpu doctor' to check GPU passthrough.",
+            exit.exit_code
+        ))
+        .into());
+    }
+
+    // MIG slices are listed under their GPU; count whichever kind was requested
+    let mig = !gpu.mig_devices.is_empty() && gpu.device_ids.as_ref().is_some_and(|ids| ids.iter().all(|id| id.starts_with("MIG-")));
+    let prefix = if mig { "MIG " } else { "GPU " };
+    let seen = output.lines().filter(|l| l.trim_start().starts_with(prefix)).count();
+    let expected = if mig { gpu.mig_devices.len() } else { gpu.count };
+    if seen != expected {
+        return Err(GeoEngineError::GpuUnavailable(format!(
+            "The container sees {} {}(s), but {} were requested ({})",
+            seen,
+            prefix.trim(),
+            expected,
+            gpu.devices.join(", ")
+        ))
+        .into());
+    }
+    println!(
+        "{} The container sees the {} requested {}(s)",
+        "✓".green().bold(),
+        expected,
+        prefix.trim()
+    );
+    Ok(())
+}
+
+async fn doctor(json: bool) -> Result<()> {
+    let mut checks = driver_checks().await;
+    match doctor::docker_checks(&mut checks).await {
+        (_, Some(info)) => checks.extend(docker_gpu_checks(&info)),
+        (_, None) => checks.push(Check::skip("Docker GPU support", "Docker daemon not reachable")),
+    }
+
+    let report = Report::new(checks);
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+    } else {
+        report.print();
+    }
+
+    if report.failed > 0 {
+        return Err(GeoEngineError::ChecksFailed { failed: report.failed }.into());
+    }
+    Ok(())
+}
+
+/// Generate the spec with the NVIDIA Container Toolkit's `nvidia-ctk`, naming each
+/// GPU and MIG slice by index and by UUID so `NVIDIA_VISIBLE_DEVICES`-style IDs resolve
+async fn cdi_generate(output: &Path) -> Result<()> {
+    let detected = GpuConfig::detect().await?;
+    if !detected.is_nvidia() {
+        return Err(GeoEngineError::GpuUnavailable("CDI specs are generated for NVIDIA GPUs, and none was detected".into()).into());
+    }
+    which::which("nvidia-ctk")
+        .with_context(|| format!("nvidia-ctk not found. Install the NVIDIA Container Toolkit: {}", TOOLKIT_GUIDE))?;
+
+    let to_stdout = output == Path::new("-");
+    let mut command = Command::new("nvidia-ctk");
+    command.args(["cdi", "generate", "--device-name-strategy=index", "--device-name-strategy=uuid"]);
+    if to_stdout {
+        command.arg("--quiet");
+    } else {
+        if let Some(dir) = output.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).with_context(|| {
+                format!("Failed to create {} (writing to the system CDI directory needs root)", dir.display())
+            })?;
+        }
+        command.arg(format!("--output={}", output.display()));
+    }
+    let result = command.output().context("Failed to run nvidia-ctk")?;
+    if !result.status.success() {
+        anyhow::bail!("nvidia-ctk cdi generate failed: {}", String::from_utf8_lossy(&result.stderr).trim());
+    }
+    if to_stdout {
+        print!("{}", String::from_utf8_lossy(&result.stdout));
+        return Ok(());
+    }
+
+    println!("{} Wrote CDI spec for {} GPU(s) to {}", "✓".green().bold(), detected.count, output.display());
+    let mut names = gpu::cdi_device_names(None);
+    names.extend((0..detected.count).map(|i| format!("{}={}", gpu::CDI_KIND, i)));
+    names.extend(detected.mig_devices.iter().map(|m| format!("{}={}", gpu::CDI_KIND, m.uuid)));
+    for name in &names {
+        println!("  {} {}", "•".cyan(), name);
+    }
+    if !gpu::CDI_SPEC_DIRS.iter().any(|dir| output.parent() == Some(Path::new(dir))) {
+        println!(
+            "{} Container engines only read specs from {}",
+            "!".yellow().bold(),
+            gpu::CDI_SPEC_DIRS.join(" and ")
+        );
+    }
+    println!();
+    println!("Use a device name with another engine, e.g.:");
+    println!("  podman run --device {} ...", names[0]);
+    println!("  apptainer run --oci --device {} ...", names[0]);
+    if !Settings::load().is_ok_and(|s| s.gpu_cdi) {
+        println!("Set gpu_cdi: true in the settings to have geoengine request GPUs by CDI name.");
+    }
+    Ok(())
+}
+
+/// Checks of the host's GPU driver stack: WSL 2 GPU passthrough inside WSL,
+/// nvidia-smi elsewhere on Linux and Windows, Metal on macOS
+pub async fn driver_checks() -> Vec<Check> {
+    if cfg!(target_os = "macos") {
+        return vec![metal_check().await];
+    }
+
+    let mut checks = Vec::new();
+    if host::is_wsl() {
+        checks.extend(wsl_checks());
+    }
+    checks.push(nvidia_smi_check());
+    checks
+}
+
+/// Checks of the Docker daemon's GPU support
+pub fn docker_gpu_checks(info: &bollard::models::SystemInfo) -> Vec<Check> {
+    if cfg!(target_os = "macos") {
+        return vec![Check::skip(
+            "Docker GPU support",
+            "Docker on macOS can't pass GPUs to containers; workers use MPS only when run natively",
+        )];
+    }
+
+    let desktop = info
+        .operating_system
+        .as_deref()
+        .is_some_and(|os| os.contains("Docker Desktop"));
+    let kernel = info.kernel_version.as_deref().unwrap_or_default();
+    let wsl_engine = kernel.contains("microsoft") || kernel.to_ascii_lowercase().contains("wsl2");
+
+    if desktop {
+        return vec![if wsl_engine {
+            Check::pass("Docker GPU support", "Docker Desktop with the WSL 2 engine")
+        } else {
+            Check::fail(
+                "Docker GPU support",
+                format!("Docker Desktop runs on the Hyper-V engine (kernel {}), which has no GPU support", kernel),
+            )
+            .fix("In Docker Desktop, open Settings > General and enable 'Use the WSL 2 based engine'")
+            .fix("Restart Docker Desktop")
+        }];
+    }
+
+    if !host::is_wsl() && which::which("nvidia-smi").is_err() {
+        return vec![Check::skip("Docker GPU support", "No NVIDIA driver on this host")];
+    }
+    if Settings::load().is_ok_and(|s| s.gpu_cdi) {
+        let specs = gpu::cdi_spec_files();
+        return vec![if specs.is_empty() {
+            Check::fail("Docker GPU support", format!("gpu_cdi is set, but no CDI spec for {} was found", gpu::CDI_KIND))
+                .fix("Run `sudo geoengine gpu cdi-generate`")
+                .fix("Enable CDI in the Docker daemon (Docker 25+: \"features\": {\"cdi\": true} in daemon.json) and restart Docker")
+        } else {
+            let specs: Vec<String> = specs.iter().map(|p| p.display().to_string()).collect();
+            Check::pass("Docker GPU support", format!("CDI devices from {}", specs.join(", ")))
+        }];
+    }
+    let runtimes: Vec<&String> = info.runtimes.iter().flat_map(|r| r.keys()).collect();
+    if runtimes.iter().any(|r| r.as_str() == "nvidia") {
+        return vec![Check::pass("Docker GPU support", "nvidia runtime registered")];
+    }
+    let mut check = Check::fail(
+        "Docker GPU support",
+        "The Docker Engine has no nvidia runtime (NVIDIA Container Toolkit missing or not configured)",
+    );
+    if host::is_wsl() {
+        check = check.fix(
+            "Use Docker Desktop's WSL integration instead of a Docker Engine inside the distro, or install the toolkit below",
+        );
+    }
+    vec![check
+        .fix(format!("Install the NVIDIA Container Toolkit: {}", TOOLKIT_GUIDE))
+        .fix("Run `sudo nvidia-ctk runtime configure --runtime=docker` and restart Docker")]
+}
+
+fn wsl_checks() -> Vec<Check> {
+    let mut checks = Vec::new();
+
+    if !host::is_wsl2() {
+        checks.push(
+            Check::fail("WSL version", "WSL 1 can't access the GPU")
+                .fix(format!("In PowerShell, run `wsl --set-version {} 2`", wsl_distro())),
+        );
+        return checks;
+    }
+    checks.push(Check::pass("WSL version", format!("WSL 2 ({})", wsl_distro())));
+
+    checks.push(if Path::new("/dev/dxg").exists() {
+        Check::pass("GPU paravirtualization", "/dev/dxg present")
+    } else {
+        Check::fail("GPU paravirtualization", "/dev/dxg is missing, so WSL exposes no GPU")
+            .fix("Use Windows 11 or Windows 10 21H2 or later")
+            .fix("In PowerShell, run `wsl --update`, then `wsl --shutdown` and reopen the distro")
+    });
+
+    checks.push(if Path::new(WSL_LIBCUDA).exists() {
+        Check::pass("WSL CUDA driver", WSL_LIBCUDA)
+    } else {
+        Check::fail("WSL CUDA driver", format!("{} not found", WSL_LIBCUDA))
+            .fix("Install the latest NVIDIA driver for Windows (it includes WSL support): https://developer.nvidia.com/cuda/wsl")
+            .fix("In PowerShell, run `wsl --shutdown` and reopen the distro")
+    });
+
+    let shadowing: Vec<String> = LINUX_LIBCUDA_DIRS
+        .iter()
+        .map(|dir| Path::new(dir).join("libcuda.so.1"))
+        .filter(|lib| {
+            lib.exists()
+                && !lib
+                    .canonicalize()
+                    .map(|target| target.starts_with("/usr/lib/wsl"))
+                    .unwrap_or(false)
+        })
+        .map(|lib| lib.display().to_string())
+        .collect();
+    checks.push(if shadowing.is_empty() {
+        Check::pass("Linux NVIDIA driver", "none installed in the distro")
+    } else {
+        Check::warn(
+            "Linux NVIDIA driver",
+            format!("{} shadows the WSL driver", shadowing.join(", ")),
+        )
+        .fix("Remove the Linux NVIDIA driver from the distro (e.g. `sudo apt remove --purge 'nvidia-driver-*' 'libnvidia-compute-*'`); in WSL the Windows driver provides CUDA")
+    });
+
+    checks
+}
+
+fn nvidia_smi_check() -> Check {
+    let output = Command::new("nvidia-smi")
+        .args(["--query-gpu=name,driver_version", "--format=csv,noheader"])
+        .output();
+    let fix_driver = if host::is_wsl() {
+        "Update the NVIDIA driver on Windows; `nvidia-smi` in WSL comes from /usr/lib/wsl/lib"
+    } else {
+        "Install the NVIDIA driver for your GPU and check that `nvidia-smi` works"
+    };
+    match output {
+        Ok(output) if output.status.success() => {
+            let gpus: Vec<String> = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.split_once(','))
+                .map(|(name, driver)| format!("{} (driver {})", name.trim(), driver.trim()))
+                .collect();
+            if gpus.is_empty() {
+                Check::warn("NVIDIA GPU", "nvidia-smi lists no GPU").fix(fix_driver)
+            } else {
+                Check::pass("NVIDIA GPU", gpus.join(", "))
+            }
+        }
+        Ok(output) => Check::fail(
+            "NVIDIA GPU",
+            format!("nvidia-smi failed: {}", String::from_utf8_lossy(&output.stderr).trim()),
+        )
+        .fix(fix_driver),
+        // Without an NVIDIA GPU there's nothing to fix: workers run on CPU
+        Err(_) if !host::is_wsl() => Check::skip("NVIDIA GPU", "nvidia-smi not found; workers run on CPU"),
+        Err(_) => Check::fail("NVIDIA GPU", "nvidia-smi not found").fix(fix_driver),
+    }
+}
+
+async fn metal_check() -> Check {
+    match GpuConfig::detect().await {
+        Ok(config) if config.gpu_type == GpuType::Metal => Check::pass("Metal GPU", config.devices.join(", ")),
+        _ => Check::warn("Metal GPU", "No Metal-capable GPU found; workers run on CPU"),
+    }
+}
+
+pub fn wsl_distro() -> String {
+    std::env::var("WSL_DISTRO_NAME").unwrap_or_else(|_| "<distro>".to_string())
+}
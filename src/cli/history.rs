@@ -0,0 +1,398 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use colored::Colorize;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+use crate::cli::worker::{run_worker, RunArgs};
+use crate::config::audit;
+use crate::config::history::{self, HistoryEntry};
+use crate::docker::client::DockerClient;
+use crate::docker::geometa::shell_quote;
+use crate::error::GeoEngineError;
+
+#[derive(Subcommand)]
+pub enum HistoryCommands {
+    /// List past runs, most recent first
+    List {
+        /// Only show runs of this worker
+        #[arg(short, long)]
+        worker: Option<String>,
+
+        /// Maximum number of runs to show
+        #[arg(short = 'n', long, default_value_t = 20)]
+        limit: usize,
+
+        /// Output as JSON (for programmatic use)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the parameters, image and outcome of a run
+    Show {
+        /// Run ID (from `geoengine history list`)
+        id: usize,
+
+        /// Output as JSON (for programmatic use)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Run a past invocation again with the same inputs, arguments and options
+    Rerun {
+        /// Run ID (from `geoengine history list`)
+        id: usize,
+    },
+
+    /// Write a reproducibility report of a run: command line, worker config, image,
+    /// parameters, input hashes, host and GPU driver
+    Report {
+        /// Run ID (from `geoengine history list`)
+        id: usize,
+
+        /// Report format
+        #[arg(long, value_enum, default_value_t = ReportFormat::Markdown)]
+        format: ReportFormat,
+
+        /// Write the report to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Json,
+}
+
+impl HistoryCommands {
+    pub async fn execute(self) -> Result<()> {
+        match self {
+            Self::List { worker, limit, json } => list(worker.as_deref(), limit, json),
+            Self::Show { id, json } => show(id, json),
+            Self::Rerun { id } => rerun(id).await,
+            Self::Report { id, format, output } => report(id, format, output.as_deref()),
+        }
+    }
+}
+
+fn list(worker: Option<&str>, limit: usize, json: bool) -> Result<()> {
+    let entries: Vec<HistoryEntry> = history::load()?
+        .into_iter()
+        .rev()
+        .filter(|e| worker.is_none_or(|w| e.worker == w))
+        .take(limit)
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string(&entries)?);
+        return Ok(());
+    }
+    if entries.is_empty() {
+        println!("{}", "No runs recorded".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{:<6} {:<20} {:<25} {:<10} {:<8} {}",
+        "ID".bold(),
+        "STARTED".bold(),
+        "WORKER".bold(),
+        "DURATION".bold(),
+        "EXIT".bold(),
+        "OUTPUT".bold()
+    );
+    println!("{}", "-".repeat(100));
+    for entry in &entries {
+        println!(
+            "{:<6} {:<20} {:<25} {:<10} {} {}",
+            entry.id,
+            format_started(&entry.started_at),
+            format!("{}:{}", entry.worker, entry.worker_version.as_deref().unwrap_or("latest")),
+            format_duration(entry),
+            format_exit(entry, 8),
+            entry.output_dirs.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+fn show(id: usize, json: bool) -> Result<()> {
+    let entry = history::get(id)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&entry)?);
+        return Ok(());
+    }
+
+    println!("{} {}", "Run".bold(), entry.id.to_string().cyan());
+    println!("  Worker:     {} {}", entry.worker, entry.worker_version.as_deref().unwrap_or_default());
+    println!("  Image:      {}", entry.image);
+    if let Some(image_id) = &entry.image_id {
+        println!("  Image ID:   {}", image_id);
+    }
+    println!("  Started:    {}", format_started(&entry.started_at));
+    println!("  Duration:   {}", format_duration(&entry));
+    println!("  Exit code:  {}", format_exit(&entry, 0));
+    if let Some(container) = &entry.container {
+        println!("  Container:  {}", container);
+    }
+    if let Some(usage) = &entry.usage {
+        println!("  Resources:  {}", usage);
+    }
+    println!("  Directory:  {}", entry.cwd);
+    if !entry.inputs.is_empty() {
+        println!("  Inputs:");
+        for (key, value) in &entry.inputs {
+            println!("    {} {} = {}", "•".cyan(), key, value);
+        }
+    }
+    if !entry.args.is_empty() {
+        println!("  Arguments:  {}", entry.args.join(" "));
+    }
+    for dir in &entry.output_dirs {
+        println!("  Output:     {}", dir);
+    }
+
+    Ok(())
+}
+
+/// A run's history entry with the worker configuration it used
+#[derive(Serialize)]
+struct RunReport {
+    generated_at: String,
+    #[serde(flatten)]
+    entry: HistoryEntry,
+    /// Resolved worker configuration (YAML), if the run recorded it
+    config: Option<String>,
+}
+
+fn report(id: usize, format: ReportFormat, output: Option<&Path>) -> Result<()> {
+    let entry = history::get(id)?;
+    let config = match &entry.config_sha256 {
+        Some(hash) => history::config_snapshot(hash)?,
+        None => None,
+    };
+    let report = RunReport {
+        generated_at: chrono::Utc::now().to_rfc3339(),
+        entry,
+        config,
+    };
+    let content = match format {
+        ReportFormat::Json => serde_json::to_string_pretty(&report)?,
+        ReportFormat::Markdown => markdown_report(&report),
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+            println!("{} Wrote the report of run {} to {}", "✓".green().bold(), id, path.display());
+        }
+        None => println!("{}", content),
+    }
+    Ok(())
+}
+
+fn markdown_report(report: &RunReport) -> String {
+    let entry = &report.entry;
+    let mut md = format!(
+        "# Run {} of {} {}\n\nGenerated on {} by geoengine {}.\n\n",
+        entry.id,
+        entry.worker,
+        entry.worker_version.as_deref().unwrap_or_default(),
+        format_started(&report.generated_at),
+        env!("CARGO_PKG_VERSION")
+    );
+
+    md.push_str("## Invocation\n\n");
+    if entry.invocation.is_empty() {
+        md.push_str("Not recorded (the run predates reproducibility reports).\n\n");
+    } else {
+        // Runs recorded before --env values were redacted still have them
+        let words: Vec<String> = audit::redact_args(&entry.invocation)
+            .iter()
+            .enumerate()
+            .map(|(i, arg)| match i {
+                // The binary as the shell found it
+                0 => Path::new(arg).file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| arg.clone()),
+                _ if arg.chars().all(|c| c.is_ascii_alphanumeric() || "-_=./:,@".contains(c)) => arg.clone(),
+                _ => shell_quote(arg),
+            })
+            .collect();
+        md.push_str(&format!("```sh\n{}\n```\n\n", words.join(" ")));
+    }
+    md.push_str(&format!(
+        "Started from `{}`. Run it again with `geoengine history rerun {}`.\n\n",
+        entry.cwd, entry.id
+    ));
+
+    md.push_str("## Outcome\n\n");
+    md.push_str(&format!("- Started: {}\n", format_started(&entry.started_at)));
+    md.push_str(&format!("- Duration: {}\n", format_duration(entry)));
+    md.push_str(&format!("- Exit code: {}\n", format_exit(entry, 0)));
+    if let Some(usage) = &entry.usage {
+        md.push_str(&format!("- Resources: {}\n", usage));
+    }
+    for dir in &entry.output_dirs {
+        md.push_str(&format!("- Output: `{}`\n", dir));
+    }
+
+    md.push_str("\n## Image\n\n");
+    md.push_str(&format!("- Tag: `{}`\n", entry.image));
+    if let Some(image_id) = &entry.image_id {
+        md.push_str(&format!("- ID: `{}`\n", image_id));
+    }
+    for digest in &entry.image_digests {
+        md.push_str(&format!("- Digest: `{}`\n", digest));
+    }
+
+    md.push_str("\n## Parameters\n\n");
+    if entry.inputs.is_empty() {
+        md.push_str("None.\n");
+    } else {
+        md.push_str("| Input | Value |\n|---|---|\n");
+        for (key, value) in &entry.inputs {
+            md.push_str(&format!("| {} | `{}` |\n", key, value.replace('|', "\\|")));
+        }
+    }
+    if !entry.args.is_empty() {
+        md.push_str(&format!("\nArguments: `{}`\n", entry.args.join(" ")));
+    }
+    if !entry.input_files.is_empty() {
+        md.push_str("\n### Input files\n\n| Input | Path | SHA-256 |\n|---|---|---|\n");
+        for file in &entry.input_files {
+            md.push_str(&format!(
+                "| {} | `{}` | `{}`{} |\n",
+                file.name,
+                file.path,
+                file.sha256,
+                if file.verified { " (verified)" } else { "" }
+            ));
+        }
+    }
+
+    if let Some(host) = &entry.host {
+        md.push_str("\n## Host\n\n");
+        md.push_str(&format!("- Hostname: {}\n", host.hostname));
+        md.push_str(&format!("- OS: {} ({})\n", host.os, host.arch));
+        md.push_str(&format!("- geoengine: {}\n", host.geoengine_version));
+        if let Some(docker) = &host.docker_version {
+            md.push_str(&format!("- Docker: {}\n", docker));
+        }
+        if let Some(driver) = &host.nvidia_driver {
+            md.push_str(&format!("- NVIDIA driver: {}\n", driver));
+        }
+        if let Some(cuda) = &host.cuda_version {
+            md.push_str(&format!("- CUDA (driver): {}\n", cuda));
+        }
+        if !host.gpus.is_empty() {
+            md.push_str(&format!("- GPUs: {}\n", host.gpus.join(", ")));
+        }
+    }
+
+    md.push_str("\n## Worker configuration\n\n");
+    match &report.config {
+        Some(config) => md.push_str(&format!("```yaml\n{}```\n", config)),
+        None => md.push_str("Not recorded.\n"),
+    }
+    md
+}
+
+async fn rerun(id: usize) -> Result<()> {
+    let entry = history::get(id)?;
+
+    // The image may have been rebuilt under the same tag since
+    if let Some(recorded) = &entry.image_id {
+        let client = DockerClient::new().await?;
+        match client.image_id(&entry.image).await {
+            Ok(current) if current != *recorded => eprintln!(
+                "{} {} has been rebuilt since run {}; results may differ",
+                "!".yellow().bold(),
+                entry.image,
+                id
+            ),
+            Err(_) => anyhow::bail!("Image {} is no longer available", entry.image),
+            _ => {}
+        }
+    }
+
+    // Relative input paths were given relative to the original directory
+    std::env::set_current_dir(&entry.cwd)
+        .with_context(|| format!("Failed to change to the run's directory {}", entry.cwd))?;
+    eprintln!(
+        "{} Re-running run {} of '{}' from {}",
+        "=>".blue().bold(),
+        id,
+        entry.worker.cyan(),
+        entry.cwd
+    );
+
+    // Values of --env aren't recorded; they come from the current environment
+    let env = entry
+        .env
+        .iter()
+        .map(|arg| match arg.split_once('=') {
+            Some((key, audit::REDACTED)) => std::env::var(key).map(|value| format!("{}={}", key, value)).map_err(|_| {
+                GeoEngineError::InputInvalid(format!(
+                    "Run {} set {} with --env, whose value isn't recorded. Export {} and try again.",
+                    id, key, key
+                ))
+                .into()
+            }),
+            _ => Ok(arg.clone()),
+        })
+        .collect::<Result<Vec<String>>>()?;
+
+    let run = RunArgs {
+        worker: Some(entry.worker),
+        inputs: entry.inputs.iter().map(|(k, v)| format!("{}={}", k, v)).collect(),
+        dev: entry.dev,
+        no_isolate: entry.no_isolate,
+        env,
+        env_file: entry.env_files.iter().map(Into::into).collect(),
+        user: entry.user,
+        group_add: entry.group_add,
+        entrypoint: entry.entrypoint,
+        workdir: entry.workdir,
+        labels: entry.labels,
+        ulimit: entry.ulimits,
+        cap_add: entry.cap_add,
+        cap_drop: entry.cap_drop,
+        device: entry.devices,
+        read_only: entry.read_only,
+        privileged: entry.privileged,
+        mig: entry.mig,
+        gpus: entry.gpus.as_deref().map(str::parse).transpose().map_err(|e: String| anyhow::anyhow!(e))?,
+        args: entry.args,
+        ..Default::default()
+    };
+    run_worker(&run).await
+}
+
+pub fn format_started(started_at: &str) -> String {
+    chrono::DateTime::parse_from_rfc3339(started_at)
+        .map(|t| t.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|_| started_at.to_string())
+}
+
+fn format_duration(entry: &HistoryEntry) -> String {
+    if entry.exit_code.is_none() {
+        return "-".to_string();
+    }
+    let secs = entry.duration_secs.round() as u64;
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+        _ => format!("{}h{:02}m", secs / 3600, (secs % 3600) / 60),
+    }
+}
+
+/// Exit code padded to `width` before coloring, so table columns stay aligned
+pub fn format_exit(entry: &HistoryEntry, width: usize) -> String {
+    match entry.exit_code {
+        Some(0) => format!("{:<width$}", 0).green().to_string(),
+        Some(code) if entry.oom_killed => format!("{:<width$}", format!("{} OOM", code)).red().to_string(),
+        Some(code) => format!("{:<width$}", code).red().to_string(),
+        None => format!("{:<width$}", "detached"),
+    }
+}
@@ -0,0 +1,135 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::cli::worker::RunOutput;
+use crate::config::worker::{HookConfig, HooksConfig};
+use crate::docker::client::DockerClient;
+use crate::docker::config::ContainerConfig;
+use crate::docker::labels;
+
+/// Runs a worker's hooks around one run of its container
+pub struct HookRunner<'a> {
+    pub client: &'a DockerClient,
+    pub hooks: &'a HooksConfig,
+    /// The run's container, whose image, mounts and environment container hooks reuse
+    pub container: &'a ContainerConfig,
+    pub worker_name: &'a str,
+    pub worker_path: &'a Path,
+    pub output_dirs: &'a [PathBuf],
+    pub output: &'a RunOutput,
+}
+
+impl HookRunner<'_> {
+    /// Run the pre_run hooks, failing on the first that fails
+    pub async fn pre_run(&self) -> Result<()> {
+        for (i, hook) in self.hooks.pre_run.iter().enumerate() {
+            let code = self.run("pre_run", i, hook, None).await?;
+            if code != 0 {
+                anyhow::bail!("pre_run hook '{}' exited with code {}", describe(hook), code);
+            }
+        }
+        Ok(())
+    }
+
+    /// Run the on_failure hooks (when the run failed) and the post_run hooks. Their
+    /// failures are only reported, so they never change the run's result.
+    pub async fn finished(&self, exit_code: Option<i64>) {
+        let failed = exit_code != Some(0);
+        let stages = [("on_failure", &self.hooks.on_failure), ("post_run", &self.hooks.post_run)];
+        for (stage, hooks) in stages {
+            if stage == "on_failure" && !failed {
+                continue;
+            }
+            for (i, hook) in hooks.iter().enumerate() {
+                match self.run(stage, i, hook, exit_code).await {
+                    Ok(0) => {}
+                    Ok(code) => eprintln!(
+                        "{} {} hook '{}' exited with code {}",
+                        "!".yellow().bold(),
+                        stage,
+                        describe(hook),
+                        code
+                    ),
+                    Err(e) => eprintln!("{} {} hook '{}' failed: {:#}", "!".yellow().bold(), stage, describe(hook), e),
+                }
+            }
+        }
+    }
+
+    async fn run(&self, stage: &str, index: usize, hook: &HookConfig, exit_code: Option<i64>) -> Result<i64> {
+        if matches!(self.output, RunOutput::Terminal) {
+            eprintln!("{} Running {} hook: {}", "=>".blue().bold(), stage, describe(hook));
+        }
+        let mut env = vec![
+            ("GEOENGINE_HOOK".to_string(), stage.to_string()),
+            ("GEOENGINE_WORKER".to_string(), self.worker_name.to_string()),
+            ("GEOENGINE_IMAGE".to_string(), self.container.image.clone()),
+        ];
+        if let Some(code) = exit_code {
+            env.push(("GEOENGINE_EXIT_CODE".to_string(), code.to_string()));
+        }
+        // Batch items log to a file per item; each hook gets its own next to it
+        let log = match self.output {
+            RunOutput::LogFile(path) => Some(path.with_extension(format!("{}-{}.log", stage, index + 1))),
+            _ => None,
+        };
+
+        if let Some(command) = &hook.run {
+            let dirs = std::env::join_paths(self.output_dirs).context("Invalid output folder path")?;
+            env.push(("GEOENGINE_WORKER_DIR".to_string(), self.worker_path.display().to_string()));
+            env.push(("GEOENGINE_OUTPUT_DIRS".to_string(), dirs.to_string_lossy().to_string()));
+            return self.run_on_host(command, env, log.as_deref());
+        }
+        let command = hook.container.as_deref().unwrap_or_default();
+        let mut container = self.container.clone();
+        container.command = Some(vec!["/bin/sh".to_string(), "-c".to_string(), command.to_string()]);
+        container.env_vars.extend(env);
+        container.name = None;
+        container.ports = Vec::new();
+        container.restart = None;
+        container.network_aliases = Vec::new();
+        container.labels.remove(labels::SERVICE);
+        container.remove_on_exit = true;
+        container.detach = false;
+        container.tty = false;
+        let exit = match (self.output, &log) {
+            (_, Some(log)) => self.client.run_container_attached_to_file(&container, log).await,
+            (RunOutput::Terminal, _) => self.client.run_container_attached(&container).await,
+            _ => self.client.run_container_attached_to_stderr(&container).await,
+        }?;
+        Ok(exit.exit_code)
+    }
+
+    fn run_on_host(&self, command: &str, env: Vec<(String, String)>, log: Option<&Path>) -> Result<i64> {
+        let mut process = if cfg!(windows) {
+            let mut process = Command::new("cmd");
+            process.arg("/C");
+            process
+        } else {
+            let mut process = Command::new("sh");
+            process.arg("-c");
+            process
+        };
+        process.arg(command).current_dir(self.worker_path).envs(env);
+        match (self.output, log) {
+            (_, Some(log)) => {
+                let file = std::fs::File::create(log).with_context(|| format!("Failed to create {}", log.display()))?;
+                process.stdout(file.try_clone()?).stderr(file);
+            }
+            // Keep stdout free for the JSON result
+            (RunOutput::Stderr, _) => {
+                process.stdout(Stdio::from(std::io::stderr()));
+            }
+            _ => {}
+        }
+        let status = process.status().with_context(|| format!("Failed to run '{}'", command))?;
+        // No code when killed by a signal
+        Ok(status.code().unwrap_or(1) as i64)
+    }
+}
+
+fn describe(hook: &HookConfig) -> &str {
+    hook.run.as_deref().or(hook.container.as_deref()).unwrap_or_default()
+}
@@ -1,21 +1,124 @@
 use anyhow::{Context, Result};
 use clap::Subcommand;
 use colored::Colorize;
-use indicatif::{ProgressBar, ProgressStyle};
-use std::path::PathBuf;
+use bollard::models::{ImageInspect, ImageSummary};
+use futures::StreamExt;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
 
-use crate::docker::client::DockerClient;
+use crate::cli::worker::{resolve_worker, worker_base_images};
+use crate::config::pins;
+use crate::config::settings::{Settings, SigningSettings};
+use crate::config::yaml_store;
+use crate::docker::client::{partial_export_path, remove_partial_export, DockerClient};
+use crate::docker::image_archive;
+use crate::docker::labels;
+use crate::docker::pull_progress::PullProgress;
+use crate::docker::registry;
+use crate::error::GeoEngineError;
+use crate::cli::output_rules::glob_regex;
+use crate::utils::sbom::{self, SbomFormat};
+use crate::utils::{cosign, paths};
+
+/// How pulls report progress
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum PullOutput {
+    /// Progress bars per layer with an overall ETA
+    Text,
+    /// Newline-delimited JSON layer events on stdout
+    Ndjson,
+}
+
+impl PullOutput {
+    pub fn progress(self, image: &str) -> PullProgress {
+        match self {
+            PullOutput::Text => PullProgress::bars(),
+            PullOutput::Ndjson => PullProgress::ndjson(image),
+        }
+    }
+}
 
 #[derive(Subcommand)]
 pub enum ImageCommands {
-    /// Import a Docker image from a tar file (for air-gapped environments)
+    /// Import Docker images from tar files (for air-gapped environments)
     Import {
-        /// Path to the tar file containing the Docker image
-        tarfile: PathBuf,
+        /// Tar file, directory of tar files, or quoted glob such as 'drop/*.tar'
+        source: String,
 
-        /// Tag to apply to the imported image
+        /// Tag to apply to the imported image (single archive only)
         #[arg(short, long)]
         tag: Option<String>,
+
+        /// Verify <tarfile>.bundle with cosign before importing (always on if `signing.enforce` is set)
+        #[arg(long)]
+        verify: bool,
+
+        /// Also tag loaded images whose reference starts with FROM under TO instead (repeatable)
+        #[arg(long, value_name = "FROM=TO")]
+        remap: Vec<String>,
+
+        /// Number of archives to load at once
+        #[arg(long, default_value_t = 1, value_name = "N")]
+        parallel: usize,
+    },
+
+    /// Export one or more images into a single tar file (for air-gapped environments)
+    Export {
+        /// Image names, IDs, or tags to export
+        images: Vec<String>,
+
+        /// Include a worker's current image and the base images of its Dockerfile (repeatable)
+        #[arg(short, long)]
+        worker: Vec<String>,
+
+        /// Output tar file
+        #[arg(short, long)]
+        output: PathBuf,
+
+        /// Compress the archive with zstd (always on with --delta)
+        #[arg(long)]
+        zstd: bool,
+
+        /// Leave out the layers the --base image already has; import rebuilds the
+        /// full images from the delta and that image
+        #[arg(long, requires = "base")]
+        delta: bool,
+
+        /// Image the receiving host already has, e.g. the previously exported version
+        #[arg(long, value_name = "IMAGE", requires = "delta")]
+        base: Option<String>,
+
+        /// Write an SBOM of each image next to the archive (with syft); pick the format with --sbom=FORMAT
+        #[arg(
+            long,
+            value_enum,
+            value_name = "FORMAT",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "spdx"
+        )]
+        sbom: Option<SbomFormat>,
+    },
+
+    /// Generate a software bill of materials (SBOM) of an image with syft
+    Sbom {
+        /// Image name, ID, or tag
+        #[arg(required_unless_present = "worker", conflicts_with = "worker")]
+        image: Option<String>,
+
+        /// Use a worker's current image
+        #[arg(short, long)]
+        worker: Option<String>,
+
+        /// SBOM format
+        #[arg(long, value_enum, default_value_t = SbomFormat::Spdx)]
+        format: SbomFormat,
+
+        /// Output file (prints to stdout if not given)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
     },
 
     /// List all Docker images under geoengine
@@ -29,15 +132,121 @@ pub enum ImageCommands {
         all: bool,
     },
 
+    /// Show an image's configuration: entrypoint, command, environment, labels and layers
+    Inspect {
+        /// Image name, ID, or tag to inspect
+        image: String,
+
+        /// Output as JSON (for programmatic use)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show the layers of an image with their sizes and the commands that created them
+    History {
+        /// Image name, ID, or tag
+        image: String,
+
+        /// Don't truncate layer commands
+        #[arg(long)]
+        no_trunc: bool,
+
+        /// Output as JSON (for programmatic use)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Compare the layers, environment, labels and configuration of two images
+    Diff {
+        /// Old image name, ID, or tag
+        old: String,
+
+        /// New image name, ID, or tag
+        new: String,
+
+        /// Output as JSON (for programmatic use)
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Add one or more tags to an existing image
+    #[command(alias = "retag")]
+    Tag {
+        /// Source image name, ID, or tag
+        source: String,
+
+        /// New tags (name:tag, repeatable)
+        #[arg(required = true)]
+        targets: Vec<String>,
+    },
+
+    /// Pull an image through the registry mirrors, rewrites and credentials in settings
+    Pull {
+        /// Image reference, e.g. python:3.11-slim or python@sha256:...
+        image: String,
+
+        /// Also tag the pulled image, e.g. to pin a tag to a digest
+        #[arg(long, value_name = "NAME")]
+        tag: Option<String>,
+
+        /// Progress output: per-layer bars, or one JSON event per line for wrapping UIs
+        #[arg(long, value_enum, default_value_t = PullOutput::Text)]
+        output: PullOutput,
+    },
+
     /// Remove a Docker image
     Remove {
         /// Image name, ID, or tag to remove
         image: String,
 
-        /// Force removal even if containers are using the image
+        /// Force removal even if containers are using the image or it is pinned
         #[arg(short, long)]
         force: bool,
     },
+
+    /// Protect an image from `image remove` and `image prune` (lists the pins without arguments)
+    Pin {
+        /// Image tag or ID; a pinned tag covers whichever image it points to
+        #[arg(conflicts_with = "worker")]
+        image: Option<String>,
+
+        /// Pin a worker's current image
+        #[arg(short, long)]
+        worker: Option<String>,
+    },
+
+    /// Remove the pin of an image
+    Unpin {
+        /// Image tag or ID
+        #[arg(required_unless_present = "worker", conflicts_with = "worker")]
+        image: Option<String>,
+
+        /// Unpin a worker's current image
+        #[arg(short, long)]
+        worker: Option<String>,
+    },
+
+    /// Remove unused worker images left behind by rebuilds, keeping pinned ones
+    Prune {
+        /// Also remove tagged worker images no container uses
+        #[arg(short, long)]
+        all: bool,
+
+        /// Only show what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Show the disk cost of each image: what removing it frees, and which images share its layers
+    Usage {
+        /// Include images not built by geoengine, such as base images
+        #[arg(short, long)]
+        all: bool,
+
+        /// Output as JSON (for programmatic use)
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 impl ImageCommands {
@@ -45,46 +254,552 @@ impl ImageCommands {
         let client = DockerClient::new().await?;
 
         match self {
-            Self::Import { tarfile, tag } => {
-                import_image(&client, &tarfile, tag.as_deref()).await
+            Self::Import { source, tag, verify, remap, parallel } => {
+                import_images(&client, &source, tag.as_deref(), verify, &remap, parallel).await
             }
+            Self::Export {
+                images,
+                worker,
+                output,
+                zstd,
+                delta: _,
+                base,
+                sbom,
+            } => export_images(&client, images, &worker, &output, zstd, base.as_deref(), sbom).await,
+            Self::Sbom {
+                image,
+                worker,
+                format,
+                output,
+            } => generate_sbom(&client, image, worker.as_deref(), format, output.as_deref()).await,
             Self::List { filter, all } => list_images(&client, filter.as_deref(), all).await,
+            Self::Inspect { image, json } => inspect_image(&client, &image, json).await,
+            Self::History { image, no_trunc, json } => image_history(&client, &image, no_trunc, json).await,
+            Self::Diff { old, new, json } => diff_images(&client, &old, &new, json).await,
+            Self::Tag { source, targets } => tag_image(&client, &source, &targets).await,
+            Self::Pull { image, tag, output } => pull_image(&client, &image, tag.as_deref(), output).await,
             Self::Remove { image, force } => remove_image(&client, &image, force).await,
+            Self::Pin { image, worker } => pin_image(&client, image, worker.as_deref()).await,
+            Self::Unpin { image, worker } => unpin_image(&client, image, worker.as_deref()).await,
+            Self::Prune { all, dry_run } => prune_images(&client, all, dry_run).await,
+            Self::Usage { all, json } => image_usage(&client, all, json).await,
         }
     }
 }
 
-async fn import_image(client: &DockerClient, tarfile: &PathBuf, tag: Option<&str>) -> Result<()> {
+async fn import_images(
+    client: &DockerClient,
+    source: &str,
+    tag: Option<&str>,
+    verify: bool,
+    remap: &[String],
+    parallel: usize,
+) -> Result<()> {
+    let rules = remap
+        .iter()
+        .map(|rule| {
+            rule.split_once('=')
+                .filter(|(from, to)| !from.is_empty() && !to.is_empty())
+                .map(|(from, to)| (from.to_string(), to.to_string()))
+                .ok_or_else(|| anyhow::anyhow!("Invalid --remap '{}': expected FROM=TO", rule))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let signing = Settings::load()?.signing.unwrap_or_default();
+    let signing = (verify || signing.enforce).then_some(&signing);
+
+    let path = Path::new(source);
+    if path.is_file() {
+        let (images, remapped) = import_image(client, path, tag, signing, &rules, None).await?;
+        for image in &images {
+            println!(
+                "{} Successfully imported image: {}",
+                "✓".green().bold(),
+                image.cyan()
+            );
+        }
+        if let Some(tag) = tag {
+            println!("{} Tagged as {}", "✓".green().bold(), tag.cyan());
+        }
+        for target in &remapped {
+            println!("{} Remapped to {}", "✓".green().bold(), target.cyan());
+        }
+        return Ok(());
+    }
+
+    let archives = find_archives(source)?;
+    if tag.is_some() && archives.len() > 1 {
+        anyhow::bail!("--tag needs a single archive, but {} matched {}", source, archives.len());
+    }
     println!(
-        "{} Importing image from {}...",
+        "{} Importing {} archive(s) from {}...",
         "=>".blue().bold(),
-        tarfile.display()
+        archives.len(),
+        source
     );
 
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")
-            .unwrap(),
+    // Bars of the archives being loaded; finished archives are reported above them
+    let multi = MultiProgress::new();
+    let results: Vec<(PathBuf, Result<Imported>)> = futures::stream::iter(archives)
+        .map(|archive| {
+            let (multi, rules) = (&multi, &rules);
+            async move {
+                let result = import_image(client, &archive, tag, signing, rules, Some(multi)).await;
+                let name = archive.display();
+                let _ = match &result {
+                    Ok((images, remapped)) if remapped.is_empty() => {
+                        multi.println(format!("{} {}: {}", "✓".green().bold(), name, images.join(", ")))
+                    }
+                    Ok((images, remapped)) => multi.println(format!(
+                        "{} {}: {} (remapped to {})",
+                        "✓".green().bold(),
+                        name,
+                        images.join(", "),
+                        remapped.join(", ")
+                    )),
+                    Err(e) => multi.println(format!("{} {}: {:#}", "✗".red().bold(), name, e)),
+                };
+                (archive, result)
+            }
+        })
+        .buffer_unordered(parallel.max(1))
+        .collect()
+        .await;
+    let _ = multi.clear();
+
+    let failed: Vec<&PathBuf> = results.iter().filter(|(_, r)| r.is_err()).map(|(a, _)| a).collect();
+    let images: usize = results.iter().filter_map(|(_, r)| r.as_ref().ok()).map(|(i, _)| i.len()).sum();
+    println!(
+        "\n{} Imported {} image(s) from {} of {} archive(s)",
+        if failed.is_empty() { "✓".green().bold() } else { "!".yellow().bold() },
+        images,
+        results.len() - failed.len(),
+        results.len()
     );
-    pb.set_message("Loading image...");
-    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    if !failed.is_empty() {
+        for archive in &failed {
+            println!("  {} {}", "✗".red().bold(), archive.display());
+        }
+        anyhow::bail!("{} of {} archive(s) failed to import", failed.len(), results.len());
+    }
+    Ok(())
+}
 
-    let image_id = client
-        .import_image(tarfile, tag)
-        .await
-        .context("Failed to import image")?;
+/// Images loaded from an archive and the tags --remap added to them
+type Imported = (Vec<String>, Vec<String>);
+
+/// Verify (when signing settings are given), load and remap one archive,
+/// returning the loaded images and the tags added by --remap
+async fn import_image(
+    client: &DockerClient,
+    tarfile: &Path,
+    tag: Option<&str>,
+    signing: Option<&SigningSettings>,
+    rules: &[(String, String)],
+    multi: Option<&MultiProgress>,
+) -> Result<Imported> {
+    if let Some(signing) = signing {
+        if multi.is_none() {
+            println!(
+                "{} Verifying signature of {}...",
+                "=>".blue().bold(),
+                tarfile.display()
+            );
+        }
+        cosign::verify_blob(tarfile, signing)?;
+        if multi.is_none() {
+            println!("{} Signature verified", "✓".green().bold());
+        }
+    }
+
+    if multi.is_none() {
+        println!(
+            "{} Importing image from {}...",
+            "=>".blue().bold(),
+            tarfile.display()
+        );
+    }
+
+    let pb = byte_progress_bar(0)?;
+    let pb = match multi {
+        Some(multi) => multi.add(pb),
+        None => pb,
+    };
+    let expanded = match expand_archive(client, tarfile, &pb).await {
+        Ok(expanded) => expanded,
+        Err(e) => {
+            pb.finish_and_clear();
+            return Err(e);
+        }
+    };
+    let archive = expanded.clone().unwrap_or_else(|| tarfile.to_path_buf());
+    let result = client.import_image(&archive, tag, &pb).await;
+    pb.finish_and_clear();
+    if let Some(expanded) = &expanded {
+        std::fs::remove_file(expanded).ok();
+    }
+    let images = result.context("Failed to import image")?;
+
+    let mut remapped = Vec::new();
+    for image in &images {
+        if let Some(target) = remap_image(image, rules) {
+            client
+                .tag_image(image, &target)
+                .await
+                .with_context(|| format!("Failed to tag {} as {}", image, target))?;
+            remapped.push(target);
+        }
+    }
+    Ok((images, remapped))
+}
+
+/// A plain tar file to load for a zstd-compressed archive: decompressed, or for a
+/// delta rebuilt with the layers of its base image. None when `tarfile` is plain.
+async fn expand_archive(client: &DockerClient, tarfile: &Path, pb: &ProgressBar) -> Result<Option<PathBuf>> {
+    if !image_archive::is_zstd(tarfile)? {
+        return Ok(None);
+    }
+    let stem = format!("import-{}-{}", std::process::id(), chrono::Utc::now().timestamp_millis());
+    let expanded = paths::get_temp_dir()?.join(format!("{}.tar", stem));
+    let source = tarfile.to_path_buf();
+
+    let Some(manifest) = image_archive::read_delta_manifest(tarfile)? else {
+        pb.set_message("Decompressing");
+        let output = expanded.clone();
+        let result = tokio::task::spawn_blocking(move || image_archive::decompress(&source, &output)).await?;
+        if let Err(e) = result {
+            std::fs::remove_file(&expanded).ok();
+            return Err(e);
+        }
+        return Ok(Some(expanded));
+    };
+
+    // Layers are matched by content, but only the exact base image is sure to have them all
+    let base = if client.image_id(&manifest.base).await.is_ok_and(|id| id == manifest.base_id) {
+        manifest.base.clone()
+    } else if client.image_id(&manifest.base_id).await.is_ok() {
+        manifest.base_id.clone()
+    } else {
+        anyhow::bail!(
+            "{} is a delta against {} ({}), which isn't on this host. Import that image first.",
+            tarfile.display(),
+            manifest.base,
+            manifest.base_id
+        );
+    };
+
+    let base_archive = paths::get_temp_dir()?.join(format!("{}-base.tar", stem));
+    let size = client.inspect_image(&base).await?.size.unwrap_or(0).max(0) as u64;
+    pb.set_length(size);
+    pb.set_position(0);
+    pb.set_message("Reading base image");
+    let exported = client.export_images(std::slice::from_ref(&base), &base_archive, pb).await;
+    let result = match exported {
+        Ok(size) => {
+            pb.set_length(size);
+            pb.set_position(0);
+            pb.set_message("Rebuilding image");
+            let (base_archive, output, pb) = (base_archive.clone(), expanded.clone(), pb.clone());
+            tokio::task::spawn_blocking(move || {
+                image_archive::reconstitute(&source, &manifest, &base_archive, &output, &pb)
+            })
+            .await?
+        }
+        Err(e) => Err(e),
+    };
+    std::fs::remove_file(&base_archive).ok();
+    remove_partial_export(&base_archive);
+    if let Err(e) = result {
+        std::fs::remove_file(&expanded).ok();
+        return Err(e.context("Failed to rebuild the images of the delta"));
+    }
+    Ok(Some(expanded))
+}
+
+/// Tar files named by a directory or a glob in its last path segment, sorted by name
+fn find_archives(source: &str) -> Result<Vec<PathBuf>> {
+    let path = Path::new(source);
+    let (dir, pattern) = if path.is_dir() {
+        (path.to_path_buf(), None)
+    } else {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if !name.contains(['*', '?']) {
+            anyhow::bail!("No such file or directory: {}", source);
+        }
+        let dir = path.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        (dir.to_path_buf(), Some(glob_regex(name)))
+    };
+
+    let mut archives: Vec<PathBuf> = std::fs::read_dir(&dir)
+        .with_context(|| format!("Failed to read directory: {}", dir.display()))?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            match &pattern {
+                Some(pattern) => pattern.is_match(name),
+                None => [".tar", ".tar.gz", ".tgz", ".tar.zst"].iter().any(|ext| name.ends_with(ext)),
+            }
+        })
+        .collect();
+    archives.sort();
+    if archives.is_empty() {
+        anyhow::bail!("No tar files found in {}", source);
+    }
+    Ok(archives)
+}
+
+/// The reference a --remap rule gives an image (longest matching FROM wins), if any
+fn remap_image(image: &str, rules: &[(String, String)]) -> Option<String> {
+    // Untagged images are reported by ID and cannot be remapped
+    if image.starts_with("sha256:") {
+        return None;
+    }
+    // Rules may name images as Docker reports them (postgres:16) or fully (docker.io/library/postgres)
+    let references = [image.to_string(), registry::normalize(image)];
+    let (reference, from, to) = rules
+        .iter()
+        .flat_map(|(from, to)| references.iter().map(move |reference| (reference, from.trim_end_matches('/'), to)))
+        .filter(|(reference, from, _)| {
+            reference.strip_prefix(from).is_some_and(|rest| rest.is_empty() || rest.starts_with(['/', ':']))
+        })
+        .max_by_key(|(_, from, _)| from.len())?;
+    Some(format!("{}{}", to.trim_end_matches('/'), &reference[from.len()..]))
+}
+
+async fn export_images(
+    client: &DockerClient,
+    mut images: Vec<String>,
+    workers: &[String],
+    output: &Path,
+    zstd: bool,
+    base: Option<&str>,
+    sbom: Option<SbomFormat>,
+) -> Result<()> {
+    for worker in workers {
+        let (worker_name, worker_path) = resolve_worker(Some(worker))?;
+        images.push(worker_image(&worker_name)?);
+        // Base images let the worker be rebuilt on the other side
+        let config = yaml_store::load_saved_config(&worker_name)?;
+        let bases = match worker_base_images(&config, &worker_path).await {
+            Ok(bases) => bases,
+            Err(e) => {
+                println!(
+                    "{} Could not read the base images of '{}': {:#}",
+                    "!".yellow().bold(),
+                    worker_name,
+                    e
+                );
+                Vec::new()
+            }
+        };
+        for base in bases {
+            if client.image_id(&base).await.is_ok() {
+                images.push(base);
+            } else {
+                println!(
+                    "{} Base image {} of '{}' is not available locally; skipping it",
+                    "!".yellow().bold(),
+                    base,
+                    worker_name
+                );
+            }
+        }
+    }
+
+    let mut unique: Vec<String> = Vec::new();
+    for image in images {
+        if !unique.contains(&image) {
+            unique.push(image);
+        }
+    }
+    if unique.is_empty() {
+        anyhow::bail!("No images to export. Pass image names or --worker.");
+    }
+    for image in &unique {
+        client
+            .image_id(image)
+            .await
+            .with_context(|| format!("Image not found: {}", image))?;
+    }
+    let base_image = match base {
+        Some(base) => Some(
+            client
+                .inspect_image(base)
+                .await
+                .with_context(|| format!("Base image not found: {}", base))?,
+        ),
+        None => None,
+    };
+    // Compressed archives are made from a plain export in a temporary file next to the
+    // output, unique per export so concurrent exports don't collide
+    let temp_archive = if zstd || base.is_some() {
+        let dir = output.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        let file = tempfile::Builder::new()
+            .prefix(".geoengine-export-")
+            .suffix(".tar")
+            .tempfile_in(dir)
+            .with_context(|| format!("Failed to create a temporary file in {}", dir.display()))?;
+        Some(file.into_temp_path())
+    } else {
+        None
+    };
+    let archive = match &temp_archive {
+        Some(path) => path.to_path_buf(),
+        None => output.to_path_buf(),
+    };
+
+    println!(
+        "{} Exporting {} image(s) to {}...",
+        "=>".blue().bold(),
+        unique.len(),
+        output.display()
+    );
+    for image in &unique {
+        println!("  {} {}", "•".cyan(), image);
+    }
+
+    // The archive holds each layer uncompressed, so its size is about the images' total
+    // (less when they share layers)
+    let mut expected = 0u64;
+    for image in &unique {
+        expected += client.inspect_image(image).await?.size.unwrap_or(0).max(0) as u64;
+    }
+    let partial = partial_export_path(&archive);
+    if let Ok(meta) = std::fs::metadata(&partial) {
+        println!(
+            "{} Resuming the interrupted export in {} ({} written) if its images are unchanged",
+            "=>".blue().bold(),
+            partial.display(),
+            format_size(meta.len() as i64)
+        );
+    }
 
+    let pb = byte_progress_bar(expected)?;
+    pb.set_message("Writing archive");
+    let result = client.export_images(&unique, &archive, &pb).await;
     pb.finish_and_clear();
+    if result.is_err() && temp_archive.is_some() {
+        // Its unique name can't be found again, so it can't be resumed
+        remove_partial_export(&archive);
+    } else if result.is_err() && partial.exists() {
+        eprintln!(
+            "{} The partial archive was kept in {}; run the same command again to resume",
+            "!".yellow().bold(),
+            partial.display()
+        );
+    }
+    let mut size = result.context("Failed to export images")?;
+
+    if archive != output {
+        let pb = byte_progress_bar(size)?;
+        let (source, target) = (archive.clone(), output.to_path_buf());
+        let written = match (base, base_image) {
+            (Some(base), Some(base_image)) => {
+                pb.set_length(2 * size);
+                pb.set_message("Writing delta");
+                let base = base.to_string();
+                let base_id = base_image.id.unwrap_or_default();
+                let base_layers: HashSet<String> =
+                    base_image.root_fs.and_then(|r| r.layers).unwrap_or_default().into_iter().collect();
+                let bar = pb.clone();
+                tokio::task::spawn_blocking(move || {
+                    image_archive::write_delta(&source, &target, &base, &base_id, &base_layers, &bar)
+                })
+                .await?
+                .map(Some)
+            }
+            _ => {
+                pb.set_message("Compressing");
+                let bar = pb.clone();
+                tokio::task::spawn_blocking(move || image_archive::compress(&source, &target, &bar).map(|_| None))
+                    .await?
+            }
+        };
+        pb.finish_and_clear();
+        std::fs::remove_file(&archive).ok();
+        if let Some(stats) = written? {
+            println!(
+                "  {} Left out {} of {} layer(s) already in {}",
+                "•".cyan(),
+                stats.omitted,
+                stats.layers,
+                base.unwrap_or_default()
+            );
+            if stats.omitted == 0 {
+                println!(
+                    "{} The images share no layers with {}; the delta holds them in full",
+                    "!".yellow().bold(),
+                    base.unwrap_or_default()
+                );
+            }
+        }
+        size = std::fs::metadata(output)?.len();
+    }
+
     println!(
-        "{} Successfully imported image: {}",
+        "{} Exported {} image(s) to {} ({})",
         "✓".green().bold(),
-        image_id.cyan()
+        unique.len(),
+        output.display(),
+        format_size(size as i64)
     );
+    if let Some(format) = sbom {
+        for image in &unique {
+            let path = sbom::sidecar_path(output, image, format);
+            sbom::generate(image, format, Some(&path))?;
+            println!("  {} SBOM of {}: {}", "•".cyan(), image, path.display());
+        }
+    }
+    println!("  Load them with: {}", format!("geoengine image import {}", output.display()).cyan());
 
     Ok(())
 }
 
+/// Current image of a worker: geoengine-local/<name>:<version>
+fn worker_image(worker: &str) -> Result<String> {
+    let config = yaml_store::load_saved_config(worker)?;
+    Ok(format!(
+        "geoengine-local/{}:{}",
+        config.name,
+        config.version.as_deref().unwrap_or("latest")
+    ))
+}
+
+async fn generate_sbom(
+    client: &DockerClient,
+    image: Option<String>,
+    worker: Option<&str>,
+    format: SbomFormat,
+    output: Option<&Path>,
+) -> Result<()> {
+    let image = match (image, worker) {
+        (Some(image), _) => image,
+        (None, worker) => worker_image(&resolve_worker(worker)?.0)?,
+    };
+    client
+        .image_id(&image)
+        .await
+        .with_context(|| format!("Image not found: {}", image))?;
+    sbom::generate(&image, format, output)?;
+    if let Some(output) = output {
+        println!("{} Wrote SBOM of {} to {}", "✓".green().bold(), image.cyan(), output.display());
+    }
+    Ok(())
+}
+
+/// Progress bar counting bytes towards `len`, which can be changed once known
+fn byte_progress_bar(len: u64) -> Result<ProgressBar> {
+    let pb = ProgressBar::new(len);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} {msg:<16} [{bar:30.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?
+            .progress_chars("=> "),
+    );
+    pb.enable_steady_tick(std::time::Duration::from_millis(100));
+    Ok(pb)
+}
+
 async fn list_images(client: &DockerClient, filter: Option<&str>, all: bool) -> Result<()> {
     let images = client
         .list_images(filter, all)
@@ -158,7 +873,409 @@ async fn list_images(client: &DockerClient, filter: Option<&str>, all: bool) ->
     Ok(())
 }
 
+/// Image details shown by `image inspect` and compared by `image diff`
+#[derive(Serialize)]
+struct ImageDetails {
+    id: String,
+    tags: Vec<String>,
+    digests: Vec<String>,
+    created: Option<String>,
+    size: i64,
+    platform: String,
+    entrypoint: Vec<String>,
+    cmd: Vec<String>,
+    workdir: Option<String>,
+    user: Option<String>,
+    env: BTreeMap<String, String>,
+    exposed_ports: Vec<String>,
+    labels: BTreeMap<String, String>,
+    layers: Vec<String>,
+}
+
+impl ImageDetails {
+    fn from_inspect(inspect: ImageInspect) -> Self {
+        let config = inspect.config.unwrap_or_default();
+        let env = config
+            .env
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| match e.split_once('=') {
+                Some((k, v)) => (k.to_string(), v.to_string()),
+                None => (e, String::new()),
+            })
+            .collect();
+        let mut exposed_ports: Vec<String> = config.exposed_ports.unwrap_or_default().into_keys().collect();
+        exposed_ports.sort();
+
+        ImageDetails {
+            id: inspect.id.unwrap_or_default(),
+            tags: inspect.repo_tags.unwrap_or_default(),
+            digests: inspect.repo_digests.unwrap_or_default(),
+            created: inspect.created,
+            size: inspect.size.unwrap_or_default(),
+            platform: format!(
+                "{}/{}",
+                inspect.os.unwrap_or_default(),
+                inspect.architecture.unwrap_or_default()
+            ),
+            entrypoint: config.entrypoint.unwrap_or_default(),
+            cmd: config.cmd.unwrap_or_default(),
+            workdir: config.working_dir.filter(|w| !w.is_empty()),
+            user: config.user.filter(|u| !u.is_empty()),
+            env,
+            exposed_ports,
+            labels: config.labels.unwrap_or_default().into_iter().collect(),
+            layers: inspect.root_fs.and_then(|r| r.layers).unwrap_or_default(),
+        }
+    }
+}
+
+async fn inspect_image(client: &DockerClient, image: &str, json: bool) -> Result<()> {
+    let details = ImageDetails::from_inspect(client.inspect_image(image).await?);
+
+    if json {
+        println!("{}", serde_json::to_string(&details)?);
+        return Ok(());
+    }
+
+    let none = || "<none>".dimmed().to_string();
+    println!();
+    println!("{:<12}: {}", "IMAGE".bold(), image.cyan());
+    println!("{:<12}: {}", "ID".bold(), details.id);
+    println!("{:<12}: {}", "TAGS".bold(), details.tags.join(", "));
+    println!("{:<12}: {}", "CREATED".bold(), details.created.clone().unwrap_or_else(none));
+    println!("{:<12}: {}", "SIZE".bold(), format_size(details.size));
+    println!("{:<12}: {}", "PLATFORM".bold(), details.platform);
+    println!("{:<12}: {}", "ENTRYPOINT".bold(), format_command(&details.entrypoint));
+    println!("{:<12}: {}", "CMD".bold(), format_command(&details.cmd));
+    println!("{:<12}: {}", "WORKDIR".bold(), details.workdir.clone().unwrap_or_else(none));
+    println!("{:<12}: {}", "USER".bold(), details.user.clone().unwrap_or_else(none));
+    if !details.exposed_ports.is_empty() {
+        println!("{:<12}: {}", "PORTS".bold(), details.exposed_ports.join(", "));
+    }
+    println!("{:<12}: {}", "LAYERS".bold(), details.layers.len());
+
+    println!();
+    println!("{}", "ENV".bold());
+    println!("{}", "-".repeat(60));
+    for (key, value) in &details.env {
+        println!("{}={}", key, value);
+    }
+
+    println!();
+    if details.labels.is_empty() {
+        println!("{}", "No labels found".yellow());
+        println!();
+        return Ok(());
+    }
+
+    let key_w = details.labels.keys().map(|k| k.len()).max().unwrap_or(5).max(5);
+    println!("{:<key_w$}   {}", "LABEL".bold(), "VALUE".bold(), key_w = key_w);
+    println!("{}", "-".repeat(key_w + 60));
+    for (key, value) in &details.labels {
+        let value = if key == labels::OCI_REVISION && value.ends_with("-dirty") {
+            value.yellow()
+        } else {
+            value.normal()
+        };
+        println!("{:<key_w$}   {}", key, value, key_w = key_w);
+    }
+    println!();
+
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct HistoryEntry {
+    id: String,
+    created: i64,
+    created_by: String,
+    size: i64,
+    comment: String,
+}
+
+async fn image_history(client: &DockerClient, image: &str, no_trunc: bool, json: bool) -> Result<()> {
+    let history: Vec<HistoryEntry> = client
+        .image_history(image)
+        .await?
+        .into_iter()
+        .map(|h| HistoryEntry {
+            id: h.id,
+            created: h.created,
+            created_by: h.created_by,
+            size: h.size,
+            comment: h.comment,
+        })
+        .collect();
+
+    if json {
+        println!("{}", serde_json::to_string(&history)?);
+        return Ok(());
+    }
+
+    println!(
+        "{:<18} {:<12} {}",
+        "CREATED".bold(),
+        "SIZE".bold(),
+        "CREATED BY".bold()
+    );
+    println!("{}", "-".repeat(100));
+    for entry in &history {
+        // Shell-form Dockerfile instructions are recorded as `/bin/sh -c #(nop) ...`
+        let command = entry.created_by.trim();
+        let command = command.strip_prefix("/bin/sh -c #(nop)").unwrap_or(command).trim();
+        let command = if !no_trunc && command.chars().count() > 70 {
+            format!("{}...", command.chars().take(67).collect::<String>())
+        } else {
+            command.to_string()
+        };
+        let size = format_size(entry.size);
+        println!(
+            "{:<18} {:<12} {}",
+            format_timestamp(entry.created),
+            if entry.size == 0 { size.dimmed() } else { size.normal() },
+            command
+        );
+    }
+
+    Ok(())
+}
+
+/// Differences between two key/value maps
+#[derive(Serialize, Default)]
+struct MapDiff {
+    added: BTreeMap<String, String>,
+    removed: BTreeMap<String, String>,
+    changed: BTreeMap<String, (String, String)>,
+}
+
+impl MapDiff {
+    fn compute(old: &BTreeMap<String, String>, new: &BTreeMap<String, String>) -> Self {
+        let mut diff = MapDiff::default();
+        for (key, value) in old {
+            match new.get(key) {
+                None => {
+                    diff.removed.insert(key.clone(), value.clone());
+                }
+                Some(v) if v != value => {
+                    diff.changed.insert(key.clone(), (value.clone(), v.clone()));
+                }
+                _ => {}
+            }
+        }
+        for (key, value) in new {
+            if !old.contains_key(key) {
+                diff.added.insert(key.clone(), value.clone());
+            }
+        }
+        diff
+    }
+
+    fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    fn print(&self, title: &str) {
+        if self.is_empty() {
+            return;
+        }
+        println!();
+        println!("{}", title.bold());
+        for (key, value) in &self.removed {
+            println!("  {} {}={}", "-".red(), key, value);
+        }
+        for (key, value) in &self.added {
+            println!("  {} {}={}", "+".green(), key, value);
+        }
+        for (key, (old, new)) in &self.changed {
+            println!("  {} {}: {} -> {}", "~".yellow(), key, old, new);
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ImageDiff {
+    old: String,
+    new: String,
+    /// Number of leading layers both images share (the common base)
+    shared_layers: usize,
+    removed_layers: Vec<String>,
+    added_layers: Vec<String>,
+    env: MapDiff,
+    labels: MapDiff,
+    /// Other configuration fields that differ: field -> (old, new)
+    config: BTreeMap<String, (String, String)>,
+}
+
+async fn diff_images(client: &DockerClient, old: &str, new: &str, json: bool) -> Result<()> {
+    let a = ImageDetails::from_inspect(client.inspect_image(old).await?);
+    let b = ImageDetails::from_inspect(client.inspect_image(new).await?);
+
+    let shared_layers = a
+        .layers
+        .iter()
+        .zip(&b.layers)
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let mut config = BTreeMap::new();
+    let fields = [
+        ("platform", a.platform.clone(), b.platform.clone()),
+        ("entrypoint", format_command(&a.entrypoint), format_command(&b.entrypoint)),
+        ("cmd", format_command(&a.cmd), format_command(&b.cmd)),
+        ("workdir", a.workdir.clone().unwrap_or_default(), b.workdir.clone().unwrap_or_default()),
+        ("user", a.user.clone().unwrap_or_default(), b.user.clone().unwrap_or_default()),
+        ("exposed_ports", a.exposed_ports.join(", "), b.exposed_ports.join(", ")),
+    ];
+    for (field, x, y) in fields {
+        if x != y {
+            config.insert(field.to_string(), (x, y));
+        }
+    }
+
+    let diff = ImageDiff {
+        old: old.to_string(),
+        new: new.to_string(),
+        shared_layers,
+        removed_layers: a.layers[shared_layers..].to_vec(),
+        added_layers: b.layers[shared_layers..].to_vec(),
+        env: MapDiff::compute(&a.env, &b.env),
+        labels: MapDiff::compute(&a.labels, &b.labels),
+        config,
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&diff)?);
+        return Ok(());
+    }
+
+    println!("{} Comparing {} -> {}", "=>".blue().bold(), old.cyan(), new.cyan());
+    if a.id == b.id {
+        println!("{} Images are identical ({})", "✓".green().bold(), short_image_id(&a.id));
+        return Ok(());
+    }
+
+    println!();
+    println!(
+        "{} {} shared, {} removed, {} added (size {} -> {})",
+        "LAYERS".bold(),
+        diff.shared_layers,
+        diff.removed_layers.len(),
+        diff.added_layers.len(),
+        format_size(a.size),
+        format_size(b.size)
+    );
+    for layer in &diff.removed_layers {
+        println!("  {} {}", "-".red(), layer);
+    }
+    for layer in &diff.added_layers {
+        println!("  {} {}", "+".green(), layer);
+    }
+
+    diff.env.print("ENV");
+    diff.labels.print("LABELS");
+
+    if !diff.config.is_empty() {
+        println!();
+        println!("{}", "CONFIG".bold());
+        for (field, (x, y)) in &diff.config {
+            println!("  {} {}: {} -> {}", "~".yellow(), field, x, y);
+        }
+    }
+    println!();
+
+    Ok(())
+}
+
+/// Render an entrypoint/cmd array, or <none> if empty
+fn format_command(command: &[String]) -> String {
+    if command.is_empty() {
+        "<none>".to_string()
+    } else {
+        serde_json::to_string(command).unwrap_or_default()
+    }
+}
+
+async fn tag_image(client: &DockerClient, source: &str, targets: &[String]) -> Result<()> {
+    client
+        .image_id(source)
+        .await
+        .with_context(|| format!("Image not found: {}", source))?;
+
+    for target in targets {
+        client
+            .tag_image(source, target)
+            .await
+            .with_context(|| format!("Failed to tag {} as {}", source, target))?;
+        println!(
+            "{} Tagged {} as {}",
+            "✓".green().bold(),
+            source.cyan(),
+            target.cyan()
+        );
+    }
+
+    Ok(())
+}
+
+async fn pull_image(client: &DockerClient, image: &str, tag: Option<&str>, output: PullOutput) -> Result<()> {
+    let text = output == PullOutput::Text;
+    if text {
+        println!("{} Pulling {}...", "=>".blue().bold(), image.cyan());
+    }
+
+    let mut progress = output.progress(image);
+    let pulled = registry::pull_with_progress(client, image, &mut progress).await;
+    progress.finish();
+    pulled?;
+
+    if let Some(tag) = tag {
+        client.tag_image(image, tag).await?;
+    }
+    let digest = registry::local_digest(client, image).await?;
+    if !text {
+        let event = serde_json::json!({ "type": "pulled", "image": image, "digest": digest, "tag": tag });
+        println!("{}", event);
+        return Ok(());
+    }
+
+    println!(
+        "{} Successfully pulled image: {}",
+        "✓".green().bold(),
+        image.cyan()
+    );
+    if let Some(tag) = tag {
+        println!("{} Tagged {}", "✓".green().bold(), tag.cyan());
+    }
+    if !image.contains('@') {
+        println!("  Pinned reference: {}@{}", registry::repository(image), digest);
+    }
+
+    Ok(())
+}
+
 async fn remove_image(client: &DockerClient, image: &str, force: bool) -> Result<()> {
+    if !force {
+        let inspect = client.inspect_image(image).await?;
+        let id = inspect.id.unwrap_or_default();
+        let tags = inspect.repo_tags.unwrap_or_default();
+        let pins = pins::load()?;
+        // Removing one of several tags only untags; the image stays
+        let untag_only = tags.len() > 1 && tags.iter().any(|t| t == image);
+        let pin = if untag_only {
+            pins.iter().find(|p| p.reference == image)
+        } else {
+            pins::find(&pins, &id, &tags)
+        };
+        if let Some(pin) = pin {
+            return Err(GeoEngineError::InputInvalid(format!(
+                "Image {} is pinned (as {}). Run 'geoengine image unpin {}' first, or pass --force.",
+                image, pin.reference, pin.reference
+            ))
+            .into());
+        }
+    }
+
     println!("{} Removing image {}...", "=>".blue().bold(), image.cyan());
 
     client
@@ -175,7 +1292,286 @@ async fn remove_image(client: &DockerClient, image: &str, force: bool) -> Result
     Ok(())
 }
 
-fn format_size(bytes: i64) -> String {
+async fn pin_image(client: &DockerClient, image: Option<String>, worker: Option<&str>) -> Result<()> {
+    let image = match (image, worker) {
+        (Some(image), _) => image,
+        (None, Some(worker)) => worker_image(&resolve_worker(Some(worker))?.0)?,
+        (None, None) => return list_pins(client).await,
+    };
+    let id = client.inspect_image(&image).await?.id.unwrap_or_default();
+    let reference = pin_reference(&image, &id);
+    if pins::pin(&reference)? {
+        println!("{} Pinned {} ({})", "✓".green().bold(), reference.cyan(), short_image_id(&id));
+    } else {
+        println!("{} {} is already pinned", "•".cyan(), reference.cyan());
+    }
+    Ok(())
+}
+
+/// What to record for a pin: the full ID for IDs and digest references, else the
+/// tag as Docker lists it (`python` is `python:latest`)
+fn pin_reference(image: &str, id: &str) -> String {
+    let bare = image.strip_prefix("sha256:").unwrap_or(image);
+    if image.contains('@') || id.strip_prefix("sha256:").unwrap_or(id).starts_with(bare) {
+        return id.to_string();
+    }
+    let name = image.rsplit('/').next().unwrap_or(image);
+    if name.contains(':') {
+        image.to_string()
+    } else {
+        format!("{}:latest", image)
+    }
+}
+
+async fn list_pins(client: &DockerClient) -> Result<()> {
+    let pins = pins::load()?;
+    if pins.is_empty() {
+        println!("{}", "No pinned images".yellow());
+        return Ok(());
+    }
+    println!("{:<50} {:<20} {}", "REFERENCE".bold(), "IMAGE ID".bold(), "PINNED".bold());
+    println!("{}", "-".repeat(90));
+    for pin in &pins {
+        let id = match client.image_id(&pin.reference).await {
+            Ok(id) => short_image_id(&id).normal(),
+            Err(_) => "<missing>".dimmed(),
+        };
+        let pinned_at = chrono::DateTime::parse_from_rfc3339(&pin.pinned_at).map(|d| d.timestamp()).unwrap_or_default();
+        let reference = if pin.reference.starts_with("sha256:") { short_image_id(&pin.reference) } else { pin.reference.clone() };
+        println!("{:<50} {:<20} {}", reference.cyan(), id, format_timestamp(pinned_at));
+    }
+    Ok(())
+}
+
+async fn unpin_image(client: &DockerClient, image: Option<String>, worker: Option<&str>) -> Result<()> {
+    let image = match (image, worker) {
+        (Some(image), _) => image,
+        (None, worker) => worker_image(&resolve_worker(worker)?.0)?,
+    };
+    // The image may be gone already; its pin can still be removed by reference
+    let inspect = client.inspect_image(&image).await.ok();
+    let id = inspect.as_ref().and_then(|i| i.id.clone());
+    let tags = inspect.and_then(|i| i.repo_tags).unwrap_or_default();
+    let removed = pins::unpin(&image, id.as_deref(), &tags)?;
+    if removed.is_empty() {
+        println!("{} {} isn't pinned", "!".yellow().bold(), image.cyan());
+    }
+    for reference in removed {
+        println!("{} Unpinned {}", "✓".green().bold(), reference.cyan());
+    }
+    Ok(())
+}
+
+/// Tags of an image, without the `<none>:<none>` of untagged ones
+fn image_tags(image: &ImageSummary) -> Vec<String> {
+    image.repo_tags.iter().filter(|t| *t != "<none>:<none>").cloned().collect()
+}
+
+/// Built by geoengine: labelled with its worker, or tagged under geoengine-local
+fn is_worker_image(image: &ImageSummary) -> bool {
+    image.labels.contains_key(labels::WORKER) || image.repo_tags.iter().any(|t| t.starts_with("geoengine-local"))
+}
+
+/// Bytes in layers no other image uses, i.e. what removing the image frees
+fn unique_size(image: &ImageSummary) -> i64 {
+    (image.size - image.shared_size.max(0)).max(0)
+}
+
+fn display_name(image: &ImageSummary) -> String {
+    image_tags(image).into_iter().next().unwrap_or_else(|| short_image_id(&image.id))
+}
+
+async fn prune_images(client: &DockerClient, all: bool, dry_run: bool) -> Result<()> {
+    let pins = pins::load()?;
+    let (images, before) = client.image_disk_usage().await?;
+    println!("{} Pruning unused worker images...", "=>".blue().bold());
+
+    let mut removed = 0;
+    let mut freed = 0;
+    for image in images.iter().filter(|i| is_worker_image(i) && i.containers <= 0) {
+        let tags = image_tags(image);
+        // Untagged worker images are earlier builds whose tags moved on
+        if !all && !tags.is_empty() {
+            continue;
+        }
+        let name = display_name(image);
+        if let Some(pin) = pins::find(&pins, &image.id, &tags) {
+            println!("  {} Keeping {} (pinned as {})", "•".cyan(), name, pin.reference);
+            continue;
+        }
+        if dry_run {
+            println!("  Would remove {} ({})", name, format_size(unique_size(image)));
+        } else if let Err(e) = client.remove_image(&image.id, true).await {
+            // E.g. another image is built on top of it
+            println!("  {} Skipped {}: {}", "!".yellow().bold(), name, e);
+            continue;
+        } else {
+            println!("  {} Removed {}", "✓".green(), name);
+        }
+        removed += 1;
+        freed += unique_size(image);
+    }
+
+    if removed == 0 {
+        println!("{} Nothing to prune", "✓".green().bold());
+    } else if dry_run {
+        println!(
+            "{} Would remove {} image(s), freeing at least {}",
+            "•".cyan(),
+            removed,
+            format_size(freed)
+        );
+    } else {
+        let (_, after) = client.image_disk_usage().await?;
+        println!(
+            "{} Removed {} image(s), freed {}",
+            "✓".green().bold(),
+            removed,
+            format_size((before - after).max(0))
+        );
+    }
+    Ok(())
+}
+
+/// Disk usage of one image in `image usage`
+#[derive(Serialize)]
+struct ImageUsage {
+    id: String,
+    tags: Vec<String>,
+    worker: Option<String>,
+    size: i64,
+    /// Bytes only this image uses: what removing it frees
+    unique_size: i64,
+    /// Bytes in layers other images use too
+    shared_size: i64,
+    containers: i64,
+    /// Reference of the pin covering the image
+    pinned: Option<String>,
+    shares_layers_with: Vec<SharedLayers>,
+}
+
+#[derive(Serialize)]
+struct SharedLayers {
+    image: String,
+    layers: usize,
+}
+
+#[derive(Serialize)]
+struct UsageReport {
+    images: Vec<ImageUsage>,
+    /// All image layers on disk, shared layers counted once
+    total_size: i64,
+    /// What `image prune --all` frees at least: unique bytes of unused, unpinned worker images
+    reclaimable: i64,
+}
+
+async fn image_usage(client: &DockerClient, all: bool, json: bool) -> Result<()> {
+    let pins = pins::load()?;
+    let (images, total_size) = client.image_disk_usage().await?;
+
+    // Layers of every image, so sharing with base images shows up too
+    let mut layers: Vec<HashSet<String>> = Vec::new();
+    for image in &images {
+        let inspect = client.inspect_image(&image.id).await?;
+        layers.push(inspect.root_fs.and_then(|r| r.layers).unwrap_or_default().into_iter().collect());
+    }
+
+    let mut report = UsageReport {
+        images: Vec::new(),
+        total_size,
+        reclaimable: 0,
+    };
+    for (i, image) in images.iter().enumerate() {
+        let worker_image = is_worker_image(image);
+        if !all && !worker_image {
+            continue;
+        }
+        let tags = image_tags(image);
+        let pinned = pins::find(&pins, &image.id, &tags).map(|p| p.reference.clone());
+        if worker_image && pinned.is_none() && image.containers <= 0 {
+            report.reclaimable += unique_size(image);
+        }
+        let mut shares_layers_with: Vec<SharedLayers> = images
+            .iter()
+            .enumerate()
+            .filter(|(j, _)| *j != i)
+            .map(|(j, other)| SharedLayers {
+                image: display_name(other),
+                layers: layers[i].intersection(&layers[j]).count(),
+            })
+            .filter(|s| s.layers > 0)
+            .collect();
+        shares_layers_with.sort_by(|a, b| b.layers.cmp(&a.layers).then_with(|| a.image.cmp(&b.image)));
+        report.images.push(ImageUsage {
+            id: image.id.clone(),
+            tags,
+            worker: image.labels.get(labels::WORKER).cloned(),
+            size: image.size,
+            unique_size: unique_size(image),
+            shared_size: image.shared_size.max(0),
+            containers: image.containers.max(0),
+            pinned,
+            shares_layers_with,
+        });
+    }
+    report.images.sort_by_key(|i| std::cmp::Reverse(i.unique_size));
+
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+    if report.images.is_empty() {
+        println!("{}", "No images found".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{:<50} {:<14} {:>10} {:>10} {:>10}",
+        "IMAGE".bold(),
+        "IMAGE ID".bold(),
+        "SIZE".bold(),
+        "UNIQUE".bold(),
+        "SHARED".bold()
+    );
+    println!("{}", "-".repeat(100));
+    for image in &report.images {
+        let name = image.tags.first().cloned().unwrap_or_else(|| "<none>".to_string());
+        let mut status = Vec::new();
+        if image.pinned.is_some() {
+            status.push("pinned".cyan());
+        }
+        if image.containers > 0 {
+            status.push("in use".green());
+        }
+        println!(
+            "{:<50} {:<14} {:>10} {:>10} {:>10}  {}",
+            name,
+            short_image_id(&image.id),
+            format_size(image.size),
+            format_size(image.unique_size),
+            format_size(image.shared_size),
+            status.iter().map(|s| s.to_string()).collect::<Vec<_>>().join(", ")
+        );
+        if !image.shares_layers_with.is_empty() {
+            let shared: Vec<String> = image
+                .shares_layers_with
+                .iter()
+                .map(|s| format!("{} ({})", s.image, s.layers))
+                .collect();
+            println!("    {} {}", "shares layers with".dimmed(), shared.join(", ").dimmed());
+        }
+    }
+    println!("{}", "-".repeat(100));
+    println!("Images on disk: {} (shared layers counted once)", format_size(report.total_size).bold());
+    println!(
+        "Reclaimable:    at least {} with 'geoengine image prune --all'",
+        format_size(report.reclaimable).bold()
+    );
+
+    Ok(())
+}
+
+pub fn format_size(bytes: i64) -> String {
     const KB: i64 = 1024;
     const MB: i64 = KB * 1024;
     const GB: i64 = MB * 1024;
@@ -0,0 +1,378 @@
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::Colorize;
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::cli::deploy::remote_command;
+use crate::cli::worker::resolve_worker;
+use crate::config::state;
+use crate::config::worker::{self as worker_config, Accelerator, VolumeKind, WorkerConfig};
+use crate::docker::client;
+use crate::error::GeoEngineError;
+use crate::utils::kubectl::Kubectl;
+
+/// How often the Job's pod is polled while waiting for it to start or finish
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Generate a Kubernetes Job running a worker's command, and optionally submit it
+#[derive(Args)]
+pub struct K8sArgs {
+    /// Worker name (defaults to the worker in the current directory)
+    worker: Option<String>,
+
+    /// Input parameters (KEY=VALUE); file and folder inputs should be paths on a --claim
+    #[arg(short, long = "input")]
+    inputs: Vec<String>,
+
+    /// Image the cluster pulls (defaults to the worker's local build, which only
+    /// clusters sharing the Docker daemon can see; push it with `deploy push` first)
+    #[arg(long)]
+    image: Option<String>,
+
+    /// Mount a PersistentVolumeClaim as CLAIM:PATH (append :ro for read-only); repeatable
+    #[arg(long = "claim", value_name = "CLAIM:PATH")]
+    claims: Vec<String>,
+
+    /// GPUs per job (defaults to 1 if `runtime.accelerator` is cuda, otherwise 0)
+    #[arg(long)]
+    gpus: Option<u32>,
+
+    /// Namespace of the Job
+    #[arg(short, long)]
+    namespace: Option<String>,
+
+    /// kubeconfig context used by --apply (defaults to the current context)
+    #[arg(long)]
+    context: Option<String>,
+
+    /// Write the manifest to this file instead of stdout
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Submit the Job with kubectl and stream its logs until it completes
+    #[arg(long)]
+    apply: bool,
+
+    /// With --apply, give up if the pod hasn't started after this many seconds
+    #[arg(long, default_value_t = 600, value_name = "SECS", requires = "apply")]
+    start_timeout: u64,
+}
+
+pub async fn export_k8s(args: K8sArgs) -> Result<()> {
+    let (name, path) = resolve_worker(args.worker.as_deref())?;
+    let config = WorkerConfig::load(&worker_config::config_file(&path))?;
+    let cmd_config = config
+        .command
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No command defined for worker '{}'", name))?;
+    let version = config.version.clone().unwrap_or("latest".to_string());
+
+    let image = match &args.image {
+        Some(image) => image.clone(),
+        None => {
+            let local = state::load_state(&config.name)
+                .ok()
+                .flatten()
+                .and_then(|s| s.image_tag)
+                .unwrap_or_else(|| format!("geoengine-local/{}:{}", config.name, version));
+            eprintln!(
+                "{} Using the local image {}; pass {} unless the cluster shares this Docker daemon",
+                "!".yellow().bold(),
+                local.cyan(),
+                "--image <registry>/<image>".cyan()
+            );
+            local
+        }
+    };
+
+    let command = remote_command(cmd_config, &args.inputs, "Put it on a volume claim (--claim) and pass its path.")?;
+    let job_name = job_name(&config.name);
+    let manifest = job_manifest(&config, &job_name, &image, &command, &args)?;
+    let yaml = serde_yaml::to_string(&manifest).context("Failed to serialize Job manifest")?;
+
+    if let Some(output) = &args.output {
+        std::fs::write(output, &yaml).with_context(|| format!("Failed to write {}", output.display()))?;
+        eprintln!("{} Wrote {}", "✓".green().bold(), output.display());
+    } else if !args.apply {
+        print!("{}", yaml);
+    }
+
+    if args.apply {
+        let kubectl = Kubectl {
+            context: args.context.clone(),
+            namespace: args.namespace.clone(),
+        };
+        run_job(&kubectl, &job_name, &yaml, Duration::from_secs(args.start_timeout)).await?;
+    }
+    Ok(())
+}
+
+/// Job names must be DNS labels; a timestamp keeps repeated submissions apart
+fn job_name(worker: &str) -> String {
+    let suffix = chrono::Utc::now().format("%Y%m%d-%H%M%S").to_string();
+    let base: String = worker
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .take(63 - suffix.len() - 1)
+        .collect();
+    format!("{}-{}", base.trim_matches('-'), suffix)
+}
+
+/// Kubernetes label value: at most 63 of `[A-Za-z0-9._-]`, starting and ending alphanumeric
+fn label_value(value: &str) -> String {
+    let value: String = value
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-') { c } else { '_' })
+        .take(63)
+        .collect();
+    value.trim_matches(|c: char| !c.is_ascii_alphanumeric()).to_string()
+}
+
+/// Kubernetes quantity for a byte count, in the largest binary unit that divides it
+fn quantity(bytes: i64) -> String {
+    for (unit, size) in [("Gi", 1 << 30), ("Mi", 1 << 20), ("Ki", 1 << 10)] {
+        if bytes % size == 0 {
+            return format!("{}{}", bytes / size, unit);
+        }
+    }
+    bytes.to_string()
+}
+
+fn job_manifest(config: &WorkerConfig, job_name: &str, image: &str, command: &str, args: &K8sArgs) -> Result<Value> {
+    let runtime = config.runtime.as_ref();
+
+    // Resources: requests equal limits so the scheduler reserves what the worker may use
+    let mut limits = serde_json::Map::new();
+    if let Some(cpus) = runtime.and_then(|r| r.cpus) {
+        if cpus <= 0.0 {
+            return Err(GeoEngineError::ConfigInvalid("runtime.cpus must be positive".to_string()).into());
+        }
+        limits.insert("cpu".to_string(), json!(cpus.to_string()));
+    }
+    if let Some(bytes) = runtime.map(|r| r.memory_bytes()).transpose()?.flatten() {
+        limits.insert("memory".to_string(), json!(quantity(bytes)));
+    }
+    let mut gpu_limits = serde_json::Map::new();
+    if let Some(profile) = runtime.and_then(|r| r.mig_profile.as_ref()) {
+        // MIG slices are advertised as their own resource by the NVIDIA device plugin (mixed strategy)
+        gpu_limits.insert(format!("nvidia.com/mig-{}", profile), json!(1));
+    } else {
+        let gpus = args
+            .gpus
+            .unwrap_or(if config.accelerator() == Accelerator::Cuda { 1 } else { 0 });
+        if gpus > 0 {
+            gpu_limits.insert("nvidia.com/gpu".to_string(), json!(gpus));
+        }
+    }
+    let mut resources = json!({});
+    if !limits.is_empty() {
+        resources["requests"] = Value::Object(limits.clone());
+    }
+    limits.extend(gpu_limits);
+    if !limits.is_empty() {
+        resources["limits"] = Value::Object(limits);
+    }
+
+    let env: Vec<Value> = runtime
+        .and_then(|r| r.env.as_ref())
+        .map(|env| env.iter().map(|(k, v)| json!({ "name": k, "value": v })).collect())
+        .unwrap_or_default();
+    if runtime.and_then(|r| r.env_files.as_ref()).is_some_and(|f| !f.is_empty()) {
+        eprintln!(
+            "{} runtime.env_files are not put in the manifest; only runtime.env is",
+            "!".yellow().bold()
+        );
+    }
+
+    // Named volumes become claims of the same name, tmpfs becomes a memory-backed emptyDir
+    let mut volumes: Vec<Value> = Vec::new();
+    let mut mounts: Vec<Value> = Vec::new();
+    for volume in runtime.and_then(|r| r.volumes.as_ref()).into_iter().flatten() {
+        let name = format!("volume-{}", volumes.len());
+        match volume.kind {
+            VolumeKind::Volume => {
+                let claim = volume.name.as_deref().unwrap_or_default();
+                volumes.push(json!({ "name": name, "persistentVolumeClaim": { "claimName": claim } }));
+            }
+            VolumeKind::Tmpfs => {
+                let mut empty_dir = json!({ "medium": "Memory" });
+                if let Some(bytes) = volume.size_bytes()? {
+                    empty_dir["sizeLimit"] = json!(quantity(bytes));
+                }
+                volumes.push(json!({ "name": name, "emptyDir": empty_dir }));
+            }
+        }
+        mounts.push(json!({
+            "name": name,
+            "mountPath": volume.target,
+            "readOnly": volume.readonly.unwrap_or(false),
+        }));
+    }
+    for claim in &args.claims {
+        let (claim_name, rest) = claim.split_once(':').ok_or_else(|| {
+            GeoEngineError::InputInvalid(format!("Invalid claim '{}'. Expected CLAIM:PATH[:ro]", claim))
+        })?;
+        let (mount_path, readonly) = match rest.strip_suffix(":ro") {
+            Some(path) => (path, true),
+            None => (rest, false),
+        };
+        let name = format!("volume-{}", volumes.len());
+        volumes.push(json!({ "name": name, "persistentVolumeClaim": { "claimName": claim_name, "readOnly": readonly } }));
+        mounts.push(json!({ "name": name, "mountPath": mount_path, "readOnly": readonly }));
+    }
+    if config.local_dir_mounts.as_ref().is_some_and(|m| !m.is_empty()) {
+        eprintln!(
+            "{} local_dir_mounts are host paths and are left out; mount the data with {}",
+            "!".yellow().bold(),
+            "--claim CLAIM:PATH".cyan()
+        );
+    }
+
+    let mut container = json!({
+        "name": "worker",
+        "image": image,
+        "imagePullPolicy": "IfNotPresent",
+        "command": ["/bin/sh", "-c", command],
+        "env": env,
+        "resources": resources,
+        "volumeMounts": mounts,
+    });
+    if let Some(security) = runtime.and_then(|r| r.security.as_ref()) {
+        let mut context = json!({});
+        if security.no_new_privileges == Some(true) {
+            context["allowPrivilegeEscalation"] = json!(false);
+        }
+        if security.read_only_rootfs == Some(true) {
+            context["readOnlyRootFilesystem"] = json!(true);
+        }
+        if let Some(caps) = security.cap_drop.as_ref().filter(|c| !c.is_empty()) {
+            context["capabilities"] = json!({ "drop": caps });
+        }
+        container["securityContext"] = context;
+    }
+
+    let labels = json!({
+        "app.kubernetes.io/name": label_value(&config.name),
+        "app.kubernetes.io/version": label_value(config.version.as_deref().unwrap_or("latest")),
+        "app.kubernetes.io/managed-by": "geoengine",
+    });
+    let mut metadata = json!({ "name": job_name, "labels": labels });
+    if let Some(namespace) = &args.namespace {
+        metadata["namespace"] = json!(namespace);
+    }
+
+    Ok(json!({
+        "apiVersion": "batch/v1",
+        "kind": "Job",
+        "metadata": metadata,
+        "spec": {
+            // A failed run is reported, not retried
+            "backoffLimit": 0,
+            "template": {
+                "metadata": { "labels": labels },
+                "spec": {
+                    "restartPolicy": "Never",
+                    "containers": [container],
+                    "volumes": volumes,
+                },
+            },
+        },
+    }))
+}
+
+/// Submit the Job, wait for its pod to start, stream its logs and report how it ended
+async fn run_job(kubectl: &Kubectl, job_name: &str, manifest: &str, start_timeout: Duration) -> Result<()> {
+    println!("{} Creating Job {}...", "=>".blue().bold(), job_name.cyan());
+    kubectl.create(manifest)?;
+    println!("{} Created Job {}", "✓".green().bold(), job_name.cyan());
+
+    // Wait for the pod to leave Pending, surfacing why it is stuck
+    let deadline = tokio::time::Instant::now() + start_timeout;
+    let mut last_reason = String::new();
+    loop {
+        if let Some(pod) = kubectl.job_pod(job_name)? {
+            if pod["status"]["phase"].as_str().unwrap_or("Pending") != "Pending" {
+                break;
+            }
+            let waiting = &pod["status"]["containerStatuses"][0]["state"]["waiting"];
+            let reason = waiting["reason"]
+                .as_str()
+                .or(pod["status"]["conditions"][0]["reason"].as_str())
+                .unwrap_or_default()
+                .to_string();
+            let message = waiting["message"].as_str().unwrap_or(&reason);
+            if matches!(reason.as_str(), "ErrImagePull" | "ImagePullBackOff" | "InvalidImageName") {
+                return Err(GeoEngineError::ImageNotFound(format!(
+                    "The cluster can't pull the worker image: {}. Delete the Job with 'kubectl delete job {}'.",
+                    message, job_name
+                ))
+                .into());
+            }
+            // Kubernetes keeps retrying these, but they don't go away without a fix
+            if matches!(
+                reason.as_str(),
+                "CreateContainerConfigError" | "CreateContainerError" | "RunContainerError"
+            ) {
+                anyhow::bail!(
+                    "The pod can't start ({}): {}. Delete the Job with 'kubectl delete job {}'.",
+                    reason,
+                    message,
+                    job_name
+                );
+            }
+            if !reason.is_empty() && reason != last_reason {
+                println!("  {} Pod pending: {}", "•".cyan(), reason);
+                last_reason = reason;
+            }
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!(
+                "The pod didn't start within {}s{}. Check it with 'kubectl describe job {}' and delete it with \
+                 'kubectl delete job {}', or allow longer with --start-timeout.",
+                start_timeout.as_secs(),
+                if last_reason.is_empty() { String::new() } else { format!(" (last reason: {})", last_reason) },
+                job_name,
+                job_name
+            );
+        }
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = client::shutdown_signal() => {
+                return Err(GeoEngineError::Interrupted).with_context(|| {
+                    format!(
+                        "Interrupted while waiting for the pod to start; the Job is still there. \
+                         Delete it with 'kubectl delete job {}'.",
+                        job_name
+                    )
+                });
+            }
+        }
+    }
+
+    kubectl.follow_logs(job_name)?;
+
+    // The Job's status can lag behind its container exiting
+    loop {
+        let job = kubectl.job(job_name)?;
+        if job["status"]["succeeded"].as_i64().unwrap_or(0) > 0 {
+            println!("{} Job {} completed", "✓".green().bold(), job_name.cyan());
+            return Ok(());
+        }
+        if job["status"]["failed"].as_i64().unwrap_or(0) > 0 {
+            let exit_code = kubectl
+                .job_pod(job_name)?
+                .and_then(|pod| pod["status"]["containerStatuses"][0]["state"]["terminated"]["exitCode"].as_i64())
+                .unwrap_or(1);
+            println!("{} Job {} failed", "✗".red().bold(), job_name.cyan());
+            return Err(GeoEngineError::ContainerFailed {
+                worker: job_name.to_string(),
+                exit_code,
+            }
+            .into());
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -1,10 +1,39 @@
+pub mod audit;
+pub mod aws_batch;
+pub mod batch;
+pub mod bundle;
+pub mod config;
+pub mod cp;
+pub mod data;
 pub mod deploy;
+pub mod doctor;
+pub mod extensions;
+pub mod gpu;
+pub mod history;
+pub mod hooks;
 pub mod image;
+pub mod k8s;
+pub mod output_rules;
 pub mod plugins;
+pub mod preview;
+pub mod service;
+pub mod stats;
+pub mod test;
+pub mod vertex;
+pub mod volume;
+pub mod wizard;
 pub mod worker;
 
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use crate::config::audit as audit_config;
+use crate::config::worker::{self as worker_config, ConfigFormat};
+use crate::error::GeoEngineError;
+use crate::utils::paths;
+use crate::utils::versioning::VersionBump;
 
 #[derive(Parser)]
 #[command(name = "geoengine")]
@@ -19,6 +48,14 @@ pub struct Cli {
     /// Enable verbose output
     #[arg(short, long, global = true)]
     verbose: bool,
+
+    /// Fail on worker configs with an outdated schema_version instead of warning
+    #[arg(long, global = true)]
+    strict: bool,
+
+    /// Settings profile to use (see `geoengine config profile`)
+    #[arg(long, global = true, env = "GEOENGINE_PROFILE")]
+    profile: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -29,11 +66,35 @@ enum Commands {
         command: image::ImageCommands,
     },
 
+    /// Manage named volumes used by workers (list, rm, prune)
+    Volume {
+        #[command(subcommand)]
+        command: volume::VolumeCommands,
+    },
+
     /// Initialize a new worker (creates geoengine.yaml)
     Init {
         /// Worker name, if not specified, uses current directory name.
         #[arg(short, long)]
         name: Option<String>,
+
+        /// Format of the config file to create
+        #[arg(long, value_enum, default_value_t = ConfigFormat::Yaml)]
+        format: ConfigFormat,
+
+        /// Walk through base image, GPU, mounts and inputs, and also create a starter Dockerfile and script
+        #[arg(short, long)]
+        interactive: bool,
+    },
+
+    /// Migrate a worker's config file to the current schema (keeps a backup)
+    Upgrade {
+        /// Worker name or path (defaults to the current directory)
+        worker: Option<String>,
+
+        /// Print the migrated config instead of writing it
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Build the Docker image for a worker
@@ -49,6 +110,36 @@ enum Commands {
         /// Build arguments (format: KEY=VALUE)
         #[arg(long, value_name = "KEY=VALUE")]
         build_arg: Vec<String>,
+
+        /// Increment the version in geoengine.yaml before building
+        #[arg(long, value_enum, conflicts_with = "dev")]
+        bump: Option<VersionBump>,
+
+        /// Fail if a base image's digest differs from geoengine.lock
+        #[arg(long)]
+        locked: bool,
+
+        /// Multi-stage Dockerfile stage to build (overrides build.target)
+        #[arg(long, value_name = "STAGE")]
+        target: Option<String>,
+
+        /// Build only this variant from build.variants (repeatable; default: all)
+        #[arg(long = "variant", value_name = "NAME")]
+        variants: Vec<String>,
+
+        /// Run command.check (default: the script with --help) in each new image and fail if it fails
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Download pip wheels / a packed conda env into vendor/ for offline builds
+    BundleDeps {
+        /// Worker name (defaults to current directory's worker)
+        worker: Option<String>,
+
+        /// Image to download dependencies in (defaults to the Dockerfile's base image)
+        #[arg(long)]
+        image: Option<String>,
     },
 
     /// Apply worker configuration: register if new, update plugins
@@ -57,6 +148,23 @@ enum Commands {
         worker: Option<String>,
     },
 
+    /// Clone a worker from a git repository and register it
+    Clone {
+        /// Git repository URL
+        url: String,
+
+        /// Directory to clone into (defaults to the repository name)
+        dir: Option<PathBuf>,
+
+        /// Branch, tag or commit to check out
+        #[arg(long = "ref", value_name = "REF")]
+        git_ref: Option<String>,
+
+        /// Build the image after registering
+        #[arg(long)]
+        build: bool,
+    },
+
     /// Delete a worker from GeoEngine
     Delete {
         /// Worker name to delete. If not provided, uses current directory's worker.
@@ -65,25 +173,114 @@ enum Commands {
     },
 
     /// Run a worker's command with input parameters
-    Run {
+    Run(Box<worker::RunArgs>),
+
+    /// Start the services defined in a worker's geoengine.yaml in the background
+    Up {
+        /// Worker name (defaults to current directory's worker)
+        worker: Option<String>,
+
+        /// Only start these services (repeatable)
+        #[arg(short, long)]
+        service: Vec<String>,
+
+        /// Attach the services to this existing network instead of creating geoengine-<worker>
+        #[arg(long, value_name = "NAME")]
+        network: Option<String>,
+    },
+
+    /// Stop and remove a worker's services and detached runs
+    Down {
+        /// Worker name (defaults to current directory's worker)
+        worker: Option<String>,
+
+        /// Only stop these services (repeatable)
+        #[arg(short, long)]
+        service: Vec<String>,
+    },
+
+    /// Show the output of a detached run or service container
+    Logs {
+        /// Container name
+        name: String,
+
+        /// Keep streaming new output (Ctrl-C to stop)
+        #[arg(short, long)]
+        follow: bool,
+
+        /// Number of lines to show from the end of the logs
+        #[arg(long, value_name = "N")]
+        tail: Option<usize>,
+    },
+
+    /// Stream a detached run's output until it exits (Ctrl-C detaches)
+    Attach {
+        /// Container name
+        name: String,
+    },
+
+    /// Copy files or directories between a container and the host
+    /// (`<container>:<path> <dest>` or `<src> <container>:<path>`)
+    Cp {
+        /// Source: a host path or <container>:<path>
+        source: String,
+
+        /// Destination: a host path or <container>:<path>
+        dest: String,
+    },
+
+    /// Run the test cases defined in a worker's geoengine.yaml
+    Test {
         /// Worker name (defaults to current directory's worker)
         worker: Option<String>,
 
-        /// Input parameters (format: KEY=VALUE, repeatable)
-        #[arg(short, long = "input", value_name = "KEY=VALUE")]
-        inputs: Vec<String>,
+        /// Only run these tests (repeatable)
+        #[arg(short, long = "test", value_name = "NAME")]
+        tests: Vec<String>,
+
+        /// Test the latest dev version of the worker
+        #[arg(long)]
+        dev: bool,
 
-        /// Emit structured JSON result to stdout (logs go to stderr)
+        /// Keep the outputs and logs of passing tests
+        #[arg(long)]
+        keep: bool,
+
+        /// Output the report as JSON (for CI)
         #[arg(long)]
         json: bool,
-        
-        /// Runs the latest dev version of the worker
+    },
+
+    /// Show output rasters and vectors on a map in the browser (TiTiler + Leaflet)
+    Preview {
+        /// File, directory, or history id of a run whose output folders to show
+        target: String,
+
+        /// Local port for the TiTiler tile server
+        #[arg(long, default_value = "8765")]
+        port: u16,
+
+        /// Print the page's path instead of opening a browser
         #[arg(long)]
-        dev: bool,
+        no_browser: bool,
+    },
+
+    /// Maintenance of services, detached runs and their leftovers
+    Service {
+        #[command(subcommand)]
+        command: service::ServiceCommands,
+    },
+
+    /// Show and re-run past runs recorded in ~/.geoengine/history.jsonl
+    History {
+        #[command(subcommand)]
+        command: history::HistoryCommands,
+    },
 
-        /// Extra arguments passed through to the container command
-        #[arg(last = true)]
-        args: Vec<String>,
+    /// List and verify the audit log of runs and administrative commands
+    Audit {
+        #[command(subcommand)]
+        command: audit::AuditCommands,
     },
 
     /// List all registered workers
@@ -95,6 +292,10 @@ enum Commands {
         /// List only workers registered in the GIS plugin (takes in only "qgis" or "arcgis")
         #[arg(long)]
         gis: Option<String>,
+
+        /// Show each worker's version, image, whether it changed since the last build, and its last run
+        #[arg(long)]
+        status: bool,
     },
     
     /// Describe a specific worker
@@ -116,46 +317,212 @@ enum Commands {
         /// - "worker" to check only the worker directory itself
         #[arg(short, long)]
         file: Option<String>,
+
+        /// Compare against the labels of the last built image instead of the last apply,
+        /// and check the Dockerfile's base images for newer digests upstream
+        #[arg(long)]
+        image: bool,
     },
 
-    /// Deploy images to GCP Artifact Registry
+    /// Deploy images to GCP Artifact Registry or GHCR, or run workers on AWS Batch or Kubernetes
     Deploy {
         #[command(subcommand)]
         command: deploy::DeployCommands,
     },
 
+    /// Manage GeoEngine settings (profiles)
+    Config {
+        #[command(subcommand)]
+        command: config::ConfigCommands,
+    },
+
+    /// Work with input data (checksum manifests, dataset downloads)
+    Data {
+        #[command(subcommand)]
+        command: data::DataCommands,
+    },
+
+    /// Check Docker, GPUs, disk space, settings, workers, services and registry credentials
+    Doctor {
+        /// Output the checks as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Inspect GPUs and diagnose GPU passthrough (info, doctor)
+    Gpu {
+        #[command(subcommand)]
+        command: gpu::GpuCommands,
+    },
+
+    /// Live dashboard of running worker containers, GPU utilization and disk usage
+    Stats {
+        /// Refresh interval in seconds
+        #[arg(long, default_value = "2")]
+        interval: u64,
+    },
+
+    /// List extension commands (geoengine-<command> programs on PATH or in the settings)
+    Extensions {
+        /// Output as JSON (for programmatic use)
+        #[arg(long)]
+        json: bool,
+    },
+
     /// Debug helper: install the QGIS plugin only if not already installed
     DebugQgis,
+
+    /// Any other command runs the extension geoengine-<command>
+    #[command(external_subcommand)]
+    External(Vec<String>),
 }
 
 impl Cli {
+    /// Select the settings profile before anything reads settings
+    pub fn select_profile(&self) {
+        paths::set_profile(self.profile.clone());
+    }
+
+    #[tracing::instrument(name = "geoengine", skip_all)]
     pub async fn execute(self) -> Result<()> {
-        match self.command {
+        worker_config::set_strict(self.strict);
+        let audited = audit_config::settings().and_then(|_| audit_event(&self.command));
+        let result = Self::dispatch(self.command, self.verbose).await;
+        if let Some((event, worker)) = audited {
+            audit_config::log(event, worker.as_deref(), outcome_details(&result));
+        }
+        if let Err(e) = &result {
+            if let Some(GeoEngineError::RegistryAuth(_)) = e.downcast_ref::<GeoEngineError>() {
+                audit_config::log("auth.failed", None, outcome_details(&result));
+            }
+        }
+        result
+    }
+
+    async fn dispatch(command: Commands, verbose: bool) -> Result<()> {
+        match command {
             Commands::Image { command } => command.execute().await,
-            Commands::Init { name } => {
-                worker::init_worker(name.as_deref()).await
+            Commands::Volume { command } => command.execute().await,
+            Commands::Init { name, format, interactive } => {
+                worker::init_worker(name.as_deref(), format, interactive).await
             }
+            Commands::Upgrade { worker, dry_run } => worker::upgrade_worker(worker.as_deref(), dry_run).await,
             Commands::Build {
                 no_cache,
                 dev,
                 build_arg,
-            } => worker::build_worker_local(no_cache, dev, &build_arg).await,
+                bump,
+                locked,
+                target,
+                variants,
+                check,
+            } => {
+                worker::build_worker_local(no_cache, dev, &build_arg, bump, locked, target.as_deref(), &variants, check)
+                    .await
+            }
+            Commands::BundleDeps { worker, image } => {
+                bundle::bundle_deps(worker.as_deref(), image.as_deref()).await
+            }
             Commands::Apply { worker } => {
                 worker::apply_worker(worker.as_deref(), false).await
             }
+            Commands::Clone { url, dir, git_ref, build } => {
+                worker::clone_worker(&url, git_ref.as_deref(), dir.as_deref(), build).await
+            }
             Commands::Delete { name } => worker::delete_worker(name.as_deref()).await,
-            Commands::Run {
-                worker,
-                inputs,
-                json,
-                dev,
-                args,
-            } => worker::run_worker(worker.as_deref(), &inputs, json, dev, &args).await,
-            Commands::Workers { json, gis } => worker::list_workers(json, gis).await,
+            Commands::Run(args) => worker::run_worker(&args).await,
+            Commands::Up { worker, service, network } => {
+                service::up(worker.as_deref(), &service, network.as_deref()).await
+            }
+            Commands::Down { worker, service } => service::down(worker.as_deref(), &service).await,
+            Commands::Logs { name, follow, tail } => service::logs(&name, follow, tail).await,
+            Commands::Attach { name } => service::attach(&name).await,
+            Commands::Cp { source, dest } => cp::cp(&source, &dest).await,
+            Commands::Test { worker, tests, dev, keep, json } => {
+                test::run_tests(worker.as_deref(), &tests, dev, keep, json).await
+            }
+            Commands::Preview { target, port, no_browser } => preview::preview(&target, port, no_browser).await,
+            Commands::Service { command } => command.execute().await,
+            Commands::History { command } => command.execute().await,
+            Commands::Audit { command } => command.execute().await,
+            Commands::Workers { json, gis, status } => worker::list_workers(json, gis, status).await,
             Commands::Describe { worker, json } => worker::describe_worker(worker.as_deref(), json).await,
-            Commands::Diff { file } => worker::diff_worker(file.as_deref()).await,
+            Commands::Diff { file, image } => worker::diff_worker(file.as_deref(), image).await,
             Commands::Deploy { command } => command.execute().await,
+            Commands::Config { command } => command.execute().await,
+            Commands::Data { command } => command.execute().await,
+            Commands::Doctor { json } => doctor::run_doctor(json).await,
+            Commands::Gpu { command } => command.execute().await,
+            Commands::Stats { interval } => stats::run_stats(interval).await,
+            Commands::Extensions { json } => extensions::list(json),
             Commands::DebugQgis => plugins::debug_qgis().await,
+            Commands::External(args) => extensions::run(&args, verbose).await,
         }
     }
 }
+
+/// Commands that change workers, images, deployments or settings, with the worker
+/// they act on. These are recorded in the audit log; read-only commands are not.
+fn audit_event(command: &Commands) -> Option<(&'static str, Option<String>)> {
+    let cwd_worker = || worker::resolve_worker(None).ok().map(|(name, _)| name);
+    let event = match command {
+        Commands::Apply { worker } => ("worker.apply", worker.clone().or_else(cwd_worker)),
+        Commands::Build { .. } => ("worker.build", cwd_worker()),
+        Commands::Delete { name } => ("worker.delete", name.clone().or_else(cwd_worker)),
+        Commands::Upgrade { worker, dry_run: false } => ("worker.upgrade", worker.clone().or_else(cwd_worker)),
+        Commands::Clone { .. } => ("worker.clone", None),
+        Commands::Image { command: image::ImageCommands::Import { .. } } => ("image.import", None),
+        Commands::Image { command: image::ImageCommands::Pull { .. } } => ("image.pull", None),
+        Commands::Image { command: image::ImageCommands::Tag { .. } } => ("image.tag", None),
+        Commands::Image { command: image::ImageCommands::Remove { .. } } => ("image.remove", None),
+        Commands::Image {
+            command: image::ImageCommands::Prune { dry_run: false, .. },
+        } => ("image.prune", None),
+        Commands::Image {
+            command: image::ImageCommands::Pin { image, worker },
+        } if image.is_some() || worker.is_some() => ("image.pin", None),
+        Commands::Image { command: image::ImageCommands::Unpin { .. } } => ("image.unpin", None),
+        Commands::Volume {
+            command: volume::VolumeCommands::Rm { .. } | volume::VolumeCommands::Prune,
+        } => ("volume.remove", None),
+        Commands::Deploy { command } => match command {
+            deploy::DeployCommands::List { .. } | deploy::DeployCommands::Verify { .. } => return None,
+            deploy::DeployCommands::Auth { .. } => ("deploy.auth", None),
+            deploy::DeployCommands::Push { .. } => ("deploy.push", None),
+            deploy::DeployCommands::Pull { .. } => ("deploy.pull", None),
+            deploy::DeployCommands::Sign { .. } => ("deploy.sign", None),
+            deploy::DeployCommands::Promote { .. } => ("deploy.promote", None),
+            deploy::DeployCommands::AwsBatch { .. } => ("deploy.aws-batch", None),
+            deploy::DeployCommands::K8s(_) => ("deploy.k8s", None),
+            deploy::DeployCommands::Vertex { .. } => ("deploy.vertex", None),
+            deploy::DeployCommands::Delete { .. } => ("deploy.delete", None),
+        },
+        Commands::Config {
+            command: config::ConfigCommands::Profile {
+                command: config::ProfileCommands::List,
+            },
+        } => return None,
+        Commands::Config { .. } => ("config.profile", None),
+        Commands::Service {
+            command: service::ServiceCommands::Reap { dry_run: false },
+        } => ("reap", None),
+        Commands::External(_) => ("extension.run", extensions::current_worker().map(|(name, _)| name)),
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// The command line and whether it succeeded
+fn outcome_details(result: &Result<()>) -> BTreeMap<String, serde_json::Value> {
+    let mut details = BTreeMap::new();
+    let args: Vec<String> = std::env::args().collect();
+    details.insert("command".to_string(), audit_config::redact_args(&args).join(" ").into());
+    details.insert(
+        "outcome".to_string(),
+        if result.is_ok() { "ok" } else { "error" }.into(),
+    );
+    if let Err(e) = result {
+        details.insert("error".to_string(), format!("{:#}", e).into());
+    }
+    details
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::config::worker::{OutputRule, WorkerConfig};
+use crate::docker::client::DockerClient;
+use crate::docker::geometa::{self, GeoMetadata};
+use crate::docker::usage::format_bytes;
+
+/// Check the files a successful run wrote against the worker's `outputs.validate`
+/// rules. Returns one message per violation; empty when the outputs are fine.
+/// Georeferencing already read for the run (`--geo-metadata`) is passed as `known`.
+pub async fn validate_outputs(
+    client: &DockerClient,
+    config: &WorkerConfig,
+    inputs: &HashMap<String, String>,
+    files: &[PathBuf],
+    known: &HashMap<PathBuf, Option<GeoMetadata>>,
+) -> Vec<String> {
+    let rules: Vec<OutputRule> = config
+        .outputs
+        .as_ref()
+        .and_then(|o| o.validate.clone())
+        .unwrap_or_default();
+    if rules.is_empty() {
+        return Vec::new();
+    }
+
+    let mut failures = Vec::new();
+    let mut matched: Vec<(&OutputRule, Vec<(String, &PathBuf)>)> = Vec::new();
+    for rule in &rules {
+        match matching_files(rule, config, inputs, files) {
+            Ok(found) => {
+                if found.is_empty() && rule.must_exist.unwrap_or(true) {
+                    failures.push(format!("{}: not written by the run", rule.path));
+                }
+                matched.push((rule, found));
+            }
+            Err(failure) => failures.push(failure),
+        }
+    }
+
+    // One GDAL container reads every file a format/crs/band_count rule needs
+    let mut geo_files: Vec<PathBuf> = matched
+        .iter()
+        .filter(|(rule, _)| rule.needs_geo_metadata())
+        .flat_map(|(_, found)| found.iter().map(|(_, path)| (*path).clone()))
+        .filter(|path| !known.contains_key(path))
+        .collect();
+    geo_files.sort();
+    geo_files.dedup();
+    let geo: Result<HashMap<PathBuf, Option<GeoMetadata>>, String> = if geo_files.is_empty() {
+        Ok(known.clone())
+    } else {
+        geometa::extract(client, &geo_files)
+            .await
+            .map(|geo| known.clone().into_iter().chain(geo_files.iter().cloned().zip(geo)).collect())
+            .map_err(|e| format!("{:#}", e))
+    };
+
+    for (rule, found) in &matched {
+        let min_size = match rule.min_size_bytes() {
+            Ok(min_size) => min_size,
+            Err(e) => {
+                failures.push(format!("{:#}", e));
+                continue;
+            }
+        };
+        for (name, path) in found {
+            if let Some(min_size) = min_size {
+                let size = std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+                if (size as i64) < min_size {
+                    failures.push(format!(
+                        "{}: {} (expected at least {})",
+                        name,
+                        format_bytes(size),
+                        format_bytes(min_size as u64)
+                    ));
+                }
+            }
+            if !rule.needs_geo_metadata() {
+                continue;
+            }
+            match &geo {
+                Ok(geo) => failures.extend(check_geo(rule, name, geo.get(*path).cloned().flatten())),
+                Err(e) => failures.push(format!("{}: could not read geospatial metadata: {}", name, e)),
+            }
+        }
+    }
+    failures
+}
+
+/// Files written by the run that match the rule's path, with their `<input>/<relative>` names
+fn matching_files<'a>(
+    rule: &OutputRule,
+    config: &WorkerConfig,
+    inputs: &HashMap<String, String>,
+    files: &'a [PathBuf],
+) -> Result<Vec<(String, &'a PathBuf)>, String> {
+    let (input, pattern) = rule
+        .path
+        .split_once('/')
+        .ok_or_else(|| format!("{}: expected <folder input>/<path>", rule.path))?;
+    let is_folder = config
+        .command
+        .as_ref()
+        .and_then(|c| c.inputs.as_ref())
+        .is_some_and(|params| params.iter().any(|p| p.name == input && p.param_type == "folder"));
+    if !is_folder {
+        return Err(format!("{}: '{}' is not a folder input", rule.path, input));
+    }
+    let Some(dir) = inputs.get(input).and_then(|value| Path::new(value).canonicalize().ok()) else {
+        // An optional output folder that wasn't given can't hold outputs
+        return Ok(Vec::new());
+    };
+    let pattern = glob_regex(pattern);
+
+    Ok(files
+        .iter()
+        .filter_map(|path| {
+            let relative = path.strip_prefix(&dir).ok()?.to_string_lossy().replace('\\', "/");
+            pattern
+                .is_match(&relative)
+                .then(|| (format!("{}/{}", input, relative), path))
+        })
+        .collect())
+}
+
+/// Regex matching a glob where `*` and `?` stay within one path segment
+pub fn glob_regex(glob: &str) -> regex::Regex {
+    let mut pattern = String::from("^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str("[^/]*"),
+            '?' => pattern.push_str("[^/]"),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    regex::Regex::new(&pattern).expect("escaped glob is a valid regex")
+}
+
+fn check_geo(rule: &OutputRule, name: &str, geo: Option<GeoMetadata>) -> Vec<String> {
+    let Some(geo) = geo else {
+        return vec![format!("{}: not a raster or vector file GDAL can read", name)];
+    };
+    let mut failures = Vec::new();
+    if let Some(expected) = &rule.format {
+        let driver = geo.driver.as_deref().unwrap_or("unknown");
+        if !driver.eq_ignore_ascii_case(expected) {
+            failures.push(format!("{}: format {} (expected {})", name, driver, expected));
+        }
+    }
+    if let Some(expected) = &rule.crs {
+        match &geo.crs {
+            Some(crs) if crs.eq_ignore_ascii_case(expected.trim()) => {}
+            Some(crs) => failures.push(format!("{}: CRS {} (expected {})", name, crs, expected)),
+            None => failures.push(format!("{}: no CRS (expected {})", name, expected)),
+        }
+    }
+    if let Some(expected) = rule.band_count {
+        match geo.band_count {
+            Some(bands) if bands == expected => {}
+            Some(bands) => failures.push(format!("{}: {} band(s) (expected {})", name, bands, expected)),
+            None => failures.push(format!("{}: not a raster (expected {} band(s))", name, expected)),
+        }
+    }
+    failures
+}
@@ -0,0 +1,484 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::config::history;
+use crate::config::provenance::PROVENANCE_FILE;
+use crate::config::settings::Settings;
+use crate::docker::client::{self, DockerClient};
+use crate::docker::config::ContainerConfig;
+use crate::docker::geometa::{self, GeoMetadata};
+use crate::docker::{host, registry, user};
+use crate::error::GeoEngineError;
+use crate::utils::paths;
+
+/// TiTiler image serving raster tiles, used when `titiler_image` is not set
+pub const DEFAULT_TITILER_IMAGE: &str = "ghcr.io/developmentseed/titiler:latest";
+
+/// Port TiTiler listens on inside its container
+const TITILER_PORT: u16 = 8000;
+
+/// How long to wait for TiTiler to answer its health check
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Layers shown at most; a map with more isn't readable anyway
+const MAX_LAYERS: usize = 20;
+
+const LEAFLET_VERSION: &str = "1.9.4";
+
+/// A raster or vector file to show on the map
+struct Layer {
+    path: PathBuf,
+    meta: GeoMetadata,
+}
+
+/// Show the rasters and vectors at `target` (a file, a directory or a history id)
+/// on a Leaflet map in the browser until Ctrl-C
+pub async fn preview(target: &str, port: u16, no_browser: bool) -> Result<()> {
+    let mut files = resolve_target(target)?;
+    if files.is_empty() {
+        return Err(GeoEngineError::InputInvalid(format!("No files found in {}", target)).into());
+    }
+
+    let client = DockerClient::new().await?;
+    eprintln!("{} Inspecting {} file(s)", "=>".blue().bold(), files.len());
+    let metas = geometa::extract(&client, &files).await?;
+    let mut layers: Vec<Layer> = files
+        .drain(..)
+        .zip(metas)
+        .filter_map(|(path, meta)| meta.map(|meta| Layer { path, meta }))
+        .collect();
+    if layers.is_empty() {
+        return Err(GeoEngineError::InputInvalid(format!("No raster or vector files in {}", target)).into());
+    }
+    if layers.len() > MAX_LAYERS {
+        eprintln!(
+            "{} Showing the first {} of {} layers",
+            "!".yellow().bold(),
+            MAX_LAYERS,
+            layers.len()
+        );
+        layers.truncate(MAX_LAYERS);
+    }
+
+    let dir = paths::get_temp_dir()?.join(format!(
+        "preview-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_millis()
+    ));
+    std::fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let mut container = None;
+    let result = serve(&client, &layers, &dir, port, no_browser, &mut container).await;
+
+    if let Some(id) = container {
+        if let Err(e) = client.remove_container(&id, true).await {
+            tracing::debug!("Failed to remove the TiTiler container: {:#}", e);
+        }
+    }
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+async fn serve(
+    client: &DockerClient,
+    layers: &[Layer],
+    dir: &Path,
+    port: u16,
+    no_browser: bool,
+    container: &mut Option<String>,
+) -> Result<()> {
+    let vectors: Vec<(usize, &Layer)> = layers.iter().enumerate().filter(|(_, l)| l.meta.kind == "vector").collect();
+    let rasters: Vec<(usize, &Layer)> = layers.iter().enumerate().filter(|(_, l)| l.meta.kind == "raster").collect();
+
+    if !vectors.is_empty() {
+        eprintln!("{} Converting {} vector layer(s) to GeoJSON", "=>".blue().bold(), vectors.len());
+        convert_vectors(client, &vectors, dir).await?;
+    }
+
+    // Rasters are tiled on the fly by TiTiler, which reads the files where they are
+    let raster_files: Vec<PathBuf> = rasters.iter().map(|(_, l)| l.path.clone()).collect();
+    let (raster_dirs, raster_paths) = geometa::data_mounts(&raster_files);
+    if !rasters.is_empty() {
+        eprintln!("{} Starting TiTiler on port {}", "=>".blue().bold(), port);
+        *container = Some(start_titiler(client, &raster_dirs, port).await?);
+        wait_for_titiler(port).await?;
+    }
+
+    let mut entries = Vec::new();
+    for (i, layer) in layers.iter().enumerate() {
+        let name = layer.path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let entry = match layer.meta.kind.as_str() {
+            "raster" => {
+                let position = rasters.iter().position(|(r, _)| *r == i).unwrap_or_default();
+                serde_json::json!({
+                    "kind": "raster",
+                    "name": name,
+                    "url": raster_paths[position],
+                    "bounds": layer.meta.wgs84_extent,
+                })
+            }
+            _ if dir.join(format!("layer-{}.js", i)).exists() => serde_json::json!({
+                "kind": "vector",
+                "name": name,
+                "index": i,
+                "bounds": layer.meta.wgs84_extent,
+            }),
+            _ => continue,
+        };
+        entries.push(entry);
+    }
+
+    let page = dir.join("index.html");
+    std::fs::write(&page, render_page(&entries, port)?)
+        .with_context(|| format!("Failed to write {}", page.display()))?;
+
+    for layer in layers {
+        eprintln!(
+            "  {} {} ({}{})",
+            "•".cyan(),
+            layer.path.display(),
+            layer.meta.kind,
+            layer.meta.crs.as_deref().map(|crs| format!(", {}", crs)).unwrap_or_default()
+        );
+    }
+    if no_browser || !open_browser(&page) {
+        eprintln!("{} Open {} in a browser", "•".cyan(), page.display());
+    } else {
+        eprintln!("{} Opened the preview in your browser", "✓".green().bold());
+    }
+    eprintln!("Press Ctrl+C to stop the preview");
+
+    client::shutdown_signal().await;
+    eprintln!();
+    eprintln!("{} Stopping the preview", "=>".blue().bold());
+    Ok(())
+}
+
+/// Files to preview: a file, the files under a directory, or the outputs of a
+/// history entry
+fn resolve_target(target: &str) -> Result<Vec<PathBuf>> {
+    let path = Path::new(target);
+    if path.exists() {
+        let path = path
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", path.display()))?;
+        let mut files = Vec::new();
+        collect_files(&path, &mut files);
+        return Ok(files);
+    }
+
+    let Ok(id) = target.parse::<usize>() else {
+        return Err(GeoEngineError::InputInvalid(format!(
+            "{} is neither a file, a directory nor a history id",
+            target
+        ))
+        .into());
+    };
+    let entry = history::get(id)?;
+    if entry.output_dirs.is_empty() {
+        return Err(GeoEngineError::InputInvalid(format!("Run {} has no output folders", id)).into());
+    }
+    let mut files = Vec::new();
+    for output in &entry.output_dirs {
+        let dir = Path::new(&entry.cwd).join(output);
+        if !dir.exists() {
+            eprintln!("{} {} no longer exists", "!".yellow().bold(), dir.display());
+            continue;
+        }
+        collect_files(&dir, &mut files);
+    }
+    Ok(files)
+}
+
+/// `path` itself if it is a file, otherwise the files below it, skipping hidden
+/// files and GeoEngine's provenance records
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+    if path.is_file() {
+        files.push(path.to_path_buf());
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let name = entry.file_name();
+        if name == PROVENANCE_FILE || name.to_string_lossy().starts_with('.') {
+            continue;
+        }
+        collect_files(&entry.path(), files);
+    }
+}
+
+/// Reproject the first layer of each vector file to WGS84 GeoJSON in a GDAL container,
+/// then wrap each as a script so the page can load it from disk without a server
+async fn convert_vectors(client: &DockerClient, vectors: &[(usize, &Layer)], dir: &Path) -> Result<()> {
+    let image = geometa::gdal_image(client).await?;
+    let files: Vec<PathBuf> = vectors.iter().map(|(_, l)| l.path.clone()).collect();
+    let (dirs, container_paths) = geometa::data_mounts(&files);
+
+    let script: Vec<String> = vectors
+        .iter()
+        .zip(&container_paths)
+        .map(|((i, layer), path)| {
+            format!(
+                "ogr2ogr -f GeoJSON -t_srs EPSG:4326 -lco RFC7946=YES /out/layer-{}.geojson {} {}",
+                i,
+                geometa::shell_quote(path),
+                layer.meta.layers.first().map(|l| geometa::shell_quote(l)).unwrap_or_default()
+            )
+        })
+        .collect();
+
+    let mut mounts: Vec<(String, String, bool)> = dirs
+        .iter()
+        .enumerate()
+        .map(|(i, dir)| (dir.display().to_string(), format!("/data/{}", i), true))
+        .collect();
+    mounts.push((dir.display().to_string(), "/out".to_string(), false));
+
+    let log = dir.join("ogr2ogr.log");
+    let config = ContainerConfig {
+        image,
+        entrypoint: None,
+        // Keep going after a file fails so the others still show up
+        command: Some(vec!["/bin/sh".to_string(), "-c".to_string(), script.join("; ")]),
+        env_vars: HashMap::new(),
+        mounts,
+        volumes: Vec::new(),
+        gpu_config: None,
+        user: user::default_user(client.user_namespace()),
+        group_add: Vec::new(),
+        workdir: None,
+        name: None,
+        labels: HashMap::new(),
+        ports: Vec::new(),
+        restart: None,
+        ulimits: Vec::new(),
+        cap_add: Vec::new(),
+        cap_drop: Vec::new(),
+        devices: Vec::new(),
+        read_only: false,
+        security_opt: Vec::new(),
+        privileged: false,
+        memory: None,
+        nano_cpus: None,
+        network: None,
+        network_aliases: Vec::new(),
+        remove_on_exit: true,
+        detach: false,
+        tty: false,
+    };
+    client
+        .run_container_attached_to_file(&config, &log)
+        .await
+        .context("Failed to run the GDAL utility container")?;
+
+    for (i, layer) in vectors {
+        let geojson = dir.join(format!("layer-{}.geojson", i));
+        let Ok(data) = std::fs::read_to_string(&geojson) else {
+            eprintln!(
+                "{} Could not convert {} to GeoJSON; skipping it",
+                "!".yellow().bold(),
+                layer.path.display()
+            );
+            continue;
+        };
+        std::fs::write(
+            dir.join(format!("layer-{}.js", i)),
+            format!("window.geoenginePreview[{}] = {};\n", i, data),
+        )?;
+    }
+    Ok(())
+}
+
+/// Start TiTiler detached, publishing it on localhost only, with the raster
+/// directories mounted read-only
+async fn start_titiler(client: &DockerClient, dirs: &[PathBuf], port: u16) -> Result<String> {
+    let image = Settings::load()?
+        .titiler_image
+        .unwrap_or_else(|| DEFAULT_TITILER_IMAGE.to_string());
+    if client.image_id(&image).await.is_err() {
+        registry::pull(client, &image).await?;
+    }
+
+    let config = ContainerConfig {
+        image,
+        entrypoint: None,
+        command: None,
+        env_vars: HashMap::from([("PORT".to_string(), TITILER_PORT.to_string())]),
+        mounts: dirs
+            .iter()
+            .enumerate()
+            .map(|(i, dir)| (dir.display().to_string(), format!("/data/{}", i), true))
+            .collect(),
+        volumes: Vec::new(),
+        gpu_config: None,
+        user: None,
+        group_add: Vec::new(),
+        workdir: None,
+        name: Some(format!("geoengine-preview-{}", std::process::id())),
+        labels: HashMap::new(),
+        ports: vec![format!("127.0.0.1:{}:{}", port, TITILER_PORT)],
+        restart: None,
+        ulimits: Vec::new(),
+        cap_add: Vec::new(),
+        cap_drop: Vec::new(),
+        devices: Vec::new(),
+        read_only: false,
+        security_opt: Vec::new(),
+        privileged: false,
+        memory: None,
+        nano_cpus: None,
+        network: None,
+        network_aliases: Vec::new(),
+        // Removed explicitly on exit; not detached so the reaper owns it after a crash
+        remove_on_exit: false,
+        detach: false,
+        tty: false,
+    };
+    client
+        .run_container_detached(&config)
+        .await
+        .with_context(|| format!("Failed to start TiTiler (is port {} free? try --port)", port))
+}
+
+async fn wait_for_titiler(port: u16) -> Result<()> {
+    let url = format!("http://127.0.0.1:{}/healthz", port);
+    let http = reqwest::Client::new();
+    let started = Instant::now();
+    loop {
+        match http.get(&url).timeout(Duration::from_secs(2)).send().await {
+            Ok(response) if response.status().is_success() => return Ok(()),
+            _ if started.elapsed() > STARTUP_TIMEOUT => {
+                anyhow::bail!(
+                    "TiTiler did not become ready on port {} within {}s",
+                    port,
+                    STARTUP_TIMEOUT.as_secs()
+                )
+            }
+            _ => tokio::time::sleep(Duration::from_millis(500)).await,
+        }
+    }
+}
+
+/// Leaflet page with an OpenStreetMap basemap and one overlay per layer. Rasters
+/// without 8-bit data are stretched to their 2nd-98th percentiles.
+fn render_page(layers: &[serde_json::Value], port: u16) -> Result<String> {
+    let scripts: String = layers
+        .iter()
+        .filter_map(|l| l.get("index").and_then(|i| i.as_u64()))
+        .map(|i| format!("  <script src=\"layer-{}.js\"></script>\n", i))
+        .collect();
+    Ok(PAGE
+        .replace("%LEAFLET%", LEAFLET_VERSION)
+        .replace("%SCRIPTS%", &scripts)
+        // `<` escaped so a file name can't close the inline script
+        .replace("%LAYERS%", &serde_json::to_string(layers)?.replace('<', "\\u003c"))
+        .replace("%TITILER%", &format!("http://127.0.0.1:{}", port)))
+}
+
+/// Open `page` with the desktop's default browser; false if no opener worked
+fn open_browser(page: &Path) -> bool {
+    let page = page.display().to_string();
+    let mut command = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(windows) {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else if host::is_wsl() && which::which("wslview").is_ok() {
+        std::process::Command::new("wslview")
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    command
+        .arg(&page)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+  <meta charset="utf-8">
+  <title>GeoEngine preview</title>
+  <link rel="stylesheet" href="https://unpkg.com/leaflet@%LEAFLET%/dist/leaflet.css">
+  <script src="https://unpkg.com/leaflet@%LEAFLET%/dist/leaflet.js"></script>
+  <style>html, body, #map { height: 100%; margin: 0; }</style>
+  <script>window.geoenginePreview = {};</script>
+%SCRIPTS%</head>
+<body>
+  <div id="map"></div>
+  <script>
+    const titiler = "%TITILER%";
+    const layers = %LAYERS%;
+    const map = L.map("map");
+    const osm = L.tileLayer("https://tile.openstreetmap.org/{z}/{x}/{y}.png", {
+      maxZoom: 19,
+      attribution: "&copy; OpenStreetMap contributors"
+    }).addTo(map);
+    const control = L.control.layers({ "OpenStreetMap": osm }, {}, { collapsed: false }).addTo(map);
+    let extent = null;
+
+    // File names and attribute values come from the run's outputs
+    function escapeHtml(text) {
+      const div = document.createElement("div");
+      div.textContent = String(text);
+      return div.innerHTML;
+    }
+
+    function fit(bounds) {
+      if (!bounds) return;
+      const b = L.latLngBounds([bounds[1], bounds[0]], [bounds[3], bounds[2]]);
+      extent = extent ? extent.extend(b) : b;
+      map.fitBounds(extent);
+    }
+
+    async function rasterLayer(layer) {
+      const url = encodeURIComponent(layer.url);
+      const info = await (await fetch(`${titiler}/cog/info?url=${url}`)).json();
+      const count = info.count || 1;
+      const bands = count >= 3 ? [1, 2, 3] : [1];
+      let params = bands.map(b => `bidx=${b}`).join("&");
+      if (info.dtype !== "uint8") {
+        const stats = await (await fetch(`${titiler}/cog/statistics?url=${url}&${params}`)).json();
+        params += Object.values(stats).map(s => `&rescale=${s.percentile_2},${s.percentile_98}`).join("");
+      }
+      if (bands.length === 1) params += "&colormap_name=viridis";
+      const tiles = `${titiler}/cog/tiles/WebMercatorQuad/{z}/{x}/{y}.png?url=${url}&${params}`;
+      return L.tileLayer(tiles, { maxZoom: 22 });
+    }
+
+    (async () => {
+      for (const layer of layers) {
+        try {
+          const overlay = layer.kind === "raster"
+            ? await rasterLayer(layer)
+            : L.geoJSON(window.geoenginePreview[layer.index], {
+                onEachFeature: (feature, l) => l.bindPopup(
+                  `<pre>${escapeHtml(JSON.stringify(feature.properties, null, 2))}</pre>`)
+              });
+          overlay.addTo(map);
+          control.addOverlay(overlay, escapeHtml(layer.name));
+          fit(layer.bounds || (overlay.getBounds && overlay.getBounds().isValid()
+            ? [overlay.getBounds().getWest(), overlay.getBounds().getSouth(),
+               overlay.getBounds().getEast(), overlay.getBounds().getNorth()]
+            : null));
+        } catch (e) {
+          console.error(`Failed to load ${layer.name}`, e);
+        }
+      }
+      if (!extent) map.setView([0, 0], 2);
+    })();
+  </script>
+</body>
+</html>
+"#;
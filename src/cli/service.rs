@@ -0,0 +1,246 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use colored::Colorize;
+
+use crate::cli::worker::{
+    execute_run, mapping_to_inputs, prepare_run, resolve_worker, resolve_worker_relative, RunArgs, RunOutput,
+};
+use crate::config::settings::Settings;
+use crate::config::worker as worker_config;
+use crate::config::yaml_store;
+use crate::docker::client::DockerClient;
+use crate::docker::reaper;
+
+#[derive(Subcommand)]
+pub enum ServiceCommands {
+    /// Remove containers and job workspaces left behind by crashed or killed runs
+    Reap {
+        /// Only list what would be removed
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+impl ServiceCommands {
+    pub async fn execute(self) -> Result<()> {
+        match self {
+            Self::Reap { dry_run } => reap(dry_run).await,
+        }
+    }
+}
+
+/// Restart policy for services that don't set one
+const DEFAULT_RESTART: &str = "unless-stopped";
+
+/// Bridge network a worker's services share, where each is reachable by its service name
+pub fn network_name(worker: &str) -> String {
+    format!("geoengine-{}", worker)
+}
+
+/// Start the services defined in a worker's geoengine.yaml in the background.
+/// Services that are already running are left alone; stopped ones are recreated.
+/// They join the worker's network, or `network` if given, which must exist.
+pub async fn up(worker: Option<&str>, only: &[String], network: Option<&str>) -> Result<()> {
+    let (worker_name, worker_path) = resolve_worker(worker)?;
+    let config = yaml_store::load_saved_config(&worker_name)?;
+    let services = config.services.clone().unwrap_or_default();
+    if services.is_empty() {
+        anyhow::bail!("No services defined in geoengine.yaml for worker '{}'", worker_name);
+    }
+    for name in only {
+        if !services.contains_key(name) {
+            let defined: Vec<&str> = services.keys().map(String::as_str).collect();
+            anyhow::bail!("Unknown service '{}'. Defined services: {}", name, defined.join(", "));
+        }
+    }
+
+    let client = DockerClient::new().await?;
+    let network = match network {
+        Some(network) => {
+            if !client.network_exists(network).await {
+                anyhow::bail!("Network not found: {}", network);
+            }
+            network.to_string()
+        }
+        None => {
+            let network = network_name(&worker_name);
+            if client.ensure_network(&network, &worker_name).await? {
+                println!("{} Created network {}", "✓".green().bold(), network.cyan());
+            }
+            network
+        }
+    };
+    let config_path = worker_config::config_file(&worker_path);
+    let port_offset = Settings::load()?.service_port_offset.unwrap_or(0);
+    for (service_name, service) in services.iter().filter(|(name, _)| only.is_empty() || only.contains(name)) {
+        let container_name = format!("{}-{}", worker_name, service_name);
+        match client.container_running(&container_name).await? {
+            Some(true) => {
+                println!("{} Service {} is already running", "•".cyan(), service_name.cyan());
+                continue;
+            }
+            // Recreate stopped containers so they pick up the current image and config
+            Some(false) => client.remove_container(&container_name, true).await?,
+            None => {}
+        }
+
+        let run = RunArgs {
+            worker: Some(worker_name.clone()),
+            env: service
+                .env
+                .iter()
+                .flatten()
+                .map(|(key, value)| format!("{}={}", key, value))
+                .collect(),
+            detach: true,
+            name: Some(container_name.clone()),
+            restart: Some(service.restart.clone().unwrap_or_else(|| DEFAULT_RESTART.to_string())),
+            publish: service
+                .ports
+                .iter()
+                .flatten()
+                .map(|spec| offset_host_port(spec, port_offset))
+                .collect::<Result<_>>()?,
+            service: Some(service_name.clone()),
+            network: Some(network.clone()),
+            args: service.args.clone().unwrap_or_default(),
+            ..Default::default()
+        };
+        let mut inputs = mapping_to_inputs(service.inputs.clone().unwrap_or_default(), &config_path, config.input_definitions())?;
+        resolve_worker_relative(&mut inputs, &worker_path);
+        let prepared = prepare_run(&run, &client).await?;
+        execute_run(&client, &prepared, &inputs, &run, prepared.gpu_config.clone(), &RunOutput::Terminal)
+            .await
+            .with_context(|| format!("Failed to start service '{}'", service_name))?;
+
+        println!(
+            "{} Started service {} as {}",
+            "✓".green().bold(),
+            service_name.cyan(),
+            container_name.cyan()
+        );
+    }
+
+    Ok(())
+}
+
+/// Shift the host port (or port range) of a [IP:]HOST_PORT:CONTAINER_PORT[/PROTOCOL]
+/// spec by `offset`. Specs without a host port are left to Docker.
+fn offset_host_port(spec: &str, offset: u16) -> Result<String> {
+    let mut parts: Vec<&str> = spec.rsplitn(3, ':').collect();
+    if offset == 0 || parts.len() < 2 || parts[1].is_empty() {
+        return Ok(spec.to_string());
+    }
+    let shift = |port: &str| -> Result<String> {
+        let port: u16 = port.parse().with_context(|| format!("Invalid host port in '{}'", spec))?;
+        port.checked_add(offset)
+            .map(|p| p.to_string())
+            .with_context(|| format!("service_port_offset {} pushes '{}' past port 65535", offset, spec))
+    };
+    let host = match parts[1].split_once('-') {
+        Some((start, end)) => format!("{}-{}", shift(start)?, shift(end)?),
+        None => shift(parts[1])?,
+    };
+    parts[1] = &host;
+    parts.reverse();
+    Ok(parts.join(":"))
+}
+
+/// Stop and remove a worker's services and detached runs
+pub async fn down(worker: Option<&str>, only: &[String]) -> Result<()> {
+    let (worker_name, _) = resolve_worker(worker)?;
+    let client = DockerClient::new().await?;
+
+    let containers: Vec<_> = client
+        .list_service_containers(&worker_name)
+        .await
+        .context("Failed to list containers")?
+        .into_iter()
+        .filter(|c| only.is_empty() || c.service.as_ref().is_some_and(|s| only.contains(s)))
+        .collect();
+    if containers.is_empty() {
+        println!("{}", format!("No services running for worker '{}'", worker_name).yellow());
+    }
+
+    for container in &containers {
+        println!("{} Stopping {}...", "=>".blue().bold(), container.name.cyan());
+        // Already-stopped containers make stop fail; removal is what matters
+        let _ = client.stop_container(&container.id, 10).await;
+        client.remove_container(&container.id, true).await?;
+        println!("{} Removed {}", "✓".green().bold(), container.name.cyan());
+    }
+
+    // The worker's own network goes with its services; networks given to `up --network` are left alone
+    let network = network_name(&worker_name);
+    if only.is_empty() && client.network_exists(&network).await {
+        match client.remove_network(&network).await {
+            Ok(()) => println!("{} Removed network {}", "✓".green().bold(), network.cyan()),
+            Err(e) => eprintln!("{} {:#}; other containers are still attached", "!".yellow().bold(), e),
+        }
+    }
+
+    Ok(())
+}
+
+/// Print the output of a detached run or service
+pub async fn logs(name: &str, follow: bool, tail: Option<usize>) -> Result<()> {
+    let client = DockerClient::new().await?;
+    if client.container_running(name).await?.is_none() {
+        anyhow::bail!("Container not found: {}", name);
+    }
+    client.print_logs(name, follow, tail).await
+}
+
+/// Follow a detached run's output until it exits; Ctrl-C detaches without stopping it
+pub async fn attach(name: &str) -> Result<()> {
+    let client = DockerClient::new().await?;
+    match client.container_running(name).await? {
+        None => anyhow::bail!("Container not found: {}", name),
+        Some(false) => anyhow::bail!(
+            "Container {} is not running. Use `geoengine logs {}` to see its output.",
+            name,
+            name
+        ),
+        Some(true) => {}
+    }
+
+    eprintln!("{} Attached to {} (Ctrl-C to detach)", "•".cyan(), name.cyan());
+    match client.attach_container(name).await? {
+        None => eprintln!("\n{} Detached; {} keeps running", "•".cyan(), name.cyan()),
+        Some(0) => eprintln!("{} {} exited successfully", "✓".green().bold(), name.cyan()),
+        Some(code) => {
+            eprintln!("{} {} exited with code {}", "✗".red().bold(), name.cyan(), code);
+            anyhow::bail!("Container {} exited with code {}", name, code);
+        }
+    }
+
+    Ok(())
+}
+
+/// Remove containers and job workspaces left behind by crashed or killed runs
+pub async fn reap(dry_run: bool) -> Result<()> {
+    let client = DockerClient::new().await?;
+    let reaped = reaper::reap(&client, dry_run).await?;
+    if reaped.is_empty() {
+        println!("{} Nothing to clean up", "✓".green().bold());
+        return Ok(());
+    }
+
+    let verb = if dry_run { "Would remove" } else { "Removed" };
+    for name in &reaped.containers {
+        println!("{} {} container {}", "•".cyan(), verb, name.cyan());
+    }
+    for dir in &reaped.workspaces {
+        println!("{} {} job workspace {}", "•".cyan(), verb, dir.display());
+    }
+    if !dry_run {
+        println!(
+            "{} Removed {} container(s) and {} job workspace(s)",
+            "✓".green().bold(),
+            reaped.containers.len(),
+            reaped.workspaces.len()
+        );
+    }
+
+    Ok(())
+}
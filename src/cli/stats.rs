@@ -0,0 +1,257 @@
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Paragraph, Row, Table};
+use ratatui::Frame;
+use std::io::IsTerminal;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::cli::image::format_size;
+use crate::docker::client::DockerClient;
+use crate::docker::gpu::{self, GpuUsage};
+use crate::utils::{disk, paths};
+
+/// One refresh of everything shown on the dashboard
+#[derive(Default)]
+struct Snapshot {
+    containers: Vec<ContainerRow>,
+    /// None when nvidia-smi is unavailable
+    gpus: Option<Vec<GpuUsage>>,
+    images_count: usize,
+    images_bytes: i64,
+    jobs_count: usize,
+    jobs_bytes: u64,
+    error: Option<String>,
+    updated_at: Option<chrono::DateTime<chrono::Local>>,
+}
+
+struct ContainerRow {
+    name: String,
+    worker: String,
+    image: String,
+    status: String,
+    cpu_percent: Option<f64>,
+    memory: Option<(u64, u64)>,
+}
+
+/// Show a live dashboard of running worker containers, GPU utilization and disk usage
+pub async fn run_stats(interval: u64) -> Result<()> {
+    if !std::io::stdout().is_terminal() {
+        anyhow::bail!("geoengine stats needs an interactive terminal");
+    }
+    let client = DockerClient::new().await?;
+    let interval = Duration::from_secs(interval.max(1));
+
+    // Sampling takes a second or more (docker stats needs two CPU readings),
+    // so collect in the background and keep the UI responsive to key presses
+    let (tx, mut rx) = watch::channel(Snapshot::default());
+    let collector = tokio::spawn(async move {
+        loop {
+            if tx.send(collect(&client).await).is_err() {
+                break;
+            }
+            tokio::time::sleep(interval).await;
+        }
+    });
+
+    let mut terminal = ratatui::init();
+    let result = async {
+        loop {
+            terminal.draw(|frame| draw(frame, &rx.borrow_and_update(), interval))?;
+
+            // Wait for a key press or the next snapshot
+            loop {
+                if tokio::task::block_in_place(|| event::poll(Duration::from_millis(200)))? {
+                    if let Event::Key(key) = event::read()? {
+                        let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                        if key.kind == KeyEventKind::Press
+                            && (matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || ctrl_c)
+                        {
+                            return Ok(());
+                        }
+                    }
+                    break;
+                }
+                if rx.has_changed().unwrap_or(false) {
+                    break;
+                }
+            }
+        }
+    }
+    .await;
+    ratatui::restore();
+    collector.abort();
+
+    result
+}
+
+async fn collect(client: &DockerClient) -> Snapshot {
+    let mut snapshot = Snapshot {
+        updated_at: Some(chrono::Local::now()),
+        ..Default::default()
+    };
+
+    match client.list_worker_containers().await {
+        Ok(containers) => {
+            let usages =
+                futures::future::join_all(containers.iter().map(|c| client.container_usage(&c.id))).await;
+            snapshot.containers = containers
+                .into_iter()
+                .zip(usages)
+                .map(|(c, usage)| {
+                    let usage = usage.ok();
+                    ContainerRow {
+                        name: c.name,
+                        worker: c.worker,
+                        image: c.image,
+                        status: c.status,
+                        cpu_percent: usage.as_ref().map(|u| u.cpu_percent),
+                        memory: usage.map(|u| (u.memory_bytes, u.memory_limit)),
+                    }
+                })
+                .collect();
+        }
+        Err(e) => snapshot.error = Some(format!("Failed to list containers: {}", e)),
+    }
+
+    if which::which("nvidia-smi").is_ok() {
+        snapshot.gpus = tokio::task::spawn_blocking(gpu::nvidia_usage).await.ok().and_then(|r| r.ok());
+    }
+
+    if let Ok(images) = client.list_images(None, false).await {
+        snapshot.images_count = images.len();
+        snapshot.images_bytes = images.iter().map(|i| i.size).sum();
+    }
+
+    if let Ok(jobs_dir) = paths::get_jobs_dir() {
+        let (count, bytes) = tokio::task::spawn_blocking(move || {
+            let count = std::fs::read_dir(&jobs_dir).map(|d| d.count()).unwrap_or(0);
+            (count, disk::path_size(&jobs_dir))
+        })
+        .await
+        .unwrap_or_default();
+        snapshot.jobs_count = count;
+        snapshot.jobs_bytes = bytes;
+    }
+
+    snapshot
+}
+
+fn draw(frame: &mut Frame, snapshot: &Snapshot, interval: Duration) {
+    let gpu_rows = snapshot.gpus.as_ref().map(|g| g.len().max(1)).unwrap_or(1) as u16;
+    let [header, containers, gpus, disk] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Min(5),
+        Constraint::Length(gpu_rows + 3),
+        Constraint::Length(4),
+    ])
+    .areas(frame.area());
+
+    let updated = snapshot
+        .updated_at
+        .map(|t| t.format("%H:%M:%S").to_string())
+        .unwrap_or_else(|| "collecting...".to_string());
+    frame.render_widget(
+        Paragraph::new(Line::from(format!(
+            " GeoEngine stats | updated {} | refresh {}s | q to quit",
+            updated,
+            interval.as_secs()
+        )))
+        .style(Style::default().add_modifier(Modifier::BOLD)),
+        header,
+    );
+
+    let header_style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+
+    // Containers
+    let rows: Vec<Row> = snapshot
+        .containers
+        .iter()
+        .map(|c| {
+            Row::new(vec![
+                c.name.clone(),
+                c.worker.clone(),
+                c.image.clone(),
+                c.cpu_percent.map(|p| format!("{:.1}%", p)).unwrap_or_else(|| "-".to_string()),
+                c.memory
+                    .map(|(used, limit)| format!("{} / {}", format_size(used as i64), format_size(limit as i64)))
+                    .unwrap_or_else(|| "-".to_string()),
+                c.status.clone(),
+            ])
+        })
+        .collect();
+    let title = match &snapshot.error {
+        Some(error) => format!(" Containers ({}) ", error),
+        None => format!(" Containers ({}) ", snapshot.containers.len()),
+    };
+    frame.render_widget(
+        Table::new(
+            rows,
+            [
+                Constraint::Percentage(20),
+                Constraint::Percentage(15),
+                Constraint::Percentage(25),
+                Constraint::Length(8),
+                Constraint::Length(22),
+                Constraint::Min(10),
+            ],
+        )
+        .header(Row::new(vec!["NAME", "WORKER", "IMAGE", "CPU", "MEMORY", "STATUS"]).style(header_style))
+        .block(Block::bordered().title(title)),
+        containers,
+    );
+
+    // GPUs
+    let gpu_block = Block::bordered().title(" GPUs ");
+    match &snapshot.gpus {
+        Some(usages) if !usages.is_empty() => {
+            let rows: Vec<Row> = usages
+                .iter()
+                .map(|g| {
+                    Row::new(vec![
+                        g.index.clone(),
+                        g.name.clone(),
+                        format!("{:.0}%", g.utilization_percent),
+                        format!("{} / {} MiB", g.memory_used_mib, g.memory_total_mib),
+                    ])
+                })
+                .collect();
+            frame.render_widget(
+                Table::new(
+                    rows,
+                    [
+                        Constraint::Length(5),
+                        Constraint::Min(20),
+                        Constraint::Length(6),
+                        Constraint::Length(22),
+                    ],
+                )
+                .header(Row::new(vec!["GPU", "NAME", "UTIL", "MEMORY"]).style(header_style))
+                .block(gpu_block),
+                gpus,
+            );
+        }
+        _ => frame.render_widget(Paragraph::new(" No NVIDIA GPU detected").block(gpu_block), gpus),
+    }
+
+    // Disk usage
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(format!(
+                " Images:    {:>10}  ({} geoengine images)",
+                format_size(snapshot.images_bytes),
+                snapshot.images_count
+            )),
+            Line::from(format!(
+                " Jobs dir:  {:>10}  ({} workspaces)",
+                format_size(snapshot.jobs_bytes as i64),
+                snapshot.jobs_count
+            )),
+        ])
+        .block(Block::bordered().title(" Disk ")),
+        disk,
+    );
+}
@@ -0,0 +1,247 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::cli::output_rules::validate_outputs;
+use crate::cli::worker::{
+    execute_run, mapping_to_inputs, oom_detail, prepare_run, resolve_worker, resolve_worker_relative, RunArgs, RunOutput,
+};
+use crate::config::state;
+use crate::config::worker::{self as worker_config, OutputAssertion, TestCase};
+use crate::config::yaml_store;
+use crate::docker::client::DockerClient;
+use crate::error::GeoEngineError;
+use crate::utils::paths;
+
+/// Container output of each test, next to its output folders
+const LOG_FILE: &str = "container.log";
+
+/// Results of `geoengine test`
+#[derive(Serialize)]
+pub struct TestReport {
+    pub worker: String,
+    pub passed: usize,
+    pub failed: usize,
+    pub tests: Vec<TestResult>,
+}
+
+#[derive(Serialize)]
+pub struct TestResult {
+    pub name: String,
+    pub passed: bool,
+    pub exit_code: Option<i64>,
+    /// Why the test failed, one entry per unmet expectation
+    pub failures: Vec<String>,
+    pub duration_secs: f64,
+    /// Directory with the test's outputs and container log (kept for failed tests)
+    pub dir: Option<String>,
+}
+
+/// Run the test cases in a worker's geoengine.yaml and report which passed.
+/// Each test writes into its own directory under ~/.geoengine/tests, which is
+/// removed when the test passes unless `keep` is set.
+pub async fn run_tests(worker: Option<&str>, only: &[String], dev: bool, keep: bool, json: bool) -> Result<()> {
+    let (worker_name, worker_path) = resolve_worker(worker)?;
+    let config = yaml_store::load_saved_config(&worker_name)?;
+    let tests = config.tests.clone().unwrap_or_default();
+    if tests.is_empty() {
+        anyhow::bail!("No tests defined in geoengine.yaml for worker '{}'", worker_name);
+    }
+    for name in only {
+        if !tests.iter().any(|t| &t.name == name) {
+            let defined: Vec<&str> = tests.iter().map(|t| t.name.as_str()).collect();
+            anyhow::bail!("Unknown test '{}'. Defined tests: {}", name, defined.join(", "));
+        }
+    }
+
+    let client = DockerClient::new().await?;
+    let base = RunArgs {
+        worker: Some(worker_name.clone()),
+        dev,
+        json,
+        no_history: true,
+        ..Default::default()
+    };
+    let mut prepared = prepare_run(&base, &client).await?;
+    let base_env = prepared.env.clone();
+
+    // Writable folder inputs are where a run's outputs go
+    let output_inputs: Vec<String> = config
+        .command
+        .iter()
+        .flat_map(|c| c.inputs.iter().flatten())
+        .filter(|i| i.param_type.eq_ignore_ascii_case("folder") && !i.readonly.unwrap_or(true))
+        .map(|i| i.name.clone())
+        .collect();
+
+    let selected: Vec<&TestCase> = tests
+        .iter()
+        .filter(|t| only.is_empty() || only.contains(&t.name))
+        .collect();
+    if !json {
+        eprintln!(
+            "{} Running {} test(s) of worker '{}'...",
+            "=>".blue().bold(),
+            selected.len(),
+            worker_name.cyan()
+        );
+    }
+
+    let tests_dir = paths::get_tests_dir()?.join(&worker_name);
+    let config_path = worker_config::config_file(&worker_path);
+    let mut results: Vec<TestResult> = Vec::new();
+    for test in selected {
+        let dir = tests_dir.join(&test.name);
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir)
+                .with_context(|| format!("Failed to clear previous test outputs: {}", dir.display()))?;
+        }
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create test directory: {}", dir.display()))?;
+
+        let mut inputs = mapping_to_inputs(test.inputs.clone().unwrap_or_default(), &config_path, config.input_definitions())?;
+        resolve_worker_relative(&mut inputs, &worker_path);
+        let mut output_dirs: HashMap<String, PathBuf> = HashMap::new();
+        for name in &output_inputs {
+            let path = match inputs.get(name) {
+                Some(value) => PathBuf::from(value),
+                None => {
+                    let path = dir.join(name);
+                    std::fs::create_dir_all(&path)?;
+                    inputs.insert(name.clone(), path.display().to_string());
+                    path
+                }
+            };
+            output_dirs.insert(name.clone(), path);
+        }
+
+        prepared.env = base_env.clone();
+        prepared.env.extend(test.env.clone().unwrap_or_default());
+        let run = RunArgs {
+            args: test.args.clone().unwrap_or_default(),
+            ..base.clone()
+        };
+
+        let log = dir.join(LOG_FILE);
+        let started = std::time::Instant::now();
+        let result = execute_run(
+            &client,
+            &prepared,
+            &inputs,
+            &run,
+            prepared.gpu_config.clone(),
+            &RunOutput::LogFile(log.clone()),
+        )
+        .await;
+        let duration_secs = started.elapsed().as_secs_f64();
+
+        let (exit_code, failures) = match result {
+            Ok(outcome) => {
+                let mut failures = check_expectations(test, outcome.exit_code, &output_dirs);
+                if outcome.oom_killed {
+                    failures.insert(0, format!("Killed: {}", oom_detail(&prepared.config, outcome.usage.as_ref())));
+                } else if outcome.exit_code == 0 {
+                    failures.extend(validate_outputs(&client, &prepared.config, &inputs, &outcome.files, &HashMap::new()).await);
+                }
+                (Some(outcome.exit_code), failures)
+            }
+            Err(e) => (None, vec![format!("{:#}", e)]),
+        };
+        let passed = failures.is_empty();
+        let kept = !passed || keep;
+        if !kept {
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+
+        if !json {
+            if passed {
+                eprintln!("{} {} {}", "✓".green().bold(), test.name, format!("({:.1}s)", duration_secs).dimmed());
+            } else {
+                eprintln!("{} {} {}", "✗".red().bold(), test.name, format!("({:.1}s)", duration_secs).dimmed());
+                for failure in &failures {
+                    eprintln!("    {}", failure);
+                }
+                eprintln!("    Log: {}", log.display());
+            }
+        }
+        results.push(TestResult {
+            name: test.name.clone(),
+            passed,
+            exit_code,
+            failures,
+            duration_secs,
+            dir: kept.then(|| dir.display().to_string()),
+        });
+    }
+
+    let passed = results.iter().filter(|r| r.passed).count();
+    let report = TestReport {
+        worker: worker_name.clone(),
+        passed,
+        failed: results.len() - passed,
+        tests: results,
+    };
+    if json {
+        println!("{}", serde_json::to_string(&report)?);
+    } else {
+        eprintln!(
+            "{} {} passed, {} failed",
+            if report.failed == 0 { "✓".green().bold() } else { "✗".red().bold() },
+            report.passed,
+            report.failed
+        );
+    }
+
+    if report.failed > 0 {
+        return Err(GeoEngineError::TestsFailed {
+            failed: report.failed,
+            total: report.tests.len(),
+        }
+        .into());
+    }
+
+    Ok(())
+}
+
+/// Compare a finished run with the test's expectations
+fn check_expectations(test: &TestCase, exit_code: i64, output_dirs: &HashMap<String, PathBuf>) -> Vec<String> {
+    let mut failures = Vec::new();
+    let expected = test.exit_code.unwrap_or(0);
+    if exit_code != expected {
+        failures.push(format!("Exit code {} (expected {})", exit_code, expected));
+    }
+    for assertion in test.outputs.iter().flatten() {
+        if let Err(failure) = check_output(assertion, output_dirs) {
+            failures.push(failure);
+        }
+    }
+    failures
+}
+
+fn check_output(assertion: &OutputAssertion, output_dirs: &HashMap<String, PathBuf>) -> Result<(), String> {
+    let (input, relative) = assertion
+        .path
+        .split_once('/')
+        .ok_or_else(|| format!("{}: expected <folder input>/<path>", assertion.path))?;
+    let dir = output_dirs
+        .get(input)
+        .ok_or_else(|| format!("{}: '{}' is not a writable folder input", assertion.path, input))?;
+    let path = dir.join(relative);
+
+    let meta = std::fs::metadata(&path).map_err(|_| format!("{}: not produced", assertion.path))?;
+    if !meta.is_file() {
+        return Err(format!("{}: not a file", assertion.path));
+    }
+    if assertion.non_empty.unwrap_or(false) && meta.len() == 0 {
+        return Err(format!("{}: empty", assertion.path));
+    }
+    if let Some(expected) = &assertion.sha256 {
+        let actual = state::compute_file_hash(&path).map_err(|e| format!("{}: {:#}", assertion.path, e))?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!("{}: SHA-256 {} (expected {})", assertion.path, actual, expected));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,430 @@
+use anyhow::Result;
+use clap::{Args, Subcommand};
+use colored::Colorize;
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::time::Duration;
+
+use crate::cli::deploy::remote_command;
+use crate::cli::worker::resolve_worker;
+use crate::config::settings::Settings;
+use crate::config::state;
+use crate::config::worker::{self as worker_config, Accelerator, WorkerConfig};
+use crate::docker::client::{self, DockerClient};
+use crate::docker::registry::{self, PushRetry};
+use crate::error::GeoEngineError;
+use crate::utils::vertex::Vertex;
+
+/// How often a watched job is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(15);
+
+/// GPU requested when `runtime.accelerator` is cuda and no --accelerator-type is given
+const DEFAULT_ACCELERATOR: &str = "NVIDIA_TESLA_T4";
+
+/// vCPU counts of the n1-standard machine types, which take every Vertex AI GPU up to the V100
+const N1_STANDARD_CPUS: [u32; 8] = [1, 2, 4, 8, 16, 32, 64, 96];
+
+/// Memory per vCPU of n1-standard machine types
+const N1_MEMORY_PER_CPU: f64 = 3.75 * 1024.0 * 1024.0 * 1024.0;
+
+#[derive(Subcommand)]
+pub enum VertexCommands {
+    /// Submit a custom job running the worker's command with the given inputs
+    Submit {
+        /// Worker name (defaults to the worker in the current directory)
+        worker: Option<String>,
+
+        /// Input parameters (KEY=VALUE); file and folder inputs should be gs:// URIs
+        #[arg(short, long = "input")]
+        inputs: Vec<String>,
+
+        /// Push the local image to Artifact Registry first
+        #[arg(long)]
+        push: bool,
+
+        /// Artifact Registry repository the image is in
+        #[arg(long, default_value = "geoengine")]
+        repository: String,
+
+        /// Machine type (defaults to the smallest n1-standard type fitting runtime.cpus and runtime.memory)
+        #[arg(long)]
+        machine_type: Option<String>,
+
+        /// GPU type, e.g. NVIDIA_L4 (defaults to NVIDIA_TESLA_T4 if `runtime.accelerator` is cuda)
+        #[arg(long)]
+        accelerator_type: Option<String>,
+
+        /// GPUs per job (defaults to 1 when a GPU type is set)
+        #[arg(long)]
+        accelerator_count: Option<u32>,
+
+        /// Service account the job runs as (defaults to the Vertex AI service agent)
+        #[arg(long)]
+        service_account: Option<String>,
+
+        /// Wait for the job to finish, streaming its state and logs
+        #[arg(long)]
+        wait: bool,
+
+        #[command(flatten)]
+        location: LocationArgs,
+    },
+
+    /// Show the state of a submitted job
+    Status {
+        /// Job ID printed by `submit`
+        job_id: String,
+
+        /// Wait for the job to finish, streaming its state and logs
+        #[arg(long)]
+        wait: bool,
+
+        /// Print the full job as JSON
+        #[arg(long, conflicts_with = "wait")]
+        json: bool,
+
+        #[command(flatten)]
+        location: LocationArgs,
+    },
+}
+
+/// GCP project and region of the jobs
+#[derive(Args)]
+pub struct LocationArgs {
+    /// GCP project ID (defaults to `gcp_project` in settings)
+    #[arg(long, env = "GCP_PROJECT")]
+    project: Option<String>,
+
+    /// GCP region (defaults to `gcp_region` in settings, then us-central1)
+    #[arg(long)]
+    region: Option<String>,
+}
+
+impl LocationArgs {
+    fn resolve(self) -> Result<(String, String)> {
+        let settings = Settings::load()?;
+        let project = self
+            .project
+            .filter(|p| !p.is_empty())
+            .or(settings.gcp_project)
+            .ok_or_else(|| {
+                GeoEngineError::ConfigInvalid(
+                    "No GCP project given. Pass --project, set GCP_PROJECT or gcp_project in settings.".to_string(),
+                )
+            })?;
+        let region = self
+            .region
+            .or(settings.gcp_region)
+            .unwrap_or_else(|| "us-central1".to_string());
+        Ok((project, region))
+    }
+}
+
+impl VertexCommands {
+    pub async fn execute(self) -> Result<()> {
+        match self {
+            Self::Submit {
+                worker,
+                inputs,
+                push,
+                repository,
+                machine_type,
+                accelerator_type,
+                accelerator_count,
+                service_account,
+                wait,
+                location,
+            } => {
+                let (project, region) = location.resolve()?;
+                let (name, path) = resolve_worker(worker.as_deref())?;
+                let config = WorkerConfig::load(&worker_config::config_file(&path))?;
+                let cmd_config = config
+                    .command
+                    .as_ref()
+                    .ok_or_else(|| anyhow::anyhow!("No command defined for worker '{}'", name))?;
+                // The job runs remotely: local paths mean nothing there
+                let command = remote_command(cmd_config, &inputs, "Upload it to Cloud Storage and pass a gs:// URI instead.")?;
+
+                let version = config.version.clone().unwrap_or("latest".to_string());
+                let image = format!(
+                    "{}-docker.pkg.dev/{}/{}/{}:{}",
+                    region, project, repository, config.name, version
+                );
+                if push {
+                    push_to_artifact_registry(&config, &version, &image).await?;
+                }
+
+                let machine = MachineSpec::for_worker(&config, machine_type, accelerator_type, accelerator_count)?;
+                let job = custom_job(&config, &image, &command, &machine, service_account.as_deref());
+
+                let vertex = Vertex::connect(&project, &region).await?;
+                println!(
+                    "{} Submitting {} to Vertex AI ({})...",
+                    "=>".blue().bold(),
+                    job["displayName"].as_str().unwrap_or_default().cyan(),
+                    region
+                );
+                let created = vertex.create_custom_job(&job).await?;
+                let job_id = created["name"].as_str().and_then(|n| n.rsplit('/').next()).unwrap_or_default().to_string();
+                println!("{} Submitted job {}", "✓".green().bold(), job_id.cyan());
+                println!("  {} {}, image {}", "•".cyan(), machine.describe(), image);
+                if !push {
+                    println!(
+                        "  {} The image must already be in Artifact Registry; pass {} to push it",
+                        "!".yellow().bold(),
+                        "--push".cyan()
+                    );
+                }
+
+                if wait {
+                    watch(&vertex, &job_id, true).await
+                } else {
+                    println!("\nFollow it with:");
+                    println!("  {}", format!("geoengine deploy vertex status {} --wait", job_id).cyan());
+                    Ok(())
+                }
+            }
+            Self::Status {
+                job_id,
+                wait,
+                json,
+                location,
+            } => {
+                let (project, region) = location.resolve()?;
+                let vertex = Vertex::connect(&project, &region).await?;
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&vertex.get_custom_job(&job_id).await?)?);
+                    return Ok(());
+                }
+                watch(&vertex, &job_id, wait).await
+            }
+        }
+    }
+}
+
+/// Machine a job runs on
+struct MachineSpec {
+    machine_type: String,
+    accelerator: Option<(String, u32)>,
+}
+
+impl MachineSpec {
+    /// The flags, else the smallest n1-standard type with `runtime.cpus` and
+    /// `runtime.memory`, and a GPU if the worker needs CUDA
+    fn for_worker(
+        config: &WorkerConfig,
+        machine_type: Option<String>,
+        accelerator_type: Option<String>,
+        accelerator_count: Option<u32>,
+    ) -> Result<Self> {
+        let machine_type = match machine_type {
+            Some(machine_type) => machine_type,
+            None => {
+                let runtime = config.runtime.as_ref();
+                let cpus = runtime.and_then(|r| r.cpus).unwrap_or(1.0);
+                let memory = runtime.map(|r| r.memory_bytes()).transpose()?.flatten().unwrap_or(0) as f64;
+                let fitting = N1_STANDARD_CPUS
+                    .iter()
+                    .find(|&&n| n as f64 >= cpus && n as f64 * N1_MEMORY_PER_CPU >= memory)
+                    .ok_or_else(|| {
+                        GeoEngineError::ConfigInvalid(
+                            "runtime.cpus or runtime.memory exceed every n1-standard machine type; pass --machine-type"
+                                .to_string(),
+                        )
+                    })?;
+                format!("n1-standard-{}", fitting)
+            }
+        };
+        let accelerator_type = accelerator_type.or_else(|| {
+            (config.accelerator() == Accelerator::Cuda).then(|| DEFAULT_ACCELERATOR.to_string())
+        });
+        let accelerator = match (accelerator_type, accelerator_count) {
+            (Some(_), Some(0)) | (None, None) => None,
+            (Some(kind), count) => Some((kind, count.unwrap_or(1))),
+            (None, Some(_)) => {
+                return Err(GeoEngineError::InputInvalid(
+                    "--accelerator-count needs --accelerator-type".to_string(),
+                )
+                .into())
+            }
+        };
+        Ok(MachineSpec {
+            machine_type,
+            accelerator,
+        })
+    }
+
+    fn describe(&self) -> String {
+        match &self.accelerator {
+            Some((kind, count)) => format!("{} with {} x {}", self.machine_type, count, kind),
+            None => self.machine_type.clone(),
+        }
+    }
+}
+
+/// CustomJob request with a single replica running the worker's command
+fn custom_job(config: &WorkerConfig, image: &str, command: &str, machine: &MachineSpec, service_account: Option<&str>) -> Value {
+    let environment: Vec<Value> = config
+        .runtime
+        .as_ref()
+        .and_then(|r| r.env.as_ref())
+        .map(|env| env.iter().map(|(k, v)| json!({ "name": k, "value": v })).collect())
+        .unwrap_or_default();
+
+    let mut machine_spec = json!({ "machineType": machine.machine_type });
+    if let Some((kind, count)) = &machine.accelerator {
+        machine_spec["acceleratorType"] = json!(kind);
+        machine_spec["acceleratorCount"] = json!(count);
+    }
+    let mut job_spec = json!({
+        "workerPoolSpecs": [{
+            "machineSpec": machine_spec,
+            "replicaCount": "1",
+            "containerSpec": {
+                "imageUri": image,
+                "command": ["/bin/sh", "-c", command],
+                "env": environment,
+            },
+        }],
+    });
+    if let Some(account) = service_account {
+        job_spec["serviceAccount"] = json!(account);
+    }
+    // Label values allow lowercase letters, digits, '-' and '_'
+    let label: String = config
+        .name
+        .to_lowercase()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '-' })
+        .take(63)
+        .collect();
+    json!({
+        "displayName": format!("{}-{}", config.name, chrono::Utc::now().format("%Y%m%d-%H%M%S")),
+        "labels": { "geoengine-worker": label },
+        "jobSpec": job_spec,
+    })
+}
+
+/// Tag the worker's local image for Artifact Registry and push it (credentials come from `deploy auth`)
+async fn push_to_artifact_registry(config: &WorkerConfig, version: &str, image: &str) -> Result<()> {
+    let client = DockerClient::new().await?;
+    let local = state::load_state(&config.name)
+        .ok()
+        .flatten()
+        .and_then(|s| s.image_tag)
+        .unwrap_or_else(|| format!("geoengine-local/{}:{}", config.name, version));
+    if client.image_id(&local).await.is_err() {
+        return Err(GeoEngineError::ImageNotFound(format!(
+            "Image '{}' not found. Run 'geoengine build' first.",
+            local
+        ))
+        .into());
+    }
+
+    println!("{} Pushing {} to {}...", "=>".blue().bold(), local.cyan(), image.cyan());
+    client.tag_image(&local, image).await?;
+    registry::push(&client, image, None, PushRetry::default()).await?;
+    println!("{} Successfully pushed: {}", "✓".green().bold(), image.cyan());
+    Ok(())
+}
+
+/// Print a job's state, polling until it finishes if `wait` is set and streaming
+/// its container output from Cloud Logging meanwhile. Ctrl-C stops watching; the
+/// job keeps running.
+async fn watch(vertex: &Vertex, job_id: &str, wait: bool) -> Result<()> {
+    let mut last_state = String::new();
+    let mut logs = LogTail::default();
+    loop {
+        let job = vertex.get_custom_job(job_id).await?;
+        let state = job["state"].as_str().unwrap_or("JOB_STATE_UNSPECIFIED");
+        let state = state.strip_prefix("JOB_STATE_").unwrap_or(state).to_string();
+        let display_name = job["displayName"].as_str().unwrap_or(job_id).to_string();
+        let id = job["name"].as_str().and_then(|n| n.rsplit('/').next()).unwrap_or(job_id).to_string();
+        let finished = matches!(state.as_str(), "SUCCEEDED" | "FAILED" | "CANCELLED" | "EXPIRED");
+
+        if wait {
+            logs.print_new(vertex, &id).await;
+        }
+        if state != last_state {
+            let reason = job["error"]["message"].as_str().unwrap_or_default();
+            let symbol = match state.as_str() {
+                "SUCCEEDED" => "✓".green().bold(),
+                "FAILED" | "CANCELLED" | "EXPIRED" => "✗".red().bold(),
+                _ => "•".cyan(),
+            };
+            if reason.is_empty() {
+                println!("{} {} {}", symbol, display_name, state);
+            } else {
+                println!("{} {} {} ({})", symbol, display_name, state, reason);
+            }
+            last_state = state.clone();
+        }
+
+        if finished {
+            if state == "SUCCEEDED" {
+                return Ok(());
+            }
+            return Err(GeoEngineError::ContainerFailed {
+                worker: display_name,
+                exit_code: 1,
+            }
+            .into());
+        }
+        if !wait {
+            println!(
+                "  {} Logs: {}",
+                "•".cyan(),
+                format!("gcloud ai custom-jobs stream-logs {} --region {}", id, vertex_region(&job)).cyan()
+            );
+            return Ok(());
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+            _ = client::shutdown_signal() => {
+                println!(
+                    "\n{} Stopped watching; the job keeps running. Check on it with {}",
+                    "!".yellow().bold(),
+                    format!("geoengine deploy vertex status {}", id).cyan()
+                );
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Region in a job's resource name: projects/<p>/locations/<region>/customJobs/<id>
+fn vertex_region(job: &Value) -> &str {
+    job["name"].as_str().and_then(|n| n.split('/').nth(3)).unwrap_or_default()
+}
+
+/// Log lines already printed while watching a job
+#[derive(Default)]
+struct LogTail {
+    /// Timestamp of the newest line printed; lines from this instant are fetched
+    /// again and skipped by ID
+    since: Option<String>,
+    printed: HashSet<String>,
+}
+
+impl LogTail {
+    async fn print_new(&mut self, vertex: &Vertex, job_id: &str) {
+        let entries = match vertex.log_entries(job_id, self.since.as_deref()).await {
+            Ok(entries) => entries,
+            // Logs are a convenience; the job state still decides the outcome
+            Err(e) => {
+                tracing::warn!("Failed to read the job's logs: {:#}", e);
+                return;
+            }
+        };
+        for entry in entries {
+            if !self.printed.insert(entry.id) {
+                continue;
+            }
+            if !entry.text.is_empty() {
+                println!("  {}", entry.text);
+            }
+            self.since = Some(entry.timestamp);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use colored::Colorize;
+
+use crate::cli::image::format_size;
+use crate::docker::client::DockerClient;
+
+#[derive(Subcommand)]
+pub enum VolumeCommands {
+    /// List named volumes created for workers
+    List,
+
+    /// Remove one or more volumes
+    Rm {
+        /// Volume names
+        #[arg(required = true)]
+        names: Vec<String>,
+
+        /// Force removal even if the volume is in use
+        #[arg(short, long)]
+        force: bool,
+    },
+
+    /// Remove all worker volumes not used by any container
+    Prune,
+}
+
+impl VolumeCommands {
+    pub async fn execute(self) -> Result<()> {
+        let client = DockerClient::new().await?;
+
+        match self {
+            Self::List => list_volumes(&client).await,
+            Self::Rm { names, force } => remove_volumes(&client, &names, force).await,
+            Self::Prune => prune_volumes(&client).await,
+        }
+    }
+}
+
+async fn list_volumes(client: &DockerClient) -> Result<()> {
+    let volumes = client
+        .list_volumes()
+        .await
+        .context("Failed to list volumes")?;
+
+    if volumes.is_empty() {
+        println!("{}", "No volumes found".yellow());
+        return Ok(());
+    }
+
+    println!(
+        "{:<40} {:<25} {:<10} {}",
+        "NAME".bold(),
+        "WORKER".bold(),
+        "DRIVER".bold(),
+        "CREATED".bold()
+    );
+    println!("{}", "-".repeat(100));
+    for volume in &volumes {
+        println!(
+            "{:<40} {:<25} {:<10} {}",
+            volume.name, volume.worker, volume.driver, volume.created
+        );
+    }
+
+    Ok(())
+}
+
+async fn remove_volumes(client: &DockerClient, names: &[String], force: bool) -> Result<()> {
+    for name in names {
+        println!("{} Removing volume {}...", "=>".blue().bold(), name.cyan());
+        client.remove_volume(name, force).await?;
+        println!("{} Successfully removed volume: {}", "✓".green().bold(), name.cyan());
+    }
+
+    Ok(())
+}
+
+async fn prune_volumes(client: &DockerClient) -> Result<()> {
+    println!("{} Removing unused worker volumes...", "=>".blue().bold());
+
+    let (removed, reclaimed) = client
+        .prune_volumes()
+        .await
+        .context("Failed to prune volumes")?;
+
+    if removed.is_empty() {
+        println!("{} No unused volumes", "✓".green().bold());
+        return Ok(());
+    }
+    for name in &removed {
+        println!("  {} {}", "•".cyan(), name);
+    }
+    println!(
+        "{} Removed {} volume(s), reclaimed {}",
+        "✓".green().bold(),
+        removed.len(),
+        format_size(reclaimed)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,296 @@
+use anyhow::Result;
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
+
+use crate::config::worker::{Accelerator, CommandConfig, InputParameter, MountConfig, RuntimeConfig, WorkerConfig};
+
+/// A suggested base image with the command that runs scripts in it
+struct BaseImage {
+    label: &'static str,
+    image: &'static str,
+    program: &'static str,
+    script: &'static str,
+    gpu: bool,
+    /// Dockerfile lines installing what the starter script needs
+    setup: &'static str,
+}
+
+const BASE_IMAGES: &[BaseImage] = &[
+    BaseImage {
+        label: "GDAL + Python (rasterio, numpy)",
+        image: "ghcr.io/osgeo/gdal:ubuntu-small-latest",
+        program: "python3",
+        script: "main.py",
+        gpu: false,
+        setup: "RUN apt-get update && apt-get install -y --no-install-recommends \\\n    python3 python3-numpy python3-rasterio \\\n    && rm -rf /var/lib/apt/lists/*\n",
+    },
+    BaseImage {
+        label: "PyTorch (CUDA)",
+        image: "pytorch/pytorch:2.4.0-cuda12.1-cudnn9-runtime",
+        program: "python",
+        script: "main.py",
+        gpu: true,
+        setup: "",
+    },
+    BaseImage {
+        label: "R (rocker/geospatial: sf, terra)",
+        image: "rocker/geospatial:latest",
+        program: "Rscript",
+        script: "main.R",
+        gpu: false,
+        setup: "",
+    },
+    BaseImage {
+        label: "Python (slim)",
+        image: "python:3.12-slim",
+        program: "python",
+        script: "main.py",
+        gpu: false,
+        setup: "",
+    },
+];
+
+const INPUT_TYPES: &[&str] = &["file", "folder", "string", "number", "boolean", "enum", "datetime"];
+
+/// What the wizard creates besides the config file
+pub struct Scaffold {
+    pub dockerfile: String,
+    pub script_name: String,
+    pub script: String,
+}
+
+/// Ask for the worker's name, base image, GPU use, mounts and inputs, and return
+/// the config together with a starter Dockerfile and script
+pub fn run(default_name: &str) -> Result<(WorkerConfig, Scaffold)> {
+    let theme = ColorfulTheme::default();
+
+    let name: String = Input::with_theme(&theme)
+        .with_prompt("Worker name")
+        .default(default_name.to_string())
+        .validate_with(|name: &String| validate_name(name))
+        .interact_text()?;
+    let description: String = Input::with_theme(&theme)
+        .with_prompt("Description")
+        .allow_empty(true)
+        .interact_text()?;
+
+    // --- Base image ---
+    let mut items: Vec<String> = BASE_IMAGES.iter().map(|b| format!("{} ({})", b.label, b.image)).collect();
+    items.push("Other image".to_string());
+    let choice = Select::with_theme(&theme)
+        .with_prompt("Base image")
+        .items(&items)
+        .default(0)
+        .interact()?;
+    let (image, program, default_script, default_gpu, setup) = match BASE_IMAGES.get(choice) {
+        Some(base) => (
+            base.image.to_string(),
+            base.program.to_string(),
+            base.script.to_string(),
+            base.gpu,
+            base.setup.to_string(),
+        ),
+        None => {
+            let image: String = Input::with_theme(&theme).with_prompt("Image").interact_text()?;
+            let program: String = Input::with_theme(&theme)
+                .with_prompt("Program that runs the script")
+                .default("python".to_string())
+                .interact_text()?;
+            let script = if program.starts_with("Rscript") { "main.R" } else { "main.py" };
+            (image, program, script.to_string(), false, String::new())
+        }
+    };
+    let script: String = Input::with_theme(&theme)
+        .with_prompt("Script")
+        .default(default_script)
+        .interact_text()?;
+
+    let gpu = Confirm::with_theme(&theme)
+        .with_prompt("Does the worker need an NVIDIA GPU?")
+        .default(default_gpu)
+        .interact()?;
+
+    // --- Mounts ---
+    let mut mounts = Vec::new();
+    let mut prompt = "Mount a local directory into every run (e.g. reference data)?";
+    while Confirm::with_theme(&theme).with_prompt(prompt).default(false).interact()? {
+        let host_path: String = Input::with_theme(&theme)
+            .with_prompt("  Host path (./ is the worker directory)")
+            .default("./data".to_string())
+            .interact_text()?;
+        let container_path: String = Input::with_theme(&theme)
+            .with_prompt("  Container path")
+            .default("/data".to_string())
+            .validate_with(|p: &String| if p.starts_with('/') { Ok(()) } else { Err("must be absolute") })
+            .interact_text()?;
+        let readonly = Confirm::with_theme(&theme)
+            .with_prompt("  Read-only?")
+            .default(true)
+            .interact()?;
+        mounts.push(MountConfig {
+            host_path,
+            container_path,
+            readonly: Some(readonly),
+        });
+        prompt = "Mount another directory?";
+    }
+
+    // --- Inputs ---
+    let mut inputs: Vec<InputParameter> = Vec::new();
+    let mut prompt = "Add an input parameter?";
+    while Confirm::with_theme(&theme).with_prompt(prompt).default(inputs.is_empty()).interact()? {
+        inputs.push(ask_input(&theme, &inputs)?);
+        prompt = "Add another input parameter?";
+    }
+
+    let mut config = WorkerConfig::template(&name);
+    config.description = (!description.trim().is_empty()).then(|| description.trim().to_string());
+    config.command = Some(CommandConfig {
+        program: program.clone(),
+        script: script.clone(),
+        inputs: (!inputs.is_empty()).then(|| inputs.clone()),
+        check: None,
+    });
+    config.local_dir_mounts = (!mounts.is_empty()).then_some(mounts);
+    config.runtime = Some(RuntimeConfig {
+        accelerator: Some(if gpu { Accelerator::Cuda } else { Accelerator::Cpu }),
+        ..Default::default()
+    });
+
+    let scaffold = Scaffold {
+        dockerfile: starter_dockerfile(&image, &setup, &script, &program),
+        script: if program.starts_with("Rscript") {
+            starter_r_script(&name, &inputs)
+        } else {
+            starter_python_script(&name, &inputs)
+        },
+        script_name: script,
+    };
+    println!();
+    Ok((config, scaffold))
+}
+
+fn validate_name(name: &str) -> std::result::Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("use letters, digits, '-' and '_'".to_string());
+    }
+    Ok(())
+}
+
+fn ask_input(theme: &ColorfulTheme, existing: &[InputParameter]) -> Result<InputParameter> {
+    let name: String = Input::with_theme(theme)
+        .with_prompt("  Name (passed as --<name>)")
+        .validate_with(|name: &String| {
+            validate_name(name)?;
+            if existing.iter().any(|i| i.name == *name) {
+                return Err(format!("'{}' is already defined", name));
+            }
+            Ok(())
+        })
+        .interact_text()?;
+    let param_type = INPUT_TYPES[Select::with_theme(theme)
+        .with_prompt("  Type")
+        .items(INPUT_TYPES)
+        .default(0)
+        .interact()?];
+
+    let readonly = if param_type == "folder" {
+        let output = Confirm::with_theme(theme)
+            .with_prompt("  Is it an output folder the worker writes to?")
+            .default(existing.iter().all(|i| i.readonly != Some(false)))
+            .interact()?;
+        Some(!output)
+    } else {
+        None
+    };
+    let enum_values = if param_type == "enum" {
+        let values: String = Input::with_theme(theme)
+            .with_prompt("  Allowed values (comma-separated)")
+            .validate_with(|v: &String| if v.trim().is_empty() { Err("enter at least one value") } else { Ok(()) })
+            .interact_text()?;
+        Some(values.split(',').map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect())
+    } else {
+        None
+    };
+    let required = Confirm::with_theme(theme)
+        .with_prompt("  Required?")
+        .default(true)
+        .interact()?;
+    let description: String = Input::with_theme(theme)
+        .with_prompt("  Description")
+        .allow_empty(true)
+        .interact_text()?;
+
+    Ok(InputParameter {
+        name,
+        param_type: param_type.to_string(),
+        required: Some(required),
+        default: None,
+        description: (!description.trim().is_empty()).then(|| description.trim().to_string()),
+        enum_values,
+        readonly,
+        group: None,
+        visible_when: None,
+    })
+}
+
+fn starter_dockerfile(image: &str, setup: &str, script: &str, program: &str) -> String {
+    let mut dockerfile = format!("FROM {}\n\n", image);
+    if !setup.is_empty() {
+        dockerfile.push_str(setup);
+        dockerfile.push('\n');
+    }
+    if !program.starts_with("Rscript") {
+        dockerfile.push_str("ENV PYTHONUNBUFFERED=1\n\n");
+    }
+    // `geoengine run` starts `<program> <script>` in the working directory
+    dockerfile.push_str(&format!("WORKDIR /app\nCOPY {} /app/{}\n", script, script));
+    dockerfile
+}
+
+fn starter_python_script(worker: &str, inputs: &[InputParameter]) -> String {
+    let mut script = format!(
+        "\"\"\"Entry point of the {} worker; GeoEngine passes each input as --<name> <value>.\"\"\"\n\nimport argparse\n\n\ndef main():\n    parser = argparse.ArgumentParser()\n",
+        worker
+    );
+    for input in inputs {
+        let mut args = vec![format!("\"--{}\"", input.name)];
+        match input.param_type.as_str() {
+            "number" => args.push("type=float".to_string()),
+            "boolean" => args.push("type=lambda v: v.lower() == \"true\"".to_string()),
+            "enum" => {
+                let choices: Vec<String> = input
+                    .enum_values
+                    .iter()
+                    .flatten()
+                    .map(|v| format!("\"{}\"", v))
+                    .collect();
+                args.push(format!("choices=[{}]", choices.join(", ")));
+            }
+            _ => {}
+        }
+        if input.required.unwrap_or(true) {
+            args.push("required=True".to_string());
+        }
+        if let Some(description) = &input.description {
+            args.push(format!("help={:?}", description));
+        }
+        script.push_str(&format!("    parser.add_argument({})\n", args.join(", ")));
+    }
+    script.push_str("    args = parser.parse_args()\n\n    # TODO: process the inputs\n    print(vars(args))\n\n\nif __name__ == \"__main__\":\n    main()\n");
+    script
+}
+
+fn starter_r_script(worker: &str, inputs: &[InputParameter]) -> String {
+    let mut script = format!(
+        "# Entry point of the {} worker; GeoEngine passes each input as --<name> <value>.\n\nargs <- commandArgs(trailingOnly = TRUE)\ninputs <- list()\nfor (i in seq_len(length(args) %/% 2)) {{\n  inputs[[sub(\"^--\", \"\", args[2 * i - 1])]] <- args[2 * i]\n}}\n\n",
+        worker
+    );
+    for input in inputs.iter().filter(|i| i.required.unwrap_or(true)) {
+        script.push_str(&format!(
+            "if (is.null(inputs[[\"{}\"]])) stop(\"--{} is required\")\n",
+            input.name, input.name
+        ));
+    }
+    script.push_str("\n# TODO: process the inputs\nprint(inputs)\n");
+    script
+}
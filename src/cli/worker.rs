@@ -1,22 +1,60 @@
 use std::cmp::Ordering;
 use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
 use colored::Colorize;
-use dialoguer::{theme::ColorfulTheme, Select};
+use dialoguer::{theme::ColorfulTheme, Confirm, Input, Select};
 use indicatif::{ProgressBar, ProgressStyle};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
-use crate::config::worker::WorkerConfig;
-use crate::config::settings::Settings;
+use crate::cli::history as history_cli;
+use crate::cli::hooks::HookRunner;
+use crate::cli::service;
+use crate::config::audit;
+use crate::config::history::{self, HistoryEntry, HostInfo};
+use crate::config::lockfile::{Lockfile, LOCK_FILE};
+use crate::config::migrate;
+use crate::config::provenance::{InputFileRecord, Provenance, VerifiedFolderRecord, PROVENANCE_FILE};
+use crate::config::worker::{self as worker_config, Accelerator, BuildVariant, ConfigFormat, InputParameter, SecurityConfig, VolumeConfig, VolumeKind, WorkerConfig};
+use crate::config::settings::{DefaultMount, Settings};
 use crate::config::state::{self, sha256_bytes, WorkerState};
 use crate::config::yaml_store;
-use crate::docker::client::DockerClient;
-use crate::docker::config::ContainerConfig;
-use crate::docker::gpu::GpuConfig;
-use crate::docker::dockerfile::get_dockerfile_config;
+use crate::docker::build_context::BuildContext;
+use crate::docker::client::{self, ContainerExit, DockerClient};
+use crate::docker::config::{parse_mount, ContainerConfig};
+use crate::docker::gpu::{self, GpuConfig, GpuRequest};
+use crate::docker::gpu_reservations::{self, GpuReservation};
+use crate::docker::dockerfile::{get_base_images, get_dockerfile_config};
+use crate::docker::geometa::{self, GeoMetadata};
+use crate::docker::host;
+use crate::docker::labels;
+use crate::docker::reaper;
+use crate::docker::registry;
+use crate::docker::usage::{self, ResourceUsage};
+use crate::docker::user::{self, UserNamespace};
+use crate::docker::wait;
+use crate::error::{ErrorInfo, GeoEngineError};
+use crate::cli::batch;
 use crate::cli::plugins;
+use crate::cli::wizard;
 use crate::cli::plugins::{verify_arcgis_plugin_installed, verify_qgis_plugin_installed};
-use crate::utils::versioning::{compare_versions, validate_version, get_latest_worker_version_clientless, get_latest_worker_version, compare_worker_version};
+use crate::cli::image::format_size;
+use crate::cli::output_rules::validate_outputs;
+use crate::utils::checksums;
+use crate::utils::datasets;
+use crate::utils::disk;
+use crate::utils::dotenv;
+use crate::utils::paths;
+use crate::utils::process;
+use crate::utils::sidecars;
+use crate::utils::stac::{self, StacFile};
+use crate::utils::versioning::{bump_version, VersionBump, compare_versions, validate_version, get_latest_worker_version_clientless, get_latest_worker_version, compare_worker_version};
+use crate::utils::workspace::JobWorkspace;
+
+/// Default `runtime.scratch_multiplier`: outputs and scratch take about twice the input size
+const DEFAULT_SCRATCH_MULTIPLIER: f64 = 2.0;
+
 // ---------------------------------------------------------------------------
 // JSON output structs (used by --json flags and plugin integration)
 // ---------------------------------------------------------------------------
@@ -27,7 +65,32 @@ struct WorkerListEntry {
     path: String,
     has_tool: bool,
     found: bool,
-    description: Option<String>
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<WorkerStatus>,
+}
+
+/// Image and build freshness of a worker, for `geoengine workers --status`
+#[derive(Serialize, Deserialize)]
+struct WorkerStatus {
+    version: Option<String>,
+    /// Image of the last build
+    image: Option<String>,
+    /// Whether that image exists locally (None when Docker isn't reachable)
+    image_present: Option<bool>,
+    /// Whether geoengine.yaml, the Dockerfiles or the script changed since the last
+    /// build (None when never built, or when the build context is remote)
+    dirty: Option<bool>,
+    last_run: Option<LastRun>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct LastRun {
+    /// History ID, for `geoengine history show`
+    id: usize,
+    started_at: String,
+    /// None for detached runs
+    exit_code: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -51,6 +114,10 @@ struct InputDescriptionJson {
     description: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     enum_values: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visible_when: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -58,8 +125,17 @@ struct RunResult {
     status: String,
     exit_code: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
+    error: Option<ErrorInfo>,
     files: Vec<OutputFileInfo>,
+    /// Writable folder inputs the run wrote to
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    output_dirs: Vec<String>,
+    /// STAC collections written for the outputs
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stac: Vec<String>,
+    /// CPU, memory and GPU use of the container
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage: Option<ResourceUsage>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -67,19 +143,26 @@ struct OutputFileInfo {
     name: String,
     path: String,
     size: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    geo: Option<GeoMetadata>,
 }
 
 // ---------------------------------------------------------------------------
 // geoengine init
 // ---------------------------------------------------------------------------
 
-pub async fn init_worker(name: Option<&str>) -> Result<()> {
+pub async fn init_worker(name: Option<&str>, format: ConfigFormat, interactive: bool) -> Result<()> {
     let current_dir = std::env::current_dir()?;
-    let config_path = current_dir.join("geoengine.yaml");
+    let config_path = current_dir.join(format.file_name());
+    if interactive && !std::io::stdin().is_terminal() {
+        anyhow::bail!("--interactive needs a terminal");
+    }
 
-    if config_path.exists() {
+    let existing = worker_config::config_file(&current_dir);
+    if existing.exists() {
+        let existing_name = existing.file_name().unwrap_or_default().to_string_lossy().to_string();
         let replace = Select::with_theme(&ColorfulTheme::default())
-            .with_prompt(format!("geoengine.yaml already exists in {}. Overwrite existing geoengine.yaml?", current_dir.display()))
+            .with_prompt(format!("{} already exists in {}. Overwrite existing {}?", existing_name, current_dir.display(), existing_name))
             .items(&["Yes", "No"])
             .default(1)
             .interact()?;
@@ -88,6 +171,11 @@ pub async fn init_worker(name: Option<&str>) -> Result<()> {
             0 => (),
             _ => return Ok(())
         }
+        // Only one config file per worker, so replacing one in another format removes it
+        if existing != config_path {
+            std::fs::remove_file(&existing)
+                .with_context(|| format!("Failed to remove {}", existing.display()))?;
+        }
     }
 
     let worker_name = name
@@ -100,6 +188,43 @@ pub async fn init_worker(name: Option<&str>) -> Result<()> {
                 .to_string()
         });
 
+    if interactive {
+        let (config, scaffold) = wizard::run(&worker_name)?;
+        std::fs::write(&config_path, format.serialize(&config)?)?;
+        println!("{} Created {}", "✓".green().bold(), format.file_name());
+
+        let dockerfile = current_dir.join("Dockerfile");
+        let replace_dockerfile = !dockerfile.exists()
+            || Confirm::with_theme(&ColorfulTheme::default())
+                .with_prompt("A Dockerfile already exists. Replace it with the starter Dockerfile?")
+                .default(false)
+                .interact()?;
+        if replace_dockerfile {
+            std::fs::write(&dockerfile, &scaffold.dockerfile)
+                .with_context(|| format!("Failed to write {}", dockerfile.display()))?;
+            println!("{} Created Dockerfile", "✓".green().bold());
+        } else {
+            println!("{} Kept the existing Dockerfile", "•".cyan());
+        }
+
+        let script = current_dir.join(&scaffold.script_name);
+        if script.exists() {
+            println!("{} Kept the existing {}", "•".cyan(), scaffold.script_name);
+        } else {
+            if let Some(parent) = script.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&script, &scaffold.script)
+                .with_context(|| format!("Failed to write {}", script.display()))?;
+            println!("{} Created {}", "✓".green().bold(), scaffold.script_name);
+        }
+
+        println!("\nNext steps:");
+        println!("  1. Fill in {} and add its dependencies to the Dockerfile", scaffold.script_name);
+        println!("  2. Run {} to register and build", "geoengine apply".cyan());
+        return Ok(());
+    }
+
     let mut template = WorkerConfig::template(&worker_name);
 
     if let Err(e) = get_dockerfile_config(&current_dir, &mut template) {
@@ -110,39 +235,169 @@ pub async fn init_worker(name: Option<&str>) -> Result<()> {
         );
     }
 
-    let yaml = serde_yaml::to_string(&template)?;
+    let content = format.serialize(&template)?;
 
-    std::fs::write(&config_path, yaml)?;
+    std::fs::write(&config_path, content)?;
 
     println!(
         "{} Created {} in {}",
         "✓".green().bold(),
-        "geoengine.yaml".cyan(),
+        format.file_name().cyan(),
         current_dir.display()
     );
     println!("\nNext steps:");
-    println!("  1. Edit geoengine.yaml to configure your worker");
+    println!("  1. Edit {} to configure your worker", format.file_name());
     println!("  2. Run {} to register and build", "geoengine apply".cyan());
 
     Ok(())
 }
 
+// ---------------------------------------------------------------------------
+// geoengine upgrade
+// ---------------------------------------------------------------------------
+
+/// Migrate a worker's config file to the current schema, keeping the original as a backup
+pub async fn upgrade_worker(worker: Option<&str>, dry_run: bool) -> Result<()> {
+    let dir = match worker {
+        Some(name) => Settings::load()?.get_worker_path(name)?,
+        None => std::env::current_dir()?,
+    };
+    if !worker_config::has_config_file(&dir) {
+        return Err(GeoEngineError::WorkerNotFound(format!(
+            "No geoengine.yaml (or geoengine.toml / geoengine.json) in {}",
+            dir.display()
+        ))
+        .into());
+    }
+    let config_path = worker_config::config_file(&dir);
+    let file_name = config_path.file_name().unwrap_or_default().to_string_lossy().to_string();
+    let format = ConfigFormat::of(&config_path);
+    let content = std::fs::read_to_string(&config_path)
+        .with_context(|| format!("Failed to read {}", config_path.display()))?;
+
+    let invalid = || GeoEngineError::ConfigInvalid(format!("Failed to parse config file: {}", config_path.display()));
+    let mut value: serde_yaml::Value = format.parse(&content).with_context(invalid)?;
+    let version = migrate::schema_version(&value).with_context(invalid)?;
+    if version == migrate::SCHEMA_VERSION {
+        println!("{} {} is already at schema {}", "✓".green().bold(), file_name, version);
+        return Ok(());
+    }
+
+    let steps = migrate::upgrade(&mut value).with_context(invalid)?;
+    // Check the result before touching the file
+    let upgraded: WorkerConfig = serde_yaml::from_value(value.clone()).with_context(invalid)?;
+    upgraded.validate_inputs().with_context(invalid)?;
+    // When only the version changes, the file is patched instead of rewritten
+    let patched = if migrate::needs_migration(version) {
+        None
+    } else {
+        migrate::set_version_line(&content, format).filter(|patched| {
+            format
+                .parse::<serde_yaml::Value>(patched)
+                .is_ok_and(|v| migrate::schema_version(&v).ok() == Some(migrate::SCHEMA_VERSION))
+        })
+    };
+
+    println!(
+        "{} Migrating {} from schema {} to {}",
+        "=>".blue().bold(),
+        file_name.cyan(),
+        version,
+        migrate::SCHEMA_VERSION
+    );
+    for step in &steps {
+        println!("  {} {}", "•".cyan(), step);
+    }
+    let rewritten = patched.is_none();
+    let migrated = match patched {
+        Some(patched) => patched,
+        None => format.serialize(&value)?,
+    };
+    if dry_run {
+        println!();
+        print!("{}", migrated);
+        return Ok(());
+    }
+    if rewritten && format != ConfigFormat::Json {
+        eprintln!(
+            "{} {} is rewritten from the migrated config: comments and formatting are not carried over",
+            "!".yellow().bold(),
+            file_name
+        );
+    }
+
+    let backup = config_path.with_file_name(format!("{}.schema{}.bak", file_name, version));
+    std::fs::copy(&config_path, &backup)
+        .with_context(|| format!("Failed to back up {} to {}", config_path.display(), backup.display()))?;
+    std::fs::write(&config_path, migrated)
+        .with_context(|| format!("Failed to write {}", config_path.display()))?;
+
+    println!("{} Upgraded {} (original saved as {})", "✓".green().bold(), file_name, backup.display());
+    if rewritten && format != ConfigFormat::Json {
+        println!("  Copy comments back from the backup if needed.");
+    }
+    println!("  Run {} to register the upgraded config", "geoengine apply".cyan());
+    Ok(())
+}
+
 // ---------------------------------------------------------------------------
 // geoengine build
 // ---------------------------------------------------------------------------
 
-pub async fn build_worker_local(no_cache: bool, dev: bool, build_args: &[String]) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+pub async fn build_worker_local(
+    no_cache: bool,
+    dev: bool,
+    build_args: &[String],
+    bump: Option<VersionBump>,
+    locked: bool,
+    target: Option<&str>,
+    variants: &[String],
+    check: bool,
+) -> Result<()> {
     let (worker_name, _) = resolve_worker_from_cwd();
-    build_worker(&worker_name, no_cache, dev, build_args).await
+    build_worker(&worker_name, no_cache, dev, build_args, bump, locked, target, variants, check).await
 }
 
-pub async fn build_worker(worker: &str, no_cache: bool, dev: bool, build_args: &[String]) -> Result<()> {
+#[allow(clippy::too_many_arguments)]
+#[tracing::instrument(skip_all, fields(worker = %worker, dev))]
+pub async fn build_worker(
+    worker: &str,
+    no_cache: bool,
+    dev: bool,
+    build_args: &[String],
+    bump: Option<VersionBump>,
+    locked: bool,
+    target: Option<&str>,
+    variants: &[String],
+    check: bool,
+) -> Result<()> {
     let settings = Settings::load()?;
     let worker_path = settings.get_worker_path(worker)?;
-    let config = yaml_store::load_saved_config(worker)?;
+    let mut config = yaml_store::load_saved_config(worker)?;
 
     let client = DockerClient::new().await?;
 
+    // --- Version bump ---
+    // geoengine.yaml is only rewritten after a successful build, so a failed
+    // build can be retried with the same --bump.
+    if let Some(part) = bump {
+        if yaml_store::check_changed_config(worker, &worker_path)? {
+            anyhow::bail!(
+                "geoengine.yaml has changes that have not been applied. Run 'geoengine apply' before using --bump."
+            );
+        }
+        let current = config.version.clone().unwrap_or_default();
+        let bumped = bump_version(&current, part).map_err(|e| anyhow::anyhow!(e.red().bold()))?;
+        println!(
+            "{} Bumping version: {} -> {}",
+            "=>".blue().bold(),
+            current,
+            bumped.cyan()
+        );
+        config.version = Some(bumped);
+    }
+
     let new_version = config.version.clone().unwrap_or("".to_string());
 
     // --- Version validation ---
@@ -179,24 +434,39 @@ pub async fn build_worker(worker: &str, no_cache: bool, dev: bool, build_args: &
         }
     };
 
+    // --- Build context (remote contexts are fetched into a temporary directory) ---
+    let fetched = BuildContext::of(&config, &worker_path).fetch().await?;
+    let context = fetched.dir.clone();
+
+    // Parse build args from CLI only
+    let mut args: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    for arg in build_args {
+        let parts: Vec<&str> = arg.splitn(2, '=').collect();
+        if parts.len() == 2 {
+            args.insert(parts[0].to_string(), parts[1].to_string());
+        }
+    }
+    let plans = build_plans(&config, &context, variants, target, &args)?;
+
     // --- File change detection ---
-    let dockerfile = worker_path.join("Dockerfile");
-    if !dockerfile.exists() {
-        anyhow::bail!("Dockerfile not found: {}", dockerfile.display());
+    // Every variant's Dockerfile counts, also when only some variants are built
+    let dockerfiles = all_dockerfiles(&config, &context)?;
+    for dockerfile in &dockerfiles {
+        if !dockerfile.exists() {
+            anyhow::bail!("Dockerfile not found: {}", dockerfile.display());
+        }
     }
 
     let yaml_build_hash = config.build_relevant_hash();
-    let dockerfile_hash = Some(state::compute_file_hash(&dockerfile)?);
+    let files_hash = dockerfiles_hash(&dockerfiles)?;
+    // A remote context's revision stands in for the files in it we don't hash
+    let dockerfile_hash = Some(match &fetched.revision {
+        Some(revision) => state::sha256_string(&(files_hash + revision)),
+        None => files_hash,
+    });
 
     // Hash the command script file (e.g. main.py) so changes to it trigger a rebuild
-    let command_hash: Option<String> = config.command.as_ref().and_then(|cmd| {
-        let script_path = worker_path.join(&cmd.script);
-        if script_path.exists() {
-            state::compute_file_hash(&script_path).ok()
-        } else {
-            None
-        }
-    });
+    let command_hash = command_script_hash(&config, &worker_path);
 
     let prev_state = state::load_state(worker)?;
     let pushed_build_hash = match dev {
@@ -264,54 +534,159 @@ pub async fn build_worker(worker: &str, no_cache: bool, dev: bool, build_args: &
         worker.cyan()
     );
 
-    let context = worker_path.clone();
-    let image_tag = format!("geoengine-local{}/{}:{}",
-        match dev {
-            true => "-dev",
-            false => ""
-        },
-        config.name,
-        match dev {
-            true => "latest".to_string(),
-            false => new_version
-        })
-    ;
+    let repository = format!("geoengine-local{}/{}", if dev { "-dev" } else { "" }, config.name);
+    let image_tag_of = |variant: Option<&str>| match (variant, dev) {
+        (None, true) => format!("{}:latest", repository),
+        (None, false) => format!("{}:{}", repository, new_version),
+        (Some(variant), true) => format!("{}:{}", repository, variant),
+        (Some(variant), false) => format!("{}:{}-{}", repository, new_version, variant),
+    };
 
-    // Parse build args from CLI only
-    let mut args: std::collections::HashMap<String, String> = std::collections::HashMap::new();
-    for arg in build_args {
-        let parts: Vec<&str> = arg.splitn(2, '=').collect();
-        if parts.len() == 2 {
-            args.insert(parts[0].to_string(), parts[1].to_string());
+    let mut plan_dockerfiles: Vec<&PathBuf> = plans.iter().map(|p| &p.dockerfile).collect();
+    plan_dockerfiles.dedup();
+
+    // With mirrors or rewrites configured, base images are pulled by us so the
+    // build finds them locally instead of reaching out to their public registries
+    if registry::redirects_configured()? {
+        for dockerfile in &plan_dockerfiles {
+            for base in get_base_images(dockerfile)? {
+                if client.image_id(&base).await.is_err() {
+                    println!("{} Pulling base image {}...", "=>".blue().bold(), base.cyan());
+                    registry::pull(&client, &base).await?;
+                }
+            }
         }
     }
 
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(
-        ProgressStyle::default_spinner()
-            .template("{spinner:.green} {msg}")?,
+    if locked {
+        verify_locked_base_images(&client, &worker_path, &plan_dockerfiles).await?;
+    }
+
+    let image_labels = labels::build_labels(
+        &config,
+        &worker_path,
+        &labels::BuildHashes {
+            config: &yaml_build_hash,
+            dockerfile: dockerfile_hash.as_deref(),
+            command: command_hash.as_deref(),
+        },
     );
-    pb.set_message("Building image...");
-    pb.enable_steady_tick(std::time::Duration::from_millis(100));
 
-    client
-        .build_image(&dockerfile, &context, &image_tag, &args, no_cache)
-        .await?;
+    for plan in &plans {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner:.green} {msg}")?,
+        );
+        pb.set_message(match &plan.variant {
+            Some(variant) => format!("Building variant '{}'...", variant),
+            None => "Building image...".to_string(),
+        });
+        pb.enable_steady_tick(std::time::Duration::from_millis(100));
 
-    pb.finish_and_clear();
-    println!(
-        "{} Successfully built image: {}",
-        "✓".green().bold(),
-        image_tag.cyan()
-    );
+        let image_tag = image_tag_of(plan.variant.as_deref());
+        client
+            .build_image(
+                &plan.dockerfile,
+                &context,
+                &image_tag,
+                &plan.args,
+                &image_labels,
+                no_cache,
+                plan.target.as_deref(),
+            )
+            .await?;
 
-    // --- Update state with new hashes after successful build ---
+        pb.finish_and_clear();
+        println!(
+            "{} Successfully built image: {}",
+            "✓".green().bold(),
+            image_tag.cyan()
+        );
+
+        if check {
+            if let Err(e) = check_image(&client, &config, &image_tag).await {
+                // Don't leave a broken image where run, export or push would pick it up
+                client.remove_image(&image_tag, false).await.ok();
+                return Err(e);
+            }
+        }
+
+        // Release builds also move the variant's own tag, like :latest
+        if let (Some(variant), false) = (&plan.variant, dev) {
+            let variant_tag = format!("{}:{}", repository, variant);
+            client.tag_image(&image_tag, &variant_tag).await?;
+            println!("{} Tagged {}", "✓".green().bold(), variant_tag.cyan());
+        }
+    }
+
+    // The variant `run` would pick on this host also gets the plain tags, so commands
+    // that take the worker's image (export, deploy, diff) find it
+    let host_variant = match config.build.as_ref().and_then(|b| b.variants.as_ref()) {
+        Some(variants) => {
+            let gpu = GpuConfig::for_accelerator(config.accelerator()).await.ok().flatten();
+            select_variant(variants, gpu.as_ref())
+                .filter(|variant| plans.iter().any(|p| p.variant.as_deref() == Some(*variant)))
+                .map(str::to_string)
+        }
+        None => None,
+    };
+    let image_tag = match &host_variant {
+        Some(variant) => {
+            let variant_tag = image_tag_of(Some(variant));
+            let plain = image_tag_of(None);
+            client.tag_image(&variant_tag, &plain).await?;
+            println!(
+                "{} Tagged {} (variant '{}', for this host)",
+                "✓".green().bold(),
+                plain.cyan(),
+                variant
+            );
+            plain
+        }
+        None => image_tag_of(plans.last().and_then(|p| p.variant.as_deref())),
+    };
+
+    // Release builds also move the worker's :latest tag
+    if !dev && (plans.iter().all(|p| p.variant.is_none()) || host_variant.is_some()) {
+        let latest_tag = format!("geoengine-local/{}:latest", config.name);
+        client.tag_image(&image_tag, &latest_tag).await?;
+        println!("{} Tagged {}", "✓".green().bold(), latest_tag.cyan());
+    }
+
+    // Record the base image digests this build used (--locked builds used the recorded ones)
+    if !locked {
+        if let Err(e) = record_base_images(&client, &worker_path, &dockerfiles).await {
+            println!("{} Could not update {}: {:#}", "!".yellow().bold(), LOCK_FILE, e);
+        }
+    }
+
+    // --- Persist the bumped version (geoengine.yaml + applied config) ---
     let prev_state = state::load_state(worker)?;
+    let mut yaml_hash = prev_state.as_ref().and_then(|s| s.yaml_hash.clone());
+    if bump.is_some() {
+        let yaml_path = worker_config::config_file(&worker_path);
+        let content = std::fs::read_to_string(&yaml_path)
+            .with_context(|| format!("Failed to read {}", yaml_path.display()))?;
+        let updated = set_version_in_config(&content, &new_version, ConfigFormat::of(&yaml_path))?;
+        std::fs::write(&yaml_path, &updated)
+            .with_context(|| format!("Failed to write {}", yaml_path.display()))?;
+        yaml_store::save_config(&config)?;
+        yaml_hash = Some(sha256_bytes(updated.as_bytes()));
+        println!(
+            "{} Updated version in {} to {}",
+            "✓".green().bold(),
+            yaml_path.display(),
+            new_version.cyan()
+        );
+    }
+
+    // --- Update state with new hashes after successful build ---
     let new_state = WorkerState {
         worker_name: worker.to_string(),
         applied_at: chrono::Utc::now().to_rfc3339(),
         yaml_build_hash,
-        yaml_hash: prev_state.as_ref().and_then(|s| s.yaml_hash.clone()),
+        yaml_hash,
         dockerfile_hash,
         command_hash,
         pushed_build_hash,
@@ -324,105 +699,628 @@ pub async fn build_worker(worker: &str, no_cache: bool, dev: bool, build_args: &
     Ok(())
 }
 
-// ---------------------------------------------------------------------------
-// geoengine apply
-// ---------------------------------------------------------------------------
+/// Status of each worker from its saved state, its config files, the local images
+/// (when Docker is reachable) and the run history
+async fn worker_statuses(workers: &[(&str, &PathBuf)]) -> Result<HashMap<String, WorkerStatus>> {
+    let client = DockerClient::new().await.ok();
+    let history = history::load()?;
+    let mut statuses = HashMap::new();
+    for (name, path) in workers {
+        let state = state::load_state(name)?;
+        let config = WorkerConfig::load(&worker_config::config_file(path))
+            .or_else(|_| yaml_store::load_saved_config(name))
+            .ok();
+        let image = state.as_ref().and_then(|s| s.image_tag.clone());
+        let image_present = match (&client, &image) {
+            (Some(client), Some(image)) => Some(client.image_id(image).await.is_ok()),
+            (Some(_), None) => Some(false),
+            (None, _) => None,
+        };
+        let dirty = match (&config, &state) {
+            (Some(config), Some(state)) => build_dirty(config, path, state),
+            _ => None,
+        };
+        let last_run = history.iter().rev().find(|e| e.worker == *name).map(|e| LastRun {
+            id: e.id,
+            started_at: e.started_at.clone(),
+            exit_code: e.exit_code,
+        });
+        statuses.insert(
+            name.to_string(),
+            WorkerStatus {
+                version: config.and_then(|c| c.version),
+                image,
+                image_present,
+                dirty,
+                last_run,
+            },
+        );
+    }
+    Ok(statuses)
+}
 
-pub async fn apply_worker(worker: Option<&str>, _force: bool) -> Result<()> {
-    // 1. Resolve the worker
-    let (worker_name, worker_path) = if let Some(name) = worker {
-        let settings = Settings::load()?;
-        match settings.get_worker_path(name) {
-            Ok(path) => (name.to_string(), path),
-            Err(_) => {
-                // Try as a path
-                let path = PathBuf::from(name);
-                if path.join("geoengine.yaml").exists() {
-                    let config = WorkerConfig::load(&path.join("geoengine.yaml"))?;
-                    (config.name.clone(), path.canonicalize()?)
-                } else {
-                    anyhow::bail!("Worker '{}' not found and no geoengine.yaml at that path.", name);
-                }
-            }
-        }
-    } else {
-        let cwd = std::env::current_dir()?;
-        let config_path = cwd.join("geoengine.yaml");
-        if !config_path.exists() {
-            anyhow::bail!("No geoengine.yaml found in current directory. Specify a worker name or run from a worker directory.");
+/// Whether the build inputs differ from those of the last build, hashed the way
+/// `build_worker` hashes them. Release builds are compared by their combined hash,
+/// which dev builds leave alone.
+fn build_dirty(config: &WorkerConfig, worker_path: &Path, state: &WorkerState) -> Option<bool> {
+    if state.dockerfile_hash.is_none() && state.pushed_build_hash.is_none() {
+        return None;
+    }
+    let BuildContext::Local(context) = BuildContext::of(config, worker_path) else {
+        return None;
+    };
+    let dockerfiles = all_dockerfiles(config, &context).ok()?;
+    let dockerfile_hash = dockerfiles_hash(&dockerfiles).ok();
+    let command_hash = command_script_hash(config, worker_path);
+    Some(match &state.pushed_build_hash {
+        Some(pushed) => {
+            *pushed
+                != config.build_relevant_hash()
+                    + dockerfile_hash.as_deref().unwrap_or_default()
+                    + command_hash.as_deref().unwrap_or_default()
         }
+        None => state.dockerfile_hash != dockerfile_hash || state.command_hash != command_hash,
+    })
+}
 
-        let settings = Settings::load()?;
-        // Try to find registered worker by cwd path
-        match settings.find_worker_by_path(&cwd) {
-            Some((name, path)) => {
-                // Check if the name in geoengine.yaml has changed
-                let config = WorkerConfig::load(&config_path)?;
-                if config.name != name {
-                    // Name changed in YAML — update registration
-                    let canonical = cwd.canonicalize().unwrap_or_else(|_| cwd.clone());
-                    let mut settings = settings;
-                    settings.unregister_worker(&name)?;
-                    settings.register_worker(&config.name, &canonical)?;
-                    settings.save()?;
-
-                    // Migrate state and saved config to the new name
-                    state::rename_state(&name, &config.name)?;
-                    yaml_store::rename_saved_config(&name, &config.name)?;
+fn print_worker_statuses(workers: &[(&str, &PathBuf)], statuses: &HashMap<String, WorkerStatus>) {
+    if statuses.values().any(|s| s.image_present.is_none()) {
+        println!("{} Docker is not reachable; image presence is unknown", "!".yellow().bold());
+    }
+    let image_text = |status: &WorkerStatus| match (&status.image, status.image_present) {
+        (Some(image), Some(false)) => format!("{} (missing)", image),
+        (Some(image), _) => image.clone(),
+        (None, _) => "not built".to_string(),
+    };
+    let name_w = workers.iter().map(|(n, _)| n.len()).max().unwrap_or(0).max(4);
+    let version_w = statuses.values().filter_map(|s| s.version.as_ref()).map(|v| v.len()).max().unwrap_or(0).max(7);
+    let image_w = statuses.values().map(|s| image_text(s).len()).max().unwrap_or(0).max(5);
 
-                    println!(
-                        "{} Worker renamed from '{}' to '{}' — registration updated.",
-                        "✓".green().bold(),
-                        name.cyan(),
-                        config.name.cyan()
-                    );
-                    (config.name.clone(), canonical)
-                } else {
-                    (name, path)
-                }
+    println!();
+    println!(
+        "{:<name_w$} {:<version_w$} {:<image_w$} {:<6} {}",
+        "NAME".bold(),
+        "VERSION".bold(),
+        "IMAGE".bold(),
+        "BUILD".bold(),
+        "LAST RUN".bold(),
+    );
+    println!("{}", "-".repeat(name_w + version_w + image_w + 45));
+    for (name, _) in workers {
+        let Some(status) = statuses.get(*name) else {
+            continue;
+        };
+        // Padded before coloring, so the columns stay aligned
+        let image = format!("{:<image_w$}", image_text(status));
+        let image = match (&status.image, status.image_present) {
+            (Some(_), Some(false)) => image.red().to_string(),
+            (None, _) => image.dimmed().to_string(),
+            _ => image,
+        };
+        let build = match status.dirty {
+            Some(true) => format!("{:<6}", "dirty").yellow().to_string(),
+            Some(false) => format!("{:<6}", "clean").green().to_string(),
+            None => format!("{:<6}", "-"),
+        };
+        let last_run = match &status.last_run {
+            Some(run) => {
+                let exit = match run.exit_code {
+                    Some(0) => "exit 0".green().to_string(),
+                    Some(code) => format!("exit {}", code).red().to_string(),
+                    None => "detached".to_string(),
+                };
+                format!("{} ({}, #{})", history_cli::format_started(&run.started_at), exit, run.id)
             }
-            None => {
-                // Path not registered — try matching by worker name from the YAML
-                let config = WorkerConfig::load(&config_path)?;
-                let canonical = cwd.canonicalize().unwrap_or_else(|_| cwd.clone());
+            None => "-".to_string(),
+        };
+        println!(
+            "{:<name_w$} {:<version_w$} {} {} {}",
+            name,
+            status.version.as_deref().unwrap_or("-"),
+            image,
+            build,
+            last_run
+        );
+    }
+    println!();
+}
 
-                if settings.workers.contains_key(&config.name) {
-                    // Worker name exists but at a different path — update path
-                    let mut settings = settings;
-                    let old_path = settings.workers.get(&config.name).cloned().unwrap();
-                    settings.register_worker(&config.name, &canonical)?;
-                    settings.save()?;
-                    println!(
-                        "{} Worker '{}' path updated: {} → {}",
-                        "✓".green().bold(),
-                        config.name.cyan(),
-                        old_path.display(),
-                        canonical.display()
-                    );
-                    (config.name.clone(), canonical)
-                } else {
-                    // Completely new worker — will be registered below
-                    (config.name.clone(), cwd)
-                }
-            }
-        }
-    };
+/// Hash of the Dockerfiles of all variants; a single Dockerfile's own hash
+fn dockerfiles_hash(dockerfiles: &[PathBuf]) -> Result<String> {
+    Ok(match dockerfiles {
+        [dockerfile] => state::compute_file_hash(dockerfile)?,
+        _ => state::sha256_string(
+            &dockerfiles
+                .iter()
+                .map(|d| state::compute_file_hash(d))
+                .collect::<Result<Vec<_>>>()?
+                .concat(),
+        ),
+    })
+}
 
-    // 2. Load current config from YAML and detect changes from saved state. If changed, save it, if not exit.
-    let config_changed = yaml_store::check_changed_config(&worker_name, &worker_path)?;
-    if !config_changed {
-        println!("{} No changes detected in geoengine.yaml of worker '{}'. Nothing to apply.", "!".yellow().bold(), worker_name);
-        return Ok(());
+/// Hash of the command's script file, if it's in the worker directory
+fn command_script_hash(config: &WorkerConfig, worker_path: &Path) -> Option<String> {
+    let script_path = worker_path.join(&config.command.as_ref()?.script);
+    if script_path.exists() {
+        state::compute_file_hash(&script_path).ok()
+    } else {
+        None
     }
-    let config = WorkerConfig::load(&worker_path.join("geoengine.yaml"))?;
-    yaml_store::save_config(&config)?;
+}
 
-    // 3. Auto-register if not already registered
-    let mut settings = Settings::load()?;
-    if settings.workers.get(&worker_name).is_none() {
-        let canonical = worker_path.canonicalize().unwrap_or_else(|_| worker_path.clone());
-        settings.register_worker(&worker_name, &canonical)?;
-        settings.save()?;
+/// Run the worker's check command in a freshly built image, failing if it exits
+/// non-zero. Its output is only shown when it fails.
+async fn check_image(client: &DockerClient, config: &WorkerConfig, image_tag: &str) -> Result<()> {
+    let command = config.command.as_ref().ok_or_else(|| {
+        GeoEngineError::ConfigInvalid(format!("Worker '{}' has no command to check", config.name))
+    })?;
+    let check = command
+        .check
+        .clone()
+        .unwrap_or_else(|| format!("{} {} --help", command.program, command.script));
+    println!("{} Checking image: {}", "=>".blue().bold(), check.cyan());
+
+    let log_path = paths::get_temp_dir()?.join(format!("build-check-{}.log", std::process::id()));
+    let container_config = ContainerConfig {
+        image: image_tag.to_string(),
+        entrypoint: None,
+        command: Some(vec!["/bin/sh".to_string(), "-c".to_string(), check.clone()]),
+        env_vars: HashMap::from([("GEOENGINE_ACCELERATOR".to_string(), "cpu".to_string())]),
+        mounts: Vec::new(),
+        volumes: Vec::new(),
+        gpu_config: None,
+        user: None,
+        group_add: Vec::new(),
+        workdir: None,
+        name: None,
+        labels: HashMap::new(),
+        ports: Vec::new(),
+        restart: None,
+        ulimits: Vec::new(),
+        cap_add: Vec::new(),
+        cap_drop: Vec::new(),
+        devices: Vec::new(),
+        read_only: false,
+        security_opt: Vec::new(),
+        privileged: false,
+        memory: None,
+        nano_cpus: None,
+        network: None,
+        network_aliases: Vec::new(),
+        remove_on_exit: true,
+        detach: false,
+        tty: false,
+    };
+    let exit = client.run_container_attached_to_file(&container_config, &log_path).await;
+    let output = std::fs::read_to_string(&log_path).unwrap_or_default();
+    std::fs::remove_file(&log_path).ok();
+    let exit_code = exit?.exit_code;
+    if exit_code != 0 {
+        let lines: Vec<&str> = output.lines().collect();
+        for line in &lines[lines.len().saturating_sub(20)..] {
+            eprintln!("  {}", line);
+        }
+        anyhow::bail!(
+            "Image check of {} failed: '{}' exited with code {}",
+            image_tag,
+            check,
+            exit_code
+        );
+    }
+    println!("{} Image check passed", "✓".green().bold());
+    Ok(())
+}
+
+/// One image `geoengine build` produces
+struct BuildPlan {
+    /// Name in build.variants, None for a worker without variants
+    variant: Option<String>,
+    dockerfile: PathBuf,
+    target: Option<String>,
+    args: HashMap<String, String>,
+}
+
+/// The images to build: the variants named in `only` (every variant when empty),
+/// or the single image of a worker without variants. CLI build args and target
+/// override the variant's.
+fn build_plans(
+    config: &WorkerConfig,
+    context: &Path,
+    only: &[String],
+    target: Option<&str>,
+    cli_args: &HashMap<String, String>,
+) -> Result<Vec<BuildPlan>> {
+    let build = config.build.as_ref();
+    let dockerfile = build.and_then(|b| b.dockerfile.as_deref()).unwrap_or("Dockerfile");
+    let default_target = target.or(build.and_then(|b| b.target.as_deref()));
+
+    let Some(variants) = build.and_then(|b| b.variants.as_ref()) else {
+        if !only.is_empty() {
+            return Err(GeoEngineError::InputInvalid(format!(
+                "Worker '{}' has no build variants",
+                config.name
+            ))
+            .into());
+        }
+        return Ok(vec![BuildPlan {
+            variant: None,
+            dockerfile: context.join(dockerfile),
+            target: default_target.map(str::to_string),
+            args: cli_args.clone(),
+        }]);
+    };
+    if let Some(unknown) = only.iter().find(|name| !variants.contains_key(*name)) {
+        return Err(GeoEngineError::InputInvalid(format!(
+            "Unknown build variant '{}'. Variants of '{}': {}",
+            unknown,
+            config.name,
+            variants.keys().cloned().collect::<Vec<_>>().join(", ")
+        ))
+        .into());
+    }
+
+    Ok(variants
+        .iter()
+        .filter(|(name, _)| only.is_empty() || only.contains(name))
+        .map(|(name, variant)| {
+            let mut args: HashMap<String, String> = variant.args.clone().unwrap_or_default().into_iter().collect();
+            args.extend(cli_args.clone());
+            BuildPlan {
+                variant: Some(name.clone()),
+                dockerfile: context.join(variant.dockerfile.as_deref().unwrap_or(dockerfile)),
+                target: target.or(variant.target.as_deref()).or(default_target).map(str::to_string),
+                args,
+            }
+        })
+        .collect())
+}
+
+/// The Dockerfiles of every build variant in a context, without duplicates
+fn all_dockerfiles(config: &WorkerConfig, context: &Path) -> Result<Vec<PathBuf>> {
+    let mut dockerfiles: Vec<PathBuf> = build_plans(config, context, &[], None, &HashMap::new())?
+        .into_iter()
+        .map(|p| p.dockerfile)
+        .collect();
+    dockerfiles.sort();
+    dockerfiles.dedup();
+    Ok(dockerfiles)
+}
+
+/// Base images of a worker's Dockerfiles, found through `build.context` and
+/// `build.dockerfile` the way `geoengine build` finds them
+pub async fn worker_base_images(config: &WorkerConfig, worker_path: &Path) -> Result<Vec<String>> {
+    let fetched = BuildContext::of(config, worker_path).fetch().await?;
+    let mut bases: Vec<String> = Vec::new();
+    for dockerfile in all_dockerfiles(config, &fetched.dir)? {
+        for base in get_base_images(&dockerfile)? {
+            if !bases.contains(&base) {
+                bases.push(base);
+            }
+        }
+    }
+    Ok(bases)
+}
+
+/// The build variant for a host: the one declared for its accelerator, else a CPU
+/// variant (which runs anywhere), else one that doesn't declare an accelerator
+pub fn select_variant<'a>(variants: &'a BTreeMap<String, BuildVariant>, gpu: Option<&GpuConfig>) -> Option<&'a str> {
+    let wanted = match GpuConfig::accelerator_name(gpu) {
+        "cuda" => Accelerator::Cuda,
+        "mps" => Accelerator::Mps,
+        _ => Accelerator::Cpu,
+    };
+    let with = |accelerator: Option<Accelerator>| {
+        variants
+            .iter()
+            .find(|(_, v)| v.accelerator == accelerator)
+            .map(|(name, _)| name.as_str())
+    };
+    with(Some(wanted))
+        .or_else(|| with(Some(Accelerator::Cpu)))
+        .or_else(|| with(None))
+}
+
+/// Write the digests the Dockerfiles' base images currently resolve to into geoengine.lock
+async fn record_base_images(client: &DockerClient, worker_path: &Path, dockerfiles: &[PathBuf]) -> Result<()> {
+    let mut lock = Lockfile::default();
+    for dockerfile in dockerfiles {
+        for base in get_base_images(dockerfile)? {
+            let digest = registry::local_digest(client, &base).await?;
+            // Never pulled, so the digest is the image ID
+            if client.local_repo_digests(&base).await?.is_empty() {
+                lock.local_images.insert(base.clone());
+            }
+            lock.base_images.insert(base, digest);
+        }
+    }
+    if Lockfile::load(worker_path)?.as_ref() != Some(&lock) {
+        lock.save(worker_path)?;
+        println!("{} Updated {}", "✓".green().bold(), LOCK_FILE);
+    }
+    Ok(())
+}
+
+/// Fail unless every base image resolves to its digest in geoengine.lock. Base
+/// images missing locally are pulled by the locked digest and tagged with the
+/// name used in the Dockerfile, so the build uses exactly that image. Images
+/// locked by image ID (built locally) are only compared, since they can't be pulled.
+async fn verify_locked_base_images(client: &DockerClient, worker_path: &Path, dockerfiles: &[&PathBuf]) -> Result<()> {
+    let lock = Lockfile::load(worker_path)?.ok_or_else(|| {
+        anyhow::anyhow!(
+            "--locked requires {} in {}. Build once without --locked to create it.",
+            LOCK_FILE,
+            worker_path.display()
+        )
+    })?;
+
+    let mut drifted: Vec<String> = Vec::new();
+    let mut bases: Vec<String> = Vec::new();
+    for dockerfile in dockerfiles {
+        bases.extend(get_base_images(dockerfile)?);
+    }
+    bases.dedup();
+    for base in bases {
+        let Some(locked) = lock.base_images.get(&base) else {
+            anyhow::bail!(
+                "Base image {} is not in {}. Build without --locked to record it.",
+                base,
+                LOCK_FILE
+            );
+        };
+        let image_id = match client.image_id(&base).await {
+            Ok(id) => id,
+            Err(_) if lock.local_images.contains(&base) => anyhow::bail!(
+                "Base image {} was built locally and is locked by its image ID {}, which can't be pulled. \
+                 Build it first.",
+                base,
+                locked
+            ),
+            Err(_) => {
+                let pinned = format!("{}@{}", registry::repository(&base), locked);
+                println!("{} Pulling base image {}...", "=>".blue().bold(), pinned.cyan());
+                registry::pull(client, &pinned).await?;
+                client.tag_image(&pinned, &base).await?;
+                client.image_id(&base).await?
+            }
+        };
+        if image_id == *locked {
+            continue;
+        }
+        let current = registry::local_digest(client, &base).await?;
+        if &current != locked {
+            drifted.push(format!("{}: locked {}, local {}", base, locked, current));
+        }
+    }
+    if !drifted.is_empty() {
+        anyhow::bail!(
+            "Base images differ from {}:\n  {}\nPull the locked digests with 'geoengine image pull <image>@<digest> --tag <image>', \
+             or build without --locked to accept the new ones.",
+            LOCK_FILE,
+            drifted.join("\n  ")
+        );
+    }
+    println!("{} Base images match {}", "✓".green().bold(), LOCK_FILE);
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// geoengine clone
+// ---------------------------------------------------------------------------
+
+/// Clone a worker from a git repository, validate its geoengine.yaml, register it and optionally build it
+pub async fn clone_worker(url: &str, git_ref: Option<&str>, dir: Option<&Path>, build: bool) -> Result<()> {
+    which::which("git").context("git not found. Install git to clone workers.")?;
+
+    let dest = match dir {
+        Some(dir) => dir.to_path_buf(),
+        None => PathBuf::from(repo_dir_name(url)?),
+    };
+    if dest.exists() && std::fs::read_dir(&dest)?.next().is_some() {
+        anyhow::bail!("Destination {} already exists and is not empty.", dest.display());
+    }
+
+    println!("{} Cloning {} into {}...", "=>".blue().bold(), url.cyan(), dest.display());
+    let status = std::process::Command::new("git")
+        .arg("clone")
+        .arg(url)
+        .arg(&dest)
+        .status()
+        .context("Failed to run git clone")?;
+    if !status.success() {
+        anyhow::bail!("git clone failed");
+    }
+
+    // Everything past this point works on a directory we created, so remove it on failure
+    let registered = async {
+        if let Some(git_ref) = git_ref {
+            // checkout handles branches, tags and commit SHAs alike
+            let status = std::process::Command::new("git")
+                .arg("-C")
+                .arg(&dest)
+                .args(["-c", "advice.detachedHead=false", "checkout", git_ref])
+                .status()
+                .context("Failed to run git checkout")?;
+            if !status.success() {
+                anyhow::bail!("git checkout {} failed", git_ref);
+            }
+        }
+
+        let config_path = worker_config::config_file(&dest);
+        if !config_path.exists() {
+            anyhow::bail!("The repository has no geoengine.yaml (or geoengine.toml / geoengine.json) at its root.");
+        }
+        let config = WorkerConfig::load(&config_path)?;
+        if config.command.is_none() {
+            anyhow::bail!("geoengine.yaml of '{}' has no command defined.", config.name);
+        }
+        validate_version(config.version.as_deref().unwrap_or_default()).map_err(|e| anyhow::anyhow!(e))?;
+
+        let settings = Settings::load()?;
+        if let Ok(existing) = settings.get_worker_path(&config.name) {
+            anyhow::bail!(
+                "A worker named '{}' is already registered at {}. Delete it first with 'geoengine delete --name {}'.",
+                config.name,
+                existing.display(),
+                config.name
+            );
+        }
+        Ok(config.name)
+    }
+    .await;
+
+    let worker_name = match registered {
+        Ok(name) => name,
+        Err(e) => {
+            let _ = std::fs::remove_dir_all(&dest);
+            return Err(e);
+        }
+    };
+    println!("{} Validated geoengine.yaml of worker '{}'", "✓".green().bold(), worker_name.cyan());
+
+    let canonical = dest.canonicalize()?;
+    let mut settings = Settings::load()?;
+    settings.register_worker(&worker_name, &canonical)?;
+    settings.save()?;
+    println!(
+        "{} Registered worker '{}' at {}",
+        "✓".green().bold(),
+        worker_name.cyan(),
+        canonical.display()
+    );
+    apply_worker(Some(&worker_name), false).await?;
+
+    if build {
+        build_worker(&worker_name, false, false, &[], None, false, None, &[], false).await?;
+    } else {
+        println!("\nNext step:");
+        println!("  Run {} in {} to build the image", "geoengine build".cyan(), dest.display());
+    }
+
+    Ok(())
+}
+
+/// Default clone directory: the last path segment of the URL without `.git`
+fn repo_dir_name(url: &str) -> Result<String> {
+    let name = url
+        .trim_end_matches('/')
+        .rsplit(['/', ':'])
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches(".git");
+    if name.is_empty() {
+        anyhow::bail!("Could not derive a directory name from '{}'. Pass a directory explicitly.", url);
+    }
+    Ok(name.to_string())
+}
+
+// ---------------------------------------------------------------------------
+// geoengine apply
+// ---------------------------------------------------------------------------
+
+pub async fn apply_worker(worker: Option<&str>, _force: bool) -> Result<()> {
+    // 1. Resolve the worker
+    let (worker_name, worker_path) = if let Some(name) = worker {
+        let settings = Settings::load()?;
+        match settings.get_worker_path(name) {
+            Ok(path) => (name.to_string(), path),
+            Err(_) => {
+                // Try as a path
+                let path = PathBuf::from(name);
+                if worker_config::has_config_file(&path) {
+                    let config = WorkerConfig::load(&worker_config::config_file(&path))?;
+                    (config.name.clone(), path.canonicalize()?)
+                } else {
+                    return Err(GeoEngineError::WorkerNotFound(format!(
+                        "Worker '{}' not found and no geoengine.yaml at that path.",
+                        name
+                    ))
+                    .into());
+                }
+            }
+        }
+    } else {
+        let cwd = std::env::current_dir()?;
+        let config_path = worker_config::config_file(&cwd);
+        if !config_path.exists() {
+            return Err(GeoEngineError::WorkerNotFound(
+                "No geoengine.yaml found in current directory. Specify a worker name or run from a worker directory."
+                    .into(),
+            )
+            .into());
+        }
+
+        let settings = Settings::load()?;
+        // Try to find registered worker by cwd path
+        match settings.find_worker_by_path(&cwd) {
+            Some((name, path)) => {
+                // Check if the name in geoengine.yaml has changed
+                let config = WorkerConfig::load(&config_path)?;
+                if config.name != name {
+                    // Name changed in YAML — update registration
+                    let canonical = cwd.canonicalize().unwrap_or_else(|_| cwd.clone());
+                    let mut settings = settings;
+                    settings.unregister_worker(&name)?;
+                    settings.register_worker(&config.name, &canonical)?;
+                    settings.save()?;
+
+                    // Migrate state and saved config to the new name
+                    state::rename_state(&name, &config.name)?;
+                    yaml_store::rename_saved_config(&name, &config.name)?;
+
+                    println!(
+                        "{} Worker renamed from '{}' to '{}' — registration updated.",
+                        "✓".green().bold(),
+                        name.cyan(),
+                        config.name.cyan()
+                    );
+                    (config.name.clone(), canonical)
+                } else {
+                    (name, path)
+                }
+            }
+            None => {
+                // Path not registered — try matching by worker name from the YAML
+                let config = WorkerConfig::load(&config_path)?;
+                let canonical = cwd.canonicalize().unwrap_or_else(|_| cwd.clone());
+
+                if settings.workers.contains_key(&config.name) {
+                    // Worker name exists but at a different path — update path
+                    let mut settings = settings;
+                    let old_path = settings.workers.get(&config.name).cloned().unwrap();
+                    settings.register_worker(&config.name, &canonical)?;
+                    settings.save()?;
+                    println!(
+                        "{} Worker '{}' path updated: {} → {}",
+                        "✓".green().bold(),
+                        config.name.cyan(),
+                        old_path.display(),
+                        canonical.display()
+                    );
+                    (config.name.clone(), canonical)
+                } else {
+                    // Completely new worker — will be registered below
+                    (config.name.clone(), cwd)
+                }
+            }
+        }
+    };
+
+    // 2. Load current config from YAML and detect changes from saved state. If changed, save it, if not exit.
+    let config_changed = yaml_store::check_changed_config(&worker_name, &worker_path)?;
+    if !config_changed {
+        println!("{} No changes detected in geoengine.yaml of worker '{}'. Nothing to apply.", "!".yellow().bold(), worker_name);
+        return Ok(());
+    }
+    let config = WorkerConfig::load(&worker_config::config_file(&worker_path))?;
+    yaml_store::save_config(&config)?;
+
+    // 3. Auto-register if not already registered
+    let mut settings = Settings::load()?;
+    if settings.workers.get(&worker_name).is_none() {
+        let canonical = worker_path.canonicalize().unwrap_or_else(|_| worker_path.clone());
+        settings.register_worker(&worker_name, &canonical)?;
+        settings.save()?;
         println!(
             "{} Registered worker '{}' at {}",
             "✓".green().bold(),
@@ -448,7 +1346,8 @@ pub async fn apply_worker(worker: Option<&str>, _force: bool) -> Result<()> {
 
     let mut res_arcgis = cur_arcgis.clone();
     let mut res_qgis = cur_qgis.clone();
-    let yaml_path = worker_path.join("geoengine.yaml");
+    let yaml_path = worker_config::config_file(&worker_path);
+    let yaml_format = ConfigFormat::of(&yaml_path);
     let yaml_content_u8 = std::fs::read(&yaml_path)
         .with_context(|| format!("Failed to read file for hashing: {}", yaml_path.display()))?;
     let mut yaml_content = String::from_utf8(yaml_content_u8)?;
@@ -480,7 +1379,7 @@ pub async fn apply_worker(worker: Option<&str>, _force: bool) -> Result<()> {
                         },
                         Err(e) => {
                             res_arcgis = false;
-                            set_plugin_flag_in_yaml(&mut yaml_content, "arcgis", false)?;
+                            set_plugin_flag(&mut yaml_content, yaml_format, "arcgis", false)?;
                             yaml_dirty = true;
                             plugin_change_msgs.push(format!("{} {}",
                                 "×".red(),
@@ -495,7 +1394,7 @@ pub async fn apply_worker(worker: Option<&str>, _force: bool) -> Result<()> {
                 }
                 _ => {
                     res_arcgis = false;
-                    set_plugin_flag_in_yaml(&mut yaml_content, "arcgis", false)?;
+                    set_plugin_flag(&mut yaml_content, yaml_format, "arcgis", false)?;
                     yaml_dirty = true;
                     plugin_change_msgs.push(format!("{} {}",
                         "×".red(),
@@ -544,7 +1443,7 @@ pub async fn apply_worker(worker: Option<&str>, _force: bool) -> Result<()> {
                         },
                         Err(e) => {
                             res_qgis = false;
-                            set_plugin_flag_in_yaml(&mut yaml_content, "qgis", false)?;
+                            set_plugin_flag(&mut yaml_content, yaml_format, "qgis", false)?;
                             yaml_dirty = true;
                             plugin_change_msgs.push(format!("{} {}",
                                 "×".red(),
@@ -559,7 +1458,7 @@ pub async fn apply_worker(worker: Option<&str>, _force: bool) -> Result<()> {
                 }
                 _ => {
                     res_qgis = false;
-                    set_plugin_flag_in_yaml(&mut yaml_content, "qgis", false)?;
+                    set_plugin_flag(&mut yaml_content, yaml_format, "qgis", false)?;
                     yaml_dirty = true;
                     plugin_change_msgs.push(format!("{} {}",
                         "×".red(),
@@ -664,11 +1563,13 @@ pub async fn delete_worker(name: Option<&str>) -> Result<()> {
     // Check if worker exists
     let worker_path = match settings.workers.get(&worker_name) {
         Some(path) => path.clone(),
-        None => anyhow::bail!("Worker '{}' is not registered", worker_name),
+        None => {
+            return Err(GeoEngineError::WorkerNotFound(format!("Worker '{}' is not registered", worker_name)).into())
+        }
     };
 
     // Warn if the worker's directory no longer contains geoengine.yaml
-    if !worker_path.join("geoengine.yaml").exists() {
+    if !worker_config::has_config_file(&worker_path) {
         println!(
             "{} Worker directory no longer contains geoengine.yaml: {}",
             "!".yellow().bold(),
@@ -700,40 +1601,978 @@ pub async fn delete_worker(name: Option<&str>) -> Result<()> {
 // geoengine run
 // ---------------------------------------------------------------------------
 
-pub async fn run_worker(
-    worker: Option<&str>,
-    input_args: &[String],
-    json_output: bool,
-    dev: bool,
-    extra_args: &[String],
-) -> Result<()> {
+/// When `geoengine run` pulls the worker image
+#[derive(Debug, Clone, Copy, Default, PartialEq, ValueEnum)]
+pub enum PullPolicy {
+    /// Pull before every run
+    Always,
+    /// Pull only when the image isn't available locally
+    Missing,
+    /// Only use local images (built with `geoengine build` or imported)
+    #[default]
+    Never,
+}
+
+/// Arguments for `geoengine run`
+#[derive(Args, Clone, Default)]
+pub struct RunArgs {
+    /// Worker name (defaults to current directory's worker)
+    pub worker: Option<String>,
+
+    /// Input parameters (format: KEY=VALUE, repeatable)
+    #[arg(short, long = "input", value_name = "KEY=VALUE")]
+    pub inputs: Vec<String>,
+
+    /// JSON or YAML file of input parameters (KEY: VALUE); --input flags take precedence
+    #[arg(long, value_name = "FILE")]
+    pub param_file: Option<PathBuf>,
+
+    /// Checksum a file or folder input must match before it is mounted: sha256:HEX
+    /// for files, manifest:FILE (from `geoengine data hash`) for folders (repeatable)
+    #[arg(long = "checksum", value_name = "KEY=CHECKSUM")]
+    pub checksums: Vec<String>,
+
+    /// Emit structured JSON result to stdout (logs go to stderr)
+    #[arg(long)]
+    pub json: bool,
+
+    /// Fail on missing required inputs instead of asking for them in a terminal
+    #[arg(long)]
+    pub no_input: bool,
+
+    /// Report CRS, extent and layout of output files (gdalinfo/ogrinfo in a GDAL container)
+    #[arg(long)]
+    pub geo_metadata: bool,
+
+    /// Write a STAC collection and items for georeferenced outputs into <output>/stac/
+    #[arg(long)]
+    pub stac: bool,
+
+    /// Runs the latest dev version of the worker
+    #[arg(long)]
+    pub dev: bool,
+
+    /// Mount read-only inputs directly instead of staging them into an isolated job workspace
+    #[arg(long)]
+    pub no_isolate: bool,
+
+    /// Skip the free disk space check before starting the container
+    #[arg(long)]
+    pub no_preflight: bool,
+
+    /// Set an environment variable in the container (format: KEY=VALUE, repeatable)
+    #[arg(short, long = "env", value_name = "KEY=VALUE")]
+    pub env: Vec<String>,
+
+    /// Load container environment variables from a dotenv file (repeatable)
+    #[arg(long, value_name = "FILE")]
+    pub env_file: Vec<PathBuf>,
+
+    /// Run the container as USER[:GROUP] (name or ID); `image` keeps the image's USER
+    #[arg(short, long, value_name = "USER[:GROUP]")]
+    pub user: Option<String>,
+
+    /// Add a supplementary group for the container user (name or GID, repeatable)
+    #[arg(long, value_name = "GROUP")]
+    pub group_add: Vec<String>,
+
+    /// Run once per parameter set in a CSV or JSONL manifest
+    #[arg(long, value_name = "MANIFEST")]
+    pub batch: Option<PathBuf>,
+
+    /// Maximum number of batch items to run at once
+    #[arg(long, default_value_t = 1, value_name = "N", requires = "batch")]
+    pub parallel: usize,
+
+    /// Skip batch items that completed in a previous run of the same manifest
+    #[arg(long, requires = "batch")]
+    pub resume: bool,
+
+    /// Start the container in the background and return (see `geoengine logs` / `attach`)
+    #[arg(short, long, conflicts_with = "batch")]
+    pub detach: bool,
+
+    /// Container name (detached runs default to <worker>-<timestamp>)
+    #[arg(long)]
+    pub name: Option<String>,
+
+    /// Restart policy: no, always, unless-stopped or on-failure[:N]
+    #[arg(long, value_name = "POLICY", requires = "detach")]
+    pub restart: Option<String>,
+
+    /// Publish a container port as [IP:]HOST_PORT:CONTAINER_PORT[/PROTOCOL] (repeatable)
+    #[arg(short, long = "publish", value_name = "PORT")]
+    pub publish: Vec<String>,
+
+    /// Override the image's ENTRYPOINT ("" clears it)
+    #[arg(long, value_name = "CMD")]
+    pub entrypoint: Option<String>,
+
+    /// Working directory inside the container
+    #[arg(short, long, value_name = "DIR")]
+    pub workdir: Option<String>,
+
+    /// Set a container label (format: KEY=VALUE, repeatable)
+    #[arg(short, long = "label", value_name = "KEY=VALUE")]
+    pub labels: Vec<String>,
+
+    /// Set a resource limit, e.g. nofile=65536 or nproc=1024:2048 (repeatable)
+    #[arg(long, value_name = "NAME=SOFT[:HARD]")]
+    pub ulimit: Vec<String>,
+
+    /// Add a Linux capability, e.g. SYS_PTRACE (repeatable)
+    #[arg(long, value_name = "CAP")]
+    pub cap_add: Vec<String>,
+
+    /// Drop a Linux capability, or ALL (repeatable)
+    #[arg(long, value_name = "CAP")]
+    pub cap_drop: Vec<String>,
+
+    /// Mount the container's root filesystem read-only
+    #[arg(long)]
+    pub read_only: bool,
+
+    /// Run the container privileged: all devices and capabilities, no seccomp or AppArmor confinement
+    #[arg(long)]
+    pub privileged: bool,
+
+    /// Join this existing Docker network instead of the worker's service network
+    #[arg(long, value_name = "NAME")]
+    pub network: Option<String>,
+
+    /// Bind-mount a host directory or file as HOST:CONTAINER[:ro] (repeatable); HOST may be a Windows path
+    #[arg(long, value_name = "HOST:CONTAINER[:ro]")]
+    pub mount: Vec<String>,
+
+    /// Give the container access to a host device as HOST[:CONTAINER[:PERMISSIONS]] (repeatable)
+    #[arg(long, value_name = "DEVICE")]
+    pub device: Vec<String>,
+
+    /// Run on a free MIG slice with this profile, e.g. 1g.10gb (overrides runtime.mig_profile)
+    #[arg(long, value_name = "PROFILE")]
+    pub mig: Option<String>,
+
+    /// GPUs to hand the container: all, a count such as 2, or device=INDEX|UUID[,...]
+    #[arg(long, value_name = "GPUS", conflicts_with = "mig")]
+    pub gpus: Option<GpuRequest>,
+
+    /// Wait up to SECS for a GPU with runtime.gpu_memory free instead of failing
+    #[arg(long, value_name = "SECS")]
+    pub wait_for_gpu: Option<u64>,
+
+    /// Run this variant from build.variants instead of the one matching the host's hardware
+    #[arg(long, value_name = "NAME")]
+    pub variant: Option<String>,
+
+    /// When to pull the worker image through the configured registries
+    #[arg(long, value_enum, value_name = "POLICY", default_value_t = PullPolicy::Never)]
+    pub pull: PullPolicy,
+
+    /// Service name recorded on the container (set by `geoengine up`)
+    #[arg(skip)]
+    pub service: Option<String>,
+
+    /// Leave the run out of the history ledger (set by `geoengine test`)
+    #[arg(skip)]
+    pub no_history: bool,
+
+    /// Extra arguments passed through to the container command
+    #[arg(last = true)]
+    pub args: Vec<String>,
+}
+
+pub async fn run_worker(run: &RunArgs) -> Result<()> {
+    if let Some(manifest) = &run.batch {
+        return batch::run_batch(run, manifest).await;
+    }
+
+    let json_output = run.json;
+    let client = DockerClient::new().await?;
+    let prepared = prepare_run(run, &client).await?;
+    let mut inputs = collect_inputs(run, prepared.config.input_definitions())?;
+    for (input, dir) in default_output_dirs(&prepared, &inputs)? {
+        if !json_output {
+            eprintln!("{} Writing {} to {}", "•".cyan(), input, dir.display());
+        }
+        inputs.insert(input, dir.display().to_string());
+    }
+    prompt_missing_inputs(&prepared.config, &mut inputs, run)?;
+    let output = if json_output { RunOutput::Stderr } else { RunOutput::Terminal };
+
+    let outcome = execute_run(&client, &prepared, &inputs, run, prepared.gpu_config.clone(), &output).await?;
+    if let Some(container) = &outcome.container {
+        if json_output {
+            println!("{}", serde_json::json!({"status": "detached", "container": container}));
+        } else {
+            eprintln!("{} Started container {}", "✓".green().bold(), container.cyan());
+            eprintln!("  Follow its output with: {}", format!("geoengine logs {} --follow", container).cyan());
+            eprintln!("  Stop it with:           {}", format!("geoengine down {}", prepared.worker_name).cyan());
+        }
+        return Ok(());
+    }
+    let exit_code = outcome.exit_code;
+
+    // Describe the produced files, optionally with their georeferencing
+    let outputs_config = prepared.config.outputs.clone().unwrap_or_default();
+    let stac = run.stac || outputs_config.stac.unwrap_or(false);
+    let geo_metadata = stac || run.geo_metadata || outputs_config.geo_metadata.unwrap_or(false);
+    let geo: Vec<Option<GeoMetadata>> = if geo_metadata && exit_code == 0 {
+        match geometa::extract(&client, &outcome.files).await {
+            Ok(geo) => geo,
+            Err(e) => {
+                eprintln!("{} Could not read geospatial metadata: {:#}", "!".yellow().bold(), e);
+                Vec::new()
+            }
+        }
+    } else {
+        Vec::new()
+    };
+    let files: Vec<OutputFileInfo> = outcome
+        .files
+        .iter()
+        .enumerate()
+        .map(|(i, path)| OutputFileInfo {
+            name: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+            path: path.display().to_string(),
+            size: std::fs::metadata(path).map(|m| m.len()).unwrap_or(0),
+            geo: geo.get(i).cloned().flatten(),
+        })
+        .collect();
+
+    let mut stac_collections: Vec<String> = Vec::new();
+    if stac && exit_code == 0 {
+        let collection_id = format!("{}-{}", prepared.worker_name, outcome.finished_at.format("%Y%m%dT%H%M%SZ"));
+        let description = format!(
+            "Outputs of {} {}",
+            prepared.worker_name,
+            prepared.config.version.as_deref().unwrap_or_default()
+        );
+        let datetime = outcome.finished_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        for dir in &outcome.output_dirs {
+            let stac_files: Vec<StacFile> = outcome
+                .files
+                .iter()
+                .zip(&files)
+                .filter(|(path, _)| path.starts_with(dir))
+                .filter_map(|(path, info)| info.geo.as_ref().map(|geo| StacFile { path, geo }))
+                .collect();
+            if stac_files.is_empty() {
+                continue;
+            }
+            match stac::write_collection(dir, &collection_id, description.trim(), &datetime, &stac_files) {
+                Ok(path) => {
+                    if !json_output {
+                        eprintln!("{} STAC collection written: {}", "✓".green().bold(), path.display());
+                    }
+                    stac_collections.push(path.display().to_string());
+                }
+                Err(e) => eprintln!("{} Could not write STAC catalog: {:#}", "!".yellow().bold(), e),
+            }
+        }
+    }
+
+    // Exit code 0 doesn't prove the outputs are usable; check them against outputs.validate
+    let invalid_outputs = if exit_code == 0 && !outcome.oom_killed {
+        // Reuses the georeferencing read above instead of running GDAL again
+        let known: HashMap<PathBuf, Option<GeoMetadata>> =
+            outcome.files.iter().cloned().zip(geo.iter().cloned()).collect();
+        validate_outputs(&client, &prepared.config, &inputs, &outcome.files, &known).await
+    } else {
+        Vec::new()
+    };
+
+    let failure = if outcome.oom_killed {
+        Some(GeoEngineError::OutOfMemory {
+            worker: prepared.worker_name.clone(),
+            detail: oom_detail(&prepared.config, outcome.usage.as_ref()),
+        })
+    } else if exit_code != 0 {
+        Some(GeoEngineError::ContainerFailed {
+            worker: prepared.worker_name.clone(),
+            exit_code,
+        })
+    } else {
+        (!invalid_outputs.is_empty()).then(|| GeoEngineError::OutputsInvalid {
+            worker: prepared.worker_name.clone(),
+            failures: invalid_outputs.clone(),
+        })
+    };
+
+    // Handle output
+    if json_output {
+        let result = RunResult {
+            status: match (exit_code, outcome.oom_killed) {
+                (_, true) => "oom_killed".to_string(),
+                (0, _) if !invalid_outputs.is_empty() => "outputs_invalid".to_string(),
+                (0, _) => "completed".to_string(),
+                _ => "failed".to_string(),
+            },
+            exit_code,
+            error: failure.as_ref().map(ErrorInfo::new),
+            files,
+            output_dirs: outcome.output_dirs.iter().map(|d| d.display().to_string()).collect(),
+            stac: stac_collections,
+            usage: outcome.usage.clone(),
+        };
+        println!("{}", serde_json::to_string(&result)?);
+    } else if exit_code == 0 {
+        if geo_metadata {
+            print_output_files(&files);
+        }
+        if let Some(usage) = &outcome.usage {
+            eprintln!("{} Resources: {}", "•".cyan(), usage);
+        }
+        if invalid_outputs.is_empty() {
+            eprintln!("{} Completed successfully", "✓".green().bold());
+        } else {
+            eprintln!("{} Exited with code 0, but its outputs failed validation:", "✗".red().bold());
+            for failure in &invalid_outputs {
+                eprintln!("  {} {}", "•".red(), failure);
+            }
+        }
+    } else {
+        if let Some(usage) = &outcome.usage {
+            eprintln!("{} Resources: {}", "•".cyan(), usage);
+        }
+        if outcome.oom_killed {
+            eprintln!(
+                "{} Killed: {}",
+                "✗".red().bold(),
+                oom_detail(&prepared.config, outcome.usage.as_ref())
+            );
+            eprintln!("  {}", oom_hint(&prepared.config));
+        } else {
+            eprintln!("{} Failed with exit code {}", "✗".red().bold(), exit_code);
+        }
+    }
+
+    if let Some(failure) = failure {
+        return Err(failure.into());
+    }
+
+    Ok(())
+}
+
+/// Print produced files with a one-line summary of their georeferencing
+fn print_output_files(files: &[OutputFileInfo]) {
+    if files.is_empty() {
+        return;
+    }
+    eprintln!("{} Output files:", "=>".blue().bold());
+    for file in files {
+        let summary = match &file.geo {
+            Some(geo) => {
+                let mut parts = vec![geo.crs.clone().unwrap_or_else(|| "no CRS".to_string())];
+                if let (Some([w, h]), Some(bands)) = (geo.size, geo.band_count) {
+                    parts.push(format!("{}x{}, {} band(s)", w, h, bands));
+                }
+                if let Some([px, py]) = geo.pixel_size {
+                    parts.push(format!("pixel {}x{}", px, py));
+                }
+                if let Some(geometry) = &geo.geometry_type {
+                    parts.push(format!("{}, {} feature(s)", geometry, geo.feature_count.unwrap_or(0)));
+                }
+                format!(" [{}]", parts.join("; "))
+            }
+            None => String::new(),
+        };
+        eprintln!("  {} {}{}", "•".cyan(), file.path, summary.dimmed());
+    }
+}
+
+/// Result of a single container run
+pub struct RunOutcome {
+    pub exit_code: i64,
+    /// Files created or modified in writable folder inputs during the run
+    pub files: Vec<PathBuf>,
+    /// Writable folder inputs (where outputs are written)
+    pub output_dirs: Vec<PathBuf>,
+    pub finished_at: chrono::DateTime<chrono::Utc>,
+    /// Name of the container left running by a detached run
+    pub container: Option<String>,
+    /// Resources the container used (attached runs only)
+    pub usage: Option<ResourceUsage>,
+    /// The kernel killed the container for exceeding its memory limit
+    pub oom_killed: bool,
+}
+
+/// Where container output goes during a run
+pub enum RunOutput {
+    /// Attached to the terminal (stdout)
+    Terminal,
+    /// Routed to stderr, keeping stdout free for JSON results
+    Stderr,
+    /// Written to a log file (batch runs)
+    LogFile(PathBuf),
+}
+
+/// Worker, image and GPU resolved once per invocation and shared by every run
+pub struct PreparedRun {
+    pub worker_name: String,
+    pub worker_path: PathBuf,
+    pub config: WorkerConfig,
+    pub image_tag: String,
+    pub gpu_config: Option<GpuConfig>,
+    /// Held while the run lasts, so concurrent runs don't pick the same devices
+    _gpu_reservation: Option<GpuReservation>,
+    /// Container environment from env files and explicit variables
+    pub env: HashMap<String, String>,
+}
+
+/// The GPUs with `required` bytes of memory free, polling for up to `wait` seconds
+/// while other processes hold it
+async fn wait_for_gpu_memory(gpu: &GpuConfig, required: u64, wait: Option<u64>, quiet: bool) -> Result<GpuConfig> {
+    let deadline = wait.map(|secs| std::time::Instant::now() + std::time::Duration::from_secs(secs));
+    let mut waiting = false;
+    loop {
+        let usage = tokio::task::spawn_blocking(gpu::nvidia_usage)
+            .await?
+            .context("Failed to query free GPU memory")?;
+        match gpu.with_free_memory(required, &usage) {
+            Ok(fitting) => return Ok(fitting),
+            Err(e) if deadline.is_some_and(|d| std::time::Instant::now() < d) => {
+                if !waiting && !quiet {
+                    eprintln!("{} {:#}", "!".yellow().bold(), e);
+                    eprintln!("{} Waiting for GPU memory to become free...", "=>".blue().bold());
+                }
+                waiting = true;
+                tokio::select! {
+                    _ = tokio::time::sleep(std::time::Duration::from_secs(5)) => {}
+                    _ = client::shutdown_signal() => return Err(GeoEngineError::Interrupted.into()),
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Resolve the worker, its image and the GPU to use for `geoengine run`
+pub async fn prepare_run(run: &RunArgs, client: &DockerClient) -> Result<PreparedRun> {
+    let json_output = run.json;
+
     // Resolve worker name and path
-    let (worker_name, worker_path) = resolve_worker(worker)?;
+    let (worker_name, worker_path) = resolve_worker(run.worker.as_deref())?;
     let config = yaml_store::load_saved_config(&worker_name)?;
 
-    // Update if version changed
-    let this_ver = config.version.unwrap_or("latest".to_string());
+    if config.command.is_none() {
+        anyhow::bail!("No command defined for worker '{}'", worker_name);
+    }
 
+    // Clean up after runs whose geoengine process crashed or was killed
+    match reaper::reap(client, false).await {
+        Ok(reaped) if !reaped.is_empty() && !json_output => eprintln!(
+            "{} Removed {} orphaned container(s) and {} job workspace(s) of interrupted runs",
+            "•".cyan(),
+            reaped.containers.len(),
+            reaped.workspaces.len()
+        ),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to clean up orphaned containers: {:#}", e),
+    }
 
-    // Get command config
-    let cmd_config = config
-        .command
-        .as_ref()
-        .ok_or_else(|| anyhow::anyhow!("No command defined for worker '{}'", worker_name))?;
+    // Resolve the GPU for the configured accelerator (auto-detect by default).
+    // Only NVIDIA configs get Docker device requests; Metal (macOS) needs no
+    // explicit passthrough but sets MPS-friendly environment defaults.
+    let mut gpu_config = GpuConfig::for_accelerator(config.accelerator()).await?;
+    let mig_profile = run
+        .mig
+        .clone()
+        .or_else(|| config.runtime.as_ref().and_then(|r| r.mig_profile.clone()));
+    let uses_mig = mig_profile.is_some();
+    let mut gpu_reservation = None;
+    if let Some(profile) = mig_profile {
+        let gpu = gpu_config
+            .as_ref()
+            .filter(|g| g.is_nvidia())
+            .ok_or_else(|| {
+                GeoEngineError::GpuUnavailable(format!(
+                    "MIG profile '{}' requested, but no NVIDIA GPU was detected",
+                    profile
+                ))
+            })?;
+        // Slices used or being picked by other runs are skipped so jobs never share a slice.
+        // Batch items are spread across all free slices, other runs take one.
+        let spread = run.batch.is_some() || run.gpus.is_some();
+        let (slices, reservation) = gpu_reservations::reserve(
+            client,
+            |busy| gpu.mig_slices(&profile, busy).map(|slices| if spread { slices } else { slices.pinned(0) }),
+            |slices| slices.device_ids.clone().unwrap_or_default(),
+        )
+        .await?;
+        gpu_config = Some(slices);
+        gpu_reservation = Some(reservation);
+    }
+    // Fail (or wait) before starting rather than have CUDA run out of memory mid-run.
+    // A MIG slice's memory is fixed by its profile and not checked.
+    let gpu_memory = config.runtime.as_ref().map(|r| r.gpu_memory_bytes()).transpose()?.flatten();
+    if let Some(required) = gpu_memory.filter(|_| !uses_mig) {
+        match &gpu_config {
+            Some(gpu) if gpu.is_nvidia() => {
+                gpu_config = Some(wait_for_gpu_memory(gpu, required.max(0) as u64, run.wait_for_gpu, json_output).await?);
+            }
+            Some(_) => {}
+            None => {
+                return Err(GeoEngineError::GpuUnavailable(format!(
+                    "runtime.gpu_memory is set to {}, but no GPU is available to the run",
+                    usage::format_bytes(required.max(0) as u64)
+                ))
+                .into())
+            }
+        }
+    }
+    if let Some(request) = &run.gpus {
+        let gpu = gpu_config.as_ref().filter(|g| g.is_nvidia()).ok_or_else(|| {
+            GeoEngineError::GpuUnavailable(format!(
+                "--gpus {} requested, but no NVIDIA GPU is available to the run",
+                request
+            ))
+        })?;
+        // Count requests prefer GPUs no other run holds or is picking
+        let (requested, reservation) = gpu_reservations::reserve(
+            client,
+            |busy| gpu.requested(request, busy),
+            |gpus| gpus.device_ids.clone().unwrap_or_default(),
+        )
+        .await?;
+        gpu_config = Some(requested);
+        gpu_reservation = Some(reservation);
+    }
+
+    let variant = match config.build.as_ref().and_then(|b| b.variants.as_ref()) {
+        Some(variants) => {
+            let name = match &run.variant {
+                Some(name) if variants.contains_key(name) => name.as_str(),
+                Some(name) => {
+                    return Err(GeoEngineError::InputInvalid(format!(
+                        "Unknown build variant '{}'. Variants of '{}': {}",
+                        name,
+                        config.name,
+                        variants.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ))
+                    .into())
+                }
+                None => select_variant(variants, gpu_config.as_ref()).ok_or_else(|| {
+                    GeoEngineError::ConfigInvalid(format!(
+                        "No build variant of '{}' runs on this host ({}); add a cpu variant or pass --variant",
+                        config.name,
+                        GpuConfig::accelerator_name(gpu_config.as_ref())
+                    ))
+                })?,
+            };
+            // A CPU image gets no GPU, and isn't told there is one
+            if variants[name].accelerator == Some(Accelerator::Cpu) {
+                gpu_config = None;
+            }
+            if !json_output {
+                eprintln!("{} Using build variant '{}'", "•".cyan(), name);
+            }
+            Some(name.to_string())
+        }
+        None if run.variant.is_some() => {
+            return Err(GeoEngineError::InputInvalid(format!("Worker '{}' has no build variants", config.name)).into())
+        }
+        None => None,
+    };
+    if let Some(cfg) = &gpu_config {
+        if !json_output {
+            let label = cfg.devices.join(", ");
+            eprintln!(
+                "{} GPU detected: {} ({})",
+                "•".cyan(),
+                label,
+                if cfg.is_nvidia() { "NVIDIA" } else { "Metal" }
+            );
+        }
+    }
 
-    // Parse --input KEY=VALUE args into a HashMap
-    let mut inputs: HashMap<String, String> = HashMap::new();
-    for arg in input_args {
+    let image_tag = match (run.dev, &variant) {
+        (true, None) => format!("geoengine-local-dev/{}:latest", config.name),
+        (true, Some(variant)) => format!("geoengine-local-dev/{}:{}", config.name, variant),
+        (false, None) => format!(
+            "geoengine-local/{}:{}",
+            config.name,
+            config.version.clone().unwrap_or("latest".to_string())
+        ),
+        (false, Some(variant)) => match &config.version {
+            Some(version) => format!("geoengine-local/{}:{}-{}", config.name, version, variant),
+            None => format!("geoengine-local/{}:{}", config.name, variant),
+        },
+    };
+
+    let missing = client.image_id(&image_tag).await.is_err();
+    let pull = match run.pull {
+        PullPolicy::Always => true,
+        PullPolicy::Missing => missing,
+        PullPolicy::Never => false,
+    };
+    let not_found = || {
+        GeoEngineError::ImageNotFound(format!(
+            "Image '{}' is not available locally. Run 'geoengine build' or 'geoengine image pull' first.",
+            image_tag
+        ))
+    };
+    // geoengine-local/... would otherwise resolve to a Docker Hub namespace nobody here owns
+    if pull && !registry::has_repository_rewrite(&image_tag)? {
+        let repository = image_tag.split('/').next().unwrap_or_default();
+        return Err(GeoEngineError::InputInvalid(format!(
+            "--pull needs a registries.rewrites entry for docker.io/{} in settings that points at your own registry",
+            repository
+        ))
+        .into());
+    }
+    if pull {
+        if !json_output {
+            eprintln!("{} Pulling {}...", "=>".blue().bold(), image_tag.cyan());
+        }
+        if let Err(e) = registry::pull(client, &image_tag).await {
+            // A worker that was never built can't be pulled either; say so rather than
+            // reporting the registry's complaint
+            if missing && !registry::is_auth_failure(&format!("{:#}", e)) {
+                return Err(e.context(not_found()));
+            }
+            return Err(e);
+        }
+    } else if missing {
+        return Err(not_found().into());
+    }
+
+    // CUDA images cannot use the GPU without an NVIDIA device
+    let has_nvidia = gpu_config.as_ref().map(|g| g.is_nvidia()).unwrap_or(false);
+    if !has_nvidia && client.is_cuda_image(&image_tag).await.unwrap_or(false) {
+        eprintln!(
+            "{} Image '{}' is built on a CUDA base image, but no NVIDIA GPU is available; it will run on CPU.",
+            "!".yellow().bold(),
+            image_tag
+        );
+        if gpu_config.as_ref().map(|g| g.is_metal()).unwrap_or(false) {
+            eprintln!(
+                "  On Apple Silicon, use a CPU base image (e.g. python:3.11-slim with the default PyTorch wheels) \
+                and set `runtime.accelerator: mps` in geoengine.yaml."
+            );
+        }
+    }
+
+    let env = collect_env(run, &config, &worker_path)?;
+
+    // Don't start until external dependencies (databases, tile servers, ...) are ready
+    if let Some(targets) = config.runtime.as_ref().and_then(|r| r.wait_for.as_ref()) {
+        wait::wait_for_all(targets, client).await?;
+    }
+
+    Ok(PreparedRun {
+        worker_name,
+        worker_path,
+        config,
+        image_tag,
+        gpu_config,
+        _gpu_reservation: gpu_reservation,
+        env,
+    })
+}
+
+/// Build the container environment. Later sources override earlier ones:
+/// `runtime.env_files` (in order), `runtime.env`, `--env-file` (in order), `--env`.
+fn collect_env(run: &RunArgs, config: &WorkerConfig, worker_path: &Path) -> Result<HashMap<String, String>> {
+    let mut env: HashMap<String, String> = HashMap::new();
+
+    if let Some(runtime) = &config.runtime {
+        for file in runtime.env_files.iter().flatten() {
+            let path = worker_path.join(file.strip_prefix("./").unwrap_or(file));
+            env.extend(dotenv::load(&path)?);
+        }
+        if let Some(vars) = &runtime.env {
+            env.extend(vars.iter().map(|(k, v)| (k.clone(), v.clone())));
+        }
+    }
+
+    for file in &run.env_file {
+        env.extend(dotenv::load(file)?);
+    }
+
+    for arg in &run.env {
+        let (key, value) = arg
+            .split_once('=')
+            .ok_or_else(|| GeoEngineError::InputInvalid(format!("Invalid env format: '{}'. Expected KEY=VALUE", arg)))?;
+        env.insert(key.to_string(), value.to_string());
+    }
+
+    Ok(env)
+}
+
+/// Collect input parameters from --param-file, then let --input KEY=VALUE args override them
+pub fn collect_inputs(run: &RunArgs, definitions: &[InputParameter]) -> Result<HashMap<String, String>> {
+    let mut inputs: HashMap<String, String> = match &run.param_file {
+        Some(path) => load_param_file(path, definitions)?,
+        None => HashMap::new(),
+    };
+    for arg in &run.inputs {
         let parts: Vec<&str> = arg.splitn(2, '=').collect();
         if parts.len() != 2 {
-            anyhow::bail!("Invalid input format: '{}'. Expected KEY=VALUE", arg);
+            return Err(
+                GeoEngineError::InputInvalid(format!("Invalid input format: '{}'. Expected KEY=VALUE", arg)).into(),
+            );
         }
         inputs.insert(parts[0].to_string(), parts[1].to_string());
     }
+    Ok(inputs)
+}
+
+/// Required inputs a run leaves unset. Those that visible_when can hide always
+/// have a default and are left to the script.
+fn missing_inputs<'a>(definitions: &'a [InputParameter], inputs: &HashMap<String, String>) -> Vec<&'a InputParameter> {
+    definitions
+        .iter()
+        .filter(|i| i.required.unwrap_or(true) && i.visible_when.is_none() && !inputs.contains_key(&i.name))
+        .collect()
+}
+
+/// Give the required inputs a run leaves unset their declared defaults, failing
+/// on those that have none. Used where nobody can be asked: runs without a
+/// terminal and jobs submitted to remote runners.
+pub fn fill_missing_inputs(definitions: &[InputParameter], inputs: &mut HashMap<String, String>) -> Result<()> {
+    let missing = missing_inputs(definitions, inputs);
+    let unset: Vec<&str> = missing.iter().filter(|i| i.default.is_none()).map(|i| i.name.as_str()).collect();
+    if !unset.is_empty() {
+        return Err(GeoEngineError::InputInvalid(format!(
+            "Missing required input(s): {}. Pass them with --input KEY=VALUE.",
+            unset.join(", ")
+        ))
+        .into());
+    }
+    for input in missing {
+        if let Some(default) = &input.default {
+            inputs.insert(input.name.clone(), yaml_value_to_display_string(default));
+        }
+    }
+    Ok(())
+}
+
+/// Ask in the terminal for the required inputs a run leaves unset, with their
+/// defaults pre-filled. Without a terminal (or with --json or --no-input), use the
+/// defaults and fail on those that have none instead.
+fn prompt_missing_inputs(config: &WorkerConfig, inputs: &mut HashMap<String, String>, run: &RunArgs) -> Result<()> {
+    let definitions = config.input_definitions();
+    let missing = missing_inputs(definitions, inputs);
+    if missing.is_empty() {
+        return Ok(());
+    }
+    let interactive = !run.no_input && !run.json && std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+    if !interactive {
+        return fill_missing_inputs(definitions, inputs);
+    }
+
+    eprintln!("{} Enter the required inputs that weren't given:", "=>".blue().bold());
+    let theme = ColorfulTheme::default();
+    for input in missing {
+        let prompt = match &input.description {
+            Some(description) => format!("{} ({})", input.name, description),
+            None => input.name.clone(),
+        };
+        let default = input.default.as_ref().map(yaml_value_to_display_string);
+        let param_type = input.param_type.to_ascii_lowercase();
+        let values = input.enum_values.clone().unwrap_or_default();
+        let value = match param_type.as_str() {
+            "enum" if !values.is_empty() => {
+                let selected = default.and_then(|d| values.iter().position(|v| *v == d)).unwrap_or(0);
+                let choice = Select::with_theme(&theme)
+                    .with_prompt(prompt)
+                    .items(&values)
+                    .default(selected)
+                    .interact()?;
+                values[choice].clone()
+            }
+            "boolean" => Confirm::with_theme(&theme)
+                .with_prompt(prompt)
+                .default(default.as_deref() == Some("true"))
+                .interact()?
+                .to_string(),
+            _ => {
+                let mut text = Input::<String>::with_theme(&theme).with_prompt(prompt);
+                if let Some(default) = default {
+                    text = text.default(default);
+                }
+                text.validate_with(|value: &String| validate_prompted_input(&param_type, value))
+                    .interact_text()?
+            }
+        };
+        inputs.insert(input.name.clone(), value);
+    }
+    Ok(())
+}
+
+/// Check a value typed at an input prompt against the input's type
+fn validate_prompted_input(param_type: &str, value: &str) -> std::result::Result<(), String> {
+    let path = Path::new(value);
+    match param_type {
+        _ if value.trim().is_empty() => Err("enter a value".to_string()),
+        "file" if !path.is_file() => Err(format!("{} is not a file", value)),
+        "folder" if !path.is_dir() => Err(format!("{} is not a directory", value)),
+        "number" if value.trim().parse::<f64>().is_err() => Err(format!("{} is not a number", value)),
+        _ => Ok(()),
+    }
+}
+
+/// Check a file input against its `sha256:` checksum, returning the verified hash
+fn verify_input_file(key: &str, path: &Path, checksum: &str) -> Result<String> {
+    let expected = checksums::parse_sha256(checksum)
+        .map_err(|e| GeoEngineError::InputInvalid(format!("Input '{}': {:#}", key, e)))?;
+    let actual = state::compute_file_hash(path)?;
+    if actual != expected {
+        return Err(GeoEngineError::InputInvalid(format!(
+            "Input '{}' does not match its checksum: {} has sha256:{} (expected sha256:{})",
+            key,
+            path.display(),
+            actual,
+            expected
+        ))
+        .into());
+    }
+    Ok(actual)
+}
+
+/// Check the files of a folder input against a `manifest:FILE` checksum manifest
+fn verify_input_folder(key: &str, dir: &Path, checksum: &str, quiet: bool) -> Result<VerifiedFolderRecord> {
+    let manifest_path = checksum.strip_prefix("manifest:").map(Path::new).ok_or_else(|| {
+        GeoEngineError::InputInvalid(format!(
+            "Input '{}' is a folder; give its checksum as manifest:FILE (see 'geoengine data hash')",
+            key
+        ))
+    })?;
+    let manifest = checksums::read_manifest(manifest_path)
+        .map_err(|e| GeoEngineError::InputInvalid(format!("Input '{}': {:#}", key, e)))?;
+
+    let total: u64 = manifest
+        .iter()
+        .filter_map(|(relative, _)| std::fs::metadata(dir.join(relative)).ok())
+        .map(|m| m.len())
+        .sum();
+    let pb = if quiet { ProgressBar::hidden() } else { ProgressBar::new(total) };
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} {msg} [{bar:30.cyan/blue}] {bytes}/{total_bytes} ({eta})")?
+            .progress_chars("=> "),
+    );
+    pb.set_message(format!("Verifying {}", key));
+    let failures = checksums::verify_tree(dir, &manifest, Some(manifest_path), &mut |size| pb.inc(size));
+    pb.finish_and_clear();
+    let failures = failures?;
+    if !failures.is_empty() {
+        return Err(GeoEngineError::InputInvalid(format!(
+            "Input '{}' does not match {}:\n  {}",
+            key,
+            manifest_path.display(),
+            failures.join("\n  ")
+        ))
+        .into());
+    }
+    if !quiet {
+        eprintln!(
+            "{} Verified {} file(s) of '{}' against {}",
+            "✓".green().bold(),
+            manifest.len(),
+            key,
+            manifest_path.display()
+        );
+    }
+    Ok(VerifiedFolderRecord {
+        name: key.to_string(),
+        path: dir.display().to_string(),
+        manifest_sha256: state::compute_file_hash(manifest_path)?,
+        files: manifest.len(),
+    })
+}
+
+/// Directories for the writable folder inputs a run leaves unset, from the worker's
+/// `outputs.dir` or the `output_dir` setting. The directories are created.
+fn default_output_dirs(prepared: &PreparedRun, inputs: &HashMap<String, String>) -> Result<Vec<(String, PathBuf)>> {
+    let template = match prepared.config.outputs.as_ref().and_then(|o| o.dir.clone()) {
+        Some(template) => template,
+        None => match Settings::load()?.output_dir {
+            Some(template) => template,
+            None => return Ok(Vec::new()),
+        },
+    };
+    let timestamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+    let mut dirs = Vec::new();
+    let unset: Vec<&InputParameter> = prepared
+        .config
+        .command
+        .iter()
+        .flat_map(|c| c.inputs.iter().flatten())
+        .filter(|input| {
+            input.param_type.eq_ignore_ascii_case("folder")
+                && !input.readonly.unwrap_or(true)
+                && !inputs.contains_key(&input.name)
+        })
+        .collect();
+    // Several inputs must not write into one directory; without {input} each gets a subdirectory
+    let per_input = unset.len() > 1 && !template.contains("{input}");
+    for input in unset {
+        let path = template
+            .replace("{worker}", &prepared.worker_name)
+            .replace("{version}", prepared.config.version.as_deref().unwrap_or("latest"))
+            .replace("{input}", &input.name)
+            .replace("{timestamp}", &timestamp);
+        let mut dir = prepared.worker_path.join(path);
+        if per_input {
+            dir.push(&input.name);
+        }
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create output directory: {}", dir.display()))?;
+        dirs.push((input.name.clone(), dir.canonicalize()?));
+    }
+    Ok(dirs)
+}
+
+/// Run the worker's command once with the given inputs and return the container's exit code
+#[tracing::instrument(
+    skip_all,
+    fields(worker = %prepared.worker_name, image = %prepared.image_tag, exit_code = tracing::field::Empty)
+)]
+pub async fn execute_run(
+    client: &DockerClient,
+    prepared: &PreparedRun,
+    inputs: &HashMap<String, String>,
+    run: &RunArgs,
+    gpu_config: Option<GpuConfig>,
+    output: &RunOutput,
+) -> Result<RunOutcome> {
+    let worker_name = &prepared.worker_name;
+    let worker_path = &prepared.worker_path;
+    let config = &prepared.config;
+    let cmd_config = config
+        .command
+        .as_ref()
+        .ok_or_else(|| anyhow::anyhow!("No command defined for worker '{}'", worker_name))?;
+
+    // Read-only inputs are staged into an isolated per-run workspace (removed when
+    // the run finishes) unless --no-isolate is given. Writable inputs are the run's
+    // outputs and are always mounted directly.
+    // Detached containers outlive this process, so they mount inputs in place.
+    let isolate = !(run.no_isolate || run.detach);
+    if !run.no_preflight {
+        preflight_disk_space(client, config, inputs, isolate).await?;
+    }
+    let workspace = if !isolate {
+        None
+    } else {
+        Some(JobWorkspace::create(worker_name)?)
+    };
+
+    let checksums = collect_checksums(run, config.input_definitions())?;
+    if let Some(key) = checksums.keys().find(|key| !inputs.contains_key(*key)) {
+        return Err(GeoEngineError::InputInvalid(format!("A checksum is given for '{}', which is not set", key)).into());
+    }
+    // Hashes of file inputs that matched their checksum, reused for provenance
+    let mut verified_files: HashMap<PathBuf, String> = HashMap::new();
+    let mut verified_folders: Vec<VerifiedFolderRecord> = Vec::new();
 
     // Build extra mounts from input values that are explicitly defined as
     // file/folder inputs in worker config.
     let mut extra_mounts: Vec<(String, String, bool)> = Vec::new();
+    // File inputs (hashed for provenance) and writable folder inputs (where provenance is written)
+    let mut input_files: Vec<(String, PathBuf)> = Vec::new();
+    let mut output_dirs: Vec<PathBuf> = Vec::new();
     let input_definitions: HashMap<String, (String, bool)> = cmd_config
         .inputs
         .as_ref()
@@ -751,74 +2590,145 @@ pub async fn run_worker(
 
     // Build script arguments from inputs
     let mut script_args: Vec<String> = Vec::new();
-    for (key, value) in &inputs {
+    for (key, value) in inputs {
         // Only auto-mount for declared file/folder inputs.
         let path = Path::new(value);
         let processed_value = if let Some((param_type, readonly)) = input_definitions.get(key) {
             match param_type.as_str() {
                 "file" => {
                     if !path.exists() {
-                        anyhow::bail!(
+                        return Err(GeoEngineError::InputInvalid(format!(
                             "Input '{}' is declared as type 'file' but path does not exist: {}",
                             key,
                             value
-                        );
+                        ))
+                        .into());
                     }
                     if !path.is_file() {
-                        anyhow::bail!(
+                        return Err(GeoEngineError::InputInvalid(format!(
                             "Input '{}' is declared as type 'file' but received a non-file path: {}",
                             key,
                             value
-                        );
+                        ))
+                        .into());
                     }
 
                     let filename = path.file_name().ok_or_else(|| {
-                        anyhow::anyhow!(
+                        GeoEngineError::InputInvalid(format!(
                             "Input '{}' is declared as type 'file' but has no file name: {}",
                             key,
                             value
-                        )
+                        ))
                     })?;
                     let abs_path = path
                         .canonicalize()
                         .with_context(|| format!("Failed to resolve input file path: {}", value))?;
+                    if let Some(checksum) = checksums.get(key) {
+                        let hash = verify_input_file(key, &abs_path, checksum)?;
+                        verified_files.insert(abs_path.clone(), hash);
+                    }
                     let container_path = format!("/inputs/{}/{}", key, filename.to_string_lossy());
+                    let host_path = match &workspace {
+                        Some(ws) if *readonly => ws.stage_file(
+                            &abs_path,
+                            &format!("inputs/{}/{}", key, filename.to_string_lossy()),
+                        )?,
+                        _ => abs_path.clone(),
+                    };
                     extra_mounts.push((
-                        abs_path.to_string_lossy().to_string(),
+                        host_path.to_string_lossy().to_string(),
                         container_path.clone(),
                         *readonly,
                     ));
+                    input_files.push((key.clone(), abs_path.clone()));
+
+                    // Multi-file formats (shapefiles, ENVI rasters, world files, ...)
+                    // need their sidecar files next to the primary file.
+                    let sidecar_files = sidecars::resolve(&abs_path);
+                    if !sidecar_files.missing_required.is_empty() {
+                        eprintln!(
+                            "{} Input '{}' is missing {} next to {}; the dataset may not open.",
+                            "!".yellow().bold(),
+                            key,
+                            sidecar_files.missing_required.join(", "),
+                            abs_path.display()
+                        );
+                    }
+                    for sidecar in sidecar_files.files {
+                        let sidecar_name = sidecar
+                            .file_name()
+                            .map(|n| n.to_string_lossy().to_string())
+                            .unwrap_or_default();
+                        let host_path = match &workspace {
+                            Some(ws) if *readonly => {
+                                ws.stage_file(&sidecar, &format!("inputs/{}/{}", key, sidecar_name))?
+                            }
+                            _ => sidecar.clone(),
+                        };
+                        extra_mounts.push((
+                            host_path.to_string_lossy().to_string(),
+                            format!("/inputs/{}/{}", key, sidecar_name),
+                            *readonly,
+                        ));
+                        input_files.push((key.clone(), sidecar));
+                    }
                     container_path
                 }
                 "folder" => {
                     if !path.exists() {
-                        anyhow::bail!(
+                        return Err(GeoEngineError::InputInvalid(format!(
                             "Input '{}' is declared as type 'folder' but path does not exist: {}",
                             key,
                             value
-                        );
+                        ))
+                        .into());
                     }
                     if !path.is_dir() {
-                        anyhow::bail!(
+                        return Err(GeoEngineError::InputInvalid(format!(
                             "Input '{}' is declared as type 'folder' but received a non-directory path: {}",
                             key,
                             value
-                        );
+                        ))
+                        .into());
                     }
 
                     let abs_path = path
                         .canonicalize()
                         .with_context(|| format!("Failed to resolve input directory path: {}", value))?;
+                    if let Some(checksum) = checksums.get(key) {
+                        let quiet = !matches!(output, RunOutput::Terminal);
+                        verified_folders.push(verify_input_folder(key, &abs_path, checksum, quiet)?);
+                    }
                     let container_path = format!("/mnt/input_{}", key);
+                    let host_path = match &workspace {
+                        Some(ws) if *readonly => ws.stage_dir(&abs_path, &format!("input_{}", key))?,
+                        _ => abs_path.clone(),
+                    };
                     extra_mounts.push((
-                        abs_path.to_string_lossy().to_string(),
+                        host_path.to_string_lossy().to_string(),
                         container_path.clone(),
                         *readonly,
                     ));
+                    if !*readonly {
+                        output_dirs.push(abs_path);
+                    }
                     container_path
                 }
+                _ if checksums.contains_key(key) => {
+                    return Err(GeoEngineError::InputInvalid(format!(
+                        "A checksum is given for '{}', which is not a file or folder input",
+                        key
+                    ))
+                    .into());
+                }
                 _ => value.clone(),
             }
+        } else if checksums.contains_key(key) {
+            return Err(GeoEngineError::InputInvalid(format!(
+                "A checksum is given for '{}', which is not a declared file or folder input",
+                key
+            ))
+            .into());
         } else {
             value.clone()
         };
@@ -828,16 +2738,15 @@ pub async fn run_worker(
     }
 
     // Add any extra trailing args
-    script_args.extend_from_slice(extra_args);
+    script_args.extend_from_slice(&run.args);
 
     // Build mounts from config
     let mut mounts: Vec<(String, String, bool)> = Vec::new();
     if let Some(mount_configs) = &config.local_dir_mounts {
         for m in mount_configs {
-            let host_path = if m.host_path.starts_with("./") {
-                worker_path.join(&m.host_path[2..])
-            } else {
-                PathBuf::from(&m.host_path)
+            let host_path = match datasets::resolve_uri(worker_name, config.data.as_ref(), &m.host_path)? {
+                Some(path) => path,
+                None => host::resolve_host_path(&m.host_path, worker_path),
             };
             mounts.push((
                 host_path.to_string_lossy().to_string(),
@@ -846,7 +2755,55 @@ pub async fn run_worker(
             ));
         }
     }
-    mounts.extend(extra_mounts);
+    for spec in &run.mount {
+        let (host_path, container_path, readonly) = parse_mount(spec)?;
+        let host_path = Path::new(&host_path)
+            .canonicalize()
+            .with_context(|| format!("Mount source doesn't exist: {}", host_path))?;
+        mounts.push((host_path.to_string_lossy().to_string(), container_path, readonly));
+    }
+    mounts.extend(extra_mounts);
+
+    // Named volumes and tmpfs mounts; missing named volumes are created on first use
+    let mut volumes: Vec<VolumeConfig> = config
+        .runtime
+        .as_ref()
+        .and_then(|r| r.volumes.clone())
+        .unwrap_or_default();
+
+    // Organization-wide mounts from the settings; the worker's own mounts win on
+    // the same container path
+    if config.runtime.as_ref().and_then(|r| r.default_mounts).unwrap_or(true) {
+        for mount in Settings::load()?.default_mounts {
+            let target = match &mount {
+                DefaultMount::Bind(m) => m.container_path.clone(),
+                DefaultMount::Volume(v) => v.target.clone(),
+            };
+            if mounts.iter().any(|(_, container, _)| *container == target) || volumes.iter().any(|v| v.target == target) {
+                continue;
+            }
+            match mount {
+                DefaultMount::Bind(m) => {
+                    let host_path = host::resolve_host_path(&m.host_path, worker_path);
+                    if !host_path.exists() {
+                        eprintln!(
+                            "{} Skipping default mount {}: it doesn't exist on this host",
+                            "!".yellow().bold(),
+                            host_path.display()
+                        );
+                        continue;
+                    }
+                    mounts.push((host_path.to_string_lossy().to_string(), target, m.readonly.unwrap_or(false)));
+                }
+                DefaultMount::Volume(v) => volumes.push(v),
+            }
+        }
+    }
+    for volume in volumes.iter().filter(|v| v.kind == VolumeKind::Volume) {
+        if client.ensure_volume(volume, worker_name).await? {
+            tracing::info!("Created volume {}", volume.name.as_deref().unwrap_or_default());
+        }
+    }
 
     // Build full command
     let full_command = if script_args.is_empty() {
@@ -856,46 +2813,190 @@ pub async fn run_worker(
         format!("{} {} {}", cmd_config.program, cmd_config.script, escaped_args.join(" "))
     };
 
-    // Auto-detect system GPUs — only keep configs that Docker can use (NVIDIA).
-    // Metal (macOS) is handled natively by the container runtime and needs no
-    // explicit device passthrough.
-    let gpu_config = match GpuConfig::detect().await {
-        Ok(cfg) if cfg.is_available() => {
-            if !json_output {
-                let label = cfg.devices.join(", ");
-                eprintln!(
-                    "{} GPU detected: {} ({})",
-                    "•".cyan(),
-                    label,
-                    if cfg.is_nvidia() { "NVIDIA" } else { "Metal" }
-                );
+    // Variables set by GeoEngine itself take precedence over user-provided ones
+    let mut env_vars: HashMap<String, String> = prepared.env.clone();
+    env_vars.insert(
+        "GEOENGINE_ACCELERATOR".to_string(),
+        GpuConfig::accelerator_name(gpu_config.as_ref()).to_string(),
+    );
+
+    // Run as the invoking user so outputs on bind mounts aren't owned by root
+    let runtime = config.runtime.as_ref();
+    let userns = client.user_namespace();
+    let container_user = user::resolve(run.user.as_deref(), runtime.and_then(|r| r.user.as_deref()), userns);
+    let mut group_add: Vec<String> = runtime.and_then(|r| r.group_add.clone()).unwrap_or_default();
+    group_add.extend(run.group_add.iter().cloned());
+    if container_user.mapped {
+        // The mapped UID usually has no home directory in the image
+        env_vars.entry("HOME".to_string()).or_insert_with(|| "/tmp".to_string());
+    }
+    // Host GIDs mean nothing inside a remapped user namespace
+    if userns == UserNamespace::Host
+        && container_user.user.as_deref().is_some_and(|u| !user::is_root(u))
+        && gpu_config.as_ref().is_some_and(|g| g.is_nvidia())
+    {
+        for gid in user::gpu_device_groups() {
+            if !group_add.contains(&gid) {
+                group_add.push(gid);
             }
-            Some(cfg)
         }
-        _ => None,
+    }
+
+    // Resource limits; Docker warns on creation when the kernel can't enforce them
+    let memory = match runtime.map(|r| r.memory_bytes()).transpose() {
+        Ok(memory) => memory.flatten(),
+        Err(e) => return Err(GeoEngineError::ConfigInvalid(format!("{:#}", e)).into()),
+    };
+    let nano_cpus = match runtime.and_then(|r| r.cpus) {
+        Some(cpus) if cpus <= 0.0 => {
+            return Err(GeoEngineError::ConfigInvalid(format!("runtime.cpus must be positive, got {}", cpus)).into())
+        }
+        Some(cpus) => Some((cpus * 1e9) as i64),
+        None => None,
     };
 
-    // Build ContainerConfig
-    let image_tag = if dev {
-        format!("geoengine-local-dev/{}:latest", config.name)
-    } else {
-        format!("geoengine-local/{}:{}", config.name, this_ver)
+    // Hardening from runtime.security; --read-only and --cap-drop add to it
+    let security = runtime.and_then(|r| r.security.clone()).unwrap_or_default();
+    let security_opt = security_options(&security, worker_path)?;
+    let read_only = run.read_only || security.read_only_rootfs.unwrap_or(false);
+    let mut cap_drop = security.cap_drop.clone().unwrap_or_default();
+    for cap in &run.cap_drop {
+        if !cap_drop.contains(cap) {
+            cap_drop.push(cap.clone());
+        }
+    }
+    check_user_namespace(userns, run, &output_dirs)?;
+    if run.privileged {
+        eprintln!(
+            "{} {}",
+            "!".red().bold(),
+            "WARNING: --privileged gives the container full access to the host: every device, every capability \
+             and no seccomp or AppArmor confinement"
+                .red()
+                .bold()
+        );
+        if !security_opt.is_empty() {
+            eprintln!("  runtime.security's seccomp and AppArmor profiles don't apply to privileged containers");
+        }
+    }
+
+    // Detached containers are kept after they exit so their logs stay available
+    let name = match &run.name {
+        Some(name) => Some(name.clone()),
+        None if run.detach => Some(format!("{}-{}", worker_name, chrono::Utc::now().format("%Y%m%d-%H%M%S"))),
+        None => None,
+    };
+    let mut container_labels = HashMap::new();
+    for label in &run.labels {
+        let (key, value) = label
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("Invalid label format: '{}'. Expected KEY=VALUE", label))?;
+        container_labels.insert(key.to_string(), value.to_string());
+    }
+    if run.detach {
+        let service = run.service.clone().or_else(|| name.clone()).unwrap_or_default();
+        container_labels.insert(labels::SERVICE.to_string(), service);
+    }
+
+    // Runs of a worker whose services are up join their network to reach them by name
+    let network = match &run.network {
+        Some(network) => Some(network.clone()),
+        None if config.services.as_ref().is_some_and(|s| !s.is_empty()) => {
+            let network = service::network_name(worker_name);
+            client.network_exists(&network).await.then_some(network)
+        }
+        None => None,
     };
+    let network_aliases: Vec<String> = run.service.iter().filter(|_| network.is_some()).cloned().collect();
+
     let container_config = ContainerConfig {
-        image: image_tag,
+        image: prepared.image_tag.clone(),
+        entrypoint: run.entrypoint.clone().map(|e| vec![e]),
         command: Some(vec!["/bin/sh".to_string(), "-c".to_string(), full_command]),
-        env_vars: HashMap::new(),
+        env_vars,
         mounts,
+        volumes,
         gpu_config,
-        workdir: None,
-        name: None,
-        remove_on_exit: true,
-        detach: false,
-        tty: !json_output,
+        user: container_user.user,
+        group_add,
+        workdir: run.workdir.clone(),
+        name: name.clone(),
+        labels: container_labels,
+        ports: run.publish.clone(),
+        restart: run.restart.clone(),
+        ulimits: run.ulimit.clone(),
+        cap_add: run.cap_add.clone(),
+        cap_drop,
+        devices: run.device.clone(),
+        read_only,
+        security_opt,
+        privileged: run.privileged,
+        memory,
+        nano_cpus,
+        network,
+        network_aliases,
+        remove_on_exit: !run.detach,
+        detach: run.detach,
+        tty: matches!(output, RunOutput::Terminal) && !run.detach,
     };
 
+    // Nothing waits for a detached container to exit, so post_run and on_failure
+    // could never run; rather than run half the lifecycle, hooks are skipped
+    if run.detach && config.hooks.is_some() {
+        eprintln!(
+            "{} Skipping the worker's hooks: detached runs and services don't run them",
+            "!".yellow().bold()
+        );
+    }
+    let hooks = config.hooks.as_ref().filter(|_| !run.detach).map(|hooks| HookRunner {
+        client,
+        hooks,
+        container: &container_config,
+        worker_name,
+        worker_path,
+        output_dirs: &output_dirs,
+        output,
+    });
+    if let Some(hooks) = &hooks {
+        hooks.pre_run().await?;
+    }
+
+    if run.detach {
+        client
+            .run_container_detached(&container_config)
+            .await
+            .context("Failed to start the container")
+            .map_err(|e| rootless_gpu_hint(e, userns))?;
+        audit_run_started(prepared, inputs, name.as_deref());
+        record_history(
+            client,
+            prepared,
+            inputs,
+            run,
+            &output_dirs,
+            &[],
+            container_config.gpu_config.as_ref(),
+            chrono::Utc::now(),
+            0.0,
+            None,
+            false,
+            name.clone(),
+            None,
+        )
+        .await;
+        return Ok(RunOutcome {
+            exit_code: 0,
+            files: Vec::new(),
+            output_dirs,
+            finished_at: chrono::Utc::now(),
+            container: name,
+            usage: None,
+            oom_killed: false,
+        });
+    }
+
     // Print status message
-    if !json_output {
+    if matches!(output, RunOutput::Terminal) {
         eprintln!(
             "{} Running worker '{}'...",
             "=>".blue().bold(),
@@ -903,40 +3004,492 @@ pub async fn run_worker(
         );
     }
 
-    // Run the container
-    let client = DockerClient::new().await?;
-    let exit_code = if json_output {
-        client.run_container_attached_to_stderr(&container_config).await?
+    // Hash file inputs up front so provenance reflects what the run actually read
+    let input_records: Vec<InputFileRecord> = if output_dirs.is_empty() {
+        Vec::new()
     } else {
-        client.run_container_attached(&container_config).await?
+        input_files
+            .iter()
+            .map(|(name, path)| {
+                Ok(InputFileRecord {
+                    name: name.clone(),
+                    path: path.display().to_string(),
+                    sha256: match verified_files.get(path) {
+                        Some(hash) => hash.clone(),
+                        None => state::compute_file_hash(path)?,
+                    },
+                    verified: verified_files.contains_key(path),
+                })
+            })
+            .collect::<Result<_>>()?
     };
 
-    // Handle output
-    if json_output {
-        let result = RunResult {
-            status: if exit_code == 0 { "completed".to_string() } else { "failed".to_string() },
+    // Run the container
+    let started_at = chrono::Utc::now();
+    let started_system = std::time::SystemTime::now();
+    let started = std::time::Instant::now();
+    audit_run_started(prepared, inputs, None);
+    let exited = match output {
+        RunOutput::Terminal => client.run_container_attached(&container_config).await,
+        RunOutput::Stderr => client.run_container_attached_to_stderr(&container_config).await,
+        RunOutput::LogFile(path) => client.run_container_attached_to_file(&container_config, path).await,
+    };
+    audit_run_finished(prepared, &exited, started.elapsed().as_secs_f64());
+    if let Some(hooks) = &hooks {
+        hooks.finished(exited.as_ref().ok().map(|e| e.exit_code)).await;
+    }
+    let ContainerExit { exit_code, oom_killed, usage } = exited.map_err(|e| rootless_gpu_hint(e, userns))?;
+    tracing::Span::current().record("exit_code", exit_code);
+    let finished_at = chrono::Utc::now();
+    record_history(
+        client,
+        prepared,
+        inputs,
+        run,
+        &output_dirs,
+        &input_records,
+        container_config.gpu_config.as_ref(),
+        started_at,
+        started.elapsed().as_secs_f64(),
+        Some(exit_code),
+        oom_killed,
+        None,
+        usage.clone(),
+    )
+    .await;
+
+    let mut files = Vec::new();
+    for dir in &output_dirs {
+        collect_new_files(dir, started_system, &mut files);
+    }
+
+    // Record provenance in every writable output folder
+    if !output_dirs.is_empty() {
+        let provenance = Provenance {
+            geoengine_version: env!("CARGO_PKG_VERSION").to_string(),
+            worker: worker_name.to_string(),
+            worker_version: config.version.clone(),
+            image: container_config.image.clone(),
+            image_id: client.image_id(&container_config.image).await.ok(),
+            image_digests: client.local_repo_digests(&container_config.image).await.unwrap_or_default(),
+            command: container_config.command.clone().unwrap_or_default(),
+            parameters: inputs.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+            input_files: input_records.clone(),
+            verified_folders,
             exit_code,
-            error: if exit_code != 0 {
-                Some(format!("Container exited with code {}", exit_code))
-            } else {
-                None
-            },
-            files: Vec::new(),
+            started_at: started_at.to_rfc3339(),
+            finished_at: finished_at.to_rfc3339(),
+            duration_secs: started.elapsed().as_secs_f64(),
         };
-        println!("{}", serde_json::to_string(&result)?);
-    } else if exit_code == 0 {
-        eprintln!("{} Completed successfully", "✓".green().bold());
+        for dir in &output_dirs {
+            match provenance.write_to(dir) {
+                Ok(path) => tracing::debug!("Wrote provenance record: {}", path.display()),
+                Err(e) => eprintln!("{} {:#}", "!".yellow().bold(), e),
+            }
+        }
+    }
+
+    Ok(RunOutcome {
+        exit_code,
+        files,
+        output_dirs,
+        finished_at,
+        container: None,
+        usage,
+        oom_killed,
+    })
+}
+
+/// Fail early on options a rootless or userns-remapped daemon can't provide, and
+/// explain who will own the files written to output folders
+fn check_user_namespace(userns: UserNamespace, run: &RunArgs, output_dirs: &[PathBuf]) -> Result<()> {
+    match userns {
+        UserNamespace::Host => {}
+        UserNamespace::Rootless => {
+            if run.privileged {
+                return Err(GeoEngineError::InputInvalid(
+                    "--privileged isn't possible with rootless Docker: the daemon has no more privileges than your \
+                     user. Grant what the worker needs with --cap-add and --device instead."
+                        .to_string(),
+                )
+                .into());
+            }
+            let inaccessible = user::inaccessible_devices(&run.device);
+            if !inaccessible.is_empty() {
+                return Err(GeoEngineError::InputInvalid(format!(
+                    "Rootless Docker can only pass through devices your user can read and write: {}. \
+                     Add yourself to the device's group or adjust its udev permissions.",
+                    inaccessible.join(", ")
+                ))
+                .into());
+            }
+        }
+        UserNamespace::Remapped => {
+            if run.privileged {
+                return Err(GeoEngineError::InputInvalid(
+                    "--privileged isn't possible while Docker remaps user namespaces (userns-remap in daemon.json)"
+                        .to_string(),
+                )
+                .into());
+            }
+            if !output_dirs.is_empty() && run.user.is_none() {
+                eprintln!(
+                    "{} Docker remaps user namespaces (userns-remap): files written to output folders will be owned \
+                     by a subordinate UID, not by you",
+                    "!".yellow().bold()
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Rootless Docker can't set up the device cgroups the NVIDIA runtime uses by default
+fn rootless_gpu_hint(e: anyhow::Error, userns: UserNamespace) -> anyhow::Error {
+    let message = format!("{:#}", e).to_lowercase();
+    if userns == UserNamespace::Rootless && message.contains("nvidia") {
+        return e.context(
+            "NVIDIA GPUs under rootless Docker need `no-cgroups = true` in /etc/nvidia-container-runtime/config.toml",
+        );
+    }
+    e
+}
+
+/// `security_opt` values for `runtime.security`. Docker's API takes the seccomp
+/// profile's JSON itself rather than a path, so profile files are read here.
+fn security_options(security: &SecurityConfig, worker_path: &Path) -> Result<Vec<String>> {
+    let mut options = Vec::new();
+    if let Some(profile) = &security.seccomp_profile {
+        if profile == "unconfined" {
+            options.push("seccomp=unconfined".to_string());
+        } else {
+            let path = worker_path.join(profile);
+            let json = std::fs::read_to_string(&path)
+                .ok()
+                .and_then(|text| serde_json::from_str::<serde_json::Value>(&text).ok())
+                .ok_or_else(|| {
+                    GeoEngineError::ConfigInvalid(format!(
+                        "runtime.security.seccomp_profile: {} is not a readable JSON seccomp profile",
+                        path.display()
+                    ))
+                })?;
+            options.push(format!("seccomp={}", json));
+        }
+    }
+    if let Some(profile) = &security.apparmor_profile {
+        options.push(format!("apparmor={}", profile));
+    }
+    if security.no_new_privileges.unwrap_or(false) {
+        options.push("no-new-privileges:true".to_string());
+    }
+    Ok(options)
+}
+
+/// What an OOM kill exceeded, with the peak memory use sampled before it
+pub fn oom_detail(config: &WorkerConfig, usage: Option<&ResourceUsage>) -> String {
+    let peak = usage
+        .filter(|u| u.peak_memory_bytes > 0)
+        .map(|u| format!(" (peak observed {})", usage::format_bytes(u.peak_memory_bytes)))
+        .unwrap_or_default();
+    match config.runtime.as_ref().and_then(|r| r.memory.as_deref()) {
+        Some(limit) => format!("exceeded memory limit {}{}", limit, peak),
+        None => format!("ran out of memory{}", peak),
+    }
+}
+
+/// How to avoid the next OOM kill
+fn oom_hint(config: &WorkerConfig) -> String {
+    match config.runtime.as_ref().and_then(|r| r.memory.as_deref()) {
+        Some(limit) => format!("Raise runtime.memory in geoengine.yaml (currently {})", limit),
+        None => "No runtime.memory limit is set, so the host (or Docker Desktop's VM) ran out of memory; \
+                 give Docker more memory or process smaller inputs"
+            .to_string(),
+    }
+}
+
+/// Append a run to the local history ledger; failures only warn
+#[allow(clippy::too_many_arguments)]
+async fn record_history(
+    client: &DockerClient,
+    prepared: &PreparedRun,
+    inputs: &HashMap<String, String>,
+    run: &RunArgs,
+    output_dirs: &[PathBuf],
+    input_files: &[InputFileRecord],
+    gpu: Option<&GpuConfig>,
+    started_at: chrono::DateTime<chrono::Utc>,
+    duration_secs: f64,
+    exit_code: Option<i64>,
+    oom_killed: bool,
+    container: Option<String>,
+    usage: Option<ResourceUsage>,
+) {
+    if run.no_history {
+        return;
+    }
+    let config_sha256 = match history::save_config_snapshot(&prepared.config) {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            tracing::warn!("Failed to store the run's worker config: {:#}", e);
+            None
+        }
+    };
+    let entry = HistoryEntry {
+        id: 0,
+        worker: prepared.worker_name.clone(),
+        worker_version: prepared.config.version.clone(),
+        image: prepared.image_tag.clone(),
+        image_id: client.image_id(&prepared.image_tag).await.ok(),
+        inputs: inputs.iter().map(|(k, v)| (k.clone(), v.clone())).collect(),
+        args: run.args.clone(),
+        env: run.env.iter().map(|arg| audit::redact_env(arg)).collect(),
+        env_files: run.env_file.iter().map(|p| p.display().to_string()).collect(),
+        dev: run.dev,
+        no_isolate: run.no_isolate,
+        user: run.user.clone(),
+        group_add: run.group_add.clone(),
+        entrypoint: run.entrypoint.clone(),
+        workdir: run.workdir.clone(),
+        labels: run.labels.clone(),
+        ulimits: run.ulimit.clone(),
+        cap_add: run.cap_add.clone(),
+        cap_drop: run.cap_drop.clone(),
+        devices: run.device.clone(),
+        read_only: run.read_only,
+        privileged: run.privileged,
+        mig: run.mig.clone(),
+        gpus: run.gpus.as_ref().map(ToString::to_string),
+        cwd: std::env::current_dir().map(|d| d.display().to_string()).unwrap_or_default(),
+        output_dirs: output_dirs.iter().map(|d| d.display().to_string()).collect(),
+        started_at: started_at.to_rfc3339(),
+        duration_secs,
+        exit_code,
+        oom_killed,
+        container,
+        usage,
+        invocation: audit::redact_args(&std::env::args().collect::<Vec<_>>()),
+        config_sha256,
+        image_digests: client.local_repo_digests(&prepared.image_tag).await.unwrap_or_default(),
+        input_files: input_files.to_vec(),
+        host: Some(host_info(client, gpu).await),
+    };
+    if let Err(e) = history::record(&entry) {
+        tracing::warn!("Failed to record run history: {:#}", e);
+    }
+}
+
+/// The host, Docker and GPU driver versions a run used
+async fn host_info(client: &DockerClient, gpu: Option<&GpuConfig>) -> HostInfo {
+    let info = client.system_info().await.ok();
+    let nvidia = gpu.is_some_and(|g| g.is_nvidia());
+    let versions = if nvidia {
+        tokio::task::spawn_blocking(gpu::nvidia_versions).await.ok().flatten()
     } else {
-        eprintln!("{} Failed with exit code {}", "✗".red().bold(), exit_code);
+        None
+    };
+    let os = info
+        .as_ref()
+        .map(|i| {
+            format!(
+                "{} (kernel {})",
+                i.operating_system.as_deref().unwrap_or(std::env::consts::OS),
+                i.kernel_version.as_deref().unwrap_or("unknown")
+            )
+        })
+        .unwrap_or_else(|| std::env::consts::OS.to_string());
+    HostInfo {
+        hostname: process::hostname(),
+        os,
+        arch: std::env::consts::ARCH.to_string(),
+        geoengine_version: env!("CARGO_PKG_VERSION").to_string(),
+        docker_version: info.and_then(|i| i.server_version),
+        nvidia_driver: versions.as_ref().map(|(driver, _)| driver.clone()),
+        cuda_version: versions.and_then(|(_, cuda)| cuda),
+        gpus: gpu.map(|g| g.devices.clone()).unwrap_or_default(),
     }
+}
 
-    if exit_code != 0 {
-        anyhow::bail!("Worker '{}' exited with code {}", worker_name, exit_code);
+fn audit_run_started(prepared: &PreparedRun, inputs: &HashMap<String, String>, container: Option<&str>) {
+    let mut details = std::collections::BTreeMap::new();
+    details.insert("image".to_string(), prepared.image_tag.clone().into());
+    details.insert("inputs".to_string(), serde_json::json!(inputs));
+    if let Some(container) = container {
+        details.insert("container".to_string(), container.into());
+    }
+    audit::log("run.started", Some(&prepared.worker_name), details);
+}
+
+fn audit_run_finished(prepared: &PreparedRun, exited: &Result<ContainerExit>, duration_secs: f64) {
+    let mut details = std::collections::BTreeMap::new();
+    details.insert("duration_secs".to_string(), serde_json::json!((duration_secs * 10.0).round() / 10.0));
+    match exited {
+        Ok(exit) => {
+            details.insert("exit_code".to_string(), exit.exit_code.into());
+            if exit.oom_killed {
+                details.insert("oom_killed".to_string(), true.into());
+            }
+        }
+        Err(e) => {
+            details.insert("error".to_string(), format!("{:#}", e).into());
+        }
+    }
+    audit::log("run.finished", Some(&prepared.worker_name), details);
+}
+
+/// Disk space required on one filesystem by a run
+struct DiskNeed {
+    path: PathBuf,
+    device: Option<u64>,
+    /// Bytes of inputs copied into the job workspace
+    staged: u64,
+    /// Whether outputs or container scratch land here
+    estimate: bool,
+    what: Vec<String>,
+}
+
+/// Fail before starting the container when the disks it writes to are too full.
+///
+/// Outputs and scratch are estimated as the size of the read-only inputs times `runtime.scratch_multiplier`
+/// and must fit on every writable folder input's filesystem and on the Docker data root
+/// (when it is local). Staged read-only inputs that can't be hard-linked must also fit in
+/// the job workspace. Needs on the same filesystem are added up.
+async fn preflight_disk_space(
+    client: &DockerClient,
+    config: &WorkerConfig,
+    inputs: &HashMap<String, String>,
+    isolate: bool,
+) -> Result<()> {
+    let definitions: HashMap<&str, &InputParameter> = config
+        .command
+        .iter()
+        .flat_map(|c| c.inputs.iter().flatten())
+        .map(|d| (d.name.as_str(), d))
+        .collect();
+
+    let jobs_dir = paths::get_jobs_dir()?;
+    let jobs_device = disk::device_id(&jobs_dir);
+    let mut input_bytes = 0u64;
+    let mut staged_bytes = 0u64;
+    let mut output_dirs: Vec<PathBuf> = Vec::new();
+    for (key, value) in inputs {
+        let Some(definition) = definitions.get(key.as_str()) else {
+            continue;
+        };
+        let param_type = definition.param_type.to_ascii_lowercase();
+        if param_type != "file" && param_type != "folder" {
+            continue;
+        }
+        let path = PathBuf::from(value);
+        let readonly = definition.readonly.unwrap_or(true);
+        if !readonly {
+            // What a writable input already holds isn't what the run reads
+            if param_type == "folder" {
+                output_dirs.push(path);
+            }
+            continue;
+        }
+        let mut size = disk::path_size(&path);
+        if param_type == "file" {
+            size += sidecars::resolve(&path).files.iter().map(|s| disk::path_size(s)).sum::<u64>();
+        }
+        input_bytes += size;
+
+        if isolate && disk::device_id(&path) != jobs_device {
+            // Staging copies inputs that live on another filesystem
+            staged_bytes += size;
+        }
+    }
+    if input_bytes == 0 {
+        return Ok(());
+    }
+
+    let multiplier = config
+        .runtime
+        .as_ref()
+        .and_then(|r| r.scratch_multiplier)
+        .unwrap_or(DEFAULT_SCRATCH_MULTIPLIER);
+    let estimate = (input_bytes as f64 * multiplier) as u64;
+
+    // Needs per filesystem: outputs and Docker scratch share one estimate,
+    // staged input copies come on top
+    let mut needs: Vec<DiskNeed> = Vec::new();
+    let mut add_need = |path: &Path, what: String, staged: u64| {
+        let device = disk::device_id(path);
+        let index = match needs.iter().position(|n| device.is_some() && n.device == device) {
+            Some(i) => i,
+            None => {
+                needs.push(DiskNeed { path: path.to_path_buf(), device, staged: 0, estimate: false, what: Vec::new() });
+                needs.len() - 1
+            }
+        };
+        let need = &mut needs[index];
+        need.staged += staged;
+        need.estimate |= staged == 0;
+        need.what.push(what);
+    };
+    if staged_bytes > 0 {
+        add_need(&jobs_dir, "staged inputs".to_string(), staged_bytes);
+    }
+    for dir in &output_dirs {
+        add_need(dir, format!("outputs in {}", dir.display()), 0);
+    }
+    // Docker Desktop keeps its data root inside a VM; only check it when it's on this host
+    if let Ok(Some(root)) = client.docker_root_dir().await {
+        if root.exists() {
+            add_need(&root, "container scratch".to_string(), 0);
+        }
+    }
+
+    for need in &needs {
+        let Some(available) = disk::available_space(&need.path) else {
+            continue;
+        };
+        let needed = need.staged + if need.estimate { estimate } else { 0 };
+        tracing::debug!("Preflight: {} needs {} bytes, {} available", need.path.display(), needed, available);
+        if needed > available {
+            anyhow::bail!(
+                "Not enough disk space on {}: about {} needed for {} ({} of inputs, scratch multiplier {}), \
+                 but only {} is free. Free up space, lower `runtime.scratch_multiplier`, or pass --no-preflight.",
+                need.path.display(),
+                format_size(needed as i64),
+                need.what.join(" and "),
+                format_size(input_bytes as i64),
+                multiplier,
+                format_size(available as i64)
+            );
+        }
     }
 
     Ok(())
 }
 
+/// Recursively collect files under `dir` modified at or after `since`, skipping
+/// GeoEngine's own provenance records
+fn collect_new_files(dir: &Path, since: std::time::SystemTime, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut entries: Vec<_> = entries.flatten().collect();
+    entries.sort_by_key(|e| e.file_name());
+    for entry in entries {
+        let path = entry.path();
+        match entry.file_type() {
+            Ok(t) if t.is_dir() => collect_new_files(&path, since, files),
+            Ok(t) if t.is_file() => {
+                if entry.file_name() == PROVENANCE_FILE {
+                    continue;
+                }
+                // Filesystem timestamps can be coarser than the clock; allow a second of slack
+                let modified = entry.metadata().and_then(|m| m.modified());
+                if modified.map(|m| m + std::time::Duration::from_secs(1) >= since).unwrap_or(false) {
+                    files.push(path);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
 // ---------------------------------------------------------------------------
 // geoengine describe
 // ---------------------------------------------------------------------------
@@ -954,6 +3507,8 @@ pub async fn describe_worker(worker: Option<&str>, json: bool) -> Result<()> {
                 default: i.default.clone(),
                 description: i.description.clone(),
                 enum_values: i.enum_values.clone(),
+                group: i.group.clone(),
+                visible_when: i.visible_when.clone(),
             }).collect()
         })
         .unwrap_or_default();
@@ -1119,7 +3674,7 @@ pub async fn describe_worker(worker: Option<&str>, json: bool) -> Result<()> {
             "-".repeat(enum_w),
         );
         // Print rows
-        for t in desc.inputs {
+        for t in &desc.inputs {
             let description = t.description.as_deref().unwrap_or("-");
             let default = t.default.clone().unwrap_or(serde_yaml::Value::from(""));
             let default_str = yaml_value_to_display_string(&default);
@@ -1145,6 +3700,32 @@ pub async fn describe_worker(worker: Option<&str>, json: bool) -> Result<()> {
                 enum_w = enum_w
             );
         }
+
+        // Dialog layout metadata, for inputs that have any
+        let mut groups: Vec<(&str, Vec<&str>)> = Vec::new();
+        for t in &desc.inputs {
+            if let Some(group) = t.group.as_deref() {
+                match groups.iter_mut().find(|(g, _)| *g == group) {
+                    Some((_, names)) => names.push(&t.name),
+                    None => groups.push((group, vec![&t.name])),
+                }
+            }
+        }
+        if !groups.is_empty() {
+            println!();
+            println!("{}", "GROUPS".bold());
+            for (group, names) in groups {
+                println!("  {} {}: {}", "•".cyan(), group, names.join(", "));
+            }
+        }
+        let conditional: Vec<&InputDescriptionJson> = desc.inputs.iter().filter(|t| t.visible_when.is_some()).collect();
+        if !conditional.is_empty() {
+            println!();
+            println!("{}", "CONDITIONS".bold());
+            for t in conditional {
+                println!("  {} {} shown when {}", "•".cyan(), t.name, t.visible_when.as_deref().unwrap_or_default());
+            }
+        }
         println!();
     }
     Ok(())
@@ -1154,9 +3735,13 @@ pub async fn describe_worker(worker: Option<&str>, json: bool) -> Result<()> {
 // geoengine workers
 // ---------------------------------------------------------------------------
 
-pub async fn list_workers(json: bool, gis: Option<String>) -> Result<()> {
+pub async fn list_workers(json: bool, gis: Option<String>, status: bool) -> Result<()> {
     let settings = Settings::load()?;
     let workers = settings.list_workers();
+    let mut statuses = match status {
+        true => Some(worker_statuses(&workers).await?),
+        false => None,
+    };
     let choice = match gis {
         Some(g) => {
             match g.as_str() {
@@ -1195,8 +3780,9 @@ pub async fn list_workers(json: bool, gis: Option<String>) -> Result<()> {
                 name: name.to_string(),
                 path: path.display().to_string(),
                 has_tool,
-                found: path.join("geoengine.yaml").exists(),
+                found: worker_config::has_config_file(path),
                 description,
+                status: statuses.as_mut().and_then(|s| s.remove(*name)),
             });
         }
         println!("{}", serde_json::to_string(&entries)?);
@@ -1212,6 +3798,11 @@ pub async fn list_workers(json: bool, gis: Option<String>) -> Result<()> {
         return Ok(());
     }
 
+    if let Some(statuses) = &statuses {
+        print_worker_statuses(&workers, statuses);
+        return Ok(());
+    }
+
     // 3 extra for tick/cross icon + space + separator
     let name_w = workers.iter().map(|(n, _)| n.len() + 3).max().unwrap_or(5).max(7);
     let found_w = 5; // "FOUND"
@@ -1235,7 +3826,7 @@ pub async fn list_workers(json: bool, gis: Option<String>) -> Result<()> {
         } else {
             "✗".red()
         };
-        let found = if path.join("geoengine.yaml").exists() {
+        let found = if worker_config::has_config_file(path) {
             "✓".green()
         } else {
             "✗".red()
@@ -1260,7 +3851,11 @@ pub async fn list_workers(json: bool, gis: Option<String>) -> Result<()> {
 ///   - `"command"`– check the command script file only
 ///
 /// If `target` is `None` the default is `"all"`.
-pub async fn diff_worker(target: Option<&str>) -> Result<()> {
+///
+/// With `against_image`, hashes are compared against the labels baked into the
+/// last built image instead of the saved state, and the Dockerfile's base images
+/// are checked for newer digests upstream.
+pub async fn diff_worker(target: Option<&str>, against_image: bool) -> Result<()> {
     let target = target.unwrap_or("all");
 
     // Validate target value
@@ -1274,18 +3869,57 @@ pub async fn diff_worker(target: Option<&str>) -> Result<()> {
 
     // Resolve worker from cwd
     let (resolved_name, cwd) = resolve_worker_from_cwd();
-    let config_path = cwd.join("geoengine.yaml");
+    let config_path = worker_config::config_file(&cwd);
     let config = WorkerConfig::load(&config_path)?;
     let worker_name = &resolved_name;
 
     let prev_state = state::load_state(worker_name)?;
-    if prev_state.is_none() {
-        anyhow::bail!(
-            "No saved state for worker '{}'. Run 'geoengine apply' first.",
-            worker_name
-        );
-    }
-    let prev = prev_state.unwrap();
+
+    // Baseline hashes: the saved state, or the labels of the last built image
+    let (client, image_ref, old_yaml, old_dockerfile, old_command) = if against_image {
+        let client = DockerClient::new().await?;
+        let image_ref = match prev_state.as_ref().and_then(|s| s.image_tag.clone()) {
+            Some(tag) => tag,
+            None => match get_latest_worker_version(worker_name, &client).await {
+                Some(v) => format!("geoengine-local/{}:{}", worker_name, v),
+                None => anyhow::bail!(
+                    "No built image found for worker '{}'. Run 'geoengine build' first.",
+                    worker_name
+                ),
+            },
+        };
+        let image_labels = client.image_labels(&image_ref).await?;
+        if !image_labels.contains_key(labels::CONFIG_HASH) {
+            println!(
+                "  {} Image {} has no build labels (built by an older geoengine); all files will show as changed.",
+                "⚠".yellow(),
+                image_ref.cyan()
+            );
+        }
+        let label = |key: &str| image_labels.get(key).cloned().unwrap_or_default();
+        (
+            Some(client),
+            Some(image_ref),
+            label(labels::CONFIG_HASH),
+            label(labels::DOCKERFILE_HASH),
+            label(labels::COMMAND_HASH),
+        )
+    } else {
+        let prev = match prev_state {
+            Some(prev) => prev,
+            None => anyhow::bail!(
+                "No saved state for worker '{}'. Run 'geoengine apply' first.",
+                worker_name
+            ),
+        };
+        (
+            None,
+            None,
+            prev.yaml_hash.unwrap_or_default(),
+            prev.dockerfile_hash.unwrap_or_default(),
+            prev.command_hash.unwrap_or_default(),
+        )
+    };
 
     // ── Collect results ────────────────────────────────────────────────
     struct DiffEntry {
@@ -1297,13 +3931,16 @@ pub async fn diff_worker(target: Option<&str>) -> Result<()> {
 
     let mut entries: Vec<DiffEntry> = Vec::new();
 
-    // YAML
+    // YAML (the image only records the build-relevant fields)
     if target == "all" || target == "yaml" {
-        let yaml_path = cwd.join("geoengine.yaml");
-        let old = prev.yaml_hash.clone().unwrap_or_default();
-        let new = state::compute_file_hash(&yaml_path)?;
+        let old = old_yaml.clone();
+        let new = if against_image {
+            config.build_relevant_hash()
+        } else {
+            state::compute_file_hash(&config_path)?
+        };
         entries.push(DiffEntry {
-            label: "geoengine.yaml".to_string(),
+            label: config_path.file_name().unwrap_or_default().to_string_lossy().to_string(),
             old_hash: old.clone(),
             new_hash: new.clone(),
             changed: old != new,
@@ -1314,7 +3951,7 @@ pub async fn diff_worker(target: Option<&str>) -> Result<()> {
     if target == "all" || target == "docker" {
         let df_path = cwd.join("Dockerfile");
         if df_path.exists() {
-            let old = prev.dockerfile_hash.clone().unwrap_or_default();
+            let old = old_dockerfile.clone();
             let new = state::compute_file_hash(&df_path)?;
             entries.push(DiffEntry {
                 label: "Dockerfile".to_string(),
@@ -1336,7 +3973,7 @@ pub async fn diff_worker(target: Option<&str>) -> Result<()> {
         if let Some(cmd) = &config.command {
             let script_path = cwd.join(&cmd.script);
             if script_path.exists() {
-                let old = prev.command_hash.clone().unwrap_or_default();
+                let old = old_command.clone();
                 let new = state::compute_file_hash(&script_path)?;
                 entries.push(DiffEntry {
                     label: format!("{} (command script)", cmd.script),
@@ -1361,14 +3998,17 @@ pub async fn diff_worker(target: Option<&str>) -> Result<()> {
     }
 
     // ── Pretty output ──────────────────────────────────────────────────
-    let any_changed = entries.iter().any(|e| e.changed);
+    let mut any_changed = entries.iter().any(|e| e.changed);
 
     println!();
     println!(
         "{} Diff for worker '{}' (target: {})",
         "=>".blue().bold(),
         worker_name.cyan(),
-        target.cyan()
+        match &image_ref {
+            Some(image) => format!("{}, against image {}", target, image).cyan(),
+            None => target.cyan(),
+        }
     );
     println!("{}", "─".repeat(60));
 
@@ -1392,7 +4032,9 @@ pub async fn diff_worker(target: Option<&str>) -> Result<()> {
                 "      {} {}{}",
                 "Run 'geoengine".yellow().italic(),
                 match entry.label.as_str() {
-                    "geoengine.yaml" => "apply".yellow().italic().bold(),
+                    label if worker_config::CONFIG_FILE_NAMES.contains(&label) && !against_image => {
+                        "apply".yellow().italic().bold()
+                    }
                     _ => "build".yellow().italic().bold()
                 },
                 "' to update.".yellow().italic()
@@ -1407,8 +4049,58 @@ pub async fn diff_worker(target: Option<&str>) -> Result<()> {
         }
     }
 
+    // Base images: compare the locally cached digest with the registry's
+    if let Some(client) = &client {
+        let dockerfile = cwd.join("Dockerfile");
+        if (target == "all" || target == "docker") && dockerfile.exists() {
+            for base in get_base_images(&dockerfile)? {
+                let local = client.local_repo_digests(&base).await.unwrap_or_default();
+                match client.remote_digest(&base).await {
+                    Ok(remote) if local.iter().any(|d| d.ends_with(&remote)) => {
+                        println!(
+                            "  {} {}  {}",
+                            "✓".green().bold(),
+                            base.cyan(),
+                            "base image up to date".green()
+                        );
+                    }
+                    Ok(remote) => {
+                        any_changed = true;
+                        println!(
+                            "  {} {}  {}",
+                            "✗".red().bold(),
+                            base.cyan(),
+                            "newer base image upstream".red()
+                        );
+                        println!(
+                            "      upstream: {}",
+                            short_hash(remote.trim_start_matches("sha256:")).yellow()
+                        );
+                        println!(
+                            "      {}",
+                            "Pull the base image and run 'geoengine build --no-cache' to update.".yellow().italic()
+                        );
+                    }
+                    Err(e) => {
+                        println!(
+                            "  {} {}  could not check upstream digest: {}",
+                            "⚠".yellow(),
+                            base.cyan(),
+                            e
+                        );
+                    }
+                }
+            }
+        }
+    }
+
     println!("{}", "─".repeat(60));
-    if any_changed {
+    if any_changed && against_image {
+        println!(
+            "{} Image is out of date. A rebuild is needed.",
+            "!".yellow().bold()
+        );
+    } else if any_changed {
         println!(
             "{} Changes detected",
             "!".yellow().bold()
@@ -1459,14 +4151,16 @@ fn resolve_worker_from_cwd() -> (String, PathBuf) {
 }
 
 /// Resolve a worker name to (name, path). If worker is None, use cwd path matching.
-fn resolve_worker(worker: Option<&str>) -> Result<(String, PathBuf)> {
+pub fn resolve_worker(worker: Option<&str>) -> Result<(String, PathBuf)> {
     if let Some(name) = worker {
         let settings = Settings::load()?;
         match settings.get_worker_path(name) {
             Ok(path) => Ok((name.to_string(), path)),
-            Err(_) => {
-                anyhow::bail!("Worker '{}' not found. Run 'geoengine apply' to register it.", name)
-            }
+            Err(_) => Err(GeoEngineError::WorkerNotFound(format!(
+                "Worker '{}' not found. Run 'geoengine apply' to register it.",
+                name
+            ))
+            .into()),
         }
     } else {
         let (name, path) = resolve_worker_from_cwd();
@@ -1484,6 +4178,40 @@ fn touch_qgis_refresh_trigger() {
     }
 }
 
+/// Set `plugins.<plugin_key>` in a worker config. TOML is edited in place so
+/// comments survive; YAML and JSON are re-serialized.
+fn set_plugin_flag(content: &mut String, format: ConfigFormat, plugin_key: &str, enabled: bool) -> Result<()> {
+    match format {
+        ConfigFormat::Yaml => set_plugin_flag_in_yaml(content, plugin_key, enabled),
+        ConfigFormat::Toml => {
+            let mut doc: toml_edit::DocumentMut = content
+                .parse()
+                .context("Failed to parse geoengine.toml while updating plugin status")?;
+            if !doc.contains_key("plugins") {
+                doc["plugins"] = toml_edit::table();
+            }
+            doc["plugins"][plugin_key] = toml_edit::value(enabled);
+            *content = doc.to_string();
+            Ok(())
+        }
+        ConfigFormat::Json => {
+            let mut value: serde_json::Value = serde_json::from_str(content)
+                .context("Failed to parse geoengine.json while updating plugin status")?;
+            let root = value
+                .as_object_mut()
+                .ok_or_else(|| anyhow::anyhow!("Expected top-level object in geoengine.json"))?;
+            let plugins = root
+                .entry("plugins")
+                .or_insert_with(|| serde_json::json!({}))
+                .as_object_mut()
+                .ok_or_else(|| anyhow::anyhow!("Expected 'plugins' to be an object in geoengine.json"))?;
+            plugins.insert(plugin_key.to_string(), serde_json::Value::Bool(enabled));
+            *content = serde_json::to_string_pretty(&value)? + "\n";
+            Ok(())
+        }
+    }
+}
+
 fn set_plugin_flag_in_yaml(yaml_content: &mut String, plugin_key: &str, enabled: bool) -> Result<()> {
     let mut yaml_value: serde_yaml::Value = serde_yaml::from_str(yaml_content)
         .context("Failed to parse geoengine.yaml while updating plugin status")?;
@@ -1506,6 +4234,124 @@ fn set_plugin_flag_in_yaml(yaml_content: &mut String, plugin_key: &str, enabled:
     Ok(())
 }
 
+/// Set the top-level `version` of geoengine.yaml, geoengine.toml or geoengine.json
+fn set_version_in_config(content: &str, version: &str, format: ConfigFormat) -> Result<String> {
+    match format {
+        ConfigFormat::Yaml => Ok(set_version_in_yaml(content, version)),
+        ConfigFormat::Toml => {
+            let mut doc: toml_edit::DocumentMut = content.parse().context("Failed to parse geoengine.toml")?;
+            doc["version"] = toml_edit::value(version);
+            Ok(doc.to_string())
+        }
+        ConfigFormat::Json => {
+            let mut value: serde_json::Value = serde_json::from_str(content).context("Failed to parse geoengine.json")?;
+            value["version"] = serde_json::Value::String(version.to_string());
+            Ok(serde_json::to_string_pretty(&value)? + "\n")
+        }
+    }
+}
+
+/// Replace the top-level `version:` line in geoengine.yaml, keeping the rest of
+/// the file (comments, ordering) untouched. Adds the key after `name:` if missing.
+fn set_version_in_yaml(content: &str, version: &str) -> String {
+    let version_line = format!("version: \"{}\"", version);
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    if let Some(line) = lines.iter_mut().find(|l| l.starts_with("version:")) {
+        *line = version_line;
+    } else {
+        let pos = lines
+            .iter()
+            .position(|l| l.starts_with("name:"))
+            .map(|i| i + 1)
+            .unwrap_or(0);
+        lines.insert(pos, version_line);
+    }
+
+    let mut updated = lines.join("\n");
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    updated
+}
+
+/// Load a JSON or YAML parameter file into KEY -> VALUE inputs. Nested values
+/// (lists, mappings) are passed to the worker as JSON strings; nulls are skipped.
+fn load_param_file(path: &Path, definitions: &[InputParameter]) -> Result<HashMap<String, String>> {
+    mapping_to_inputs(read_param_file(path)?, path, definitions)
+}
+
+fn read_param_file(path: &Path) -> Result<serde_yaml::Mapping> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read parameter file: {}", path.display()))?;
+    // YAML is a superset of JSON, so one parser handles both formats
+    serde_yaml::from_str(&content).with_context(|| format!("Failed to parse parameter file: {}", path.display()))
+}
+
+/// Checksums inputs must match: `{path, checksum}` entries of the parameter file,
+/// overridden by --checksum flags
+pub fn collect_checksums(run: &RunArgs, definitions: &[InputParameter]) -> Result<HashMap<String, String>> {
+    let mut checksums = HashMap::new();
+    if let Some(path) = &run.param_file {
+        for (key, value) in read_param_file(path)? {
+            let Some(key) = key.as_str().filter(|key| is_path_input(definitions, key)) else {
+                continue;
+            };
+            if let Some(checksum) = value.get("checksum").and_then(|c| c.as_str()) {
+                checksums.insert(key.to_string(), checksum.to_string());
+            }
+        }
+    }
+    for arg in &run.checksums {
+        let (key, checksum) = arg.split_once('=').ok_or_else(|| {
+            GeoEngineError::InputInvalid(format!("Invalid checksum format: '{}'. Expected KEY=CHECKSUM", arg))
+        })?;
+        checksums.insert(key.to_string(), checksum.to_string());
+    }
+    Ok(checksums)
+}
+
+/// Convert a KEY: VALUE mapping read from `source` into run inputs
+pub fn mapping_to_inputs(
+    params: serde_yaml::Mapping,
+    source: &Path,
+    definitions: &[InputParameter],
+) -> Result<HashMap<String, String>> {
+    let mut inputs = HashMap::new();
+    for (key, value) in params {
+        let key = key
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Parameter names must be strings in {}", source.display()))?;
+        if value.is_null() {
+            continue;
+        }
+        // `{path: ..., checksum: ...}` carries a checksum alongside a file or folder input
+        let value = match value.get("path") {
+            Some(path) if value.is_mapping() && is_path_input(definitions, key) => path.clone(),
+            _ => value,
+        };
+        inputs.insert(key.to_string(), yaml_value_to_display_string(&value));
+    }
+    Ok(inputs)
+}
+
+/// Whether `name` is a declared file or folder input
+fn is_path_input(definitions: &[InputParameter], name: &str) -> bool {
+    definitions
+        .iter()
+        .any(|d| d.name == name && matches!(d.param_type.to_ascii_lowercase().as_str(), "file" | "folder"))
+}
+
+/// Resolve input values starting with ./ against the worker directory, as in local_dir_mounts
+pub fn resolve_worker_relative(inputs: &mut HashMap<String, String>, worker_path: &Path) {
+    for value in inputs.values_mut() {
+        let resolved = host::resolve_host_path(value, worker_path);
+        if resolved.as_os_str() != value.as_str() {
+            *value = resolved.display().to_string();
+        }
+    }
+}
+
 fn yaml_value_to_display_string(value: &serde_yaml::Value) -> String {
     match value {
         serde_yaml::Value::Null => String::new(),
@@ -1517,7 +4363,7 @@ fn yaml_value_to_display_string(value: &serde_yaml::Value) -> String {
 }
 
 /// Shell-escape a string for safe inclusion in a shell command
-fn shell_escape(s: &str) -> String {
+pub fn shell_escape(s: &str) -> String {
     if s.chars().any(|c| " \t\n\"'\\$`!*?[]{}();<>&|".contains(c)) {
         format!("'{}'", s.replace('\'', "'\\''"))
     } else {
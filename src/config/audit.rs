@@ -0,0 +1,356 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::settings::{AuditSettings, Settings};
+use crate::utils::{paths, process};
+
+/// Directory under ~/.geoengine holding the current log and its rotated files
+const AUDIT_DIR: &str = "audit";
+const AUDIT_FILE: &str = "audit.jsonl";
+
+/// How much of the end of a log is read to find its last record
+const TAIL_BYTES: u64 = 64 * 1024;
+
+/// One audited event. Records are only ever appended; with `hash_chain` each one
+/// carries the hash of its predecessor, so edited or removed records are detectable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub time: String,
+    /// e.g. run.started, run.finished, worker.apply, auth.failed
+    pub event: String,
+    /// user@host the event came from
+    pub actor: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub worker: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<String, serde_json::Value>,
+    /// Hash of the previous record
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prev: Option<String>,
+    /// SHA-256 of this record's JSON without the `hash` field
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+}
+
+impl AuditRecord {
+    fn compute_hash(&self) -> Result<String> {
+        let unhashed = AuditRecord {
+            hash: None,
+            ..self.clone()
+        };
+        let json = serde_json::to_string(&unhashed).context("Failed to serialize audit record")?;
+        Ok(format!("{:x}", Sha256::digest(json.as_bytes())))
+    }
+}
+
+/// Stands in for the value of an `--env KEY=VALUE` flag in recorded command lines
+pub const REDACTED: &str = "<redacted>";
+
+/// `--env KEY=VALUE` as `--env KEY=<redacted>`: environment variables often carry
+/// tokens and passwords, which don't belong in logs kept on disk
+pub fn redact_env(arg: &str) -> String {
+    match arg.split_once('=') {
+        Some((key, _)) => format!("{}={}", key, REDACTED),
+        None => arg.to_string(),
+    }
+}
+
+/// A command line with the values of its `-e`/`--env` flags redacted
+pub fn redact_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut value_next = false;
+    for arg in args {
+        if value_next {
+            redacted.push(redact_env(arg));
+            value_next = false;
+        } else if arg == "--" {
+            // Arguments for the worker's script follow
+            redacted.extend(args[redacted.len()..].iter().cloned());
+            break;
+        } else if arg == "-e" || arg == "--env" {
+            redacted.push(arg.clone());
+            value_next = true;
+        } else if let Some(value) = arg.strip_prefix("--env=") {
+            redacted.push(format!("--env={}", redact_env(value)));
+        } else if let Some(value) = arg.strip_prefix("-e").filter(|v| !v.is_empty() && !arg.starts_with("--")) {
+            redacted.push(format!("-e{}", redact_env(value)));
+        } else {
+            redacted.push(arg.clone());
+        }
+    }
+    redacted
+}
+
+/// The `audit` settings, if audit logging is turned on
+pub fn settings() -> Option<AuditSettings> {
+    Settings::load().ok()?.audit
+}
+
+/// Append an event when audit logging is on. A log that can't be written is
+/// reported but doesn't fail the command that caused the event.
+pub fn log(event: &str, worker: Option<&str>, details: BTreeMap<String, serde_json::Value>) {
+    let Some(settings) = settings() else {
+        return;
+    };
+    if let Err(e) = append(&settings, event, worker, details) {
+        eprintln!("{} Failed to write the audit log: {:#}", "!".yellow().bold(), e);
+    }
+}
+
+fn append(
+    settings: &AuditSettings,
+    event: &str,
+    worker: Option<&str>,
+    details: BTreeMap<String, serde_json::Value>,
+) -> Result<()> {
+    let dir = audit_dir()?;
+    // Held until the record is written, so concurrent commands keep the chain linear
+    let _lock = process::lock_file(&dir.join(".lock")).context("Failed to lock the audit log")?;
+
+    let current = dir.join(AUDIT_FILE);
+    let prev = if settings.hash_chain {
+        chain_head(&dir, &current)?
+    } else {
+        None
+    };
+    if let Some(max_mb) = settings.max_size_mb {
+        let size = std::fs::metadata(&current).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size >= max_mb.saturating_mul(1024 * 1024) {
+            rotate(&current)?;
+        }
+    }
+    if let Some(days) = settings.retention_days {
+        expire(&dir, days)?;
+    }
+
+    let mut record = AuditRecord {
+        time: chrono::Utc::now().to_rfc3339(),
+        event: event.to_string(),
+        actor: actor(),
+        worker: worker.map(str::to_string),
+        details,
+        prev,
+        hash: None,
+    };
+    if settings.hash_chain {
+        record.hash = Some(record.compute_hash()?);
+    }
+    write_record(&current, &record)
+}
+
+fn write_record(path: &Path, record: &AuditRecord) -> Result<()> {
+    let mut line = serde_json::to_string(record).context("Failed to serialize audit record")?;
+    line.push('\n');
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Hash the next record links to. When the newest record can't be read (a crash
+/// mid-write leaves a truncated line), the current log is rotated aside as it is,
+/// so `audit verify` still reports the damage, and a new chain is started with an
+/// `audit.chain_broken` record.
+fn chain_head(dir: &Path, current: &Path) -> Result<Option<String>> {
+    let error = match last_record(dir) {
+        Ok(record) => return Ok(record.and_then(|r| r.hash)),
+        Err(e) => e,
+    };
+    eprintln!(
+        "{} {:#}; starting a new audit chain",
+        "!".yellow().bold(),
+        error
+    );
+    if std::fs::metadata(current).is_ok_and(|m| m.len() > 0) {
+        rotate(current)?;
+    }
+    let mut record = AuditRecord {
+        time: chrono::Utc::now().to_rfc3339(),
+        event: "audit.chain_broken".to_string(),
+        actor: actor(),
+        worker: None,
+        details: BTreeMap::from([("reason".to_string(), serde_json::json!(format!("{:#}", error)))]),
+        prev: None,
+        hash: None,
+    };
+    let hash = record.compute_hash()?;
+    record.hash = Some(hash.clone());
+    write_record(current, &record)?;
+    Ok(Some(hash))
+}
+
+fn audit_dir() -> Result<PathBuf> {
+    let dir = paths::get_config_dir()?.join(AUDIT_DIR);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn actor() -> String {
+    let user = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string());
+    format!("{}@{}", user, process::hostname())
+}
+
+/// Log files oldest first: rotated files (named by rotation time), then the current one
+pub fn log_files() -> Result<Vec<PathBuf>> {
+    let dir = audit_dir()?;
+    let mut files: Vec<PathBuf> = rotated_files(&dir)?;
+    let current = dir.join(AUDIT_FILE);
+    if current.exists() {
+        files.push(current);
+    }
+    Ok(files)
+}
+
+fn rotated_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("Failed to read {}", dir.display()))?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with("audit-") && n.ends_with(".jsonl"))
+        })
+        .collect();
+    files.sort();
+    Ok(files)
+}
+
+/// The newest record, looking into the last rotated file when the current one is empty
+fn last_record(dir: &Path) -> Result<Option<AuditRecord>> {
+    let mut files = rotated_files(dir)?;
+    files.push(dir.join(AUDIT_FILE));
+    for path in files.iter().rev() {
+        let Ok(mut file) = File::open(path) else {
+            continue;
+        };
+        let len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(len.saturating_sub(TAIL_BYTES)))?;
+        let mut tail = Vec::new();
+        file.read_to_end(&mut tail)?;
+        let tail = String::from_utf8_lossy(&tail);
+        if let Some(line) = tail.lines().rev().find(|l| !l.trim().is_empty()) {
+            let record = serde_json::from_str(line)
+                .with_context(|| format!("Failed to parse the last record of {}", path.display()))?;
+            return Ok(Some(record));
+        }
+    }
+    Ok(None)
+}
+
+/// Move the current log aside as audit-<time>.jsonl and make it read-only
+fn rotate(current: &Path) -> Result<()> {
+    let rotated = current.with_file_name(format!(
+        "audit-{}.jsonl",
+        chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    ));
+    std::fs::rename(current, &rotated)
+        .with_context(|| format!("Failed to rotate {}", current.display()))?;
+    let mut permissions = std::fs::metadata(&rotated)?.permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(&rotated, permissions)?;
+    Ok(())
+}
+
+/// Delete rotated files last written more than `days` days ago
+fn expire(dir: &Path, days: u64) -> Result<()> {
+    let max_age = std::time::Duration::from_secs(days * 24 * 60 * 60);
+    for path in rotated_files(dir)? {
+        let age = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.elapsed().ok());
+        if age.is_some_and(|age| age > max_age) {
+            let mut permissions = std::fs::metadata(&path)?.permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+            std::fs::set_permissions(&path, permissions).ok();
+            std::fs::remove_file(&path).with_context(|| format!("Failed to remove {}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Load every readable record, oldest first
+pub fn load() -> Result<Vec<AuditRecord>> {
+    let mut records = Vec::new();
+    for path in log_files()? {
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        for (i, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            // Damaged lines are reported by `verify`
+            match serde_json::from_str(line) {
+                Ok(record) => records.push(record),
+                Err(e) => tracing::warn!("{}:{}: not an audit record ({})", path.display(), i + 1, e),
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Result of checking the hash chain
+pub struct ChainReport {
+    pub records: usize,
+    /// Records written while hash chaining was off
+    pub unchained: usize,
+    /// One message per broken link or altered record
+    pub failures: Vec<String>,
+}
+
+/// Recompute every record's hash and check that it links to the record before it.
+/// The oldest remaining record's link isn't checked, since retention may have
+/// deleted its predecessor.
+pub fn verify() -> Result<ChainReport> {
+    let mut report = ChainReport {
+        records: 0,
+        unchained: 0,
+        failures: Vec::new(),
+    };
+    let mut previous: Option<Option<String>> = None;
+    for path in log_files()? {
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        let content =
+            std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+        for (i, line) in content.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            report.records += 1;
+            let at = format!("{}:{}", name, i + 1);
+            let record: AuditRecord = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(e) => {
+                    report.failures.push(format!("{}: not an audit record ({})", at, e));
+                    previous = Some(None);
+                    continue;
+                }
+            };
+            match &record.hash {
+                None => report.unchained += 1,
+                Some(hash) => {
+                    if *hash != record.compute_hash()? {
+                        report.failures.push(format!("{}: record was modified", at));
+                    }
+                    if let Some(Some(expected)) = &previous {
+                        if record.prev.as_ref() != Some(expected) {
+                            report
+                                .failures
+                                .push(format!("{}: previous record is missing or was replaced", at));
+                        }
+                    }
+                }
+            }
+            previous = Some(record.hash.clone());
+        }
+    }
+    Ok(report)
+}
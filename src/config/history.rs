@@ -0,0 +1,201 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::config::provenance::InputFileRecord;
+use crate::config::state;
+use crate::config::worker::WorkerConfig;
+use crate::docker::usage::ResourceUsage;
+use crate::utils::paths;
+
+/// One line per run; an entry's ID is its line number, so appends never need coordination
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// A recorded `geoengine run`, with everything needed to run it again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Position in the ledger (1-based); assigned when loading
+    #[serde(default, skip_deserializing, skip_serializing_if = "is_unassigned")]
+    pub id: usize,
+    pub worker: String,
+    pub worker_version: Option<String>,
+    pub image: String,
+    /// Local image ID at the time of the run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_id: Option<String>,
+    /// Input parameters after --param-file and --input were merged
+    pub inputs: BTreeMap<String, String>,
+    /// Extra arguments passed after `--`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// `--env` flags, with their values redacted
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub env_files: Vec<String>,
+    #[serde(default)]
+    pub dev: bool,
+    #[serde(default)]
+    pub no_isolate: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub group_add: Vec<String>,
+    /// Docker options passed to `geoengine run`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entrypoint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub workdir: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub labels: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ulimits: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cap_add: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cap_drop: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<String>,
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub privileged: bool,
+    /// MIG profile requested with --mig
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mig: Option<String>,
+    /// GPU request given with --gpus
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpus: Option<String>,
+    /// Directory the run was started from; relative input paths resolve against it
+    pub cwd: String,
+    /// Writable folder inputs
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub output_dirs: Vec<String>,
+    pub started_at: String,
+    pub duration_secs: f64,
+    /// None for detached runs
+    pub exit_code: Option<i64>,
+    /// The container was killed for exceeding its memory limit
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub oom_killed: bool,
+    /// Container name of detached runs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+    /// CPU, memory and GPU use sampled during the run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<ResourceUsage>,
+    /// geoengine command line that started the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub invocation: Vec<String>,
+    /// SHA-256 of the worker configuration snapshot the run used (see `config_snapshot`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config_sha256: Option<String>,
+    /// Registry digests of the image at the time of the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub image_digests: Vec<String>,
+    /// Hashes of file inputs, taken for runs with an output folder
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input_files: Vec<InputFileRecord>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<HostInfo>,
+}
+
+/// The machine a run executed on
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HostInfo {
+    pub hostname: String,
+    /// Operating system and kernel as the Docker daemon reports them
+    pub os: String,
+    pub arch: String,
+    pub geoengine_version: String,
+    pub docker_version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nvidia_driver: Option<String>,
+    /// Highest CUDA version the driver supports
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cuda_version: Option<String>,
+    /// GPUs handed to the container
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub gpus: Vec<String>,
+}
+
+fn is_unassigned(id: &usize) -> bool {
+    *id == 0
+}
+
+fn history_file() -> Result<PathBuf> {
+    Ok(paths::get_config_dir()?.join(HISTORY_FILE))
+}
+
+/// Append an entry to the ledger
+pub fn record(entry: &HistoryEntry) -> Result<()> {
+    let path = history_file()?;
+    let mut line = serde_json::to_string(entry).context("Failed to serialize history entry")?;
+    line.push('\n');
+    // One write per entry keeps lines intact when runs finish concurrently
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    file.write_all(line.as_bytes())
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(())
+}
+
+/// Load all entries, oldest first. Lines that can't be parsed keep their ID but are skipped.
+pub fn load() -> Result<Vec<HistoryEntry>> {
+    let path = history_file()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(content
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let mut entry: HistoryEntry = serde_json::from_str(line).ok()?;
+            entry.id = i + 1;
+            Some(entry)
+        })
+        .collect())
+}
+
+/// Directory of the worker configurations runs used, stored once per content
+fn snapshots_dir() -> Result<PathBuf> {
+    let dir = paths::get_config_dir()?.join("history").join("configs");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Store the resolved worker configuration of a run as YAML, unless an identical
+/// one is stored already, and return its SHA-256
+pub fn save_config_snapshot(config: &WorkerConfig) -> Result<String> {
+    let yaml = serde_yaml::to_string(config).context("Failed to serialize worker config")?;
+    let hash = state::sha256_string(&yaml);
+    let path = snapshots_dir()?.join(format!("{}.yaml", hash));
+    if !path.exists() {
+        std::fs::write(&path, &yaml).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(hash)
+}
+
+/// The worker configuration stored by `save_config_snapshot`, if it still exists
+pub fn config_snapshot(hash: &str) -> Result<Option<String>> {
+    let path = snapshots_dir()?.join(format!("{}.yaml", hash));
+    if !path.exists() {
+        return Ok(None);
+    }
+    Ok(Some(std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?))
+}
+
+/// Load the entry with the given ID
+pub fn get(id: usize) -> Result<HistoryEntry> {
+    load()?
+        .into_iter()
+        .find(|e| e.id == id)
+        .ok_or_else(|| anyhow::anyhow!("No run with ID {} in the history", id))
+}
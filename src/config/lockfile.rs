@@ -0,0 +1,47 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+/// Written next to geoengine.yaml by `geoengine build`
+pub const LOCK_FILE: &str = "geoengine.lock";
+
+const HEADER: &str = "# Base image digests resolved by `geoengine build`. Commit this file and\n\
+                      # build with --locked to fail when a base image changes.\n";
+
+/// Digests the Dockerfile's base images resolved to at the last build
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Lockfile {
+    /// Base image as written in `FROM` -> `sha256:` digest (image ID for images
+    /// that were built locally and never pulled)
+    #[serde(default)]
+    pub base_images: BTreeMap<String, String>,
+    /// Base images locked by image ID, which exist only locally and can't be pulled
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub local_images: BTreeSet<String>,
+}
+
+impl Lockfile {
+    pub fn path(worker_path: &Path) -> PathBuf {
+        worker_path.join(LOCK_FILE)
+    }
+
+    /// The worker's lockfile, or None if it has none yet
+    pub fn load(worker_path: &Path) -> Result<Option<Self>> {
+        let path = Self::path(worker_path);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let lock = serde_yaml::from_str(&content)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        Ok(Some(lock))
+    }
+
+    pub fn save(&self, worker_path: &Path) -> Result<()> {
+        let path = Self::path(worker_path);
+        let content = format!("{}{}", HEADER, serde_yaml::to_string(self)?);
+        std::fs::write(&path, content).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
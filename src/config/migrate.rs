@@ -0,0 +1,101 @@
+use anyhow::{Context, Result};
+use serde_yaml::{Mapping, Value};
+
+use crate::config::worker::ConfigFormat;
+
+/// Schema version of the worker config this build writes and understands
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Rewrites a config from `from` to `from + 1`
+struct Migration {
+    from: u32,
+    description: &'static str,
+    /// None when the step only bumps the version and the format is unchanged
+    apply: Option<fn(&mut Mapping) -> Result<()>>,
+}
+
+/// Every schema change, oldest first. Configs without `schema_version` are version 0.
+/// A migration must accept anything its source version accepted, so old configs
+/// keep loading (with a warning) until they are upgraded.
+const MIGRATIONS: &[Migration] = &[Migration {
+    from: 0,
+    description: "record schema_version (the 0 -> 1 schema is otherwise unchanged)",
+    apply: None,
+}];
+
+/// Whether a config of `version` has to be rewritten to be read as the current
+/// schema. Versions only behind by steps that don't change the format are read as is.
+pub fn needs_migration(version: u32) -> bool {
+    version > SCHEMA_VERSION || MIGRATIONS.iter().any(|m| m.from >= version && m.apply.is_some())
+}
+
+/// Schema version declared by a parsed config (0 when it has none)
+pub fn schema_version(config: &Value) -> Result<u32> {
+    match config.get("schema_version") {
+        None | Some(Value::Null) => Ok(0),
+        Some(value) => value
+            .as_u64()
+            .and_then(|v| u32::try_from(v).ok())
+            .context("schema_version must be a whole number"),
+    }
+}
+
+/// Migrate a parsed config in place to `SCHEMA_VERSION`. Returns the applied steps.
+pub fn upgrade(config: &mut Value) -> Result<Vec<&'static str>> {
+    let version = schema_version(config)?;
+    if version > SCHEMA_VERSION {
+        anyhow::bail!(
+            "schema_version {} is newer than this GeoEngine supports ({}); upgrade geoengine",
+            version,
+            SCHEMA_VERSION
+        );
+    }
+    let mapping = config.as_mapping_mut().context("The config must be a mapping")?;
+
+    let mut applied = Vec::new();
+    for migration in MIGRATIONS.iter().filter(|m| m.from >= version) {
+        if let Some(apply) = migration.apply {
+            apply(mapping).with_context(|| {
+                format!("Failed to migrate from schema {}: {}", migration.from, migration.description)
+            })?;
+        }
+        applied.push(migration.description);
+    }
+
+    // schema_version goes first, like in configs created by `geoengine init`
+    mapping.remove("schema_version");
+    let mut upgraded = Mapping::new();
+    upgraded.insert("schema_version".into(), SCHEMA_VERSION.into());
+    upgraded.extend(std::mem::take(mapping));
+    *mapping = upgraded;
+
+    Ok(applied)
+}
+
+/// Set `schema_version` in the text of a YAML or TOML config, keeping everything
+/// else (comments, key order) as written. The line goes before the first
+/// top-level key, like in configs created by `geoengine init`. None for JSON.
+pub fn set_version_line(content: &str, format: ConfigFormat) -> Option<String> {
+    let line = match format {
+        ConfigFormat::Yaml => format!("schema_version: {}", SCHEMA_VERSION),
+        ConfigFormat::Toml => format!("schema_version = {}", SCHEMA_VERSION),
+        ConfigFormat::Json => return None,
+    };
+    let is_version = |l: &str| {
+        l.strip_prefix("schema_version")
+            .is_some_and(|rest| rest.trim_start().starts_with([':', '=']))
+    };
+    let mut lines: Vec<&str> = content.lines().collect();
+    if let Some(existing) = lines.iter().position(|l| is_version(l)) {
+        lines[existing] = &line;
+    } else {
+        let first_key = lines
+            .iter()
+            .position(|l| !l.is_empty() && !l.starts_with(['#', ' ', '\t', '-', '%']))
+            .unwrap_or(lines.len());
+        lines.insert(first_key, &line);
+    }
+    let mut patched = lines.join("\n");
+    patched.push('\n');
+    Some(patched)
+}
@@ -1,4 +1,11 @@
+pub mod audit;
+pub mod history;
+pub mod lockfile;
+pub mod migrate;
+pub mod pins;
+pub mod provenance;
 pub mod settings;
 pub mod state;
+pub mod visibility;
 pub mod worker;
 pub mod yaml_store;
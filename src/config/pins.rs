@@ -0,0 +1,77 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::utils::paths;
+
+/// An image `image remove` and `image prune` leave alone
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinnedImage {
+    /// Reference as pinned: a tag such as geoengine-local/ndvi:1.2.0, or an image ID
+    pub reference: String,
+    pub pinned_at: String,
+}
+
+impl PinnedImage {
+    /// Whether the pin covers the image with this ID and tags. A pinned tag
+    /// covers whichever image it points to now.
+    pub fn matches(&self, id: &str, tags: &[String]) -> bool {
+        if tags.contains(&self.reference) {
+            return true;
+        }
+        let short = self.reference.strip_prefix("sha256:").unwrap_or(&self.reference);
+        let id = id.strip_prefix("sha256:").unwrap_or(id);
+        short.len() >= 12 && id.starts_with(short)
+    }
+}
+
+/// ~/.geoengine/pinned-images.json
+fn pins_file() -> Result<PathBuf> {
+    Ok(paths::get_config_dir()?.join("pinned-images.json"))
+}
+
+pub fn load() -> Result<Vec<PinnedImage>> {
+    let path = pins_file()?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+fn save(pins: &[PinnedImage]) -> Result<()> {
+    let path = pins_file()?;
+    std::fs::write(&path, serde_json::to_string_pretty(pins)?).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Pin a reference; returns false if it already was
+pub fn pin(reference: &str) -> Result<bool> {
+    let mut pins = load()?;
+    if pins.iter().any(|p| p.reference == reference) {
+        return Ok(false);
+    }
+    pins.push(PinnedImage {
+        reference: reference.to_string(),
+        pinned_at: chrono::Utc::now().to_rfc3339(),
+    });
+    save(&pins)?;
+    Ok(true)
+}
+
+/// Remove the pins covering the image with this ID and tags, or pinned exactly
+/// as `reference`; returns the references removed
+pub fn unpin(reference: &str, id: Option<&str>, tags: &[String]) -> Result<Vec<String>> {
+    let pins = load()?;
+    let (removed, kept): (Vec<_>, Vec<_>) = pins
+        .into_iter()
+        .partition(|p| p.reference == reference || id.is_some_and(|id| p.matches(id, tags)));
+    if !removed.is_empty() {
+        save(&kept)?;
+    }
+    Ok(removed.into_iter().map(|p| p.reference).collect())
+}
+
+/// The pin covering the image with this ID and tags, if any
+pub fn find<'a>(pins: &'a [PinnedImage], id: &str, tags: &[String]) -> Option<&'a PinnedImage> {
+    pins.iter().find(|p| p.matches(id, tags))
+}
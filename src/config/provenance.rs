@@ -0,0 +1,69 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// File name of the provenance record written to each output folder
+pub const PROVENANCE_FILE: &str = "geoengine-provenance.json";
+
+/// Record of a single worker run, written next to its outputs so published
+/// datasets can be traced back to the exact image, parameters and inputs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Provenance {
+    pub geoengine_version: String,
+    pub worker: String,
+    pub worker_version: Option<String>,
+    /// Image tag the run used
+    pub image: String,
+    /// Local image ID (sha256 of the image config)
+    pub image_id: Option<String>,
+    /// Registry digests of the image, if it was pushed or pulled
+    pub image_digests: Vec<String>,
+    /// Command executed inside the container
+    pub command: Vec<String>,
+    /// Input parameters as given on the command line / parameter file
+    pub parameters: BTreeMap<String, String>,
+    /// Hashes of file inputs taken before the run
+    pub input_files: Vec<InputFileRecord>,
+    /// Folder inputs checked against a checksum manifest before the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub verified_folders: Vec<VerifiedFolderRecord>,
+    pub exit_code: i64,
+    pub started_at: String,
+    pub finished_at: String,
+    pub duration_secs: f64,
+}
+
+/// Hash of a single file input
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InputFileRecord {
+    pub name: String,
+    pub path: String,
+    pub sha256: String,
+    /// The hash matched a checksum given with the input
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub verified: bool,
+}
+
+/// A folder input whose files matched a checksum manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifiedFolderRecord {
+    pub name: String,
+    pub path: String,
+    /// SHA-256 of the manifest file
+    pub manifest_sha256: String,
+    /// Number of files the manifest lists
+    pub files: usize,
+}
+
+impl Provenance {
+    /// Write the record as `geoengine-provenance.json` in the given directory
+    pub fn write_to(&self, dir: &Path) -> Result<PathBuf> {
+        let path = dir.join(PROVENANCE_FILE);
+        let json = serde_json::to_string_pretty(self)
+            .context("Failed to serialize provenance record")?;
+        std::fs::write(&path, json)
+            .with_context(|| format!("Failed to write provenance record: {}", path.display()))?;
+        Ok(path)
+    }
+}
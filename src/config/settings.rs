@@ -1,8 +1,10 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
 
+use crate::config::worker::{MountConfig, VolumeConfig};
+use crate::error::GeoEngineError;
 use crate::utils::paths;
 
 /// Global GeoEngine settings stored in ~/.geoengine/settings.yaml
@@ -18,6 +20,184 @@ pub struct Settings {
     /// Default GCP region
     pub gcp_region: Option<String>,
 
+    /// Image signing and verification settings (cosign)
+    pub signing: Option<SigningSettings>,
+
+    /// Named deployment environments used by `deploy promote` (e.g. dev, staging, prod)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub environments: HashMap<String, EnvironmentSettings>,
+
+    /// OpenTelemetry trace export
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub telemetry: Option<TelemetrySettings>,
+
+    /// GitHub Container Registry defaults for `deploy --provider ghcr`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ghcr: Option<GhcrSettings>,
+
+    /// AWS defaults for `deploy aws-batch`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aws: Option<AwsSettings>,
+
+    /// GDAL image used to inspect run outputs (defaults to the official slim GDAL image)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gdal_image: Option<String>,
+
+    /// TiTiler image serving raster tiles for `geoengine preview`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub titiler_image: Option<String>,
+
+    /// Mirrors, rewrites and credentials used when pulling base images
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registries: Option<RegistrySettings>,
+
+    /// Docker context to connect to (as listed by `docker context ls`); `DOCKER_HOST` takes precedence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub docker_context: Option<String>,
+
+    /// Added to the host port of every service's published ports, so profiles
+    /// can run the same services side by side
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_port_offset: Option<u16>,
+
+    /// Default for writable folder inputs a run leaves unset, for workers without
+    /// `outputs.dir` (same placeholders, relative to the worker directory)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<String>,
+
+    /// Append-only audit log of runs and administrative commands (off unless set)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audit: Option<AuditSettings>,
+
+    /// Extension commands (name -> executable), in addition to geoengine-<name> programs on PATH
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, PathBuf>,
+
+    /// Request NVIDIA GPUs by CDI device name (`nvidia.com/gpu=...`) instead of through
+    /// the nvidia runtime, for Podman and Docker 25+ with CDI enabled
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub gpu_cdi: bool,
+
+    /// Host directories and volumes mounted into every run, unless the worker
+    /// sets `runtime.default_mounts: false`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub default_mounts: Vec<DefaultMount>,
+}
+
+/// An entry of `default_mounts`: a host directory (`host_path`, `container_path`)
+/// or a named volume or tmpfs (`type`, `target`), as in geoengine.yaml
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DefaultMount {
+    Bind(MountConfig),
+    Volume(VolumeConfig),
+}
+
+/// Where and how long audit records are kept in ~/.geoengine/audit
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AuditSettings {
+    /// Link each record to the previous one by SHA-256 (`geoengine audit verify` checks the chain)
+    #[serde(default)]
+    pub hash_chain: bool,
+
+    /// Start a new file once the log reaches this size in MB; rotated files are made read-only
+    pub max_size_mb: Option<u64>,
+
+    /// Delete rotated files older than this many days
+    pub retention_days: Option<u64>,
+}
+
+/// How images are pulled on networks that can't reach public registries directly
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RegistrySettings {
+    /// Docker Hub mirrors tried in order before Docker Hub itself, e.g. mirror.corp:5000
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mirrors: Vec<String>,
+
+    /// Reference prefixes to replace, e.g. docker.io -> artifactory.corp/docker-remote.
+    /// A rewritten image is only pulled from its new location.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rewrites: BTreeMap<String, String>,
+
+    /// Credentials per registry host
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub credentials: HashMap<String, RegistryCredential>,
+}
+
+/// Login for one registry. `password_env` takes precedence over `password`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RegistryCredential {
+    pub username: Option<String>,
+
+    pub password: Option<String>,
+
+    /// Environment variable holding the password or token
+    pub password_env: Option<String>,
+}
+
+/// GitHub Container Registry account. `GHCR_TOKEN`/`GITHUB_TOKEN` take precedence over `token`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct GhcrSettings {
+    /// GitHub user or organization that owns the packages
+    pub owner: Option<String>,
+
+    /// Personal access token with read:packages/write:packages
+    pub token: Option<String>,
+}
+
+/// AWS account defaults. `AWS_REGION` and the command-line flags take precedence.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AwsSettings {
+    /// Region of the ECR registry and AWS Batch compute environment, e.g. us-east-1
+    pub region: Option<String>,
+
+    /// AWS Batch job queue jobs are submitted to
+    pub job_queue: Option<String>,
+
+    /// ECR repository prefix; worker images are pushed to <repository>/<worker>
+    pub repository: Option<String>,
+
+    /// IAM role assumed by job containers (e.g. for S3 access)
+    pub job_role_arn: Option<String>,
+}
+
+/// Where to send OpenTelemetry traces. `OTEL_EXPORTER_OTLP_ENDPOINT` and
+/// `OTEL_SERVICE_NAME` take precedence over these values.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TelemetrySettings {
+    /// OTLP/HTTP collector base URL, e.g. http://localhost:4318
+    pub otlp_endpoint: Option<String>,
+
+    /// Service name reported with every span (defaults to "geoengine")
+    pub service_name: Option<String>,
+}
+
+/// A registry repository images can be promoted into
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvironmentSettings {
+    /// Full repository path, e.g. us-central1-docker.pkg.dev/my-project/geoengine-prod
+    pub registry: String,
+
+    /// Refuse to promote images into this environment without a valid signature
+    #[serde(default)]
+    pub require_signature: bool,
+}
+
+/// Expected signer identity used to verify images with cosign
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct SigningSettings {
+    /// Path to the cosign public key used for key-based verification
+    pub public_key: Option<PathBuf>,
+
+    /// Expected certificate identity for keyless verification (e.g. an email)
+    pub identity: Option<String>,
+
+    /// Expected OIDC issuer for keyless verification
+    pub oidc_issuer: Option<String>,
+
+    /// Require a valid signature on `deploy pull` and `image import`
+    #[serde(default)]
+    pub enforce: bool,
 }
 
 impl Settings {
@@ -42,15 +222,18 @@ impl Settings {
 
     /// Save settings to disk
     pub fn save(&self) -> Result<()> {
-        let settings_path = paths::get_settings_file()?;
+        self.save_to(&paths::get_settings_file()?)
+    }
 
+    /// Save settings to a specific file, e.g. a new profile
+    pub fn save_to(&self, settings_path: &Path) -> Result<()> {
         // Ensure directory exists
         if let Some(parent) = settings_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
         let content = serde_yaml::to_string(self)?;
-        std::fs::write(&settings_path, content)?;
+        std::fs::write(settings_path, content)?;
 
         Ok(())
     }
@@ -77,7 +260,7 @@ impl Settings {
     /// Unregister a worker
     pub fn unregister_worker(&mut self, name: &str) -> Result<()> {
         if self.workers.remove(name).is_none() {
-            anyhow::bail!("Worker '{}' is not registered", name);
+            return Err(GeoEngineError::WorkerNotFound(format!("Worker '{}' is not registered", name)).into());
         }
         Ok(())
     }
@@ -91,14 +274,28 @@ impl Settings {
 
         // Check if it's a path
         let path = PathBuf::from(name);
-        if path.exists() && path.join("geoengine.yaml").exists() {
+        if path.exists() && crate::config::worker::has_config_file(&path) {
             return Ok(path.canonicalize()?);
         }
 
-        anyhow::bail!(
+        Err(GeoEngineError::WorkerNotFound(format!(
             "Worker '{}' not found. Run 'geoengine apply' to register it.",
             name
-        )
+        ))
+        .into())
+    }
+
+    /// Get a deployment environment by name
+    pub fn get_environment(&self, name: &str) -> Result<&EnvironmentSettings> {
+        self.environments.get(name).with_context(|| {
+            let mut known: Vec<&str> = self.environments.keys().map(|k| k.as_str()).collect();
+            known.sort();
+            format!(
+                "Environment '{}' is not defined in ~/.geoengine/settings.yaml (known: {})",
+                name,
+                if known.is_empty() { "none".to_string() } else { known.join(", ") }
+            )
+        })
     }
 
     /// List all registered workers
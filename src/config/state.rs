@@ -91,11 +91,14 @@ pub fn rename_state(old_name: &str, new_name: &str) -> Result<()> {
     Ok(())
 }
 
-/// Compute SHA-256 hash of a file's contents
+/// Compute SHA-256 hash of a file's contents (streamed, so large rasters are not loaded into memory)
 pub fn compute_file_hash(path: &Path) -> Result<String> {
-    let content = std::fs::read(path)
+    let mut file = std::fs::File::open(path)
         .with_context(|| format!("Failed to read file for hashing: {}", path.display()))?;
-    Ok(sha256_bytes(&content))
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)
+        .with_context(|| format!("Failed to read file for hashing: {}", path.display()))?;
+    Ok(format!("{:x}", hasher.finalize()))
 }
 
 /// Compute SHA-256 hash of a byte slice
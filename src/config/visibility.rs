@@ -0,0 +1,55 @@
+use anyhow::Result;
+
+/// One comparison of a `visible_when` expression, e.g. `method=ml|dl`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    /// Input whose value is compared
+    pub input: String,
+    /// Values that satisfy the comparison (any of them)
+    pub values: Vec<String>,
+    /// `!=`: satisfied when the input has none of the values
+    pub negated: bool,
+}
+
+/// Parse a `visible_when` expression: comparisons joined with `&&`, each
+/// `input=value`, `input==value` or `input!=value`. `|` separates alternative
+/// values, and values may be quoted.
+pub fn parse(expr: &str) -> Result<Vec<Condition>> {
+    let mut conditions = Vec::new();
+    for part in expr.split("&&") {
+        let part = part.trim();
+        let (input, negated, value) = if let Some((input, value)) = part.split_once("!=") {
+            (input, true, value)
+        } else if let Some((input, value)) = part.split_once("==") {
+            (input, false, value)
+        } else if let Some((input, value)) = part.split_once('=') {
+            (input, false, value)
+        } else {
+            anyhow::bail!("'{}' is not a comparison; expected INPUT=VALUE or INPUT!=VALUE", part);
+        };
+
+        let input = input.trim();
+        if input.is_empty() || !input.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            anyhow::bail!("'{}' is not a valid input name in '{}'", input, part);
+        }
+        let values: Vec<String> = value.split('|').map(|v| unquote(v.trim()).to_string()).collect();
+        if values.iter().any(String::is_empty) {
+            anyhow::bail!("Missing value in '{}'", part);
+        }
+        conditions.push(Condition {
+            input: input.to_string(),
+            values,
+            negated,
+        });
+    }
+    Ok(conditions)
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
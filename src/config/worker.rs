@@ -1,11 +1,94 @@
 use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-use crate::config::state;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use colored::Colorize;
+use crate::config::migrate::{self, SCHEMA_VERSION};
+use crate::config::{state, visibility};
+use crate::error::GeoEngineError;
 
-/// Worker configuration loaded from geoengine.yaml
+/// `--strict`: refuse configs with an outdated schema_version instead of warning
+static STRICT: AtomicBool = AtomicBool::new(false);
+
+/// Whether the outdated-schema warning was already shown by this process
+static WARNED_OUTDATED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_strict(strict: bool) {
+    STRICT.store(strict, Ordering::Relaxed);
+}
+
+/// File names a worker config may have, in the order they are looked up
+pub const CONFIG_FILE_NAMES: [&str; 4] = ["geoengine.yaml", "geoengine.yml", "geoengine.toml", "geoengine.json"];
+
+/// Config file of the worker in `dir`: the first of `CONFIG_FILE_NAMES` that
+/// exists, or geoengine.yaml when there is none yet
+pub fn config_file(dir: &Path) -> PathBuf {
+    CONFIG_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| dir.join(CONFIG_FILE_NAMES[0]))
+}
+
+/// Whether `dir` contains a worker config in any supported format
+pub fn has_config_file(dir: &Path) -> bool {
+    config_file(dir).is_file()
+}
+
+/// On-disk format of a worker config, chosen by file extension
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    #[default]
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// Format of a config file; anything but .toml and .json is read as YAML
+    pub fn of(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()).map(str::to_ascii_lowercase).as_deref() {
+            Some("toml") => ConfigFormat::Toml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+
+    /// Config file name written by `geoengine init`
+    pub fn file_name(self) -> &'static str {
+        match self {
+            ConfigFormat::Yaml => "geoengine.yaml",
+            ConfigFormat::Toml => "geoengine.toml",
+            ConfigFormat::Json => "geoengine.json",
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(self, content: &str) -> Result<T> {
+        Ok(match self {
+            ConfigFormat::Yaml => serde_yaml::from_str(content)?,
+            ConfigFormat::Toml => toml::from_str(content)?,
+            ConfigFormat::Json => serde_json::from_str(content)?,
+        })
+    }
+
+    pub fn serialize<T: Serialize>(self, value: &T) -> Result<String> {
+        Ok(match self {
+            ConfigFormat::Yaml => serde_yaml::to_string(value)?,
+            ConfigFormat::Toml => toml::to_string_pretty(value)?,
+            ConfigFormat::Json => serde_json::to_string_pretty(value)? + "\n",
+        })
+    }
+}
+
+/// Worker configuration loaded from geoengine.yaml (or geoengine.toml / geoengine.json)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WorkerConfig {
+    /// Version of the config schema; configs without it are version 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schema_version: Option<u32>,
+
     /// Worker name (required)
     pub name: String,
 
@@ -21,11 +104,39 @@ pub struct WorkerConfig {
     /// Local directory mounts
     pub local_dir_mounts: Option<Vec<MountConfig>>,
 
+    /// Where `geoengine build` takes the Docker build context from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub build: Option<BuildConfig>,
+
     /// GIS plugin registration
     pub plugins: Option<PluginsConfig>,
 
     /// Deployment configuration
     pub deploy: Option<DeployConfig>,
+
+    /// Container runtime settings
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub runtime: Option<RuntimeConfig>,
+
+    /// Post-processing of the files a run produces
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<OutputsConfig>,
+
+    /// Long-running entries started with `geoengine up` and stopped with `geoengine down`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub services: Option<BTreeMap<String, ServiceConfig>>,
+
+    /// Test cases run with `geoengine test`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tests: Option<Vec<TestCase>>,
+
+    /// Datasets downloaded with `geoengine data fetch` and mounted as `data://<name>`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<BTreeMap<String, DatasetConfig>>,
+
+    /// Commands run before and after each run of the worker's command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hooks: Option<HooksConfig>,
 }
 
 /// Command configuration defining the entrypoint and input parameters
@@ -39,6 +150,11 @@ pub struct CommandConfig {
 
     /// Input parameter definitions
     pub inputs: Option<Vec<InputParameter>>,
+
+    /// Shell command `geoengine build --check` runs in the new image
+    /// (defaults to `<program> <script> --help`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub check: Option<String>,
 }
 
 /// Input parameter definition for a worker command
@@ -68,6 +184,14 @@ pub struct InputParameter {
     /// Mark as readonly (only for types folder and file, defaults to true)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub readonly: Option<bool>,
+
+    /// Section the input is shown under in GIS dialogs, e.g. "Advanced"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+
+    /// Show the input only when other inputs have certain values, e.g. `method=ml`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub visible_when: Option<String>,
 }
 
 /// Volume mount configuration
@@ -93,6 +217,48 @@ pub struct PluginsConfig {
     pub qgis: Option<bool>,
 }
 
+/// Docker build context of the worker image
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BuildConfig {
+    /// Local directory (relative to the worker directory), git URL
+    /// (`https://host/org/repo.git#ref:subdir`) or tarball URL; defaults to the worker directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+
+    /// Dockerfile path within the context (defaults to Dockerfile)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<String>,
+
+    /// Stage of a multi-stage Dockerfile to build (defaults to the last one)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    /// Named image variants built from the same context, e.g. cpu and gpu
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub variants: Option<BTreeMap<String, BuildVariant>>,
+}
+
+/// One image variant of a worker, tagged `<version>-<variant>`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct BuildVariant {
+    /// Dockerfile path within the context (defaults to build.dockerfile)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dockerfile: Option<String>,
+
+    /// Stage of a multi-stage Dockerfile to build (defaults to build.target)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+
+    /// Build arguments of this variant; `--build-arg` overrides them
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<BTreeMap<String, String>>,
+
+    /// Hardware the variant is for (cuda, mps or cpu); `run` picks the variant
+    /// matching the detected accelerator
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accelerator: Option<Accelerator>,
+}
+
 /// Deployment configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeployConfig {
@@ -100,39 +266,628 @@ pub struct DeployConfig {
     pub tenant_id: Option<String>,
 }
 
+/// A file the worker needs at run time, downloaded once into the data directory
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DatasetConfig {
+    /// http(s)://, s3:// or gs:// URL
+    pub url: String,
+
+    /// `sha256:<hex>` of the downloaded file, checked before unpacking
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checksum: Option<String>,
+
+    /// How to unpack the download (defaults to auto: by file extension)
+    #[serde(default, skip_serializing_if = "is_auto_unpack")]
+    pub unpack: Unpack,
+}
+
+/// Dataset names become directories next to each other, so `.`, `..` and hidden
+/// names (which would clash with the `.<name>.part` downloads) are refused
+pub fn check_dataset_name(name: &str) -> Result<()> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.';
+    if name.is_empty() || name.starts_with('.') || !name.chars().all(allowed) {
+        return Err(GeoEngineError::ConfigInvalid(format!(
+            "Invalid dataset name '{}': use letters, digits, '-', '_' and '.', not starting with '.'",
+            name
+        ))
+        .into());
+    }
+    Ok(())
+}
+
+/// Unpacking of a downloaded dataset
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Unpack {
+    /// `.zip` with unzip, `.tar`, `.tar.gz`, `.tgz`, `.tar.bz2` and `.tar.xz` with tar, anything else kept as is
+    #[default]
+    Auto,
+    /// Keep the file as downloaded
+    None,
+    Tar,
+    Zip,
+}
+
+fn is_auto_unpack(unpack: &Unpack) -> bool {
+    *unpack == Unpack::Auto
+}
+
+/// A long-running container of the worker's command (e.g. an inference server)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ServiceConfig {
+    /// Input parameters passed to the command, as for `geoengine run --input`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<serde_yaml::Mapping>,
+
+    /// Extra arguments appended to the command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+
+    /// Environment variables for this service; override `runtime.env`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<BTreeMap<String, String>>,
+
+    /// Published ports as [IP:]HOST_PORT:CONTAINER_PORT[/PROTOCOL]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ports: Option<Vec<String>>,
+
+    /// Docker restart policy (defaults to unless-stopped)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub restart: Option<String>,
+}
+
+/// Hooks around `geoengine run`, each run in order
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HooksConfig {
+    /// Before the container starts; a failing hook aborts the run
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre_run: Vec<HookConfig>,
+
+    /// After the container exits, whether the run succeeded or not
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post_run: Vec<HookConfig>,
+
+    /// After a run that failed, before the post_run hooks
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_failure: Vec<HookConfig>,
+}
+
+/// One hook: a shell command on the host or in the worker image (exactly one of them)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HookConfig {
+    /// Shell command run on the host, in the worker directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run: Option<String>,
+
+    /// Shell command run in a container of the worker image, with the run's mounts and environment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+}
+
+/// A run of the worker's command with expected results, checked by `geoengine test`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TestCase {
+    /// Test name (unique within the worker)
+    pub name: String,
+
+    /// Input parameters; paths starting with ./ are relative to the worker directory.
+    /// Writable folder inputs left out get a fresh directory for each test.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub inputs: Option<serde_yaml::Mapping>,
+
+    /// Extra arguments appended to the command
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub args: Option<Vec<String>>,
+
+    /// Environment variables for this test; override `runtime.env`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<BTreeMap<String, String>>,
+
+    /// Expected exit code (defaults to 0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i64>,
+
+    /// Files the run must produce
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outputs: Option<Vec<OutputAssertion>>,
+}
+
+/// A file expected in a writable folder input after a test run
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OutputAssertion {
+    /// `<folder input>/<relative path>`, e.g. output_folder/result.tif
+    pub path: String,
+
+    /// Fail if the file is empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub non_empty: Option<bool>,
+
+    /// Expected SHA-256 of the file (hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+}
+
+/// Post-processing of run outputs
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OutputsConfig {
+    /// Inspect output files with gdalinfo/ogrinfo and report CRS, extent and layout
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geo_metadata: Option<bool>,
+
+    /// Write a STAC collection and items for georeferenced outputs (implies geo_metadata)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stac: Option<bool>,
+
+    /// Checks the files a successful run wrote must pass; the run fails otherwise
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub validate: Option<Vec<OutputRule>>,
+
+    /// Directory for writable folder inputs a run leaves unset, relative to the worker
+    /// directory; `{worker}`, `{version}`, `{input}` and `{timestamp}` are filled in
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dir: Option<String>,
+}
+
+/// Assertions on the output files of a run that exited with code 0
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct OutputRule {
+    /// `<folder input>/<relative path>`; `*` and `?` match within a path segment,
+    /// e.g. output_folder/*.tif. Only files written by the run count.
+    pub path: String,
+
+    /// Fail if the run wrote no matching file (defaults to true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub must_exist: Option<bool>,
+
+    /// Minimum size of every matching file, in bytes or with a k/m/g suffix
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_size: Option<String>,
+
+    /// GDAL/OGR driver every matching file must open with, e.g. GTiff or GPKG
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+
+    /// Expected CRS, e.g. EPSG:4326
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crs: Option<String>,
+
+    /// Expected raster band count
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub band_count: Option<usize>,
+}
+
+impl OutputRule {
+    /// Minimum file size in bytes, parsed from `min_size`
+    pub fn min_size_bytes(&self) -> Result<Option<i64>> {
+        let Some(size) = &self.min_size else {
+            return Ok(None);
+        };
+        parse_size(size)
+            .map(Some)
+            .with_context(|| format!("Invalid min_size '{}' for {}", size, self.path))
+    }
+
+    /// Whether checking the rule needs gdalinfo/ogrinfo
+    pub fn needs_geo_metadata(&self) -> bool {
+        self.format.is_some() || self.crs.is_some() || self.band_count.is_some()
+    }
+}
+
+/// Container runtime settings applied by `geoengine run`
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct RuntimeConfig {
+    /// Accelerator to use: auto (default), cuda, mps, or cpu
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub accelerator: Option<Accelerator>,
+
+    /// Dotenv files loaded into the container environment (relative to the worker directory)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env_files: Option<Vec<String>>,
+
+    /// Environment variables set in the container; override values from `env_files`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<BTreeMap<String, String>>,
+
+    /// Dependencies that must be ready before the worker's container starts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_for: Option<Vec<WaitTarget>>,
+
+    /// Named Docker volumes and tmpfs mounts
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volumes: Option<Vec<VolumeConfig>>,
+
+    /// Container user as USER[:GROUP] (name or ID); `image` keeps the image's USER.
+    /// Defaults to the invoking user's UID:GID on Linux
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+
+    /// Supplementary groups for the container user (names or GIDs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_add: Option<Vec<String>>,
+
+    /// Disk space a run needs for outputs and scratch, as a multiple of its input size
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scratch_multiplier: Option<f64>,
+
+    /// Run on a free MIG slice with this profile (e.g. 1g.10gb) instead of whole GPUs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mig_profile: Option<String>,
+
+    /// Memory limit of the container (e.g. 512m, 8g); the kernel kills it when exceeded
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memory: Option<String>,
+
+    /// Free GPU memory a run needs (e.g. 12g); only GPUs with that much free are used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpu_memory: Option<String>,
+
+    /// Number of CPUs the container may use (e.g. 1.5)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpus: Option<f64>,
+
+    /// Hardening options (seccomp, AppArmor, capabilities, ...)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub security: Option<SecurityConfig>,
+
+    /// Add the `default_mounts` from the settings to every run (default true)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default_mounts: Option<bool>,
+}
+
+/// Container hardening applied to every run of the worker
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SecurityConfig {
+    /// Seccomp profile: a JSON file relative to the worker directory, or `unconfined`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seccomp_profile: Option<String>,
+
+    /// AppArmor profile loaded on the host, or `unconfined`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub apparmor_profile: Option<String>,
+
+    /// Keep processes from gaining privileges through setuid binaries or file capabilities
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_new_privileges: Option<bool>,
+
+    /// Mount the container's root filesystem read-only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub read_only_rootfs: Option<bool>,
+
+    /// Linux capabilities to drop, or ALL
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cap_drop: Option<Vec<String>>,
+}
+
+/// A named Docker volume or tmpfs mounted into the worker's container
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeConfig {
+    /// `volume` or `tmpfs`
+    #[serde(rename = "type")]
+    pub kind: VolumeKind,
+
+    /// Volume name (type: volume); created on first use if missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// Mount path inside the container
+    pub target: String,
+
+    /// Volume driver (type: volume, defaults to local)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver: Option<String>,
+
+    /// Driver options used when creating the volume (type: volume)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub driver_opts: Option<BTreeMap<String, String>>,
+
+    /// Size limit, e.g. 512m or 2g (type: tmpfs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<String>,
+
+    /// Mount read-only (type: volume)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub readonly: Option<bool>,
+}
+
+/// Kind of non-bind mount
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeKind {
+    Volume,
+    Tmpfs,
+}
+
+impl VolumeConfig {
+    /// tmpfs size in bytes, parsed from `size` (plain bytes or a k/m/g suffix)
+    pub fn size_bytes(&self) -> Result<Option<i64>> {
+        let Some(size) = &self.size else {
+            return Ok(None);
+        };
+        parse_size(size)
+            .map(Some)
+            .with_context(|| format!("Invalid tmpfs size '{}' for {}", size, self.target))
+    }
+}
+
+impl RuntimeConfig {
+    /// Container memory limit in bytes, parsed from `memory`
+    pub fn memory_bytes(&self) -> Result<Option<i64>> {
+        let Some(memory) = &self.memory else {
+            return Ok(None);
+        };
+        parse_size(memory)
+            .map(Some)
+            .with_context(|| format!("Invalid runtime.memory '{}'", memory))
+    }
+
+    /// Required free GPU memory in bytes, parsed from `gpu_memory`
+    pub fn gpu_memory_bytes(&self) -> Result<Option<i64>> {
+        let Some(memory) = &self.gpu_memory else {
+            return Ok(None);
+        };
+        parse_size(memory)
+            .map(Some)
+            .with_context(|| format!("Invalid runtime.gpu_memory '{}'", memory))
+    }
+}
+
+/// Parse a size given in bytes or with a k/m/g suffix (binary units), e.g. 512m or 2g
+fn parse_size(size: &str) -> Result<i64> {
+    let size = size.trim().to_ascii_lowercase();
+    let size = size.trim_end_matches('b');
+    let (digits, multiplier) = match size.chars().last() {
+        Some('k') => (&size[..size.len() - 1], 1024),
+        Some('m') => (&size[..size.len() - 1], 1024 * 1024),
+        Some('g') => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+        _ => (size, 1),
+    };
+    let value: i64 = digits.trim().parse()?;
+    Ok(value * multiplier)
+}
+
+/// A readiness check run from the host before `geoengine run` starts the container.
+/// Exactly one of `url`, `tcp` or `container` must be set.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaitTarget {
+    /// HTTP(S) URL that must answer with a success status
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+
+    /// host:port that must accept TCP connections
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tcp: Option<String>,
+
+    /// Docker container that must be running (and healthy, if it has a healthcheck)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub container: Option<String>,
+
+    /// Seconds to wait before giving up (default 60)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timeout: Option<u64>,
+}
+
+/// Accelerator selection for a worker's container
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Accelerator {
+    /// Use whatever GPU is detected on the host
+    #[default]
+    Auto,
+    /// Require an NVIDIA GPU
+    Cuda,
+    /// Apple Metal (PyTorch MPS backend)
+    Mps,
+    /// Never request a GPU
+    Cpu,
+}
+
 impl WorkerConfig {
-    /// Load worker configuration from a YAML file
+    /// Accelerator selected in the runtime section (defaults to auto)
+    pub fn accelerator(&self) -> Accelerator {
+        self.runtime
+            .as_ref()
+            .and_then(|r| r.accelerator)
+            .unwrap_or_default()
+    }
+
+    /// The inputs declared by the command
+    pub fn input_definitions(&self) -> &[InputParameter] {
+        self.command.as_ref().and_then(|c| c.inputs.as_deref()).unwrap_or_default()
+    }
+
+    /// Load worker configuration from a YAML, TOML or JSON file (by extension)
     pub fn load(path: &Path) -> Result<Self> {
         let content = std::fs::read_to_string(path)
             .with_context(|| format!("Failed to read config file: {}", path.display()))?;
 
-        let config: WorkerConfig = serde_yaml::from_str(&content)
-            .with_context(|| format!("Failed to parse config file: {}", path.display()))?;
+        let parse_error = || GeoEngineError::ConfigInvalid(format!("Failed to parse config file: {}", path.display()));
+        let format = ConfigFormat::of(path);
+        let mut value: serde_yaml::Value = format.parse(&content).with_context(parse_error)?;
+        let version = migrate::schema_version(&value).with_context(parse_error)?;
+
+        let config: WorkerConfig = if !migrate::needs_migration(version) {
+            // Parsed from the text again so errors point at a line
+            format.parse(&content).with_context(parse_error)?
+        } else {
+            if version < SCHEMA_VERSION {
+                let message = format!(
+                    "{} uses config schema {}, the current one is {}. Run `geoengine upgrade` to migrate it.",
+                    path.display(),
+                    version,
+                    SCHEMA_VERSION
+                );
+                if STRICT.load(Ordering::Relaxed) {
+                    return Err(GeoEngineError::ConfigInvalid(message).into());
+                }
+                if !WARNED_OUTDATED.swap(true, Ordering::Relaxed) {
+                    eprintln!("{} {}", "!".yellow().bold(), message);
+                }
+            }
+            migrate::upgrade(&mut value).with_context(parse_error)?;
+            serde_yaml::from_value(value).with_context(parse_error)?
+        };
+        config
+            .validate_inputs()
+            .and_then(|_| config.validate_output_rules())
+            .and_then(|_| config.validate_build_variants())
+            .and_then(|_| config.validate_hooks())
+            .and_then(|_| config.validate_tests())
+            .and_then(|_| config.validate_data())
+            .with_context(|| GeoEngineError::ConfigInvalid(format!("Invalid config file: {}", path.display())))?;
 
         Ok(config)
     }
 
+    /// Check the `group` and `visible_when` fields of the command inputs
+    pub fn validate_inputs(&self) -> Result<()> {
+        let inputs = self.command.iter().flat_map(|c| c.inputs.iter().flatten());
+        for input in inputs.clone() {
+            if input.group.as_deref().is_some_and(|g| g.trim().is_empty()) {
+                anyhow::bail!("Input '{}' has an empty group", input.name);
+            }
+            let Some(expr) = &input.visible_when else {
+                continue;
+            };
+            let conditions = visibility::parse(expr)
+                .with_context(|| format!("Invalid visible_when of input '{}'", input.name))?;
+            if input.required.unwrap_or(true) && input.default.is_none() {
+                anyhow::bail!(
+                    "Input '{}' is required but can be hidden by visible_when; make it optional or give it a default",
+                    input.name
+                );
+            }
+            for condition in conditions {
+                if condition.input == input.name {
+                    anyhow::bail!("visible_when of input '{}' refers to the input itself", input.name);
+                }
+                let Some(target) = inputs.clone().find(|i| i.name == condition.input) else {
+                    anyhow::bail!(
+                        "visible_when of input '{}' refers to unknown input '{}'",
+                        input.name,
+                        condition.input
+                    );
+                };
+                let allowed: Vec<String> = match target.param_type.to_ascii_lowercase().as_str() {
+                    "enum" => target.enum_values.clone().unwrap_or_default(),
+                    "boolean" => vec!["true".to_string(), "false".to_string()],
+                    _ => continue,
+                };
+                if let Some(value) = condition.values.iter().find(|v| !allowed.contains(v)) {
+                    anyhow::bail!(
+                        "visible_when of input '{}' compares '{}' with '{}', which is not one of: {}",
+                        input.name,
+                        condition.input,
+                        value,
+                        allowed.join(", ")
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that `outputs.validate` rules point into folder inputs and have valid sizes
+    pub fn validate_output_rules(&self) -> Result<()> {
+        let rules = self.outputs.iter().flat_map(|o| o.validate.iter().flatten());
+        for rule in rules {
+            let Some((input, _)) = rule.path.split_once('/') else {
+                anyhow::bail!("Output rule '{}' must be <folder input>/<path>", rule.path);
+            };
+            let is_folder = self
+                .command
+                .iter()
+                .flat_map(|c| c.inputs.iter().flatten())
+                .any(|i| i.name == input && i.param_type == "folder");
+            if !is_folder {
+                anyhow::bail!("Output rule '{}' refers to '{}', which is not a folder input", rule.path, input);
+            }
+            rule.min_size_bytes()?;
+        }
+        Ok(())
+    }
+
+    /// Check that variant names can be used in image tags
+    pub fn validate_build_variants(&self) -> Result<()> {
+        let variants = self.build.iter().flat_map(|b| b.variants.iter().flatten());
+        for (name, variant) in variants {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.') {
+                anyhow::bail!("Invalid build variant '{}': use letters, digits, '_' and '.'", name);
+            }
+            if variant.accelerator == Some(Accelerator::Auto) {
+                anyhow::bail!("Build variant '{}' has accelerator 'auto'; use cuda, mps or cpu", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that every hook has exactly one of `run` and `container`
+    pub fn validate_hooks(&self) -> Result<()> {
+        let Some(hooks) = &self.hooks else {
+            return Ok(());
+        };
+        let stages = [("pre_run", &hooks.pre_run), ("post_run", &hooks.post_run), ("on_failure", &hooks.on_failure)];
+        for (stage, list) in stages {
+            for (i, hook) in list.iter().enumerate() {
+                if hook.run.is_some() == hook.container.is_some() {
+                    anyhow::bail!("hooks.{}[{}] must set exactly one of 'run' and 'container'", stage, i);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that test names are unique and usable as a directory name, since
+    /// `geoengine test` keeps each test's outputs under ~/.geoengine/tests/<worker>/<name>
+    pub fn validate_tests(&self) -> Result<()> {
+        let tests = self.tests.iter().flatten();
+        let mut seen = std::collections::HashSet::new();
+        for test in tests {
+            let name = test.name.as_str();
+            if matches!(name, "" | "." | "..") || name.contains(['/', '\\', '\0']) {
+                anyhow::bail!("Invalid test name '{}': use a name without path separators", name);
+            }
+            if !seen.insert(name) {
+                anyhow::bail!("Duplicate test name '{}'", name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that dataset names are usable as a directory name under ~/.geoengine/data/<worker>
+    pub fn validate_data(&self) -> Result<()> {
+        for name in self.data.iter().flat_map(|d| d.keys()) {
+            check_dataset_name(name)?;
+        }
+        Ok(())
+    }
+
     /// Compute a SHA-256 hash of only the build-relevant fields:
     /// name, version, command, and local_dir_mounts.
     /// This excludes description, plugins, and deploy which don't affect the Docker image.
     pub fn build_relevant_hash(&self) -> String {
-        let build_fields = serde_json::json!({
+        let mut build_fields = serde_json::json!({
             "name": self.name,
             "command": self.command.as_ref().map(|c| serde_json::to_value(c).unwrap_or_default()),
             "local_dir_mounts": self.local_dir_mounts.as_ref().map(|m| serde_json::to_value(m).unwrap_or_default()),
         });
+        // Only added when set, so hashes of existing workers stay the same
+        if let Some(build) = &self.build {
+            build_fields["build"] = serde_json::to_value(build).unwrap_or_default();
+        }
         state::sha256_string(&build_fields.to_string())
     }
 
     /// Create a template configuration for a new worker
     pub fn template(name: &str) -> Self {
         WorkerConfig {
+            schema_version: Some(SCHEMA_VERSION),
             name: name.to_string(),
             version: Some("1.0".to_string()),
             description: Some("A geoengine worker".to_string()),
             command: Some(CommandConfig {
                 program: "python".to_string(),
                 script: "main.py".to_string(),
+                check: None,
                 inputs: Some(vec![
                     InputParameter {
                         name: "input_file".to_string(),
@@ -142,6 +897,8 @@ impl WorkerConfig {
                         description: Some("Input file to process".to_string()),
                         enum_values: None,
                         readonly: Some(true),
+                        group: None,
+                        visible_when: None,
                     },
                     InputParameter {
                         name: "output_folder".to_string(),
@@ -151,6 +908,8 @@ impl WorkerConfig {
                         description: Some("Output folder for results".to_string()),
                         enum_values: None,
                         readonly: Some(false),
+                        group: None,
+                        visible_when: None,
                     },
                     InputParameter {
                         name: "format".to_string(),
@@ -164,6 +923,8 @@ impl WorkerConfig {
                             "jpeg".to_string(),
                         ]),
                         readonly: None,
+                        group: None,
+                        visible_when: None,
                     },
                 ]),
             }),
@@ -179,6 +940,7 @@ impl WorkerConfig {
                     readonly: Some(false),
                 },
             ]),
+            build: None,
             plugins: Some(PluginsConfig {
                 arcgis: Some(false),
                 qgis: Some(false),
@@ -186,6 +948,12 @@ impl WorkerConfig {
             deploy: Some(DeployConfig {
                 tenant_id: None,
             }),
+            runtime: None,
+            outputs: None,
+            services: None,
+            tests: None,
+            data: None,
+            hooks: None,
         }
     }
 }
@@ -1,7 +1,8 @@
 use anyhow::{Context, Result};
 use std::path::PathBuf;
 use crate::config::state;
-use crate::config::worker::WorkerConfig;
+use crate::config::worker::{self, WorkerConfig};
+use crate::error::GeoEngineError;
 use crate::utils::paths;
 
 /// Get the directory for saved worker configs (~/.geoengine/configs)
@@ -34,11 +35,12 @@ pub fn save_config(config: &WorkerConfig) -> Result<()> {
 pub fn load_saved_config(worker_name: &str) -> Result<WorkerConfig> {
     let path = config_path(worker_name)?;
     if !path.exists() {
-        anyhow::bail!(
+        return Err(GeoEngineError::WorkerNotFound(format!(
             "No applied configuration found for worker '{}'.\n\
              Run 'geoengine apply' to save the configuration first.",
             worker_name
-        );
+        ))
+        .into());
     }
     let content = std::fs::read_to_string(&path)
         .with_context(|| format!("Failed to read saved config: {}", path.display()))?;
@@ -86,7 +88,7 @@ pub fn check_changed_config(worker_name: &str, worker_path: &PathBuf) -> Result<
     match worker_state {
         Some(s) => {
             let old_hash = s.yaml_hash.unwrap_or("".to_string());
-            let new_hash = state::compute_file_hash(&worker_path.join("geoengine.yaml"))?;
+            let new_hash = state::compute_file_hash(&worker::config_file(worker_path))?;
             Ok(old_hash != new_hash)
         },
         None => Ok(true)
@@ -0,0 +1,247 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::state::compute_file_hash;
+use crate::config::worker::WorkerConfig;
+use crate::error::GeoEngineError;
+use crate::utils::paths;
+
+/// Where a worker image is built from, as given by `build.context`
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildContext {
+    /// A directory on this machine
+    Local(PathBuf),
+    /// A git repository, optionally at a branch/tag/commit and in a subdirectory
+    Git {
+        url: String,
+        git_ref: Option<String>,
+        subdir: Option<String>,
+    },
+    /// A (optionally compressed) tar archive served over HTTP(S)
+    Tarball(String),
+}
+
+impl BuildContext {
+    /// The context a worker's `build.context` names, relative to its directory
+    pub fn of(config: &WorkerConfig, worker_path: &Path) -> Self {
+        match config.build.as_ref().and_then(|b| b.context.as_deref()) {
+            Some(spec) => Self::parse(spec, worker_path),
+            None => BuildContext::Local(worker_path.to_path_buf()),
+        }
+    }
+
+    /// Classify a context the way `docker build` does: `git://`, `git@` and
+    /// http(s) URLs ending in `.git` are repositories, other http(s) URLs tarballs
+    pub fn parse(spec: &str, worker_path: &Path) -> Self {
+        let (url, fragment) = spec.split_once('#').unwrap_or((spec, ""));
+        let is_http = url.starts_with("https://") || url.starts_with("http://");
+        let is_git = url.starts_with("git://")
+            || url.starts_with("git@")
+            || url.starts_with("ssh://")
+            || (is_http && url.trim_end_matches('/').ends_with(".git"));
+        if is_git {
+            let (git_ref, subdir) = fragment.split_once(':').unwrap_or((fragment, ""));
+            let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+            BuildContext::Git {
+                url: url.to_string(),
+                git_ref: non_empty(git_ref),
+                subdir: non_empty(subdir.trim_matches('/')),
+            }
+        } else if is_http {
+            BuildContext::Tarball(spec.to_string())
+        } else {
+            BuildContext::Local(worker_path.join(spec))
+        }
+    }
+
+    /// Make the context available as a local directory. Remote contexts are
+    /// fetched into a temporary directory that is removed when the result is dropped.
+    pub async fn fetch(&self) -> Result<FetchedContext> {
+        match self {
+            BuildContext::Local(dir) => {
+                if !dir.is_dir() {
+                    return Err(GeoEngineError::ConfigInvalid(format!(
+                        "Build context not found: {}",
+                        dir.display()
+                    ))
+                    .into());
+                }
+                Ok(FetchedContext {
+                    dir: dir.clone(),
+                    revision: None,
+                    temp_dir: None,
+                })
+            }
+            BuildContext::Git { url, git_ref, subdir } => {
+                let temp_dir = temp_context_dir()?;
+                let mut fetched = FetchedContext {
+                    dir: temp_dir.clone(),
+                    revision: None,
+                    temp_dir: Some(temp_dir),
+                };
+                let revision = fetch_git(url, git_ref.as_deref(), &fetched.dir)?;
+                println!(
+                    "{} Fetched {} at {}",
+                    "✓".green().bold(),
+                    url.cyan(),
+                    &revision[..revision.len().min(12)]
+                );
+                fetched.revision = Some(revision);
+                if let Some(subdir) = subdir {
+                    fetched.dir = context_subdir(&fetched.dir, subdir)?;
+                }
+                Ok(fetched)
+            }
+            BuildContext::Tarball(url) => {
+                let temp_dir = temp_context_dir()?;
+                let mut fetched = FetchedContext {
+                    dir: temp_dir.join("context"),
+                    revision: None,
+                    temp_dir: Some(temp_dir.clone()),
+                };
+                let archive = temp_dir.join("context.tar");
+                download(url, &archive).await?;
+                fetched.revision = Some(compute_file_hash(&archive)?);
+                std::fs::create_dir_all(&fetched.dir)?;
+                let status = Command::new("tar")
+                    .arg("-xf")
+                    .arg(&archive)
+                    .arg("-C")
+                    .arg(&fetched.dir)
+                    .status()
+                    .context("Failed to run tar")?;
+                if !status.success() {
+                    anyhow::bail!("Failed to extract the build context downloaded from {}", url);
+                }
+                std::fs::remove_file(&archive).ok();
+                fetched.dir = unwrap_single_dir(&fetched.dir)?;
+                println!("{} Downloaded build context from {}", "✓".green().bold(), url.cyan());
+                Ok(fetched)
+            }
+        }
+    }
+}
+
+/// A build context on local disk
+pub struct FetchedContext {
+    /// Directory to send to the Docker build
+    pub dir: PathBuf,
+
+    /// Commit (git) or archive SHA-256 (tarball) of a remote context
+    pub revision: Option<String>,
+
+    temp_dir: Option<PathBuf>,
+}
+
+impl Drop for FetchedContext {
+    fn drop(&mut self) {
+        if let Some(dir) = &self.temp_dir {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+    }
+}
+
+fn temp_context_dir() -> Result<PathBuf> {
+    let dir = paths::get_temp_dir()?.join(format!(
+        "build-context-{}-{}",
+        std::process::id(),
+        chrono::Utc::now().timestamp_millis()
+    ));
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Shallow-fetch one ref (or the default branch) of a repository, returning the commit
+fn fetch_git(url: &str, git_ref: Option<&str>, dest: &Path) -> Result<String> {
+    which::which("git").context("git not found. Install git to build from a git context.")?;
+    println!("{} Fetching build context from {}...", "=>".blue().bold(), url.cyan());
+
+    let git = |args: &[&str]| -> Result<()> {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(dest)
+            .args(args)
+            .status()
+            .context("Failed to run git")?;
+        if !status.success() {
+            anyhow::bail!("git {} failed", args.join(" "));
+        }
+        Ok(())
+    };
+    // Fetching by name works for branches, tags and (on most hosts) commit SHAs alike
+    git(&["init", "--quiet"])?;
+    git(&["fetch", "--quiet", "--depth", "1", url, git_ref.unwrap_or("HEAD")])?;
+    git(&["-c", "advice.detachedHead=false", "checkout", "--quiet", "FETCH_HEAD"])?;
+    git(&["submodule", "update", "--quiet", "--init", "--recursive", "--depth", "1"])?;
+
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dest)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .context("Failed to run git rev-parse")?;
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// A subdirectory of a fetched context, refusing paths that leave it
+fn context_subdir(root: &Path, subdir: &str) -> Result<PathBuf> {
+    if Path::new(subdir)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(GeoEngineError::ConfigInvalid(format!(
+            "Invalid build context subdirectory '{}'",
+            subdir
+        ))
+        .into());
+    }
+    let dir = root.join(subdir);
+    if !dir.is_dir() {
+        anyhow::bail!("Build context subdirectory '{}' not found in the repository", subdir);
+    }
+    // A symlink in the checkout must not point the build context somewhere else on the host
+    let dir = dir.canonicalize()?;
+    if !dir.starts_with(root.canonicalize()?) {
+        return Err(GeoEngineError::ConfigInvalid(format!(
+            "Build context subdirectory '{}' leads outside the repository",
+            subdir
+        ))
+        .into());
+    }
+    Ok(dir)
+}
+
+async fn download(url: &str, dest: &Path) -> Result<()> {
+    println!("{} Downloading build context from {}...", "=>".blue().bold(), url.cyan());
+    let mut response = reqwest::get(url)
+        .await
+        .with_context(|| format!("Failed to download {}", url))?;
+    if !response.status().is_success() {
+        anyhow::bail!("Failed to download {}: HTTP {}", url, response.status());
+    }
+    let mut file = tokio::fs::File::create(dest).await?;
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .with_context(|| format!("Failed to download {}", url))?
+    {
+        file.write_all(&chunk).await?;
+    }
+    file.flush().await?;
+    Ok(())
+}
+
+/// Archives like GitHub's wrap everything in one top-level directory; build from inside it
+fn unwrap_single_dir(dir: &Path) -> Result<PathBuf> {
+    let entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+    match entries.as_slice() {
+        [only] if only.is_dir() => Ok(only.clone()),
+        _ => Ok(dir.to_path_buf()),
+    }
+}
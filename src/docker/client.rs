@@ -1,17 +1,42 @@
 use anyhow::{Context, Result};
-use bollard::container::{Config, CreateContainerOptions, LogsOptions, StartContainerOptions, WaitContainerOptions};
+use bollard::container::{Config, CreateContainerOptions, LogsOptions, StartContainerOptions, StopContainerOptions, WaitContainerOptions};
 use bollard::image::{BuildImageOptions, CreateImageOptions, ImportImageOptions, TagImageOptions};
+use bollard::auth::DockerCredentials;
 use bollard::Docker;
+use colored::Colorize;
 use futures::StreamExt;
 use std::collections::HashMap;
-use std::path::PathBuf;
-use tokio::io::AsyncWriteExt;
-
-use super::config::ContainerConfig;
+use std::path::{Path, PathBuf};
+use indicatif::ProgressBar;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+
+use super::dockerfile;
+use super::gpu;
+use super::config::{parse_devices, parse_ports, parse_restart_policy, parse_ulimits, ContainerConfig};
+use crate::config::settings::Settings;
+use crate::config::worker::{VolumeConfig, VolumeKind};
+use super::host;
+use super::labels;
+use super::usage::{self, ResourceUsage, UsageMonitor};
+use super::user::UserNamespace;
+use crate::docker::pull_progress::PullProgress;
+use crate::docker::push_progress::PushProgress;
+use crate::error::GeoEngineError;
+use crate::utils::process;
+
+/// Seconds a container gets to exit cleanly after SIGTERM before Docker kills it
+const STOP_TIMEOUT_SECS: i64 = 10;
+
+/// Name in the build context of a Dockerfile cut off after its target stage
+const TARGET_DOCKERFILE: &str = ".geoengine-target.Dockerfile";
+
+/// How much of a partial export is compared with the new stream before resuming it
+const EXPORT_RESUME_CHECK_BYTES: u64 = 1024 * 1024;
 
 /// Docker client wrapper for GeoEngine operations
 pub struct DockerClient {
     docker: Docker,
+    userns: UserNamespace,
 }
 
 /// Information about a Docker image
@@ -23,57 +48,185 @@ pub struct ImageInfo {
     pub created: i64,
 }
 
+/// A running container started from a worker image
+pub struct WorkerContainer {
+    pub id: String,
+    pub name: String,
+    pub worker: String,
+    pub image: String,
+    pub status: String,
+    /// Service or detached run name (`geoengine.service` label)
+    pub service: Option<String>,
+}
+
+/// An attached container and the geoengine process that started it
+pub struct OwnedContainer {
+    pub id: String,
+    pub name: String,
+    pub owner_pid: u32,
+    pub owner_host: String,
+    pub running: bool,
+}
+
+/// A named volume created by geoengine
+pub struct VolumeInfo {
+    pub name: String,
+    pub worker: String,
+    pub driver: String,
+    pub created: String,
+}
+
+/// How an attached container run ended
+pub struct ContainerExit {
+    pub exit_code: i64,
+    /// The kernel killed the container for exceeding its memory limit
+    pub oom_killed: bool,
+    /// CPU, memory and GPU use sampled while the container ran
+    pub usage: Option<ResourceUsage>,
+}
+
+/// One resource usage sample of a container
+pub struct ContainerUsage {
+    pub cpu_percent: f64,
+    pub memory_bytes: u64,
+    pub memory_limit: u64,
+}
+
 impl DockerClient {
     /// Create a new Docker client
     pub async fn new() -> Result<Self> {
-        let docker = Docker::connect_with_local_defaults()
-            .context("Failed to connect to Docker daemon. Is Docker running?")?;
+        let docker = host::connect()?;
 
         // Verify connection
         docker
             .ping()
             .await
-            .context("Failed to ping Docker daemon")?;
+            .context(GeoEngineError::DockerUnavailable(host::connect_hint().to_string()))?;
+
+        // Rootless and userns-remap daemons change who owns files written to bind mounts
+        let userns = match docker.info().await {
+            Ok(info) => UserNamespace::from_security_options(&info.security_options.unwrap_or_default()),
+            Err(e) => {
+                tracing::debug!("Failed to read the daemon's security options: {}", e);
+                UserNamespace::Host
+            }
+        };
+        tracing::debug!("Docker user namespace: {:?}", userns);
+
+        Ok(Self { docker, userns })
+    }
 
-        Ok(Self { docker })
+    /// User namespace the daemon runs containers in
+    pub fn user_namespace(&self) -> UserNamespace {
+        self.userns
     }
 
-    /// Import a Docker image from a tar file
-    pub async fn import_image(&self, tarfile: &PathBuf, tag: Option<&str>) -> Result<String> {
-        // Read the entire tar file into memory
-        let file_contents = tokio::fs::read(tarfile)
+    /// Import a Docker image from a tar file, advancing `pb` by the bytes sent and
+    /// then by the bytes of the layers Docker has loaded
+    #[tracing::instrument(skip_all, fields(tarfile = %tarfile.display()))]
+    pub async fn import_image(&self, tarfile: &PathBuf, tag: Option<&str>, pb: &ProgressBar) -> Result<Vec<String>> {
+        // Streamed as it is read, so parallel imports don't hold whole archives in memory
+        let file = tokio::fs::File::open(tarfile)
             .await
             .with_context(|| format!("Failed to read tar file: {}", tarfile.display()))?;
-
-        let options = ImportImageOptions {
-            ..Default::default()
+        let size = file.metadata().await?.len();
+        pb.set_length(size);
+        pb.set_position(0);
+        pb.set_message("Sending archive");
+        let read_error: std::sync::Arc<std::sync::Mutex<Option<std::io::Error>>> = Default::default();
+        let body = {
+            let (pb, read_error) = (pb.clone(), read_error.clone());
+            futures::stream::unfold(file, move |mut file| {
+                let (pb, read_error) = (pb.clone(), read_error.clone());
+                async move {
+                    let mut chunk = vec![0u8; 1024 * 1024];
+                    match file.read(&mut chunk).await {
+                        Ok(0) => None,
+                        Ok(read) => {
+                            chunk.truncate(read);
+                            pb.inc(read as u64);
+                            Some((bytes::Bytes::from(chunk), file))
+                        }
+                        Err(e) => {
+                            *read_error.lock().unwrap() = Some(e);
+                            None
+                        }
+                    }
+                }
+            })
+        };
+        // A failed read cuts the body short; report that rather than Docker's complaint about it
+        let read_failed = || {
+            read_error
+                .lock()
+                .unwrap()
+                .take()
+                .map(|e| anyhow::Error::new(e).context(format!("Failed to read tar file: {}", tarfile.display())))
         };
 
-        let mut stream = self.docker.import_image(options, file_contents.into(), None);
+        let options = ImportImageOptions { quiet: false };
+        let mut stream = self.docker.import_image_stream(options, body, None);
+        let mut loading = false;
 
-        let mut image_id = String::new();
+        // An archive can hold several images; Docker reports each as
+        // "Loaded image: <name:tag>" (or "Loaded image ID: <id>" when untagged)
+        let mut loaded: Vec<String> = Vec::new();
+        let mut layers: HashMap<String, u64> = HashMap::new();
         while let Some(result) = stream.next().await {
             match result {
                 Ok(info) => {
-                    if let Some(status) = info.status {
+                    if !loading {
+                        loading = true;
+                        pb.set_position(0);
+                        pb.set_message("Loading layers");
+                    }
+                    if let Some(status) = &info.status {
                         tracing::debug!("Import status: {}", status);
                     }
-                    if let Some(id) = info.id {
-                        image_id = id;
+                    if let Some(error) = info.error {
+                        return Err(read_failed().unwrap_or_else(|| anyhow::anyhow!("Import failed: {}", error)));
+                    }
+                    // Layer progress counts the layer's uncompressed bytes, about its share of the archive
+                    if let (Some(layer), Some(current)) = (
+                        info.id.as_ref().filter(|_| info.status.as_deref() == Some("Loading layer")),
+                        info.progress_detail.as_ref().and_then(|d| d.current),
+                    ) {
+                        layers.insert(layer.clone(), current.max(0) as u64);
+                        pb.set_position(layers.values().sum::<u64>().min(size));
+                        continue;
+                    }
+                    if let Some(line) = info.stream {
+                        let line = line.trim();
+                        if let Some(image) = line
+                            .strip_prefix("Loaded image ID: ")
+                            .or_else(|| line.strip_prefix("Loaded image: "))
+                        {
+                            loaded.push(image.to_string());
+                        }
                     }
                 }
                 Err(e) => {
-                    return Err(anyhow::anyhow!("Import failed: {}", e));
+                    return Err(read_failed().unwrap_or_else(|| anyhow::anyhow!("Import failed: {}", e)));
                 }
             }
         }
+        if let Some(e) = read_failed() {
+            return Err(e);
+        }
+        pb.set_position(size);
 
         // Tag the image if requested
         if let Some(tag) = tag {
-            self.tag_image(&image_id, tag).await?;
+            match loaded.as_slice() {
+                [image] => self.tag_image(image, tag).await?,
+                _ => anyhow::bail!(
+                    "--tag needs an archive with exactly one image, but {} were loaded",
+                    loaded.len()
+                ),
+            }
         }
 
-        Ok(image_id)
+        Ok(loaded)
     }
 
     /// List Docker images under geoengine
@@ -111,21 +264,121 @@ impl DockerClient {
         Ok(result)
     }
 
-    /// Pull a Docker image from a registry
-    pub async fn pull_image(&self, image: &str) -> Result<()> {
+    /// Every local image with its disk usage (`docker system df -v`), and the
+    /// size of all image layers together, shared layers counted once
+    pub async fn image_disk_usage(&self) -> Result<(Vec<bollard::models::ImageSummary>, i64)> {
+        let usage = self.docker.df().await?;
+        Ok((usage.images.unwrap_or_default(), usage.layers_size.unwrap_or_default()))
+    }
+
+    /// Get the labels attached to a local image
+    pub async fn image_labels(&self, image: &str) -> Result<HashMap<String, String>> {
+        let inspect = self
+            .docker
+            .inspect_image(image)
+            .await
+            .with_context(|| format!("Image not found: {}", image))?;
+        Ok(inspect
+            .config
+            .and_then(|c| c.labels)
+            .unwrap_or_default())
+    }
+
+    /// Inspect a local image (config, layers, tags, size)
+    pub async fn inspect_image(&self, image: &str) -> Result<bollard::models::ImageInspect> {
+        self.docker
+            .inspect_image(image)
+            .await
+            .with_context(|| format!("Image not found: {}", image))
+    }
+
+    /// Get the build history of a local image, newest layer first
+    pub async fn image_history(&self, image: &str) -> Result<Vec<bollard::models::HistoryResponseItem>> {
+        self.docker
+            .image_history(image)
+            .await
+            .with_context(|| format!("Image not found: {}", image))
+    }
+
+    /// Version, OS and runtimes of the daemon (`docker info`)
+    pub async fn system_info(&self) -> Result<bollard::models::SystemInfo> {
+        Ok(self.docker.info().await?)
+    }
+
+    /// Directory where the daemon keeps images, containers and volumes
+    pub async fn docker_root_dir(&self) -> Result<Option<PathBuf>> {
+        let info = self.docker.info().await?;
+        Ok(info.docker_root_dir.map(PathBuf::from))
+    }
+
+    /// Check whether an image is built on an NVIDIA CUDA base image
+    pub async fn is_cuda_image(&self, image: &str) -> Result<bool> {
+        let inspect = self.docker.inspect_image(image).await?;
+        let config = inspect.config.unwrap_or_default();
+        let cuda_env = config.env.unwrap_or_default().iter().any(|e| {
+            e.starts_with("CUDA_VERSION=") || e.starts_with("NVIDIA_REQUIRE_CUDA=")
+        });
+        let cuda_label = config
+            .labels
+            .unwrap_or_default()
+            .contains_key("com.nvidia.cuda.version");
+        Ok(cuda_env || cuda_label)
+    }
+
+    /// Get the ID (config digest) of a local image
+    pub async fn image_id(&self, image: &str) -> Result<String> {
+        let inspect = self.docker.inspect_image(image).await?;
+        inspect
+            .id
+            .ok_or_else(|| anyhow::anyhow!("Docker returned no ID for image {}", image))
+    }
+
+    /// Get the repository digests (`repo@sha256:...`) recorded for a local image
+    pub async fn local_repo_digests(&self, image: &str) -> Result<Vec<String>> {
+        let inspect = self.docker.inspect_image(image).await?;
+        Ok(inspect.repo_digests.unwrap_or_default())
+    }
+
+    /// Ask the registry for the current manifest digest of an image reference
+    pub async fn remote_digest(&self, image: &str) -> Result<String> {
+        self.remote_digest_with(image, None).await
+    }
+
+    /// `remote_digest` for a registry that needs credentials
+    pub async fn remote_digest_with(&self, image: &str, credentials: Option<DockerCredentials>) -> Result<String> {
+        let inspect = self
+            .docker
+            .inspect_registry_image(image, credentials)
+            .await
+            .with_context(|| format!("Failed to query registry for {}", image))?;
+        inspect
+            .descriptor
+            .digest
+            .ok_or_else(|| anyhow::anyhow!("Registry returned no digest for {}", image))
+    }
+
+    /// Pull a Docker image from a registry, reporting Docker's per-layer progress to `progress`
+    #[tracing::instrument(skip(self, credentials, progress))]
+    pub async fn pull_image(
+        &self,
+        image: &str,
+        credentials: Option<DockerCredentials>,
+        progress: &mut PullProgress,
+    ) -> Result<()> {
         let options = Some(CreateImageOptions {
             from_image: image,
             ..Default::default()
         });
 
-        let mut stream = self.docker.create_image(options, None, None);
+        let mut stream = self.docker.create_image(options, None, credentials);
 
         while let Some(result) = stream.next().await {
             match result {
                 Ok(info) => {
-                    if let Some(status) = info.status {
-                        tracing::debug!("Pull status: {}", status);
+                    if let Some(error) = &info.error {
+                        return Err(anyhow::anyhow!("Pull failed: {}", error));
                     }
+                    progress.update(&info);
                 }
                 Err(e) => {
                     return Err(anyhow::anyhow!("Pull failed: {}", e));
@@ -147,36 +400,131 @@ impl DockerClient {
         Ok(())
     }
 
-    /// Export a Docker image to a tar file
-    pub async fn export_image(&self, image: &str, output: &PathBuf) -> Result<()> {
-        let mut stream = self.docker.export_image(image);
+    /// Export one or more Docker images into a single tar file. Layers shared
+    /// between the images are stored once. Returns the number of bytes written.
+    ///
+    /// The archive is written to `partial_export_path(output)` and renamed when
+    /// complete. A partial file left by an interrupted export of the same images
+    /// (recorded next to it) is resumed: `docker save` streams the same bytes again,
+    /// and the ones already on disk are skipped instead of being written or read
+    /// back, apart from a short stretch before the resume point that is compared
+    /// to catch an archive that came out differently.
+    pub async fn export_images(&self, images: &[String], output: &Path, pb: &ProgressBar) -> Result<u64> {
+        let partial = partial_export_path(output);
+        let record = export_record_path(&partial);
+        let mut identity = String::new();
+        for image in images {
+            identity.push_str(&format!("{} {}\n", image, self.image_id(image).await?));
+        }
 
-        let mut file = tokio::fs::File::create(output)
+        let mut file = tokio::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&partial)
             .await
-            .with_context(|| format!("Failed to create output file: {}", output.display()))?;
-
-        while let Some(result) = stream.next().await {
-            match result {
-                Ok(data) => {
-                    file.write_all(&data).await?;
+            .with_context(|| format!("Failed to create output file: {}", partial.display()))?;
+        let same_images = tokio::fs::read_to_string(&record).await.is_ok_and(|r| r == identity);
+        let mut resume_at = if same_images { file.metadata().await?.len() } else { 0 };
+        loop {
+            match self.export_from(images, &mut file, resume_at, &identity, &record, pb).await? {
+                Some(written) => {
+                    file.flush().await?;
+                    drop(file);
+                    tokio::fs::rename(&partial, output)
+                        .await
+                        .with_context(|| format!("Failed to move {} to {}", partial.display(), output.display()))?;
+                    tokio::fs::remove_file(&record).await.ok();
+                    return Ok(written);
                 }
-                Err(e) => {
-                    return Err(anyhow::anyhow!("Export failed: {}", e));
+                None => {
+                    tracing::debug!("Partial export differs from the new one; starting over");
+                    resume_at = 0;
                 }
             }
         }
+    }
 
-        file.flush().await?;
-        Ok(())
+    /// One `docker save` into `file`, keeping its first `resume_at` bytes. None when
+    /// those bytes turn out not to match the stream, in which case nothing was kept.
+    async fn export_from(
+        &self,
+        images: &[String],
+        file: &mut tokio::fs::File,
+        resume_at: u64,
+        identity: &str,
+        record: &Path,
+        pb: &ProgressBar,
+    ) -> Result<Option<u64>> {
+        // The last bytes before the resume point, compared with the new stream
+        let check_from = resume_at.saturating_sub(EXPORT_RESUME_CHECK_BYTES);
+        let mut tail = vec![0u8; (resume_at - check_from) as usize];
+        file.seek(std::io::SeekFrom::Start(check_from)).await?;
+        file.read_exact(&mut tail).await?;
+        file.set_len(resume_at).await?;
+        file.seek(std::io::SeekFrom::Start(resume_at)).await?;
+        if resume_at == 0 {
+            tokio::fs::write(record, identity).await?;
+        }
+
+        let names: Vec<&str> = images.iter().map(String::as_str).collect();
+        let mut stream = self.docker.export_images(&names);
+        let shutdown = shutdown_signal();
+        tokio::pin!(shutdown);
+        let mut offset = 0u64;
+        loop {
+            let data = tokio::select! {
+                next = stream.next() => match next {
+                    Some(Ok(data)) => data,
+                    Some(Err(e)) => {
+                        file.flush().await?;
+                        return Err(anyhow::anyhow!("Export failed: {}", e));
+                    }
+                    None => break,
+                },
+                _ = &mut shutdown => {
+                    file.flush().await?;
+                    return Err(GeoEngineError::Interrupted.into());
+                }
+            };
+
+            let start = offset;
+            offset += data.len() as u64;
+            if offset > check_from && start < resume_at {
+                let from = start.max(check_from);
+                let to = offset.min(resume_at);
+                let expected = &tail[(from - check_from) as usize..(to - check_from) as usize];
+                if &data[(from - start) as usize..(to - start) as usize] != expected {
+                    file.set_len(0).await?;
+                    file.seek(std::io::SeekFrom::Start(0)).await?;
+                    return Ok(None);
+                }
+            }
+            if offset > resume_at {
+                file.write_all(&data[resume_at.saturating_sub(start) as usize..]).await?;
+            }
+            // The expected size is an estimate
+            if pb.length().is_some_and(|len| offset > len) {
+                pb.set_length(offset);
+            }
+            pb.set_position(offset);
+        }
+        if offset < resume_at {
+            // Shorter than what was already written: not the same archive
+            file.set_len(0).await?;
+            file.seek(std::io::SeekFrom::Start(0)).await?;
+            return Ok(None);
+        }
+        Ok(Some(offset))
     }
 
     /// Tag a Docker image
     pub async fn tag_image(&self, source: &str, target: &str) -> Result<()> {
-        let (repo, tag) = if target.contains(':') {
-            let parts: Vec<&str> = target.rsplitn(2, ':').collect();
-            (parts[1], parts[0])
-        } else {
-            (target, "latest")
+        // A colon followed by a path is a registry port, not a tag
+        let (repo, tag) = match target.rsplit_once(':') {
+            Some((repo, tag)) if !tag.contains('/') => (repo, tag),
+            _ => (target, "latest"),
         };
 
         let options = TagImageOptions { repo, tag };
@@ -184,8 +532,16 @@ impl DockerClient {
         Ok(())
     }
 
-    /// Push a Docker image to a registry
-    pub async fn push_image(&self, image: &str) -> Result<()> {
+    /// Push a Docker image to a registry, reporting Docker's per-layer progress. Fails
+    /// if the daemon reports nothing for `stall_timeout`, so a hung upload can be retried.
+    #[tracing::instrument(skip(self, credentials, progress))]
+    pub async fn push_image(
+        &self,
+        image: &str,
+        credentials: Option<DockerCredentials>,
+        progress: &mut PushProgress,
+        stall_timeout: std::time::Duration,
+    ) -> Result<()> {
         let options = bollard::image::PushImageOptions::<String> {
             tag: image.split(':').last().unwrap_or("latest").to_string(),
         };
@@ -193,21 +549,28 @@ impl DockerClient {
         let mut stream = self.docker.push_image(
             image.split(':').next().unwrap_or(image),
             Some(options),
-            None,
+            credentials,
         );
 
-        while let Some(result) = stream.next().await {
+        loop {
+            let Ok(next) = tokio::time::timeout(stall_timeout, stream.next()).await else {
+                return Err(anyhow::anyhow!(
+                    "Push stalled: no progress for {}s",
+                    stall_timeout.as_secs()
+                ));
+            };
+            let Some(result) = next else {
+                break;
+            };
             match result {
                 Ok(info) => {
-                    if let Some(status) = info.status {
-                        tracing::debug!("Push status: {}", status);
-                    }
                     if let Some(error) = info.error {
-                        return Err(anyhow::anyhow!("Push failed: {}", error));
+                        return Err(push_error(error));
                     }
+                    progress.update(&info);
                 }
                 Err(e) => {
-                    return Err(anyhow::anyhow!("Push failed: {}", e));
+                    return Err(push_error(e.to_string()));
                 }
             }
         }
@@ -216,13 +579,17 @@ impl DockerClient {
     }
 
     /// Build a Docker image
+    #[tracing::instrument(skip_all, fields(tag = %tag, no_cache))]
+    #[allow(clippy::too_many_arguments)]
     pub async fn build_image(
         &self,
         dockerfile: &PathBuf,
         context: &PathBuf,
         tag: &str,
         build_args: &HashMap<String, String>,
+        labels: &HashMap<String, String>,
         no_cache: bool,
+        target: Option<&str>,
     ) -> Result<()> {
         // Create tar archive of context
         let tar_path = std::env::temp_dir().join(format!("geoengine-build-{}-{}.tar", std::process::id(), chrono::Utc::now().timestamp()));
@@ -237,20 +604,43 @@ impl DockerClient {
             anyhow::bail!("Failed to create build context");
         }
 
-        let tar_contents = tokio::fs::read(&tar_path).await?;
-        tokio::fs::remove_file(&tar_path).await.ok();
-
-        let dockerfile_rel = dockerfile
+        let mut dockerfile_rel = dockerfile
             .strip_prefix(context)
             .unwrap_or(dockerfile)
             .to_str()
             .unwrap_or("Dockerfile");
 
+        // The Docker API we speak has no build target, so a stage is built from the
+        // Dockerfile cut off after it, added to the context under its own name
+        if let Some(target) = target {
+            let content = std::fs::read_to_string(dockerfile)
+                .with_context(|| format!("Failed to read {}", dockerfile.display()))?;
+            let staged = dockerfile::truncate_to_stage(&content, target).ok_or_else(|| {
+                anyhow::anyhow!("{} has no build stage '{}' (FROM ... AS {})", dockerfile.display(), target, target)
+            })?;
+            let dir = std::env::temp_dir().join(format!("geoengine-build-{}-target", std::process::id()));
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(dir.join(TARGET_DOCKERFILE), staged)?;
+            let status = std::process::Command::new("tar")
+                .args(["--no-xattrs", "-rf", tar_path.to_str().unwrap(), "-C", dir.to_str().unwrap(), TARGET_DOCKERFILE])
+                .status()
+                .context("Failed to add the stage's Dockerfile to the build context")?;
+            std::fs::remove_dir_all(&dir).ok();
+            if !status.success() {
+                anyhow::bail!("Failed to add the stage's Dockerfile to the build context");
+            }
+            dockerfile_rel = TARGET_DOCKERFILE;
+        }
+
+        let tar_contents = tokio::fs::read(&tar_path).await?;
+        tokio::fs::remove_file(&tar_path).await.ok();
+
         let options = BuildImageOptions {
             t: tag,
             dockerfile: dockerfile_rel,
             nocache: no_cache,
             buildargs: build_args.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect(),
+            labels: labels.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect(),
             rm: true,
             ..Default::default()
         };
@@ -280,13 +670,15 @@ impl DockerClient {
     }
 
     /// Run a container and wait for it to complete (attached mode)
-    pub async fn run_container_attached(&self, config: &ContainerConfig) -> Result<i64> {
+    #[tracing::instrument(skip_all, fields(image = %config.image))]
+    pub async fn run_container_attached(&self, config: &ContainerConfig) -> Result<ContainerExit> {
         let container_id = self.create_container(config).await?;
 
         // Start the container
         self.docker
             .start_container(&container_id, None::<StartContainerOptions<String>>)
             .await?;
+        let monitor = UsageMonitor::start(&self.docker, &container_id, config.gpu_config.as_ref());
         
         let command_display = config
             .command
@@ -305,16 +697,26 @@ impl DockerClient {
 
         let mut log_stream = self.docker.logs(&container_id, Some(log_options));
 
-        while let Some(result) = log_stream.next().await {
-            match result {
-                Ok(output) => {
-                    print!("{}", output);
-                }
-                Err(e) => {
-                    tracing::warn!("Log stream error: {}", e);
-                    break;
+        let interrupted = tokio::select! {
+            _ = async {
+                while let Some(result) = log_stream.next().await {
+                    match result {
+                        Ok(output) => {
+                            print!("{}", output);
+                        }
+                        Err(e) => {
+                            tracing::warn!("Log stream error: {}", e);
+                            break;
+                        }
+                    }
                 }
-            }
+            } => false,
+            _ = shutdown_signal() => true,
+        };
+        if interrupted {
+            monitor.finish();
+            self.shutdown_container(&container_id).await;
+            return Err(GeoEngineError::Interrupted.into());
         }
 
         // Wait for container to finish
@@ -334,6 +736,8 @@ impl DockerClient {
         } else {
             0
         };
+        let usage = monitor.finish();
+        let oom_killed = self.oom_killed(&container_id).await;
 
         // Remove container if requested
         if config.remove_on_exit {
@@ -349,18 +753,20 @@ impl DockerClient {
                 .ok();
         }
 
-        Ok(exit_code)
+        Ok(ContainerExit { exit_code, oom_killed, usage })
     }
 
     /// Run a container attached, routing all container output to host stderr.
     /// This keeps host stdout free for structured output (e.g. JSON results).
-    pub async fn run_container_attached_to_stderr(&self, config: &ContainerConfig) -> Result<i64> {
+    #[tracing::instrument(skip_all, fields(image = %config.image))]
+    pub async fn run_container_attached_to_stderr(&self, config: &ContainerConfig) -> Result<ContainerExit> {
         let container_id = self.create_container(config).await?;
 
         // Start the container
         self.docker
             .start_container(&container_id, None::<StartContainerOptions<String>>)
             .await?;
+        let monitor = UsageMonitor::start(&self.docker, &container_id, config.gpu_config.as_ref());
 
         // Stream logs to stderr
         let log_options = LogsOptions::<String> {
@@ -372,16 +778,115 @@ impl DockerClient {
 
         let mut log_stream = self.docker.logs(&container_id, Some(log_options));
 
-        while let Some(result) = log_stream.next().await {
-            match result {
-                Ok(output) => {
-                    eprint!("{}", output);
+        let interrupted = tokio::select! {
+            _ = async {
+                while let Some(result) = log_stream.next().await {
+                    match result {
+                        Ok(output) => {
+                            eprint!("{}", output);
+                        }
+                        Err(e) => {
+                            tracing::warn!("Log stream error: {}", e);
+                            break;
+                        }
+                    }
                 }
+            } => false,
+            _ = shutdown_signal() => true,
+        };
+        if interrupted {
+            monitor.finish();
+            self.shutdown_container(&container_id).await;
+            return Err(GeoEngineError::Interrupted.into());
+        }
+
+        // Wait for container to finish
+        let wait_options = WaitContainerOptions {
+            condition: "not-running",
+        };
+
+        let mut wait_stream = self.docker.wait_container(&container_id, Some(wait_options));
+        let exit_code = if let Some(result) = wait_stream.next().await {
+            match result {
+                Ok(response) => response.status_code,
                 Err(e) => {
-                    tracing::warn!("Log stream error: {}", e);
-                    break;
+                    tracing::warn!("Wait error: {}", e);
+                    -1
                 }
             }
+        } else {
+            0
+        };
+        let usage = monitor.finish();
+        let oom_killed = self.oom_killed(&container_id).await;
+
+        // Remove container if requested
+        if config.remove_on_exit {
+            self.docker
+                .remove_container(
+                    &container_id,
+                    Some(bollard::container::RemoveContainerOptions {
+                        force: true,
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .ok();
+        }
+
+        Ok(ContainerExit { exit_code, oom_killed, usage })
+    }
+
+    /// Run a container attached, writing all container output to a log file.
+    /// Used for batch runs, where several containers run at once.
+    #[tracing::instrument(skip_all, fields(image = %config.image))]
+    pub async fn run_container_attached_to_file(&self, config: &ContainerConfig, log_path: &std::path::Path) -> Result<ContainerExit> {
+        let mut log_file = tokio::fs::File::create(log_path)
+            .await
+            .with_context(|| format!("Failed to create log file: {}", log_path.display()))?;
+
+        let container_id = self.create_container(config).await?;
+
+        // Start the container
+        self.docker
+            .start_container(&container_id, None::<StartContainerOptions<String>>)
+            .await?;
+        let monitor = UsageMonitor::start(&self.docker, &container_id, config.gpu_config.as_ref());
+
+        // Stream logs to the file
+        let log_options = LogsOptions::<String> {
+            follow: true,
+            stdout: true,
+            stderr: true,
+            ..Default::default()
+        };
+
+        let mut log_stream = self.docker.logs(&container_id, Some(log_options));
+
+        let interrupted = tokio::select! {
+            _ = async {
+                while let Some(result) = log_stream.next().await {
+                    match result {
+                        Ok(output) => {
+                            if let Err(e) = log_file.write_all(&output.into_bytes()).await {
+                                tracing::warn!("Failed to write container log: {}", e);
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!("Log stream error: {}", e);
+                            break;
+                        }
+                    }
+                }
+            } => false,
+            _ = shutdown_signal() => true,
+        };
+        log_file.flush().await.ok();
+        if interrupted {
+            monitor.finish();
+            self.shutdown_container(&container_id).await;
+            return Err(GeoEngineError::Interrupted.into());
         }
 
         // Wait for container to finish
@@ -401,6 +906,8 @@ impl DockerClient {
         } else {
             0
         };
+        let usage = monitor.finish();
+        let oom_killed = self.oom_killed(&container_id).await;
 
         // Remove container if requested
         if config.remove_on_exit {
@@ -416,7 +923,7 @@ impl DockerClient {
                 .ok();
         }
 
-        Ok(exit_code)
+        Ok(ContainerExit { exit_code, oom_killed, usage })
     }
 
     /// Run a container in detached mode
@@ -442,19 +949,66 @@ impl DockerClient {
         let binds: Vec<String> = config
             .mounts
             .iter()
-            .map(|(host, container, ro)| {
+            .map(|(host_path, container, ro)| {
+                let host_path = host::docker_host_path(host_path);
                 if *ro {
-                    format!("{}:{}:ro", host, container)
+                    format!("{}:{}:ro", host_path, container)
                 } else {
-                    format!("{}:{}", host, container)
+                    format!("{}:{}", host_path, container)
                 }
             })
             .collect();
 
+        // Named volumes and tmpfs
+        let mounts = config
+            .volumes
+            .iter()
+            .map(|volume| -> Result<bollard::models::Mount> {
+                Ok(match volume.kind {
+                    VolumeKind::Volume => bollard::models::Mount {
+                        target: Some(volume.target.clone()),
+                        source: volume.name.clone(),
+                        typ: Some(bollard::models::MountTypeEnum::VOLUME),
+                        read_only: volume.readonly,
+                        ..Default::default()
+                    },
+                    VolumeKind::Tmpfs => bollard::models::Mount {
+                        target: Some(volume.target.clone()),
+                        typ: Some(bollard::models::MountTypeEnum::TMPFS),
+                        tmpfs_options: Some(bollard::models::MountTmpfsOptions {
+                            size_bytes: volume.size_bytes()?,
+                            mode: None,
+                        }),
+                        ..Default::default()
+                    },
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let port_bindings = parse_ports(&config.ports)?;
+        let restart_policy = config.restart.as_deref().map(parse_restart_policy).transpose()?;
+        let ulimits = parse_ulimits(&config.ulimits)?;
+        let devices = parse_devices(&config.devices)?;
+
         // Build host config
         let mut host_config = bollard::models::HostConfig {
             binds: Some(binds),
-            auto_remove: Some(config.remove_on_exit && config.detach),
+            mounts: (!mounts.is_empty()).then_some(mounts),
+            // Docker refuses auto-removal together with a restart policy
+            auto_remove: Some(config.remove_on_exit && config.detach && restart_policy.is_none()),
+            group_add: (!config.group_add.is_empty()).then(|| config.group_add.clone()),
+            port_bindings: (!port_bindings.is_empty()).then(|| port_bindings.clone()),
+            restart_policy,
+            ulimits: (!ulimits.is_empty()).then_some(ulimits),
+            devices: (!devices.is_empty()).then_some(devices),
+            cap_add: (!config.cap_add.is_empty()).then(|| config.cap_add.clone()),
+            cap_drop: (!config.cap_drop.is_empty()).then(|| config.cap_drop.clone()),
+            readonly_rootfs: config.read_only.then_some(true),
+            security_opt: (!config.security_opt.is_empty()).then(|| config.security_opt.clone()),
+            privileged: config.privileged.then_some(true),
+            memory: config.memory,
+            nano_cpus: config.nano_cpus,
+            network_mode: config.network.clone(),
             ..Default::default()
         };
 
@@ -462,29 +1016,75 @@ impl DockerClient {
         // Metal (macOS) does not require Docker-level GPU passthrough.
         if let Some(gpu_config) = &config.gpu_config {
             if gpu_config.is_nvidia() {
-                host_config.device_requests = Some(vec![bollard::models::DeviceRequest {
-                    driver: Some("nvidia".to_string()),
-                    count: Some(-1), // All available GPUs
-                    capabilities: Some(vec![vec!["gpu".to_string()]]),
-                    ..Default::default()
-                }]);
+                let request = if Settings::load().is_ok_and(|s| s.gpu_cdi) {
+                    // Devices of the spec written by `geoengine gpu cdi-generate`
+                    bollard::models::DeviceRequest {
+                        driver: Some("cdi".to_string()),
+                        device_ids: Some(gpu::cdi_device_names(gpu_config.device_ids.as_deref())),
+                        ..Default::default()
+                    }
+                } else {
+                    bollard::models::DeviceRequest {
+                        driver: Some("nvidia".to_string()),
+                        count: match gpu_config.device_ids {
+                            Some(_) => None,
+                            None => Some(-1), // All available GPUs
+                        },
+                        device_ids: gpu_config.device_ids.clone(),
+                        capabilities: Some(vec![vec!["gpu".to_string()]]),
+                        ..Default::default()
+                    }
+                };
+                host_config.device_requests = Some(vec![request]);
 
                 // Add NVIDIA env vars
-                env.push("NVIDIA_VISIBLE_DEVICES=all".to_string());
+                env.push(format!(
+                    "NVIDIA_VISIBLE_DEVICES={}",
+                    gpu_config.device_ids.as_ref().map(|ids| ids.join(",")).unwrap_or_else(|| "all".to_string())
+                ));
                 env.push("NVIDIA_DRIVER_CAPABILITIES=compute,utility".to_string());
+            } else if gpu_config.is_metal() {
+                // Let PyTorch fall back to CPU for ops the MPS backend lacks
+                env.push("PYTORCH_ENABLE_MPS_FALLBACK=1".to_string());
             }
         }
 
+        // Attached containers record their owner so the reaper can remove them after a crash
+        let mut container_labels = config.labels.clone();
+        if !config.detach {
+            container_labels.insert(labels::OWNER_PID.to_string(), std::process::id().to_string());
+            container_labels.insert(labels::OWNER_HOST.to_string(), process::hostname());
+        }
+        if let Some(ids) = config.gpu_config.as_ref().filter(|g| g.is_nvidia()).and_then(|g| g.device_ids.as_ref()) {
+            container_labels.insert(labels::GPU_DEVICES.to_string(), ids.join(","));
+        }
+
         let container_config = Config {
             image: Some(config.image.clone()),
+            entrypoint: config.entrypoint.clone(),
             cmd: config.command.clone(),
             env: Some(env),
             working_dir: config.workdir.clone(),
+            user: config.user.clone(),
+            labels: Some(container_labels),
+            exposed_ports: (!port_bindings.is_empty())
+                .then(|| port_bindings.keys().map(|port| (port.clone(), HashMap::new())).collect()),
             tty: Some(config.tty),
             attach_stdin: Some(!config.detach),
             attach_stdout: Some(!config.detach),
             attach_stderr: Some(!config.detach),
             host_config: Some(host_config),
+            networking_config: config.network.as_ref().filter(|_| !config.network_aliases.is_empty()).map(|network| {
+                bollard::container::NetworkingConfig {
+                    endpoints_config: HashMap::from([(
+                        network.clone(),
+                        bollard::models::EndpointSettings {
+                            aliases: Some(config.network_aliases.clone()),
+                            ..Default::default()
+                        },
+                    )]),
+                }
+            }),
             ..Default::default()
         };
 
@@ -497,18 +1097,457 @@ impl DockerClient {
             .docker
             .create_container(options, container_config)
             .await?;
+        // Docker discards limits the kernel can't enforce (e.g. without the memory cgroup) with a warning
+        for warning in &response.warnings {
+            eprintln!("{} Docker: {}", "!".yellow().bold(), warning);
+        }
 
         Ok(response.id)
     }
 
-    /// Stop a running container
-    pub async fn stop_container(&self, container_id: &str) -> Result<()> {
+    /// Stop a running container, giving it `timeout_secs` to exit before it is killed
+    pub async fn stop_container(&self, container_id: &str, timeout_secs: i64) -> Result<()> {
         self.docker
-            .stop_container(container_id, None)
+            .stop_container(container_id, Some(StopContainerOptions { t: timeout_secs }))
             .await?;
         Ok(())
     }
 
+    /// Stop and remove a container after the user interrupted a run
+    async fn shutdown_container(&self, container_id: &str) {
+        eprintln!("\nInterrupted, stopping container (up to {}s)...", STOP_TIMEOUT_SECS);
+        if let Err(e) = self.stop_container(container_id, STOP_TIMEOUT_SECS).await {
+            tracing::warn!("Failed to stop container {}: {}", container_id, e);
+        }
+        if let Err(e) = self.remove_container(container_id, true).await {
+            tracing::warn!("Failed to remove container {}: {}", container_id, e);
+        }
+    }
+
+    /// Check whether a container is running and, if it defines a healthcheck, healthy.
+    /// Returns a description of its state when it is not ready.
+    pub async fn container_ready(&self, name: &str) -> Result<std::result::Result<(), String>> {
+        let inspect = self
+            .docker
+            .inspect_container(name, None)
+            .await
+            .with_context(|| format!("Container not found: {}", name))?;
+        let state = inspect.state.unwrap_or_default();
+        if state.running != Some(true) {
+            return Ok(Err(format!(
+                "container is {}",
+                state.status.map(|s| s.to_string()).unwrap_or_else(|| "not running".to_string())
+            )));
+        }
+        match state.health.and_then(|h| h.status) {
+            Some(bollard::models::HealthStatusEnum::HEALTHY)
+            | Some(bollard::models::HealthStatusEnum::NONE)
+            | Some(bollard::models::HealthStatusEnum::EMPTY)
+            | None => Ok(Ok(())),
+            Some(status) => Ok(Err(format!("container health is {}", status))),
+        }
+    }
+
+    /// List running containers created from worker images (identified by the worker label)
+    pub async fn list_worker_containers(&self) -> Result<Vec<WorkerContainer>> {
+        self.find_worker_containers(vec![labels::WORKER.to_string()], false).await
+    }
+
+    /// List a worker's service and detached-run containers, including stopped ones
+    pub async fn list_service_containers(&self, worker: &str) -> Result<Vec<WorkerContainer>> {
+        let filters = vec![format!("{}={}", labels::WORKER, worker), labels::SERVICE.to_string()];
+        self.find_worker_containers(filters, true).await
+    }
+
+    /// GPU indices and MIG UUIDs handed to running geoengine containers
+    pub async fn busy_gpu_devices(&self) -> Result<Vec<String>> {
+        let mut filters = HashMap::new();
+        filters.insert("label".to_string(), vec![labels::GPU_DEVICES.to_string()]);
+        let options = bollard::container::ListContainersOptions {
+            filters,
+            ..Default::default()
+        };
+
+        let containers = self.docker.list_containers(Some(options)).await?;
+        Ok(containers
+            .into_iter()
+            .filter_map(|c| c.labels?.get(labels::GPU_DEVICES).cloned())
+            .flat_map(|ids| ids.split(',').map(String::from).collect::<Vec<_>>())
+            .collect())
+    }
+
+    /// Containers started attached by a geoengine process, running or not
+    pub async fn list_owned_containers(&self) -> Result<Vec<OwnedContainer>> {
+        let mut filters = HashMap::new();
+        filters.insert("label".to_string(), vec![labels::OWNER_PID.to_string()]);
+        let options = bollard::container::ListContainersOptions {
+            all: true,
+            filters,
+            ..Default::default()
+        };
+
+        let containers = self.docker.list_containers(Some(options)).await?;
+        Ok(containers
+            .into_iter()
+            .filter_map(|c| {
+                let container_labels = c.labels.unwrap_or_default();
+                Some(OwnedContainer {
+                    id: c.id.unwrap_or_default(),
+                    name: c
+                        .names
+                        .and_then(|n| n.first().cloned())
+                        .unwrap_or_default()
+                        .trim_start_matches('/')
+                        .to_string(),
+                    owner_pid: container_labels.get(labels::OWNER_PID)?.parse().ok()?,
+                    owner_host: container_labels.get(labels::OWNER_HOST).cloned().unwrap_or_default(),
+                    running: c.state.as_deref() == Some("running"),
+                })
+            })
+            .collect())
+    }
+
+    async fn find_worker_containers(&self, label_filters: Vec<String>, all: bool) -> Result<Vec<WorkerContainer>> {
+        let mut filters = HashMap::new();
+        filters.insert("label".to_string(), label_filters);
+        let options = bollard::container::ListContainersOptions {
+            all,
+            filters,
+            ..Default::default()
+        };
+
+        let containers = self.docker.list_containers(Some(options)).await?;
+        Ok(containers
+            .into_iter()
+            .map(|c| {
+                let container_labels = c.labels.unwrap_or_default();
+                WorkerContainer {
+                    id: c.id.unwrap_or_default(),
+                    name: c
+                        .names
+                        .and_then(|n| n.first().cloned())
+                        .unwrap_or_default()
+                        .trim_start_matches('/')
+                        .to_string(),
+                    worker: container_labels.get(labels::WORKER).cloned().unwrap_or_default(),
+                    image: c.image.unwrap_or_default(),
+                    status: c.status.unwrap_or_default(),
+                    service: container_labels.get(labels::SERVICE).cloned(),
+                }
+            })
+            .collect())
+    }
+
+    /// Whether a container exists and is running; None when there is no such container
+    pub async fn container_running(&self, name: &str) -> Result<Option<bool>> {
+        match self.docker.inspect_container(name, None).await {
+            Ok(inspect) => Ok(Some(inspect.state.and_then(|s| s.running).unwrap_or(false))),
+            Err(bollard::errors::Error::DockerResponseServerError { status_code: 404, .. }) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Print a container's logs to stdout, optionally following new output until Ctrl-C
+    pub async fn print_logs(&self, name: &str, follow: bool, tail: Option<usize>) -> Result<()> {
+        let log_options = LogsOptions::<String> {
+            follow,
+            stdout: true,
+            stderr: true,
+            timestamps: false,
+            tail: tail.map(|n| n.to_string()).unwrap_or_else(|| "all".to_string()),
+            ..Default::default()
+        };
+        let mut log_stream = self.docker.logs(name, Some(log_options));
+
+        tokio::select! {
+            result = async {
+                while let Some(result) = log_stream.next().await {
+                    print!("{}", result.with_context(|| format!("Failed to read logs of {}", name))?);
+                }
+                Ok::<(), anyhow::Error>(())
+            } => result,
+            _ = shutdown_signal() => Ok(()),
+        }
+    }
+
+    /// Stream a running container's output until it exits (returning its exit code)
+    /// or the user presses Ctrl-C (returning None, leaving the container running)
+    pub async fn attach_container(&self, name: &str) -> Result<Option<i64>> {
+        let options = bollard::container::AttachContainerOptions::<String> {
+            stdout: Some(true),
+            stderr: Some(true),
+            stream: Some(true),
+            logs: Some(false),
+            ..Default::default()
+        };
+        let mut attached = self
+            .docker
+            .attach_container(name, Some(options))
+            .await
+            .with_context(|| format!("Failed to attach to {}", name))?;
+
+        let detached = tokio::select! {
+            _ = async {
+                while let Some(Ok(output)) = attached.output.next().await {
+                    print!("{}", output);
+                }
+            } => false,
+            _ = shutdown_signal() => true,
+        };
+        if detached {
+            return Ok(None);
+        }
+
+        let wait_options = WaitContainerOptions {
+            condition: "not-running",
+        };
+        let exit_code = match self.docker.wait_container(name, Some(wait_options)).next().await {
+            Some(Ok(response)) => response.status_code,
+            // Containers that were already stopped report their exit code via inspect
+            _ => self
+                .docker
+                .inspect_container(name, None)
+                .await?
+                .state
+                .and_then(|s| s.exit_code)
+                .unwrap_or(-1),
+        };
+        Ok(Some(exit_code))
+    }
+
+    /// Write a tar archive of a file or directory in a container to `dest`, counting bytes on `pb`
+    pub async fn download_path(&self, container: &str, path: &str, dest: &Path, pb: &ProgressBar) -> Result<()> {
+        let options = bollard::container::DownloadFromContainerOptions { path };
+        let mut stream = self.docker.download_from_container(container, Some(options));
+        let mut file = tokio::fs::File::create(dest)
+            .await
+            .with_context(|| format!("Failed to create {}", dest.display()))?;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|e| match e {
+                bollard::errors::Error::DockerResponseServerError { status_code: 404, .. } => {
+                    anyhow::anyhow!("No such file or directory in {}: {}", container, path)
+                }
+                e => anyhow::anyhow!("Failed to copy {} from {}: {}", path, container, e),
+            })?;
+            file.write_all(&chunk).await?;
+            pb.inc(chunk.len() as u64);
+        }
+        file.flush().await?;
+        Ok(())
+    }
+
+    /// Extract a tar archive, streamed as it is written, into a directory of a container
+    pub async fn upload_archive(
+        &self,
+        container: &str,
+        dir: &str,
+        archive: impl futures::Stream<Item = bytes::Bytes> + Send + 'static,
+    ) -> Result<()> {
+        let options = bollard::container::UploadToContainerOptions {
+            path: dir,
+            ..Default::default()
+        };
+        self.docker
+            .upload_to_container_streaming(container, Some(options), archive)
+            .await
+            .with_context(|| format!("Failed to copy into {}:{}", container, dir))
+    }
+
+    /// Whether `path` is an existing directory in a container. Only the first
+    /// header of its archive is read, so this works on read-only and stopped
+    /// containers and does not download the contents.
+    pub async fn is_container_dir(&self, container: &str, path: &str) -> bool {
+        let options = bollard::container::DownloadFromContainerOptions { path };
+        let mut stream = self.docker.download_from_container(container, Some(options));
+        let mut buf: Vec<u8> = Vec::new();
+        let mut offset = 0;
+        loop {
+            while buf.len() < offset + 512 {
+                match stream.next().await {
+                    Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                    _ => return false,
+                }
+            }
+            let header = tar::Header::from_byte_slice(&buf[offset..offset + 512]);
+            let kind = header.entry_type();
+            // Long names and PAX attributes come as entries of their own before the real one
+            if kind.is_pax_global_extensions()
+                || kind.is_pax_local_extensions()
+                || kind.is_gnu_longname()
+                || kind.is_gnu_longlink()
+            {
+                let size = header.entry_size().unwrap_or(0) as usize;
+                offset += 512 + size.div_ceil(512) * 512;
+                continue;
+            }
+            return kind.is_dir();
+        }
+    }
+
+    /// Sample a container's CPU and memory usage, computed the same way as `docker stats`
+    pub async fn container_usage(&self, container_id: &str) -> Result<ContainerUsage> {
+        let options = bollard::container::StatsOptions {
+            stream: false,
+            one_shot: false,
+        };
+        let stats = self
+            .docker
+            .stats(container_id, Some(options))
+            .next()
+            .await
+            .context("No stats returned")??;
+
+        let cpu_delta = stats
+            .cpu_stats
+            .cpu_usage
+            .total_usage
+            .saturating_sub(stats.precpu_stats.cpu_usage.total_usage) as f64;
+        let system_delta = stats
+            .cpu_stats
+            .system_cpu_usage
+            .unwrap_or(0)
+            .saturating_sub(stats.precpu_stats.system_cpu_usage.unwrap_or(0)) as f64;
+        let cpus = stats
+            .cpu_stats
+            .online_cpus
+            .or_else(|| stats.cpu_stats.cpu_usage.percpu_usage.as_ref().map(|p| p.len() as u64))
+            .unwrap_or(1) as f64;
+        let cpu_percent = if system_delta > 0.0 {
+            cpu_delta / system_delta * cpus * 100.0
+        } else {
+            0.0
+        };
+
+        Ok(ContainerUsage {
+            cpu_percent,
+            memory_bytes: usage::memory_in_use(&stats),
+            memory_limit: stats.memory_stats.limit.unwrap_or(0),
+        })
+    }
+
+    /// Create a named volume unless it already exists, labelled with the worker that
+    /// declared it. Returns true if the volume was created.
+    pub async fn ensure_volume(&self, volume: &VolumeConfig, worker: &str) -> Result<bool> {
+        let name = volume
+            .name
+            .as_deref()
+            .with_context(|| format!("Volume mounted at {} has no name", volume.target))?;
+        if self.docker.inspect_volume(name).await.is_ok() {
+            return Ok(false);
+        }
+
+        let options = bollard::volume::CreateVolumeOptions {
+            name: name.to_string(),
+            driver: volume.driver.clone().unwrap_or_else(|| "local".to_string()),
+            driver_opts: volume
+                .driver_opts
+                .iter()
+                .flatten()
+                .map(|(k, v)| (k.clone(), v.clone()))
+                .collect(),
+            labels: HashMap::from([(labels::WORKER.to_string(), worker.to_string())]),
+        };
+        self.docker
+            .create_volume(options)
+            .await
+            .with_context(|| format!("Failed to create volume {}", name))?;
+        Ok(true)
+    }
+
+    /// List the volumes geoengine created for workers
+    pub async fn list_volumes(&self) -> Result<Vec<VolumeInfo>> {
+        let options = bollard::volume::ListVolumesOptions {
+            filters: HashMap::from([("label".to_string(), vec![labels::WORKER.to_string()])]),
+        };
+        let response = self.docker.list_volumes(Some(options)).await?;
+
+        let mut volumes: Vec<VolumeInfo> = response
+            .volumes
+            .unwrap_or_default()
+            .into_iter()
+            .map(|v| VolumeInfo {
+                worker: v.labels.get(labels::WORKER).cloned().unwrap_or_default(),
+                name: v.name,
+                driver: v.driver,
+                created: v.created_at.unwrap_or_default(),
+            })
+            .collect();
+        volumes.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(volumes)
+    }
+
+    /// Remove a volume
+    pub async fn remove_volume(&self, name: &str, force: bool) -> Result<()> {
+        self.docker
+            .remove_volume(name, Some(bollard::volume::RemoveVolumeOptions { force }))
+            .await
+            .with_context(|| format!("Failed to remove volume {}", name))?;
+        Ok(())
+    }
+
+    /// Remove geoengine volumes not used by any container. Returns the removed
+    /// volume names and the reclaimed space in bytes.
+    pub async fn prune_volumes(&self) -> Result<(Vec<String>, i64)> {
+        // Since API 1.42 prune only touches anonymous volumes unless all=true
+        let options = bollard::volume::PruneVolumesOptions {
+            filters: HashMap::from([
+                ("label".to_string(), vec![labels::WORKER.to_string()]),
+                ("all".to_string(), vec!["true".to_string()]),
+            ]),
+        };
+        let response = self.docker.prune_volumes(Some(options)).await?;
+        Ok((
+            response.volumes_deleted.unwrap_or_default(),
+            response.space_reclaimed.unwrap_or(0),
+        ))
+    }
+
+    pub async fn network_exists(&self, name: &str) -> bool {
+        self.docker
+            .inspect_network(name, None::<bollard::network::InspectNetworkOptions<String>>)
+            .await
+            .is_ok()
+    }
+
+    /// Create a worker's bridge network unless it exists. Returns whether it was created.
+    pub async fn ensure_network(&self, name: &str, worker: &str) -> Result<bool> {
+        if self.network_exists(name).await {
+            return Ok(false);
+        }
+        let options = bollard::network::CreateNetworkOptions {
+            name: name.to_string(),
+            driver: "bridge".to_string(),
+            check_duplicate: true,
+            labels: HashMap::from([(labels::WORKER.to_string(), worker.to_string())]),
+            ..Default::default()
+        };
+        self.docker
+            .create_network(options)
+            .await
+            .with_context(|| format!("Failed to create network {}", name))?;
+        Ok(true)
+    }
+
+    /// Remove a network; fails while containers are still attached to it
+    pub async fn remove_network(&self, name: &str) -> Result<()> {
+        self.docker
+            .remove_network(name)
+            .await
+            .with_context(|| format!("Failed to remove network {}", name))?;
+        Ok(())
+    }
+
+    /// Whether the kernel's OOM killer stopped the container (false if it can't be inspected)
+    async fn oom_killed(&self, container_id: &str) -> bool {
+        self.docker
+            .inspect_container(container_id, None)
+            .await
+            .ok()
+            .and_then(|c| c.state)
+            .and_then(|s| s.oom_killed)
+            .unwrap_or(false)
+    }
+
     /// Remove a container
     pub async fn remove_container(&self, container_id: &str, force: bool) -> Result<()> {
         let options = bollard::container::RemoveContainerOptions {
@@ -519,3 +1558,58 @@ impl DockerClient {
         Ok(())
     }
 }
+
+/// Where `export_images` writes the archive until it is complete
+pub fn partial_export_path(output: &Path) -> PathBuf {
+    let mut name = output.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    output.with_file_name(name)
+}
+
+/// Delete what an interrupted export left behind for `output`
+pub fn remove_partial_export(output: &Path) {
+    let partial = partial_export_path(output);
+    std::fs::remove_file(export_record_path(&partial)).ok();
+    std::fs::remove_file(partial).ok();
+}
+
+/// Images (names and IDs) a partial export holds, so only an export of the same
+/// images resumes it
+fn export_record_path(partial: &Path) -> PathBuf {
+    let mut name = partial.file_name().unwrap_or_default().to_os_string();
+    name.push(".images");
+    partial.with_file_name(name)
+}
+
+/// Resolve when the process receives Ctrl-C or (on Unix) SIGTERM
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut term) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = term.recv() => {}
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+/// Error for a failed push, classified as an authentication failure when the registry says so
+fn push_error(message: String) -> anyhow::Error {
+    if super::registry::is_auth_failure(&message) {
+        GeoEngineError::RegistryAuth(format!("Push failed: {}", message)).into()
+    } else {
+        anyhow::anyhow!("Push failed: {}", message)
+    }
+}
@@ -1,16 +1,168 @@
+use anyhow::Result;
+use bollard::models::{DeviceMapping, PortBinding, ResourcesUlimits, RestartPolicy, RestartPolicyNameEnum};
 use std::collections::HashMap;
 
+use crate::config::worker::VolumeConfig;
 use crate::docker::gpu::GpuConfig;
 
+#[derive(Clone)]
 pub struct ContainerConfig {
     pub image: String,
+    pub entrypoint: Option<Vec<String>>, // [""] clears the image's ENTRYPOINT
     pub command: Option<Vec<String>>,
     pub env_vars: HashMap<String, String>,
     pub mounts: Vec<(String, String, bool)>, // (host, container, readonly)
+    pub volumes: Vec<VolumeConfig>,
     pub gpu_config: Option<GpuConfig>,
+    pub user: Option<String>, // USER[:GROUP], None keeps the image's USER
+    pub group_add: Vec<String>,
     pub workdir: Option<String>,
     pub name: Option<String>,
+    pub labels: HashMap<String, String>,
+    pub ports: Vec<String>, // [IP:]HOST_PORT:CONTAINER_PORT[/PROTOCOL]
+    pub restart: Option<String>,
+    pub ulimits: Vec<String>, // NAME=SOFT[:HARD]
+    pub cap_add: Vec<String>,
+    pub cap_drop: Vec<String>,
+    pub devices: Vec<String>, // HOST[:CONTAINER[:PERMISSIONS]]
+    pub read_only: bool,
+    pub security_opt: Vec<String>, // e.g. no-new-privileges:true, apparmor=PROFILE
+    pub privileged: bool,
+    pub memory: Option<i64>, // bytes
+    pub nano_cpus: Option<i64>, // CPUs * 1e9
+    pub network: Option<String>, // user-defined network to join instead of the default bridge
+    pub network_aliases: Vec<String>, // names other containers on `network` reach this one by
     pub remove_on_exit: bool,
     pub detach: bool,
     pub tty: bool,
 }
+
+/// Parse a Docker restart policy: no, always, unless-stopped or on-failure[:MAX_RETRIES]
+pub fn parse_restart_policy(policy: &str) -> Result<RestartPolicy> {
+    let (name, retries) = match policy.split_once(':') {
+        Some((name, retries)) => (name, Some(retries)),
+        None => (policy, None),
+    };
+    let name = match name {
+        "no" => RestartPolicyNameEnum::NO,
+        "always" => RestartPolicyNameEnum::ALWAYS,
+        "unless-stopped" => RestartPolicyNameEnum::UNLESS_STOPPED,
+        "on-failure" => RestartPolicyNameEnum::ON_FAILURE,
+        _ => anyhow::bail!(
+            "Invalid restart policy '{}'. Expected no, always, unless-stopped or on-failure[:N]",
+            policy
+        ),
+    };
+    let maximum_retry_count = match retries {
+        Some(_) if name != RestartPolicyNameEnum::ON_FAILURE => {
+            anyhow::bail!("Only on-failure accepts a retry count: '{}'", policy)
+        }
+        Some(n) => Some(
+            n.parse::<i64>()
+                .map_err(|_| anyhow::anyhow!("Invalid retry count in restart policy '{}'", policy))?,
+        ),
+        None => None,
+    };
+    Ok(RestartPolicy {
+        name: Some(name),
+        maximum_retry_count,
+    })
+}
+
+/// Parse published ports into Docker's exposed ports and port bindings
+pub fn parse_ports(ports: &[String]) -> Result<HashMap<String, Option<Vec<PortBinding>>>> {
+    let mut bindings: HashMap<String, Option<Vec<PortBinding>>> = HashMap::new();
+    for port in ports {
+        let (spec, protocol) = port.split_once('/').unwrap_or((port.as_str(), "tcp"));
+        let parts: Vec<&str> = spec.rsplitn(3, ':').collect();
+        let (host_ip, host_port, container_port) = match parts.as_slice() {
+            [container] => (None, None, *container),
+            [container, host] => (None, Some(*host), *container),
+            [container, host, ip] => (Some(*ip), Some(*host), *container),
+            _ => unreachable!(),
+        };
+        if container_port.parse::<u16>().is_err() || host_port.is_some_and(|p| p.parse::<u16>().is_err()) {
+            anyhow::bail!(
+                "Invalid port '{}'. Expected [IP:]HOST_PORT:CONTAINER_PORT[/PROTOCOL]",
+                port
+            );
+        }
+        bindings
+            .entry(format!("{}/{}", container_port, protocol))
+            .or_insert_with(|| Some(Vec::new()))
+            .get_or_insert_with(Vec::new)
+            .push(PortBinding {
+                host_ip: host_ip.map(|ip| ip.trim_matches(['[', ']']).to_string()),
+                host_port: host_port.map(String::from),
+            });
+    }
+    Ok(bindings)
+}
+
+/// Parse ulimits given as NAME=SOFT[:HARD], e.g. nofile=65536 or nproc=1024:2048
+pub fn parse_ulimits(ulimits: &[String]) -> Result<Vec<ResourcesUlimits>> {
+    ulimits
+        .iter()
+        .map(|ulimit| {
+            let invalid = || anyhow::anyhow!("Invalid ulimit '{}'. Expected NAME=SOFT[:HARD]", ulimit);
+            let (name, limits) = ulimit.split_once('=').ok_or_else(invalid)?;
+            let (soft, hard) = limits.split_once(':').unwrap_or((limits, limits));
+            Ok(ResourcesUlimits {
+                name: Some(name.to_string()),
+                soft: Some(soft.parse().map_err(|_| invalid())?),
+                hard: Some(hard.parse().map_err(|_| invalid())?),
+            })
+        })
+        .collect()
+}
+
+/// Parse a bind mount given as HOST:CONTAINER[:ro|rw] into (host, container, readonly).
+/// The host side may be a Windows path (`C:\data`, `C:/data`, `\\server\share`) or
+/// contain spaces; the container side is the absolute path after the last `:/`.
+pub fn parse_mount(spec: &str) -> Result<(String, String, bool)> {
+    let invalid = || {
+        anyhow::anyhow!(
+            "Invalid mount '{}'. Expected HOST:CONTAINER[:ro|rw] with an absolute container path",
+            spec
+        )
+    };
+    let (rest, readonly) = match spec.rsplit_once(':') {
+        Some((rest, "ro")) => (rest, true),
+        Some((rest, "rw")) => (rest, false),
+        _ => (spec, false),
+    };
+    let split = rest.rfind(":/").ok_or_else(invalid)?;
+    let (host, container) = (&rest[..split], &rest[split + 1..]);
+    // `C:/data` without a container path splits right after the drive letter
+    let drive_only = host.len() == 1 && host.chars().all(|c| c.is_ascii_alphabetic());
+    if host.is_empty() || drive_only {
+        return Err(invalid());
+    }
+    Ok((host.to_string(), container.to_string(), readonly))
+}
+
+/// Parse device mappings given as HOST[:CONTAINER[:PERMISSIONS]], e.g. /dev/ttyUSB0 or /dev/fuse:/dev/fuse:rwm
+pub fn parse_devices(devices: &[String]) -> Result<Vec<DeviceMapping>> {
+    devices
+        .iter()
+        .map(|device| {
+            let parts: Vec<&str> = device.splitn(3, ':').collect();
+            let (host, container, permissions) = match parts.as_slice() {
+                [host] => (*host, *host, "rwm"),
+                // HOST:PERMISSIONS is accepted like the docker CLI does
+                [host, perms] if !perms.starts_with('/') => (*host, *host, *perms),
+                [host, container] => (*host, *container, "rwm"),
+                [host, container, perms] => (*host, *container, *perms),
+                _ => unreachable!(),
+            };
+            if host.is_empty() || !permissions.chars().all(|c| "rwm".contains(c)) {
+                anyhow::bail!("Invalid device '{}'. Expected HOST[:CONTAINER[:PERMISSIONS]]", device);
+            }
+            Ok(DeviceMapping {
+                path_on_host: Some(host.to_string()),
+                path_in_container: Some(container.to_string()),
+                cgroup_permissions: Some(permissions.to_string()),
+            })
+        })
+        .collect()
+}
@@ -39,12 +39,14 @@ pub fn get_dockerfile_config(current_dir: &PathBuf, config: &mut WorkerConfig) -
                         program: parts[0].to_string(),
                         script: parts[1..].join(" "),
                         inputs: config.command.as_ref().and_then(|c| c.inputs.clone()),
+                        check: config.command.as_ref().and_then(|c| c.check.clone()),
                     });
                 } else if parts.len() == 1 {
                     config.command = Some(CommandConfig {
                         program: parts[0].to_string(),
                         script: String::new(),
                         inputs: config.command.as_ref().and_then(|c| c.inputs.clone()),
+                        check: config.command.as_ref().and_then(|c| c.check.clone()),
                     });
                 }
             },
@@ -59,6 +61,62 @@ pub fn get_dockerfile_config(current_dir: &PathBuf, config: &mut WorkerConfig) -
     Ok(())
 }
 
+/// Collect the external base images referenced by `FROM` instructions in a Dockerfile.
+/// Build-stage aliases, `scratch`, and images using build-arg substitution are skipped.
+pub fn get_base_images(dockerfile: &Path) -> Result<Vec<String>> {
+    let file = File::open(dockerfile)?;
+    let mut stages: Vec<String> = Vec::new();
+    let mut images: Vec<String> = Vec::new();
+
+    for line in io::BufReader::new(file).lines() {
+        let line = line?;
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.first().map(|w| w.eq_ignore_ascii_case("FROM")) != Some(true) {
+            continue;
+        }
+        let args: Vec<&str> = words[1..]
+            .iter()
+            .copied()
+            .filter(|w| !w.starts_with("--"))
+            .collect();
+        let Some(image) = args.first() else { continue };
+        if let (Some(kw), Some(alias)) = (args.get(1), args.get(2)) {
+            if kw.eq_ignore_ascii_case("AS") {
+                stages.push(alias.to_string());
+            }
+        }
+        let image = image.to_string();
+        if image == "scratch" || image.contains('$') || stages.contains(&image) || images.contains(&image) {
+            continue;
+        }
+        images.push(image);
+    }
+
+    Ok(images)
+}
+
+/// The Dockerfile cut off after the stage named `target`, which makes that stage
+/// the final image. None if no `FROM ... AS <target>` names it.
+pub fn truncate_to_stage(content: &str, target: &str) -> Option<String> {
+    let mut kept: Vec<&str> = Vec::new();
+    let mut in_target = false;
+    for line in content.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        if words.first().map(|w| w.eq_ignore_ascii_case("FROM")) == Some(true) {
+            if in_target {
+                break;
+            }
+            let args: Vec<&str> = words[1..].iter().copied().filter(|w| !w.starts_with("--")).collect();
+            in_target = matches!(
+                (args.get(1), args.get(2)),
+                (Some(kw), Some(alias)) if kw.eq_ignore_ascii_case("AS") && alias.eq_ignore_ascii_case(target)
+            );
+        }
+        kept.push(line);
+    }
+    in_target.then(|| kept.join("\n") + "\n")
+}
+
 fn map_dockerfile_line(line: &str, last: bool, entry: bool) -> Option<(String, String)> {
     let words: Vec<&str> = line.trim().split_whitespace().collect();
     if words.is_empty() {
@@ -0,0 +1,344 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::client::DockerClient;
+use super::config::ContainerConfig;
+use super::registry;
+use crate::config::settings::Settings;
+
+/// Official GDAL image with the command line utilities, used when `gdal_image` is not set
+pub const DEFAULT_GDAL_IMAGE: &str = "ghcr.io/osgeo/gdal:alpine-small-latest";
+
+/// Separates the per-file reports in the utility container's output
+const MARKER: &str = "@@geoengine-file";
+
+/// Georeferencing and layout of a raster or vector output file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GeoMetadata {
+    /// `raster` or `vector`
+    pub kind: String,
+    /// GDAL/OGR driver name (e.g. GTiff, GPKG)
+    pub driver: Option<String>,
+    /// `EPSG:<code>` when known, otherwise the CRS name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub crs: Option<String>,
+    /// [minx, miny, maxx, maxy] in the file's CRS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub extent: Option<[f64; 4]>,
+    /// [minx, miny, maxx, maxy] in WGS84 longitude/latitude
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wgs84_extent: Option<[f64; 4]>,
+    /// Raster width and height in pixels
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<[u64; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub band_count: Option<usize>,
+    /// Pixel width and height in CRS units
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pixel_size: Option<[f64; 2]>,
+    /// Geometry type of the first layer (vector)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub geometry_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub feature_count: Option<u64>,
+    /// Layer names (vector)
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub layers: Vec<String>,
+}
+
+/// Run gdalinfo (and ogrinfo for files GDAL can't open as rasters) on `files` in a
+/// single GDAL utility container. Returns one entry per file, None for files that
+/// are not geospatial.
+pub async fn extract(client: &DockerClient, files: &[PathBuf]) -> Result<Vec<Option<GeoMetadata>>> {
+    if files.is_empty() {
+        return Ok(Vec::new());
+    }
+    let image = gdal_image(client).await?;
+    let (dirs, container_paths) = data_mounts(files);
+
+    let script: Vec<String> = container_paths
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let path = shell_quote(path);
+            format!(
+                "echo '{marker} {i} raster'; gdalinfo -json {path} 2>/dev/null || \
+                 {{ echo '{marker} {i} vector'; ogrinfo -json -so -al -ro {path} 2>/dev/null; }}",
+                marker = MARKER,
+                i = i,
+                path = path
+            )
+        })
+        .collect();
+
+    let log = tempfile_path()?;
+    let config = ContainerConfig {
+        image,
+        entrypoint: None,
+        command: Some(vec!["/bin/sh".to_string(), "-c".to_string(), script.join("; ")]),
+        env_vars: HashMap::new(),
+        mounts: dirs
+            .iter()
+            .enumerate()
+            .map(|(i, dir)| (dir.display().to_string(), format!("/data/{}", i), true))
+            .collect(),
+        volumes: Vec::new(),
+        gpu_config: None,
+        user: None,
+        group_add: Vec::new(),
+        workdir: None,
+        name: None,
+        labels: HashMap::new(),
+        ports: Vec::new(),
+        restart: None,
+        ulimits: Vec::new(),
+        cap_add: Vec::new(),
+        cap_drop: Vec::new(),
+        devices: Vec::new(),
+        read_only: false,
+        security_opt: Vec::new(),
+        privileged: false,
+        memory: None,
+        nano_cpus: None,
+        network: None,
+        network_aliases: Vec::new(),
+        remove_on_exit: true,
+        detach: false,
+        tty: false,
+    };
+    let result = client.run_container_attached_to_file(&config, &log).await;
+    let output = std::fs::read_to_string(&log).unwrap_or_default();
+    let _ = std::fs::remove_file(&log);
+    result.context("Failed to run the GDAL utility container")?;
+
+    Ok(parse_reports(&output, files.len()))
+}
+
+/// The GDAL utility image (`gdal_image` in settings), pulled if it isn't present
+pub async fn gdal_image(client: &DockerClient) -> Result<String> {
+    let image = Settings::load()?
+        .gdal_image
+        .unwrap_or_else(|| DEFAULT_GDAL_IMAGE.to_string());
+    if client.image_id(&image).await.is_err() {
+        registry::pull(client, &image).await?;
+    }
+    Ok(image)
+}
+
+/// Mount each distinct parent directory of `files` once, at `/data/<index>`.
+/// Returns the directories and the path of each file inside the container.
+pub fn data_mounts(files: &[PathBuf]) -> (Vec<PathBuf>, Vec<String>) {
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    let mut container_paths: Vec<String> = Vec::new();
+    for file in files {
+        let dir = file.parent().map(Path::to_path_buf).unwrap_or_default();
+        let index = match dirs.iter().position(|d| *d == dir) {
+            Some(i) => i,
+            None => {
+                dirs.push(dir);
+                dirs.len() - 1
+            }
+        };
+        let name = file.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        container_paths.push(format!("/data/{}/{}", index, name));
+    }
+    (dirs, container_paths)
+}
+
+/// Split the container output at the markers and parse each file's last report
+fn parse_reports(output: &str, count: usize) -> Vec<Option<GeoMetadata>> {
+    let mut results: Vec<Option<GeoMetadata>> = vec![None; count];
+    let mut current: Option<(usize, String)> = None;
+    let mut body = String::new();
+
+    let mut flush = |current: &Option<(usize, String)>, body: &str| {
+        if let Some((index, kind)) = current {
+            if let Ok(json) = serde_json::from_str::<Value>(body.trim()) {
+                let parsed = match kind.as_str() {
+                    "raster" => parse_gdalinfo(&json),
+                    _ => parse_ogrinfo(&json),
+                };
+                if parsed.is_some() && *index < count {
+                    results[*index] = parsed;
+                }
+            }
+        }
+    };
+
+    for line in output.lines() {
+        if let Some(rest) = line.strip_prefix(MARKER) {
+            flush(&current, &body);
+            body.clear();
+            let mut parts = rest.split_whitespace();
+            current = match (parts.next().and_then(|i| i.parse().ok()), parts.next()) {
+                (Some(index), Some(kind)) => Some((index, kind.to_string())),
+                _ => None,
+            };
+        } else {
+            body.push_str(line);
+            body.push('\n');
+        }
+    }
+    flush(&current, &body);
+
+    results
+}
+
+fn parse_gdalinfo(json: &Value) -> Option<GeoMetadata> {
+    let size = json.get("size").and_then(|s| {
+        Some([s.get(0)?.as_u64()?, s.get(1)?.as_u64()?])
+    });
+    let geo_transform: Option<Vec<f64>> = json
+        .get("geoTransform")
+        .and_then(|g| g.as_array())
+        .map(|g| g.iter().filter_map(|v| v.as_f64()).collect());
+
+    let extent = json.get("cornerCoordinates").and_then(|c| {
+        let points: Vec<(f64, f64)> = ["upperLeft", "lowerLeft", "upperRight", "lowerRight"]
+            .iter()
+            .filter_map(|k| {
+                let p = c.get(*k)?;
+                Some((p.get(0)?.as_f64()?, p.get(1)?.as_f64()?))
+            })
+            .collect();
+        bounds(&points)
+    });
+    let wgs84_extent = json
+        .get("wgs84Extent")
+        .and_then(|e| e.get("coordinates"))
+        .and_then(|c| c.get(0))
+        .and_then(|ring| ring.as_array())
+        .and_then(|ring| {
+            let points: Vec<(f64, f64)> = ring
+                .iter()
+                .filter_map(|p| Some((p.get(0)?.as_f64()?, p.get(1)?.as_f64()?)))
+                .collect();
+            bounds(&points)
+        });
+
+    let crs = json
+        .get("stac")
+        .and_then(|s| s.get("proj:epsg"))
+        .and_then(|e| e.as_u64())
+        .map(|code| format!("EPSG:{}", code))
+        .or_else(|| {
+            json.get("coordinateSystem")
+                .and_then(|c| c.get("wkt"))
+                .and_then(|w| w.as_str())
+                .and_then(crs_from_wkt)
+        });
+
+    // Plain images (PNG thumbnails, ...) open in GDAL but aren't georeferenced
+    if crs.is_none() && geo_transform.is_none() {
+        return None;
+    }
+
+    Some(GeoMetadata {
+        kind: "raster".to_string(),
+        driver: json.get("driverShortName").and_then(|d| d.as_str()).map(String::from),
+        crs,
+        extent,
+        wgs84_extent,
+        size,
+        band_count: json.get("bands").and_then(|b| b.as_array()).map(|b| b.len()),
+        pixel_size: geo_transform
+            .filter(|g| g.len() == 6)
+            .map(|g| [g[1].abs(), g[5].abs()]),
+        geometry_type: None,
+        feature_count: None,
+        layers: Vec::new(),
+    })
+}
+
+fn parse_ogrinfo(json: &Value) -> Option<GeoMetadata> {
+    let layers = json.get("layers")?.as_array()?;
+    let first = layers.first()?;
+    // Tables without geometry (CSV, ...) open in OGR but aren't spatial
+    let geometry = first
+        .get("geometryFields")
+        .and_then(|g| g.as_array())
+        .and_then(|g| g.first())?;
+
+    let extent = geometry
+        .get("extent")
+        .and_then(|e| e.as_array())
+        .and_then(|e| {
+            let v: Vec<f64> = e.iter().filter_map(|x| x.as_f64()).collect();
+            (v.len() == 4).then(|| [v[0], v[1], v[2], v[3]])
+        });
+    let crs_json = geometry.get("coordinateSystem");
+    let crs = crs_json
+        .and_then(|c| c.get("projjson"))
+        .and_then(|p| p.get("id"))
+        .and_then(|id| {
+            Some(format!(
+                "{}:{}",
+                id.get("authority")?.as_str()?,
+                id.get("code").map(|c| c.to_string().trim_matches('"').to_string())?
+            ))
+        })
+        .or_else(|| {
+            crs_json
+                .and_then(|c| c.get("wkt"))
+                .and_then(|w| w.as_str())
+                .and_then(crs_from_wkt)
+        });
+    // Geographic WGS84 extents can be reported as-is
+    let wgs84_extent = match crs.as_deref() {
+        Some("EPSG:4326") | Some("OGC:CRS84") => extent,
+        _ => None,
+    };
+
+    Some(GeoMetadata {
+        kind: "vector".to_string(),
+        driver: json.get("driverShortName").and_then(|d| d.as_str()).map(String::from),
+        crs,
+        extent,
+        wgs84_extent,
+        size: None,
+        band_count: None,
+        pixel_size: None,
+        geometry_type: geometry.get("type").and_then(|t| t.as_str()).map(String::from),
+        feature_count: first.get("featureCount").and_then(|c| c.as_u64()),
+        layers: layers
+            .iter()
+            .filter_map(|l| l.get("name").and_then(|n| n.as_str()).map(String::from))
+            .collect(),
+    })
+}
+
+/// The CRS identifier of a WKT definition: the outermost EPSG ID (the last one
+/// in WKT2, the last AUTHORITY in WKT1), or the CRS name
+fn crs_from_wkt(wkt: &str) -> Option<String> {
+    let re = Regex::new(r#"(?:ID|AUTHORITY)\["EPSG",\s*"?(\d+)"?\]"#).unwrap();
+    if let Some(code) = re.captures_iter(wkt).last().map(|c| c[1].to_string()) {
+        return Some(format!("EPSG:{}", code));
+    }
+    let name = Regex::new(r#"^\w+\["([^"]+)""#).unwrap();
+    name.captures(wkt).map(|c| c[1].to_string())
+}
+
+fn bounds(points: &[(f64, f64)]) -> Option<[f64; 4]> {
+    if points.is_empty() {
+        return None;
+    }
+    let min_x = points.iter().map(|p| p.0).fold(f64::INFINITY, f64::min);
+    let min_y = points.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+    let max_x = points.iter().map(|p| p.0).fold(f64::NEG_INFINITY, f64::max);
+    let max_y = points.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+    Some([min_x, min_y, max_x, max_y])
+}
+
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Scratch file for the utility container's output
+fn tempfile_path() -> Result<PathBuf> {
+    let dir = crate::utils::paths::get_temp_dir()?;
+    Ok(dir.join(format!("geometa-{}-{}.log", std::process::id(), chrono::Utc::now().timestamp_millis())))
+}
@@ -1,6 +1,11 @@
 use anyhow::{Context, Result};
 use std::process::Command;
 
+use crate::config::settings::Settings;
+use crate::config::worker::Accelerator;
+use crate::docker::usage::format_bytes;
+use crate::error::GeoEngineError;
+
 /// GPU configuration for container execution
 #[derive(Debug, Clone)]
 pub struct GpuConfig {
@@ -12,6 +17,75 @@ pub struct GpuConfig {
 
     /// GPU device names
     pub devices: Vec<String>,
+
+    /// Restrict the container to these GPU indices or MIG UUIDs (all GPUs if None)
+    pub device_ids: Option<Vec<String>>,
+
+    /// MIG slices of GPUs in MIG mode (NVIDIA A100/H100)
+    pub mig_devices: Vec<MigDevice>,
+}
+
+/// One MIG slice, as listed by `nvidia-smi -L`
+#[derive(Debug, Clone)]
+pub struct MigDevice {
+    /// Index of the physical GPU
+    pub gpu_index: usize,
+    /// Slice profile, e.g. 1g.10gb
+    pub profile: String,
+    /// MIG-... UUID, accepted by NVIDIA_VISIBLE_DEVICES
+    pub uuid: String,
+}
+
+/// GPUs asked for with `geoengine run --gpus`, in `docker run --gpus` syntax
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpuRequest {
+    /// `all`: every GPU available to the run
+    All,
+    /// `N`: this many GPUs, those no running container uses first
+    Count(usize),
+    /// `device=0,2` (or `0,2`): these GPU indices or GPU-/MIG- UUIDs
+    Devices(Vec<String>),
+}
+
+impl std::str::FromStr for GpuRequest {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let s = s.trim();
+        if s == "all" {
+            return Ok(GpuRequest::All);
+        }
+        // A bare number is a count, as with docker; single devices need device=
+        if let Ok(count) = s.parse::<usize>() {
+            if count == 0 {
+                return Err("request at least one GPU".to_string());
+            }
+            return Ok(GpuRequest::Count(count));
+        }
+        let devices: Vec<String> = s
+            .strip_prefix("device=")
+            .unwrap_or(s)
+            .split(',')
+            .map(|d| d.trim().to_string())
+            .collect();
+        if devices.iter().any(|d| d.is_empty() || !(d.chars().all(|c| c.is_ascii_digit()) || d.starts_with("GPU-") || d.starts_with("MIG-"))) {
+            return Err(format!(
+                "'{}' is not a GPU request; use all, a count, or device=INDEX|UUID[,...]",
+                s
+            ));
+        }
+        Ok(GpuRequest::Devices(devices))
+    }
+}
+
+impl std::fmt::Display for GpuRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GpuRequest::All => write!(f, "all"),
+            GpuRequest::Count(count) => write!(f, "{}", count),
+            GpuRequest::Devices(devices) => write!(f, "device={}", devices.join(",")),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -43,6 +117,8 @@ impl GpuConfig {
             gpu_type: GpuType::None,
             count: 0,
             devices: vec![],
+            device_ids: None,
+            mig_devices: vec![],
         })
     }
 
@@ -55,6 +131,222 @@ impl GpuConfig {
     pub fn is_nvidia(&self) -> bool {
         self.gpu_type == GpuType::Nvidia
     }
+
+    /// Check if this is an Apple Metal GPU
+    pub fn is_metal(&self) -> bool {
+        self.gpu_type == GpuType::Metal
+    }
+
+    /// Resolve the GPU to hand to a container for the requested accelerator.
+    /// Returns None when the container should run on CPU.
+    pub async fn for_accelerator(accelerator: Accelerator) -> Result<Option<Self>> {
+        if accelerator == Accelerator::Cpu {
+            return Ok(None);
+        }
+
+        let detected = Self::detect().await?;
+        match accelerator {
+            Accelerator::Cuda if !detected.is_nvidia() => {
+                return Err(GeoEngineError::GpuUnavailable(
+                    "runtime.accelerator is 'cuda' but no NVIDIA GPU was detected. \
+                    Set it to 'auto' or 'cpu' to run without CUDA."
+                        .into(),
+                )
+                .into())
+            }
+            Accelerator::Mps if !detected.is_metal() => {
+                return Err(GeoEngineError::GpuUnavailable(
+                    "runtime.accelerator is 'mps' but no Metal-capable GPU was detected. \
+                    MPS is only available on macOS."
+                        .into(),
+                )
+                .into())
+            }
+            _ => {}
+        }
+
+        Ok(if detected.is_available() { Some(detected) } else { None })
+    }
+
+    /// Restrict this configuration to a single GPU (or MIG slice) by index
+    /// (used to spread batch items across GPUs)
+    pub fn pinned(&self, index: usize) -> Self {
+        let id = self
+            .device_ids
+            .as_ref()
+            .and_then(|ids| ids.get(index).cloned())
+            .unwrap_or_else(|| index.to_string());
+        GpuConfig {
+            gpu_type: self.gpu_type.clone(),
+            count: 1,
+            devices: self.devices.get(index).cloned().into_iter().collect(),
+            // Kept so the slice can still be traced to its physical GPU (usage sampling)
+            mig_devices: self.mig_devices.iter().filter(|m| m.uuid == id).cloned().collect(),
+            device_ids: Some(vec![id]),
+        }
+    }
+
+    /// Restrict this configuration to the MIG slices with the given profile that
+    /// aren't in `busy` (slices already handed to running containers)
+    pub fn mig_slices(&self, profile: &str, busy: &[String]) -> Result<Self> {
+        if self.mig_devices.is_empty() {
+            return Err(GeoEngineError::GpuUnavailable(format!(
+                "MIG profile '{}' requested, but no GPU is in MIG mode (see `nvidia-smi -L`)",
+                profile
+            ))
+            .into());
+        }
+        let matching: Vec<&MigDevice> = self.mig_devices.iter().filter(|m| m.profile == profile).collect();
+        if matching.is_empty() {
+            let mut profiles: Vec<&str> = self.mig_devices.iter().map(|m| m.profile.as_str()).collect();
+            profiles.sort();
+            profiles.dedup();
+            return Err(GeoEngineError::GpuUnavailable(format!(
+                "No MIG slice with profile '{}'. Available profiles: {}",
+                profile,
+                profiles.join(", ")
+            ))
+            .into());
+        }
+        let free: Vec<&MigDevice> = matching.iter().copied().filter(|m| !busy.contains(&m.uuid)).collect();
+        if free.is_empty() {
+            return Err(GeoEngineError::GpuUnavailable(format!(
+                "All {} MIG slice(s) with profile '{}' are in use by running containers",
+                matching.len(),
+                profile
+            ))
+            .into());
+        }
+
+        Ok(GpuConfig {
+            gpu_type: GpuType::Nvidia,
+            count: free.len(),
+            devices: free
+                .iter()
+                .map(|m| {
+                    let name = self.devices.get(m.gpu_index).map(String::as_str).unwrap_or("GPU");
+                    format!("{} MIG {} ({})", name, m.profile, m.uuid)
+                })
+                .collect(),
+            device_ids: Some(free.iter().map(|m| m.uuid.clone()).collect()),
+            mig_devices: free.into_iter().cloned().collect(),
+        })
+    }
+
+    /// Restrict this configuration to the NVIDIA GPUs with at least `required` bytes
+    /// of free memory according to `usage`, most free memory first
+    pub fn with_free_memory(&self, required: u64, usage: &[GpuUsage]) -> Result<Self> {
+        let mut candidates: Vec<(&GpuUsage, u64)> = usage
+            .iter()
+            .filter(|u| {
+                self.device_ids
+                    .as_ref()
+                    .is_none_or(|ids| ids.contains(&u.index))
+            })
+            .map(|u| (u, u.memory_total_mib.saturating_sub(u.memory_used_mib) * 1024 * 1024))
+            .collect();
+        candidates.sort_by_key(|(_, free)| std::cmp::Reverse(*free));
+        let fitting: Vec<&GpuUsage> = candidates.iter().filter(|(_, free)| *free >= required).map(|(u, _)| *u).collect();
+        if fitting.is_empty() {
+            let free: Vec<String> = candidates
+                .iter()
+                .map(|(u, free)| {
+                    format!(
+                        "GPU {} ({}): {} free of {}",
+                        u.index,
+                        u.name,
+                        format_bytes(*free),
+                        format_bytes(u.memory_total_mib * 1024 * 1024)
+                    )
+                })
+                .collect();
+            return Err(GeoEngineError::GpuUnavailable(format!(
+                "runtime.gpu_memory needs {} of free GPU memory, but no GPU has that much. {}",
+                format_bytes(required),
+                free.join("; ")
+            ))
+            .into());
+        }
+
+        Ok(GpuConfig {
+            gpu_type: GpuType::Nvidia,
+            count: fitting.len(),
+            devices: fitting.iter().map(|u| u.name.clone()).collect(),
+            device_ids: Some(fitting.iter().map(|u| u.index.clone()).collect()),
+            mig_devices: Vec::new(),
+        })
+    }
+
+    /// Restrict this configuration to the GPUs of a `--gpus` request. `busy` lists
+    /// the devices handed to running containers, which a count takes last.
+    pub fn requested(&self, request: &GpuRequest, busy: &[String]) -> Result<Self> {
+        // Device IDs the run may use, with their names
+        let available: Vec<(String, String)> = match &self.device_ids {
+            Some(ids) => ids
+                .iter()
+                .enumerate()
+                .map(|(i, id)| (id.clone(), self.devices.get(i).cloned().unwrap_or_else(|| "GPU".to_string())))
+                .collect(),
+            None => self.devices.iter().enumerate().map(|(i, name)| (i.to_string(), name.clone())).collect(),
+        };
+        let selected: Vec<(String, String)> = match request {
+            GpuRequest::All => return Ok(self.clone()),
+            GpuRequest::Count(count) => {
+                if *count > available.len() {
+                    return Err(GeoEngineError::GpuUnavailable(format!(
+                        "--gpus {} requested, but only {} GPU(s) are available to the run",
+                        count,
+                        available.len()
+                    ))
+                    .into());
+                }
+                let mut ordered = available;
+                ordered.sort_by_key(|(id, _)| busy.contains(id));
+                ordered.truncate(*count);
+                ordered
+            }
+            GpuRequest::Devices(ids) => ids
+                .iter()
+                .map(|id| {
+                    if let Some(device) = available.iter().find(|(a, _)| a == id) {
+                        return Ok(device.clone());
+                    }
+                    if let Some(mig) = self.mig_devices.iter().find(|m| m.uuid == *id) {
+                        let name = self.devices.get(mig.gpu_index).map(String::as_str).unwrap_or("GPU");
+                        return Ok((id.clone(), format!("{} MIG {} ({})", name, mig.profile, mig.uuid)));
+                    }
+                    // The runtime checks GPU UUIDs when the container starts
+                    if id.starts_with("GPU-") && self.device_ids.is_none() {
+                        return Ok((id.clone(), id.clone()));
+                    }
+                    Err(GeoEngineError::GpuUnavailable(format!(
+                        "GPU '{}' isn't available to the run. Available: {}",
+                        id,
+                        available.iter().map(|(a, _)| a.as_str()).collect::<Vec<_>>().join(", ")
+                    ))
+                    .into())
+                })
+                .collect::<Result<_>>()?,
+        };
+
+        let ids: Vec<String> = selected.iter().map(|(id, _)| id.clone()).collect();
+        Ok(GpuConfig {
+            gpu_type: self.gpu_type.clone(),
+            count: selected.len(),
+            devices: selected.into_iter().map(|(_, name)| name).collect(),
+            mig_devices: self.mig_devices.iter().filter(|m| ids.contains(&m.uuid)).cloned().collect(),
+            device_ids: Some(ids),
+        })
+    }
+
+    /// Name of the accelerator exposed to the container via GEOENGINE_ACCELERATOR
+    pub fn accelerator_name(gpu: Option<&Self>) -> &'static str {
+        match gpu.map(|g| &g.gpu_type) {
+            Some(GpuType::Nvidia) => "cuda",
+            Some(GpuType::Metal) => "mps",
+            _ => "cpu",
+        }
+    }
 }
 
 /// Detect NVIDIA GPUs using nvidia-smi
@@ -96,11 +388,55 @@ async fn detect_nvidia() -> Result<GpuConfig> {
         gpu_type: GpuType::Nvidia,
         count: devices.len(),
         devices,
+        device_ids: None,
+        mig_devices: detect_mig_devices(),
     })
 }
 
+/// List MIG slices from `nvidia-smi -L`, which prints them under their GPU:
+///
+/// ```text
+/// GPU 0: NVIDIA A100-SXM4-40GB (UUID: GPU-5d5ba0d6-...)
+///   MIG 1g.5gb      Device  0: (UUID: MIG-c6d4f1ef-...)
+/// ```
+fn detect_mig_devices() -> Vec<MigDevice> {
+    let Ok(output) = Command::new("nvidia-smi").arg("-L").output() else {
+        return Vec::new();
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut mig_devices = Vec::new();
+    let mut gpu_index = 0;
+    for line in stdout.lines() {
+        let line = line.trim();
+        if let Some(rest) = line.strip_prefix("GPU ") {
+            if let Some(index) = rest.split(':').next().and_then(|i| i.trim().parse().ok()) {
+                gpu_index = index;
+            }
+        } else if let Some(rest) = line.strip_prefix("MIG ") {
+            let profile = rest.split_whitespace().next().unwrap_or_default();
+            let uuid = rest
+                .split_once("UUID: ")
+                .map(|(_, uuid)| uuid.trim_end_matches(')').trim());
+            if let Some(uuid) = uuid {
+                mig_devices.push(MigDevice {
+                    gpu_index,
+                    profile: profile.to_string(),
+                    uuid: uuid.to_string(),
+                });
+            }
+        }
+    }
+    mig_devices
+}
+
 /// Verify NVIDIA Container Toolkit is properly configured
 fn verify_nvidia_docker() -> Result<()> {
+    // CDI devices don't go through the nvidia runtime
+    if Settings::load().is_ok_and(|s| s.gpu_cdi) {
+        return Ok(());
+    }
+
     // Check for nvidia-container-toolkit or nvidia-docker
     let has_toolkit = which::which("nvidia-container-cli").is_ok()
         || which::which("nvidia-docker").is_ok();
@@ -126,6 +462,92 @@ fn verify_nvidia_docker() -> Result<()> {
     Ok(())
 }
 
+/// Kind of the NVIDIA devices in a Container Device Interface spec
+pub const CDI_KIND: &str = "nvidia.com/gpu";
+
+/// Directories container engines read CDI specs from
+pub const CDI_SPEC_DIRS: &[&str] = &["/etc/cdi", "/var/run/cdi"];
+
+/// CDI device names for GPU indices or MIG UUIDs, or for all GPUs.
+/// The spec names devices both ways (see `geoengine gpu cdi-generate`).
+pub fn cdi_device_names(device_ids: Option<&[String]>) -> Vec<String> {
+    match device_ids {
+        Some(ids) => ids.iter().map(|id| format!("{}={}", CDI_KIND, id)).collect(),
+        None => vec![format!("{}=all", CDI_KIND)],
+    }
+}
+
+/// CDI spec files in the standard directories that define NVIDIA GPUs
+pub fn cdi_spec_files() -> Vec<std::path::PathBuf> {
+    CDI_SPEC_DIRS
+        .iter()
+        .filter_map(|dir| std::fs::read_dir(dir).ok())
+        .flatten()
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            matches!(path.extension().and_then(|e| e.to_str()), Some("yaml" | "yml" | "json"))
+                && std::fs::read_to_string(path).is_ok_and(|content| content.contains(CDI_KIND))
+        })
+        .collect()
+}
+
+/// Current utilization of one NVIDIA GPU
+pub struct GpuUsage {
+    pub index: String,
+    pub name: String,
+    pub utilization_percent: f64,
+    pub memory_used_mib: u64,
+    pub memory_total_mib: u64,
+}
+
+/// Driver version and the highest CUDA version it supports, from the header of
+/// `nvidia-smi`'s output
+pub fn nvidia_versions() -> Option<(String, Option<String>)> {
+    let output = Command::new("nvidia-smi").output().ok().filter(|o| o.status.success())?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let field = |name: &str| {
+        stdout
+            .split_once(name)
+            .and_then(|(_, rest)| rest.split_whitespace().next())
+            .map(str::to_string)
+    };
+    Some((field("Driver Version:")?, field("CUDA Version:")))
+}
+
+/// Query utilization and memory of all NVIDIA GPUs via nvidia-smi
+pub fn nvidia_usage() -> Result<Vec<GpuUsage>> {
+    let output = Command::new("nvidia-smi")
+        .args([
+            "--query-gpu=index,name,utilization.gpu,memory.used,memory.total",
+            "--format=csv,noheader,nounits",
+        ])
+        .output()
+        .context("Failed to run nvidia-smi")?;
+
+    if !output.status.success() {
+        anyhow::bail!("nvidia-smi failed");
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout
+        .lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split(',').map(|f| f.trim()).collect();
+            let [index, name, utilization, used, total] = fields[..] else {
+                return None;
+            };
+            Some(GpuUsage {
+                index: index.to_string(),
+                name: name.to_string(),
+                // "[N/A]" on GPUs that don't report a value
+                utilization_percent: utilization.parse().unwrap_or(0.0),
+                memory_used_mib: used.parse().unwrap_or(0),
+                memory_total_mib: total.parse().unwrap_or(0),
+            })
+        })
+        .collect())
+}
+
 /// Detect Metal GPU on macOS
 #[cfg(target_os = "macos")]
 async fn detect_metal() -> Result<GpuConfig> {
@@ -166,6 +588,8 @@ async fn detect_metal() -> Result<GpuConfig> {
                     gpu_type: GpuType::Metal,
                     count: devices.len(),
                     devices,
+                    device_ids: None,
+                    mig_devices: vec![],
                 });
             }
         }
@@ -185,6 +609,9 @@ pub async fn print_gpu_info() -> Result<()> {
             println!("Devices:");
             for (i, device) in config.devices.iter().enumerate() {
                 println!("  [{}] {}", i, device);
+                for mig in config.mig_devices.iter().filter(|m| m.gpu_index == i) {
+                    println!("      MIG {} ({})", mig.profile, mig.uuid);
+                }
             }
         }
         GpuType::Metal => {
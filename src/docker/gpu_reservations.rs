@@ -0,0 +1,73 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use super::client::DockerClient;
+use crate::utils::{paths, process};
+
+/// GPUs and MIG slices picked by a run, held until the run is done. Docker only
+/// reports the devices of running containers, so without these two runs starting
+/// at once could both pick the same free slice. Dropping it releases the devices.
+pub struct GpuReservation {
+    files: Vec<PathBuf>,
+}
+
+impl Drop for GpuReservation {
+    fn drop(&mut self) {
+        for file in &self.files {
+            std::fs::remove_file(file).ok();
+        }
+    }
+}
+
+/// ~/.geoengine/gpu-reservations, one file per reserved device holding the PID of its run
+fn reservations_dir() -> Result<PathBuf> {
+    let dir = paths::get_config_dir()?.join("gpu-reservations");
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn file_name(device: &str) -> String {
+    device
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect()
+}
+
+/// Pick devices with `select`, given those held by running containers and by other
+/// runs still starting, and reserve the ones it returns. Selection is serialized
+/// across geoengine processes with a lock file.
+pub async fn reserve<T>(
+    client: &DockerClient,
+    select: impl FnOnce(&[String]) -> Result<T>,
+    devices: impl FnOnce(&T) -> Vec<String>,
+) -> Result<(T, GpuReservation)> {
+    let dir = reservations_dir()?;
+    let _lock = process::lock_file(&dir.join(".lock"))?;
+
+    let mut busy = client.busy_gpu_devices().await?;
+    for entry in std::fs::read_dir(&dir)?.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with('.') {
+            continue;
+        }
+        let content = std::fs::read_to_string(entry.path()).unwrap_or_default();
+        let mut lines = content.lines();
+        let pid = lines.next().and_then(|pid| pid.parse::<u32>().ok());
+        match (pid, lines.next()) {
+            (Some(pid), Some(device)) if process::pid_alive(pid) => busy.push(device.to_string()),
+            // Left behind by a run that crashed
+            _ => {
+                std::fs::remove_file(entry.path()).ok();
+            }
+        }
+    }
+
+    let selected = select(&busy)?;
+    let mut reservation = GpuReservation { files: Vec::new() };
+    for device in devices(&selected) {
+        let file = dir.join(file_name(&device));
+        std::fs::write(&file, format!("{}\n{}\n", std::process::id(), device))?;
+        reservation.files.push(file);
+    }
+    Ok((selected, reservation))
+}
@@ -0,0 +1,216 @@
+use anyhow::{Context, Result};
+use bollard::Docker;
+use std::path::{Path, PathBuf};
+
+use crate::config::settings::Settings;
+use crate::error::GeoEngineError;
+
+/// Request timeout for the Docker API (matches bollard's default)
+const DOCKER_TIMEOUT_SECS: u64 = 120;
+
+/// Docker Desktop's socket exposed inside WSL2 distros with WSL integration enabled
+#[cfg(unix)]
+const WSL_DOCKER_DESKTOP_SOCKET: &str = "/mnt/wsl/docker-desktop/shared-sockets/host-services/docker.proxy.sock";
+
+/// Connect to the Docker daemon for the current platform.
+///
+/// `DOCKER_HOST` always wins, then the `docker_context` of the settings profile.
+/// Otherwise Windows uses the `docker_engine` named pipe, and Unix tries the
+/// standard socket, Docker Desktop's per-user socket and, inside WSL2, the socket
+/// shared by Docker Desktop's WSL integration.
+pub fn connect() -> Result<Docker> {
+    if std::env::var("DOCKER_HOST").is_ok() {
+        return Docker::connect_with_defaults()
+            .context(unavailable("Failed to connect to the Docker daemon at DOCKER_HOST"));
+    }
+
+    let settings = Settings::load()
+        .with_context(|| GeoEngineError::ConfigInvalid("Invalid settings; can't pick the Docker context".into()))?;
+    if let Some(context) = settings.docker_context {
+        return connect_to_context(&context);
+    }
+
+    #[cfg(windows)]
+    {
+        Docker::connect_with_named_pipe_defaults()
+            .context(unavailable(
+                "Failed to connect to Docker via npipe:////./pipe/docker_engine. Is Docker Desktop running?",
+            ))
+    }
+
+    #[cfg(unix)]
+    {
+        for socket in socket_candidates() {
+            if std::path::Path::new(&socket).exists() {
+                tracing::debug!("Using Docker socket {}", socket);
+                return Docker::connect_with_unix(&socket, DOCKER_TIMEOUT_SECS, bollard::API_DEFAULT_VERSION)
+                    .with_context(|| unavailable(&format!("Failed to connect to Docker socket {}", socket)));
+            }
+        }
+        Docker::connect_with_unix_defaults().context(unavailable(connect_hint()))
+    }
+}
+
+/// Connect to the endpoint of a Docker CLI context, with its TLS material when
+/// it has some (`docker context create --docker "host=tcp://...,ca=...,cert=...,key=..."`)
+fn connect_to_context(context: &str) -> Result<Docker> {
+    let output = std::process::Command::new("docker")
+        .args(["context", "inspect", context, "--format", "{{json .}}"])
+        .output()
+        .with_context(|| unavailable(&format!("Failed to run docker context inspect {}", context)))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(unavailable(&format!("Docker context '{}' not found: {}", context, stderr.trim())).into());
+    }
+    let inspect: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .with_context(|| unavailable(&format!("Failed to parse docker context inspect {}", context)))?;
+    let endpoint = &inspect["Endpoints"]["docker"];
+    let host = endpoint["Host"].as_str().unwrap_or_default().to_string();
+    tracing::debug!("Using Docker context {} ({})", context, host);
+
+    // The CLI keeps a context's certificates in <TLSPath>/docker/{ca,cert,key}.pem
+    let tls_dir = inspect["Storage"]["TLSPath"]
+        .as_str()
+        .map(|dir| Path::new(dir).join("docker"))
+        .filter(|dir| dir.is_dir());
+    let fail = |reason: &str| -> Result<Docker> {
+        Err(unavailable(&format!("Docker context '{}' ({}) {}", context, host, reason)).into())
+    };
+
+    let connected = if host.starts_with("tcp://") || host.starts_with("https://") {
+        if endpoint["SkipTLSVerify"].as_bool() == Some(true) {
+            return fail("skips TLS verification, which GeoEngine doesn't support; give it a ca certificate");
+        }
+        match tls_dir {
+            Some(dir) => {
+                let (ca, cert, key) = (dir.join("ca.pem"), dir.join("cert.pem"), dir.join("key.pem"));
+                if !(ca.is_file() && cert.is_file() && key.is_file()) {
+                    return fail(&format!(
+                        "uses TLS, but GeoEngine needs all of ca.pem, cert.pem and key.pem in {}",
+                        dir.display()
+                    ));
+                }
+                Docker::connect_with_ssl(&host, &key, &cert, &ca, DOCKER_TIMEOUT_SECS, bollard::API_DEFAULT_VERSION)
+            }
+            None if host.starts_with("https://") => {
+                return fail("uses https:// but has no TLS certificates");
+            }
+            None => Docker::connect_with_http(&host, DOCKER_TIMEOUT_SECS, bollard::API_DEFAULT_VERSION),
+        }
+    } else if host.starts_with("http://") {
+        Docker::connect_with_http(&host, DOCKER_TIMEOUT_SECS, bollard::API_DEFAULT_VERSION)
+    } else if host.starts_with("unix://") || host.starts_with("npipe://") {
+        Docker::connect_with_socket(&host, DOCKER_TIMEOUT_SECS, bollard::API_DEFAULT_VERSION)
+    } else if host.starts_with("ssh://") {
+        return fail(
+            "uses ssh://, which GeoEngine can't connect to; forward the remote socket \
+             (ssh -NL /tmp/docker.sock:/var/run/docker.sock <host>) and use a unix:// context",
+        );
+    } else {
+        return fail("uses a transport GeoEngine can't connect to; use a unix://, npipe:// or tcp:// context");
+    };
+    connected.with_context(|| unavailable(&format!("Failed to connect to Docker context '{}' ({})", context, host)))
+}
+
+fn unavailable(message: &str) -> GeoEngineError {
+    GeoEngineError::DockerUnavailable(message.to_string())
+}
+
+/// Unix sockets to try, in order of preference
+#[cfg(unix)]
+fn socket_candidates() -> Vec<String> {
+    let mut candidates = vec!["/var/run/docker.sock".to_string()];
+    if let Some(home) = dirs::home_dir() {
+        candidates.push(home.join(".docker").join("run").join("docker.sock").display().to_string());
+    }
+    if is_wsl() {
+        candidates.push(WSL_DOCKER_DESKTOP_SOCKET.to_string());
+    }
+    candidates
+}
+
+/// Error hint shown when no daemon could be reached
+pub fn connect_hint() -> &'static str {
+    if is_wsl() {
+        "Failed to connect to Docker daemon. Is Docker Desktop running with WSL integration enabled for this distro?"
+    } else {
+        "Failed to connect to Docker daemon. Is Docker running?"
+    }
+}
+
+/// Detect whether we are running inside WSL (Windows Subsystem for Linux)
+pub fn is_wsl() -> bool {
+    if !cfg!(target_os = "linux") {
+        return false;
+    }
+    if std::env::var_os("WSL_DISTRO_NAME").is_some() {
+        return true;
+    }
+    std::fs::read_to_string("/proc/sys/kernel/osrelease")
+        .map(|r| {
+            let r = r.to_ascii_lowercase();
+            r.contains("microsoft") || r.contains("wsl")
+        })
+        .unwrap_or(false)
+}
+
+/// Whether this is WSL 2, the only WSL version with GPU support. WSL 1 runs on the
+/// NT kernel ("...-Microsoft"); WSL 2 runs a real Linux kernel ("...-microsoft-standard-WSL2").
+pub fn is_wsl2() -> bool {
+    if !is_wsl() {
+        return false;
+    }
+    std::path::Path::new("/dev/dxg").exists()
+        || std::fs::read_to_string("/proc/sys/kernel/osrelease")
+            .map(|r| r.contains("microsoft-standard") || r.to_ascii_lowercase().contains("wsl2"))
+            .unwrap_or(false)
+}
+
+/// Translate a host path into the form the Docker daemon expects for bind mounts.
+///
+/// - Windows extended-length prefixes from `canonicalize()` (`\\?\C:\`, `\\?\UNC\`) are stripped.
+/// - Inside WSL2, Windows drive paths (`C:\data`) are mapped to `/mnt/c/data`.
+pub fn docker_host_path(path: &str) -> String {
+    let path = if let Some(rest) = path.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", rest)
+    } else if let Some(rest) = path.strip_prefix(r"\\?\") {
+        rest.to_string()
+    } else {
+        path.to_string()
+    };
+
+    if is_wsl() {
+        if let Some((drive, rest)) = split_drive(&path) {
+            return format!(
+                "/mnt/{}/{}",
+                drive.to_ascii_lowercase(),
+                rest.replace('\\', "/").trim_start_matches('/')
+            );
+        }
+    }
+
+    path
+}
+
+/// Resolve a host path from geoengine.yaml or an input: `./` (and `.\` on Windows)
+/// is relative to `base`, anything else is used as given
+pub fn resolve_host_path(path: &str, base: &Path) -> PathBuf {
+    let relative = path
+        .strip_prefix("./")
+        .or_else(|| path.strip_prefix(".\\").filter(|_| cfg!(windows)));
+    match relative {
+        Some(rest) => base.join(rest),
+        None => PathBuf::from(path),
+    }
+}
+
+/// Split `C:\rest` or `C:/rest` into the drive letter and remainder
+fn split_drive(path: &str) -> Option<(char, &str)> {
+    let mut chars = path.chars();
+    let drive = chars.next()?;
+    if drive.is_ascii_alphabetic() && chars.next()? == ':' && matches!(chars.next(), Some('\\') | Some('/')) {
+        Some((drive, &path[2..]))
+    } else {
+        None
+    }
+}
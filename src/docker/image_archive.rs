@@ -0,0 +1,256 @@
+use anyhow::{Context, Result};
+use indicatif::ProgressBar;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::docker::client::partial_export_path;
+
+/// First entry of a delta archive
+const DELTA_MANIFEST: &str = "geoengine-delta.json";
+
+/// Compression level of exported archives; layers are mostly already-compressed
+/// rasters and binaries, where higher levels cost far more time than they save
+const ZSTD_LEVEL: i32 = 6;
+
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// What a delta archive leaves out and which image has it
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeltaManifest {
+    /// Base image as named on export
+    pub base: String,
+    /// Image ID of the base image
+    pub base_id: String,
+    /// Layer files left out of the archive: path in the archive -> layer diff ID
+    pub omitted: BTreeMap<String, String>,
+}
+
+/// Layer counts of a written delta archive
+pub struct DeltaStats {
+    pub layers: usize,
+    pub omitted: usize,
+}
+
+/// Whether `path` is zstd-compressed
+pub fn is_zstd(path: &Path) -> Result<bool> {
+    let mut magic = [0u8; 4];
+    let mut file = File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(file.read_exact(&mut magic).is_ok() && magic == ZSTD_MAGIC)
+}
+
+/// The delta manifest of a zstd archive, or None for a plain compressed archive
+pub fn read_delta_manifest(path: &Path) -> Result<Option<DeltaManifest>> {
+    let decoder = zstd::Decoder::new(File::open(path)?)?;
+    let mut archive = tar::Archive::new(decoder);
+    let Some(entry) = archive.entries()?.next() else {
+        return Ok(None);
+    };
+    let mut entry = entry?;
+    if entry.path()? != Path::new(DELTA_MANIFEST) {
+        return Ok(None);
+    }
+    let mut content = String::new();
+    entry.read_to_string(&mut content)?;
+    let manifest = serde_json::from_str(&content).with_context(|| format!("Invalid {} in {}", DELTA_MANIFEST, path.display()))?;
+    Ok(Some(manifest))
+}
+
+/// Compress a `docker save` archive with zstd, advancing `pb` by the bytes read
+pub fn compress(archive: &Path, output: &Path, pb: &ProgressBar) -> Result<u64> {
+    let mut input = pb.wrap_read(BufReader::new(File::open(archive)?));
+    write_atomically(output, |file| {
+        let mut encoder = zstd::Encoder::new(file, ZSTD_LEVEL)?;
+        std::io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?.flush()?;
+        Ok(())
+    })
+}
+
+/// Decompress a zstd archive that isn't a delta
+pub fn decompress(archive: &Path, output: &Path) -> Result<()> {
+    let mut decoder = zstd::Decoder::new(File::open(archive)?)?;
+    let mut file = BufWriter::new(File::create(output).with_context(|| format!("Failed to create {}", output.display()))?);
+    std::io::copy(&mut decoder, &mut file).with_context(|| format!("Failed to decompress {}", archive.display()))?;
+    file.flush()?;
+    Ok(())
+}
+
+/// Write a zstd-compressed copy of the `docker save` archive `full` without the
+/// layers in `base_layers` (diff IDs of the base image), headed by a delta manifest
+pub fn write_delta(
+    full: &Path,
+    output: &Path,
+    base: &str,
+    base_id: &str,
+    base_layers: &HashSet<String>,
+    pb: &ProgressBar,
+) -> Result<DeltaStats> {
+    // An uncompressed layer file's SHA-256 is the layer's diff ID
+    let hashes = hash_files(full, pb)?;
+    let mut omitted = BTreeMap::new();
+    let layers = archive_layers(full)?;
+    for layer in &layers {
+        let Some(diff_id) = hashes.get(Path::new(layer)) else {
+            // A link to a layer stored once for several images
+            continue;
+        };
+        if base_layers.contains(diff_id) && !omitted.values().any(|d| d == diff_id) {
+            omitted.insert(layer.clone(), diff_id.clone());
+        }
+    }
+    let manifest = DeltaManifest {
+        base: base.to_string(),
+        base_id: base_id.to_string(),
+        omitted,
+    };
+
+    write_atomically(output, |file| {
+        let mut builder = tar::Builder::new(zstd::Encoder::new(file, ZSTD_LEVEL)?);
+        let json = serde_json::to_vec_pretty(&manifest)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(json.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(chrono::Utc::now().timestamp().max(0) as u64);
+        builder.append_data(&mut header, DELTA_MANIFEST, json.as_slice())?;
+
+        let mut archive = tar::Archive::new(BufReader::new(File::open(full)?));
+        for entry in archive.entries()? {
+            let entry = entry?;
+            let path = entry.path()?.into_owned();
+            pb.inc(entry.size());
+            if manifest.omitted.contains_key(path.to_string_lossy().as_ref()) {
+                continue;
+            }
+            copy_entry(&mut builder, entry, &path)?;
+        }
+        builder.into_inner()?.finish()?.flush()?;
+        Ok(())
+    })?;
+
+    Ok(DeltaStats {
+        layers: layers.len(),
+        omitted: manifest.omitted.len(),
+    })
+}
+
+/// Rebuild the full `docker save` archive of a delta, taking the layers it leaves
+/// out from `base_archive`, a `docker save` of the base image
+pub fn reconstitute(delta: &Path, manifest: &DeltaManifest, base_archive: &Path, output: &Path, pb: &ProgressBar) -> Result<()> {
+    // Layer file in the base archive for each diff ID the delta needs
+    let needed: HashSet<&String> = manifest.omitted.values().collect();
+    let found: HashMap<String, PathBuf> = hash_files(base_archive, pb)?
+        .into_iter()
+        .filter(|(_, diff_id)| needed.contains(diff_id))
+        .map(|(path, diff_id)| (diff_id, path))
+        .collect();
+    if let Some((_, missing)) = manifest.omitted.iter().find(|(_, diff_id)| !found.contains_key(*diff_id)) {
+        anyhow::bail!(
+            "Base image {} has no layer {}; export the delta again against the base image this host has",
+            manifest.base,
+            missing
+        );
+    }
+    // Where each base layer goes in the rebuilt archive
+    let targets: HashMap<&PathBuf, &String> = manifest
+        .omitted
+        .iter()
+        .map(|(path, diff_id)| (&found[diff_id], path))
+        .collect();
+
+    let mut builder = tar::Builder::new(BufWriter::new(
+        File::create(output).with_context(|| format!("Failed to create {}", output.display()))?,
+    ));
+    let mut archive = tar::Archive::new(zstd::Decoder::new(File::open(delta)?)?);
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = entry.path()?.into_owned();
+        if path == Path::new(DELTA_MANIFEST) {
+            continue;
+        }
+        copy_entry(&mut builder, entry, &path)?;
+    }
+    let mut archive = tar::Archive::new(BufReader::new(File::open(base_archive)?));
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let path = entry.path()?.into_owned();
+        if let Some(target) = targets.get(&path) {
+            pb.inc(entry.size());
+            copy_entry(&mut builder, entry, Path::new(target.as_str()))?;
+        }
+    }
+    builder.into_inner()?.flush()?;
+    Ok(())
+}
+
+/// SHA-256 (as `sha256:<hex>`) of every regular file in a tar archive
+fn hash_files(archive: &Path, pb: &ProgressBar) -> Result<HashMap<PathBuf, String>> {
+    let mut hashes = HashMap::new();
+    let mut archive = tar::Archive::new(BufReader::new(
+        File::open(archive).with_context(|| format!("Failed to read {}", archive.display()))?,
+    ));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.header().entry_type() != tar::EntryType::Regular {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        let mut hasher = Sha256::new();
+        let size = std::io::copy(&mut entry, &mut hasher)?;
+        pb.inc(size);
+        hashes.insert(path, format!("sha256:{:x}", hasher.finalize()));
+    }
+    Ok(hashes)
+}
+
+/// Layer paths listed in the manifest.json of a `docker save` archive
+fn archive_layers(archive: &Path) -> Result<Vec<String>> {
+    let mut archive = tar::Archive::new(BufReader::new(File::open(archive)?));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()? != Path::new("manifest.json") {
+            continue;
+        }
+        let mut content = String::new();
+        entry.read_to_string(&mut content)?;
+        let images: Vec<serde_json::Value> = serde_json::from_str(&content).context("Invalid manifest.json in the image archive")?;
+        let mut layers: Vec<String> = Vec::new();
+        for layer in images.iter().filter_map(|i| i["Layers"].as_array()).flatten().filter_map(|l| l.as_str()) {
+            if !layers.iter().any(|l| l == layer) {
+                layers.push(layer.to_string());
+            }
+        }
+        return Ok(layers);
+    }
+    anyhow::bail!("The image archive has no manifest.json")
+}
+
+/// Append an entry under `path`, keeping links as links
+fn copy_entry<W: std::io::Write, R: Read>(builder: &mut tar::Builder<W>, mut entry: tar::Entry<R>, path: &Path) -> Result<()> {
+    let mut header = entry.header().clone();
+    match header.entry_type() {
+        tar::EntryType::Symlink | tar::EntryType::Link => {
+            let target = entry.link_name()?.context("Link without a target in the image archive")?.into_owned();
+            builder.append_link(&mut header, path, target)?;
+        }
+        _ => builder.append_data(&mut header, path, &mut entry)?,
+    }
+    Ok(())
+}
+
+/// Run `write` on a `.part` file next to `output` and rename it into place once
+/// complete, returning the size written
+fn write_atomically(output: &Path, write: impl FnOnce(BufWriter<File>) -> Result<()>) -> Result<u64> {
+    let partial = partial_export_path(output);
+    let file = File::create(&partial).with_context(|| format!("Failed to create {}", partial.display()))?;
+    if let Err(e) = write(BufWriter::new(file)) {
+        std::fs::remove_file(&partial).ok();
+        return Err(e);
+    }
+    let size = std::fs::metadata(&partial)?.len();
+    std::fs::rename(&partial, output).with_context(|| format!("Failed to move {} to {}", partial.display(), output.display()))?;
+    Ok(size)
+}
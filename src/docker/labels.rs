@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::worker::WorkerConfig;
+
+/// Name of the worker that produced the image
+pub const WORKER: &str = "geoengine.worker";
+/// Hash of the build-relevant geoengine.yaml fields at build time
+pub const CONFIG_HASH: &str = "geoengine.config-hash";
+/// Hash of the Dockerfile at build time
+pub const DOCKERFILE_HASH: &str = "geoengine.dockerfile-hash";
+/// Hash of the command script at build time
+pub const COMMAND_HASH: &str = "geoengine.command-hash";
+/// Command the worker runs (program + script)
+pub const COMMAND: &str = "geoengine.command";
+/// Comma-separated names of the worker's declared inputs
+pub const INPUTS: &str = "geoengine.inputs";
+/// Service (or detached run) name on long-running containers
+pub const SERVICE: &str = "geoengine.service";
+/// PID of the geoengine process an attached container belongs to
+pub const OWNER_PID: &str = "geoengine.owner-pid";
+/// Host of that process, since several hosts can share one Docker daemon
+pub const OWNER_HOST: &str = "geoengine.owner-host";
+/// Comma-separated GPU indices or MIG UUIDs a container was restricted to
+pub const GPU_DEVICES: &str = "geoengine.gpu-devices";
+
+// Standard OCI annotation keys (https://github.com/opencontainers/image-spec/blob/main/annotations.md)
+pub const OCI_TITLE: &str = "org.opencontainers.image.title";
+pub const OCI_DESCRIPTION: &str = "org.opencontainers.image.description";
+pub const OCI_VERSION: &str = "org.opencontainers.image.version";
+pub const OCI_REVISION: &str = "org.opencontainers.image.revision";
+pub const OCI_CREATED: &str = "org.opencontainers.image.created";
+
+/// Build hashes recorded on the image
+pub struct BuildHashes<'a> {
+    pub config: &'a str,
+    pub dockerfile: Option<&'a str>,
+    pub command: Option<&'a str>,
+}
+
+/// Build the labels attached to every worker image so later commands can tell
+/// which code and configuration produced it.
+pub fn build_labels(config: &WorkerConfig, worker_dir: &Path, hashes: &BuildHashes) -> HashMap<String, String> {
+    let mut labels = HashMap::new();
+    labels.insert(WORKER.to_string(), config.name.clone());
+    labels.insert(CONFIG_HASH.to_string(), hashes.config.to_string());
+    if let Some(hash) = hashes.dockerfile {
+        labels.insert(DOCKERFILE_HASH.to_string(), hash.to_string());
+    }
+    if let Some(hash) = hashes.command {
+        labels.insert(COMMAND_HASH.to_string(), hash.to_string());
+    }
+
+    if let Some(cmd) = &config.command {
+        labels.insert(COMMAND.to_string(), format!("{} {}", cmd.program, cmd.script).trim().to_string());
+        let inputs: Vec<&str> = cmd
+            .inputs
+            .iter()
+            .flatten()
+            .map(|i| i.name.as_str())
+            .collect();
+        labels.insert(INPUTS.to_string(), inputs.join(","));
+    }
+
+    labels.insert(OCI_TITLE.to_string(), config.name.clone());
+    if let Some(description) = &config.description {
+        labels.insert(OCI_DESCRIPTION.to_string(), description.clone());
+    }
+    if let Some(version) = &config.version {
+        labels.insert(OCI_VERSION.to_string(), version.clone());
+    }
+    if let Some(revision) = git_revision(worker_dir) {
+        labels.insert(OCI_REVISION.to_string(), revision);
+    }
+    labels.insert(OCI_CREATED.to_string(), chrono::Utc::now().to_rfc3339());
+
+    labels
+}
+
+/// Get the git commit of the worker directory, suffixed with `-dirty` if there
+/// are uncommitted changes under it (changes elsewhere in the repository don't
+/// count). Returns None outside a git repository.
+fn git_revision(dir: &Path) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    let dirty = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(["status", "--porcelain", "--", "."])
+        .output()
+        .map(|o| !o.stdout.is_empty())
+        .unwrap_or(false);
+
+    Some(if dirty { format!("{}-dirty", sha) } else { sha })
+}
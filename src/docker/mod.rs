@@ -1,4 +1,17 @@
+pub mod build_context;
 pub mod client;
 pub mod config;
 pub mod gpu;
+pub mod gpu_reservations;
 pub mod dockerfile;
+pub mod geometa;
+pub mod host;
+pub mod image_archive;
+pub mod labels;
+pub mod pull_progress;
+pub mod push_progress;
+pub mod reaper;
+pub mod registry;
+pub mod usage;
+pub mod user;
+pub mod wait;
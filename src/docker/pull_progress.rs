@@ -0,0 +1,152 @@
+use bollard::models::CreateImageInfo;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::collections::HashMap;
+
+/// How an image pull reports the progress Docker streams for each layer
+pub enum PullProgress {
+    /// Debug logging only, for pulls made on the way to something else
+    Quiet,
+    /// A bar per layer plus an overall download bar with ETA
+    Bars(LayerBars),
+    /// One JSON object per layer event on stdout, for UIs wrapping the CLI
+    Ndjson { image: String },
+}
+
+impl PullProgress {
+    pub fn bars() -> Self {
+        PullProgress::Bars(LayerBars::new())
+    }
+
+    pub fn ndjson(image: &str) -> Self {
+        PullProgress::Ndjson {
+            image: image.to_string(),
+        }
+    }
+
+    /// Record one event of the pull stream
+    pub fn update(&mut self, info: &CreateImageInfo) {
+        let status = info.status.as_deref().unwrap_or_default();
+        tracing::debug!("Pull status: {} {}", info.id.as_deref().unwrap_or_default(), status);
+        // "<tag>: Pulling from <repo>" carries the tag as its id; only layers are tracked
+        let Some(layer) = info.id.as_deref().filter(|_| !status.starts_with("Pulling from")) else {
+            return;
+        };
+        let current = info.progress_detail.as_ref().and_then(|d| d.current).map(|c| c.max(0) as u64);
+        let total = info.progress_detail.as_ref().and_then(|d| d.total).map(|t| t.max(0) as u64);
+        match self {
+            PullProgress::Quiet => {}
+            PullProgress::Bars(bars) => bars.update(layer, status, current, total),
+            PullProgress::Ndjson { image } => {
+                let event = serde_json::json!({
+                    "type": "layer",
+                    "image": image,
+                    "layer": layer,
+                    "status": status,
+                    "current": current,
+                    "total": total,
+                });
+                println!("{}", event);
+            }
+        }
+    }
+
+    /// Start over, e.g. when the pull is retried from another mirror
+    pub fn reset(&mut self) {
+        if let PullProgress::Bars(bars) = self {
+            bars.clear();
+            *bars = LayerBars::new();
+        }
+    }
+
+    /// Remove the bars once the pull has finished or failed
+    pub fn finish(&mut self) {
+        if let PullProgress::Bars(bars) = self {
+            bars.clear();
+        }
+    }
+}
+
+/// Per-layer bars under an overall bar that sums the layers' download progress
+pub struct LayerBars {
+    multi: MultiProgress,
+    overall: ProgressBar,
+    layers: HashMap<String, ProgressBar>,
+    /// Bytes downloaded and total size per layer
+    downloads: HashMap<String, (u64, u64)>,
+}
+
+impl LayerBars {
+    fn new() -> Self {
+        let multi = MultiProgress::new();
+        let overall = multi.add(ProgressBar::new(0));
+        overall.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} {msg:<16} [{bar:30.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+        overall.set_message("Pulling");
+        overall.enable_steady_tick(std::time::Duration::from_millis(100));
+        LayerBars {
+            multi,
+            overall,
+            layers: HashMap::new(),
+            downloads: HashMap::new(),
+        }
+    }
+
+    fn update(&mut self, layer: &str, status: &str, current: Option<u64>, total: Option<u64>) {
+        let bar = self.layers.entry(layer.to_string()).or_insert_with(|| {
+            let bar = self.multi.add(ProgressBar::new(0));
+            bar.set_style(
+                ProgressStyle::default_bar()
+                    .template("  {prefix:.dim} {msg:<18} [{bar:25.cyan/blue}] {bytes}/{total_bytes}")
+                    .unwrap()
+                    .progress_chars("=> "),
+            );
+            bar.set_prefix(layer.chars().take(12).collect::<String>());
+            bar
+        });
+        if bar.is_finished() {
+            return;
+        }
+        bar.set_message(status.to_string());
+        if let (Some(current), Some(total)) = (current, total) {
+            bar.set_length(total);
+            bar.set_position(current.min(total));
+        }
+
+        match status {
+            "Downloading" => {
+                if let (Some(current), Some(total)) = (current, total) {
+                    self.downloads.insert(layer.to_string(), (current.min(total), total));
+                }
+            }
+            "Download complete" => {
+                if let Some(download) = self.downloads.get_mut(layer) {
+                    download.0 = download.1;
+                }
+            }
+            "Pull complete" | "Already exists" => bar.finish(),
+            _ => {}
+        }
+
+        let downloaded: u64 = self.downloads.values().map(|d| d.0).sum();
+        let size: u64 = self.downloads.values().map(|d| d.1).sum();
+        self.overall.set_length(size);
+        self.overall.set_position(downloaded);
+        if size > 0 && downloaded == size {
+            self.overall.set_message("Extracting");
+        } else if size > 0 {
+            self.overall.set_message("Downloading");
+        }
+    }
+
+    fn clear(&mut self) {
+        for bar in self.layers.values() {
+            bar.finish_and_clear();
+        }
+        self.overall.finish_and_clear();
+        let _ = self.multi.clear();
+    }
+}
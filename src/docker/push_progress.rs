@@ -0,0 +1,105 @@
+use bollard::models::PushImageInfo;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use std::collections::HashMap;
+
+/// Per-layer bars for an image push under an overall upload bar.
+///
+/// Docker's push stream carries no layer IDs through bollard, so layers are told
+/// apart by their compressed size, which every "Pushing" event repeats.
+pub struct PushProgress {
+    multi: MultiProgress,
+    overall: ProgressBar,
+    layers: HashMap<i64, ProgressBar>,
+    /// Bytes uploaded and total size per layer
+    uploads: HashMap<i64, (u64, u64)>,
+    layer_count: usize,
+    /// Layers the registry already had, from an earlier push or another tag
+    pub existing: usize,
+    pub pushed: usize,
+}
+
+impl PushProgress {
+    pub fn new(layer_count: usize) -> Self {
+        let multi = MultiProgress::new();
+        let overall = multi.add(ProgressBar::new(0));
+        overall.set_style(
+            ProgressStyle::default_bar()
+                .template("{spinner:.green} {msg:<22} [{bar:30.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                .unwrap()
+                .progress_chars("=> "),
+        );
+        overall.enable_steady_tick(std::time::Duration::from_millis(100));
+        let progress = PushProgress {
+            multi,
+            overall,
+            layers: HashMap::new(),
+            uploads: HashMap::new(),
+            layer_count,
+            existing: 0,
+            pushed: 0,
+        };
+        progress.set_message();
+        progress
+    }
+
+    /// Record one event of the push stream
+    pub fn update(&mut self, info: &PushImageInfo) {
+        let status = info.status.as_deref().unwrap_or_default();
+        tracing::debug!("Push status: {} {}", status, info.progress.as_deref().unwrap_or_default());
+        match status {
+            "Pushing" => {
+                let detail = info.progress_detail.as_ref();
+                let (Some(current), Some(total)) = (detail.and_then(|d| d.current), detail.and_then(|d| d.total))
+                else {
+                    return;
+                };
+                let (current, size) = (current.max(0) as u64, total.max(0) as u64);
+                let bar = self.layers.entry(total).or_insert_with(|| {
+                    let bar = self.multi.add(ProgressBar::new(size));
+                    bar.set_style(
+                        ProgressStyle::default_bar()
+                            .template("  {msg:<12} [{bar:25.cyan/blue}] {bytes}/{total_bytes}")
+                            .unwrap()
+                            .progress_chars("=> "),
+                    );
+                    bar.set_message("Pushing");
+                    bar
+                });
+                bar.set_position(current.min(size));
+                if current >= size {
+                    bar.finish_with_message("Pushed");
+                }
+                self.uploads.insert(total, (current.min(size), size));
+            }
+            "Pushed" => self.pushed += 1,
+            "Layer already exists" => self.existing += 1,
+            _ if status.starts_with("Mounted from") => self.existing += 1,
+            _ => {}
+        }
+
+        let uploaded: u64 = self.uploads.values().map(|u| u.0).sum();
+        let size: u64 = self.uploads.values().map(|u| u.1).sum();
+        self.overall.set_length(size);
+        self.overall.set_position(uploaded);
+        self.set_message();
+    }
+
+    fn set_message(&self) {
+        let done = self.pushed + self.existing;
+        if self.layer_count > 0 {
+            self.overall
+                .set_message(format!("Pushing {}/{} layers", done.min(self.layer_count), self.layer_count));
+        } else {
+            self.overall.set_message(format!("Pushing ({} layers done)", done));
+        }
+    }
+
+    /// Remove the bars once the push has finished or failed
+    pub fn finish(&mut self) {
+        for bar in self.layers.values() {
+            bar.finish_and_clear();
+        }
+        self.overall.finish_and_clear();
+        let _ = self.multi.clear();
+    }
+}
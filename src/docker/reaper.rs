@@ -0,0 +1,57 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use super::client::DockerClient;
+use crate::utils::{process, workspace};
+
+/// Seconds a running orphan gets to exit before it is killed
+const STOP_TIMEOUT_SECS: i64 = 10;
+
+/// Leftovers of geoengine processes that are gone
+#[derive(Default)]
+pub struct Reaped {
+    /// Container names
+    pub containers: Vec<String>,
+    /// Job workspace directories
+    pub workspaces: Vec<PathBuf>,
+}
+
+impl Reaped {
+    pub fn is_empty(&self) -> bool {
+        self.containers.is_empty() && self.workspaces.is_empty()
+    }
+}
+
+/// Find attached containers and job workspaces whose geoengine process no longer
+/// exists (crashed or killed) and, unless `dry_run`, stop and remove them.
+/// Detached runs and services have no owner and are never touched. Containers
+/// started from other hosts sharing the daemon are left to those hosts.
+pub async fn reap(client: &DockerClient, dry_run: bool) -> Result<Reaped> {
+    let mut reaped = Reaped::default();
+    let host = process::hostname();
+    let own_pid = std::process::id();
+
+    for container in client.list_owned_containers().await? {
+        if container.owner_host != host || container.owner_pid == own_pid || process::pid_alive(container.owner_pid) {
+            continue;
+        }
+        if !dry_run {
+            if container.running {
+                let _ = client.stop_container(&container.id, STOP_TIMEOUT_SECS).await;
+            }
+            client.remove_container(&container.id, true).await?;
+            tracing::info!("Removed orphaned container {} (owner PID {})", container.name, container.owner_pid);
+        }
+        reaped.containers.push(container.name);
+    }
+
+    for dir in workspace::orphaned()? {
+        if !dry_run {
+            std::fs::remove_dir_all(&dir)?;
+            tracing::info!("Removed orphaned job workspace {}", dir.display());
+        }
+        reaped.workspaces.push(dir);
+    }
+
+    Ok(reaped)
+}
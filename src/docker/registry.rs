@@ -0,0 +1,270 @@
+use anyhow::Result;
+use bollard::auth::DockerCredentials;
+use colored::Colorize;
+use std::time::Duration;
+
+use super::client::DockerClient;
+use super::pull_progress::PullProgress;
+use super::push_progress::PushProgress;
+use crate::config::settings::{RegistrySettings, Settings};
+use crate::error::GeoEngineError;
+
+/// Registry that image references without a host refer to
+const DOCKER_HUB: &str = "docker.io";
+
+/// Expand an image reference to `registry/path:tag` form, the way Docker resolves it:
+/// `python:3.11` -> `docker.io/library/python:3.11`, `osgeo/gdal` -> `docker.io/osgeo/gdal`
+pub fn normalize(image: &str) -> String {
+    let (first, rest) = match image.split_once('/') {
+        Some((first, rest)) => (first, Some(rest)),
+        None => (image, None),
+    };
+    match rest {
+        // A first component with a dot, a port or `localhost` is a registry host
+        Some(rest) if first.contains('.') || first.contains(':') || first == "localhost" => {
+            format!("{}/{}", first, rest)
+        }
+        Some(_) => format!("{}/{}", DOCKER_HUB, image),
+        None => format!("{}/library/{}", DOCKER_HUB, image),
+    }
+}
+
+/// Registry host of an image reference
+pub fn registry_host(image: &str) -> String {
+    normalize(image).split('/').next().unwrap_or(DOCKER_HUB).to_string()
+}
+
+/// References to try, in order, when pulling `image`. A matching rewrite (longest
+/// prefix wins) replaces the reference outright. Otherwise Docker Hub images go
+/// through the configured mirrors first and fall back to Docker Hub itself.
+pub fn candidates(image: &str, settings: &RegistrySettings) -> Vec<String> {
+    let normalized = normalize(image);
+    if let Some((from, to)) = rewrite_for(image, settings) {
+        let rest = &normalized[from.trim_end_matches('/').len()..];
+        return vec![format!("{}{}", to.trim_end_matches('/'), rest)];
+    }
+
+    if registry_host(image) == DOCKER_HUB && !settings.mirrors.is_empty() {
+        let path = &normalized[DOCKER_HUB.len()..];
+        let mut refs: Vec<String> = settings
+            .mirrors
+            .iter()
+            .map(|mirror| format!("{}{}", mirror.trim_end_matches('/'), path))
+            .collect();
+        refs.push(image.to_string());
+        return refs;
+    }
+
+    vec![image.to_string()]
+}
+
+/// The rewrite (from, to) that applies to `image`; the longest matching prefix wins
+fn rewrite_for<'a>(image: &str, settings: &'a RegistrySettings) -> Option<(&'a String, &'a String)> {
+    let normalized = normalize(image);
+    settings
+        .rewrites
+        .iter()
+        .filter(|(from, _)| {
+            let from = from.trim_end_matches('/');
+            normalized == from || normalized.starts_with(&format!("{}/", from))
+        })
+        .max_by_key(|(from, _)| from.len())
+}
+
+/// Whether a rewrite names `image`'s repository path, not just its registry host.
+/// Images that exist only locally (geoengine-local/...) resolve to Docker Hub, so
+/// only such a rewrite sends a pull of them somewhere their owner controls.
+pub fn has_repository_rewrite(image: &str) -> Result<bool> {
+    let settings = Settings::load()?.registries.unwrap_or_default();
+    Ok(rewrite_for(image, &settings).is_some_and(|(from, _)| from.trim_end_matches('/').contains('/')))
+}
+
+/// Credentials configured for the registry hosting `image`
+pub fn credentials(image: &str, settings: &RegistrySettings) -> Result<Option<DockerCredentials>> {
+    let host = registry_host(image);
+    let Some(creds) = settings.credentials.get(&host) else {
+        return Ok(None);
+    };
+    let password = match (&creds.password_env, &creds.password) {
+        (Some(var), _) => Some(
+            std::env::var(var)
+                .map_err(|_| anyhow::anyhow!("{} (password_env for registry {}) is not set", var, host))?,
+        ),
+        (None, password) => password.clone(),
+    };
+    Ok(Some(DockerCredentials {
+        username: creds.username.clone(),
+        password,
+        serveraddress: Some(host),
+        ..Default::default()
+    }))
+}
+
+/// Pull `image` through the configured rewrites, mirrors and credentials. When it
+/// comes from somewhere else, it is also tagged under its original name so builds
+/// (`FROM`) and runs find it locally.
+pub async fn pull(client: &DockerClient, image: &str) -> Result<()> {
+    pull_with_progress(client, image, &mut PullProgress::Quiet).await
+}
+
+/// `pull`, reporting the layers' progress to `progress`
+pub async fn pull_with_progress(client: &DockerClient, image: &str, progress: &mut PullProgress) -> Result<()> {
+    let settings = Settings::load()?.registries.unwrap_or_default();
+    let mut errors: Vec<String> = Vec::new();
+    for candidate in candidates(image, &settings) {
+        let creds = credentials(&candidate, &settings)?;
+        if !errors.is_empty() {
+            progress.reset();
+        }
+        match client.pull_image(&candidate, creds, progress).await {
+            Ok(()) => {
+                if candidate != image {
+                    tracing::info!("Pulled {} as {}", image, candidate);
+                    // Digest references can't be tagged; they resolve by digest anyway
+                    if let Some(target) = with_tag(image) {
+                        client.tag_image(&candidate, &target).await?;
+                    }
+                }
+                return Ok(());
+            }
+            Err(e) => {
+                tracing::debug!("Pull of {} failed: {}", candidate, e);
+                errors.push(format!("{}: {}", candidate, e));
+            }
+        }
+    }
+    let message = format!("Failed to pull {}:\n  {}", image, errors.join("\n  "));
+    if errors.iter().all(|e| is_auth_failure(e)) {
+        return Err(GeoEngineError::RegistryAuth(message).into());
+    }
+    anyhow::bail!(message)
+}
+
+/// How `push` retries uploads that fail or stall
+#[derive(Debug, Clone, Copy)]
+pub struct PushRetry {
+    /// Attempts after the first one
+    pub retries: u32,
+    /// Give up on an attempt after this long without progress
+    pub stall_timeout: Duration,
+}
+
+impl Default for PushRetry {
+    fn default() -> Self {
+        PushRetry {
+            retries: 3,
+            stall_timeout: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Push an image with per-layer progress, retrying failed or stalled attempts with
+/// exponential backoff. Layers the registry received in an earlier attempt are
+/// skipped, so a retry resumes where the last one stopped at layer granularity.
+/// Rejected credentials are not retried.
+pub async fn push(
+    client: &DockerClient,
+    image: &str,
+    credentials: Option<DockerCredentials>,
+    retry: PushRetry,
+) -> Result<()> {
+    let layer_count = client
+        .inspect_image(image)
+        .await
+        .ok()
+        .and_then(|i| i.root_fs)
+        .and_then(|r| r.layers)
+        .map_or(0, |l| l.len());
+    let mut attempt = 0;
+    loop {
+        let mut progress = PushProgress::new(layer_count);
+        let pushed = client
+            .push_image(image, credentials.clone(), &mut progress, retry.stall_timeout)
+            .await;
+        progress.finish();
+        match pushed {
+            Ok(()) => {
+                if attempt > 0 || progress.existing > 0 {
+                    println!(
+                        "  {} {} layer(s) uploaded, {} already in the registry",
+                        "•".cyan(),
+                        progress.pushed,
+                        progress.existing
+                    );
+                }
+                return Ok(());
+            }
+            Err(e) if attempt >= retry.retries || matches!(e.downcast_ref(), Some(GeoEngineError::RegistryAuth(_))) => {
+                return Err(e);
+            }
+            Err(e) => {
+                attempt += 1;
+                // Doubles with each attempt, capped at 64s however many retries were asked for
+                let delay = Duration::from_secs(2u64.saturating_pow(attempt.min(6)));
+                eprintln!(
+                    "{} {:#}. Retrying in {}s (attempt {} of {})...",
+                    "!".yellow().bold(),
+                    e,
+                    delay.as_secs(),
+                    attempt + 1,
+                    retry.retries + 1
+                );
+                tokio::time::sleep(delay).await;
+            }
+        }
+    }
+}
+
+/// Whether a registry error message means the credentials were missing or rejected
+pub fn is_auth_failure(message: &str) -> bool {
+    let message = message.to_lowercase();
+    ["unauthorized", "authentication required", "denied", "401"]
+        .iter()
+        .any(|m| message.contains(m))
+}
+
+/// Digest a local image was pulled by (`sha256:...`), taken from the repo digest
+/// of its own repository. Images that were never pulled (built locally) have no
+/// repo digest; their image ID is returned instead.
+pub async fn local_digest(client: &DockerClient, image: &str) -> Result<String> {
+    let repo = repository(image);
+    let digests = client.local_repo_digests(image).await?;
+    let digest = digests
+        .iter()
+        .filter_map(|d| d.split_once('@'))
+        .find(|(r, _)| normalize(r) == repo)
+        .or_else(|| digests.first().and_then(|d| d.split_once('@')))
+        .map(|(_, digest)| digest.to_string());
+    match digest {
+        Some(digest) => Ok(digest),
+        None => client.image_id(image).await,
+    }
+}
+
+/// Normalized reference without its tag or digest: `python:3.11` -> `docker.io/library/python`
+pub fn repository(image: &str) -> String {
+    let normalized = normalize(image);
+    let without_digest = normalized.split('@').next().unwrap_or(&normalized);
+    match without_digest.rsplit_once(':') {
+        Some((repo, tag)) if !tag.contains('/') => repo.to_string(),
+        _ => without_digest.to_string(),
+    }
+}
+
+/// Whether any rewrites or mirrors are configured
+pub fn redirects_configured() -> Result<bool> {
+    let settings = Settings::load()?.registries.unwrap_or_default();
+    Ok(!settings.rewrites.is_empty() || !settings.mirrors.is_empty())
+}
+
+/// Add the implicit `:latest` tag Docker assumes for untagged references.
+/// None for digest references.
+fn with_tag(image: &str) -> Option<String> {
+    if image.contains('@') {
+        return None;
+    }
+    Some(match image.rsplit_once(':') {
+        Some((_, tag)) if !tag.contains('/') => image.to_string(),
+        _ => format!("{}:latest", image),
+    })
+}
@@ -0,0 +1,231 @@
+use bollard::container::{MemoryStatsStats, Stats, StatsOptions};
+use bollard::Docker;
+use futures::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::gpu::{self, GpuConfig};
+
+/// How often nvidia-smi is sampled; `docker stats` streams about once a second on its own
+const GPU_SAMPLE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Resources a container used over its whole run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceUsage {
+    /// CPU time consumed by all processes in the container
+    pub cpu_seconds: f64,
+    /// Average CPU load in percent of one core (200 = two busy cores)
+    pub avg_cpu_percent: f64,
+    /// Highest memory use, page cache excluded (as `docker stats` reports it)
+    pub peak_memory_bytes: u64,
+    pub avg_memory_bytes: u64,
+    /// Memory limit of the container (host memory when unlimited)
+    pub memory_limit_bytes: u64,
+    /// Number of `docker stats` samples the figures are based on
+    pub samples: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<GpuUsageSummary>,
+}
+
+/// Utilization of the GPUs assigned to a container. nvidia-smi reports whole
+/// devices, so other processes on the same GPUs are counted too.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuUsageSummary {
+    /// Indices of the sampled GPUs
+    pub devices: Vec<String>,
+    /// Busy time summed over the GPUs (utilization x wall time)
+    pub gpu_seconds: f64,
+    pub peak_utilization_percent: f64,
+    pub avg_utilization_percent: f64,
+    /// Highest memory in use, summed over the GPUs
+    pub peak_memory_mib: u64,
+}
+
+impl std::fmt::Display for ResourceUsage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "CPU {:.1}s (avg {:.0}%), memory peak {} / avg {}",
+            self.cpu_seconds,
+            self.avg_cpu_percent,
+            format_bytes(self.peak_memory_bytes),
+            format_bytes(self.avg_memory_bytes)
+        )?;
+        if self.memory_limit_bytes > 0 {
+            write!(f, " of {}", format_bytes(self.memory_limit_bytes))?;
+        }
+        if let Some(gpu) = &self.gpu {
+            write!(
+                f,
+                ", GPU {:.1}s (peak {:.0}%, avg {:.0}%, {} MiB)",
+                gpu.gpu_seconds, gpu.peak_utilization_percent, gpu.avg_utilization_percent, gpu.peak_memory_mib
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Memory in use by a container, leaving out reclaimable page cache like the Docker CLI does
+pub fn memory_in_use(stats: &Stats) -> u64 {
+    let cache = match stats.memory_stats.stats {
+        Some(MemoryStatsStats::V1(v1)) => v1.total_inactive_file,
+        Some(MemoryStatsStats::V2(v2)) => v2.inactive_file,
+        None => 0,
+    };
+    stats.memory_stats.usage.unwrap_or(0).saturating_sub(cache)
+}
+
+#[derive(Default)]
+struct Samples {
+    cpu_total_ns: u64,
+    memory_peak: u64,
+    memory_sum: u128,
+    memory_limit: u64,
+    count: usize,
+    gpu_seconds: f64,
+    gpu_peak_utilization: f64,
+    gpu_utilization_sum: f64,
+    gpu_memory_peak: u64,
+    gpu_count: usize,
+}
+
+/// Samples `docker stats` (and nvidia-smi for NVIDIA runs) in the background
+/// while a container runs
+pub struct UsageMonitor {
+    samples: Arc<Mutex<Samples>>,
+    gpu_devices: Option<Vec<String>>,
+    tasks: Vec<tokio::task::JoinHandle<()>>,
+    started: Instant,
+}
+
+impl UsageMonitor {
+    pub fn start(docker: &Docker, container_id: &str, gpu_config: Option<&GpuConfig>) -> Self {
+        let samples = Arc::new(Mutex::new(Samples::default()));
+        let mut tasks = Vec::new();
+
+        let stats_samples = samples.clone();
+        let mut stream = docker.stats(
+            container_id,
+            Some(StatsOptions {
+                stream: true,
+                one_shot: false,
+            }),
+        );
+        tasks.push(tokio::spawn(async move {
+            while let Some(Ok(stats)) = stream.next().await {
+                // Samples of a container that hasn't started yet (or already exited) are empty
+                if stats.memory_stats.usage.is_none() {
+                    continue;
+                }
+                let memory = memory_in_use(&stats);
+                let mut s = stats_samples.lock().unwrap();
+                s.cpu_total_ns = s.cpu_total_ns.max(stats.cpu_stats.cpu_usage.total_usage);
+                s.memory_peak = s.memory_peak.max(memory);
+                s.memory_sum += memory as u128;
+                s.memory_limit = stats.memory_stats.limit.unwrap_or(s.memory_limit);
+                s.count += 1;
+            }
+        }));
+
+        let gpu_devices = gpu_config.filter(|g| g.is_nvidia()).map(sampled_gpus);
+        if let Some(devices) = gpu_devices.clone() {
+            let gpu_samples = samples.clone();
+            tasks.push(tokio::spawn(async move {
+                let mut last = Instant::now();
+                loop {
+                    tokio::time::sleep(GPU_SAMPLE_INTERVAL).await;
+                    let Ok(Ok(usage)) = tokio::task::spawn_blocking(gpu::nvidia_usage).await else {
+                        break;
+                    };
+                    let elapsed = last.elapsed().as_secs_f64();
+                    last = Instant::now();
+                    let sampled: Vec<_> = usage
+                        .iter()
+                        .filter(|u| devices.is_empty() || devices.contains(&u.index))
+                        .collect();
+                    if sampled.is_empty() {
+                        continue;
+                    }
+                    let mut s = gpu_samples.lock().unwrap();
+                    for gpu in &sampled {
+                        s.gpu_seconds += gpu.utilization_percent / 100.0 * elapsed;
+                        s.gpu_peak_utilization = s.gpu_peak_utilization.max(gpu.utilization_percent);
+                    }
+                    s.gpu_utilization_sum +=
+                        sampled.iter().map(|g| g.utilization_percent).sum::<f64>() / sampled.len() as f64;
+                    s.gpu_memory_peak = s.gpu_memory_peak.max(sampled.iter().map(|g| g.memory_used_mib).sum());
+                    s.gpu_count += 1;
+                }
+            }));
+        }
+
+        UsageMonitor {
+            samples,
+            gpu_devices,
+            tasks,
+            started: Instant::now(),
+        }
+    }
+
+    /// Stop sampling and summarize. None when no sample arrived (e.g. very short runs).
+    pub fn finish(self) -> Option<ResourceUsage> {
+        for task in &self.tasks {
+            task.abort();
+        }
+        let wall = self.started.elapsed().as_secs_f64();
+        let s = self.samples.lock().unwrap();
+        if s.count == 0 {
+            return None;
+        }
+        let cpu_seconds = s.cpu_total_ns as f64 / 1e9;
+        let gpu = self.gpu_devices.filter(|_| s.gpu_count > 0).map(|devices| GpuUsageSummary {
+            devices,
+            gpu_seconds: s.gpu_seconds,
+            peak_utilization_percent: s.gpu_peak_utilization,
+            avg_utilization_percent: s.gpu_utilization_sum / s.gpu_count as f64,
+            peak_memory_mib: s.gpu_memory_peak,
+        });
+        Some(ResourceUsage {
+            cpu_seconds,
+            avg_cpu_percent: if wall > 0.0 { cpu_seconds / wall * 100.0 } else { 0.0 },
+            peak_memory_bytes: s.memory_peak,
+            avg_memory_bytes: (s.memory_sum / s.count as u128) as u64,
+            memory_limit_bytes: s.memory_limit,
+            samples: s.count,
+            gpu,
+        })
+    }
+}
+
+/// nvidia-smi indices of the GPUs a container can use; empty for all of them.
+/// MIG slices are attributed to their physical GPU.
+fn sampled_gpus(config: &GpuConfig) -> Vec<String> {
+    let Some(ids) = &config.device_ids else {
+        return Vec::new();
+    };
+    let mut devices: Vec<String> = ids
+        .iter()
+        .map(|id| {
+            config
+                .mig_devices
+                .iter()
+                .find(|m| &m.uuid == id)
+                .map(|m| m.gpu_index.to_string())
+                .unwrap_or_else(|| id.clone())
+        })
+        .collect();
+    devices.sort();
+    devices.dedup();
+    devices
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const MIB: f64 = 1024.0 * 1024.0;
+    let mib = bytes as f64 / MIB;
+    if mib >= 1024.0 {
+        format!("{:.1} GiB", mib / 1024.0)
+    } else {
+        format!("{:.0} MiB", mib)
+    }
+}
@@ -0,0 +1,150 @@
+/// `user` value that keeps the image's own USER instead of mapping the host user
+pub const IMAGE_USER: &str = "image";
+
+/// The user a worker container runs as, and why
+pub struct ContainerUser {
+    /// Value for Docker's `User` (None keeps the image's USER)
+    pub user: Option<String>,
+    /// True when the host user was mapped by default rather than configured
+    pub mapped: bool,
+}
+
+/// User namespace the Docker daemon runs containers in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UserNamespace {
+    /// Container UIDs are host UIDs
+    Host,
+    /// Rootless dockerd: container root is the user running the daemon, other
+    /// UIDs map to that user's subordinate range
+    Rootless,
+    /// `userns-remap`: all container UIDs, root included, map to a subordinate range
+    Remapped,
+}
+
+impl UserNamespace {
+    /// Read the mode from the daemon's security options (`name=rootless`, `name=userns`)
+    pub fn from_security_options(options: &[String]) -> Self {
+        let has = |name: &str| {
+            options
+                .iter()
+                .any(|o| o.split(',').any(|field| field == format!("name={}", name)))
+        };
+        if has("rootless") {
+            UserNamespace::Rootless
+        } else if has("userns") {
+            UserNamespace::Remapped
+        } else {
+            UserNamespace::Host
+        }
+    }
+}
+
+/// Resolve the container user: `--user` wins over `runtime.user`. Without either,
+/// Linux maps the invoking user's UID:GID so files written to bind mounts belong
+/// to them instead of root. Under rootless Docker, container root already is the
+/// invoking user on the host, so root is used instead. Docker Desktop (macOS,
+/// Windows) already translates ownership on bind mounts, so other platforms keep
+/// the image's USER.
+pub fn resolve(cli: Option<&str>, configured: Option<&str>, userns: UserNamespace) -> ContainerUser {
+    match cli.or(configured) {
+        Some(IMAGE_USER) => ContainerUser { user: None, mapped: false },
+        Some(user) => ContainerUser { user: Some(user.to_string()), mapped: false },
+        None if userns == UserNamespace::Rootless => ContainerUser {
+            user: Some("0:0".to_string()),
+            mapped: false,
+        },
+        None => {
+            let user = host_user();
+            ContainerUser { mapped: user.is_some(), user }
+        }
+    }
+}
+
+/// Container user for helper containers writing to bind mounts (see [`resolve`])
+pub fn default_user(userns: UserNamespace) -> Option<String> {
+    resolve(None, None, userns).user
+}
+
+/// `UID:GID` of the invoking user on Linux, None when running as root
+#[cfg(target_os = "linux")]
+pub fn host_user() -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+    // /proc/self is owned by the process's effective UID and GID
+    let meta = std::fs::metadata("/proc/self").ok()?;
+    (meta.uid() != 0).then(|| format!("{}:{}", meta.uid(), meta.gid()))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn host_user() -> Option<String> {
+    None
+}
+
+/// True when `user` (name or UID, optionally with a group) is root
+pub fn is_root(user: &str) -> bool {
+    matches!(user.split(':').next(), Some("root") | Some("0"))
+}
+
+/// Host GIDs owning the GPU device nodes (NVIDIA, and DRI render/video nodes).
+/// A non-root container user needs these supplementary groups when the nodes
+/// aren't world-accessible, e.g. on hosts that restrict them to `video`.
+#[cfg(target_os = "linux")]
+pub fn gpu_device_groups() -> Vec<String> {
+    use std::os::unix::fs::MetadataExt;
+    let mut nodes: Vec<std::path::PathBuf> = Vec::new();
+    for dir in ["/dev", "/dev/dri"] {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with("nvidia") || name.starts_with("renderD") || name.starts_with("card") {
+                nodes.push(entry.path());
+            }
+        }
+    }
+
+    let mut groups: Vec<String> = Vec::new();
+    for node in nodes {
+        let Ok(meta) = std::fs::metadata(&node) else {
+            continue;
+        };
+        // Skip nodes everyone can read and write
+        if meta.gid() == 0 || meta.mode() & 0o006 == 0o006 {
+            continue;
+        }
+        let gid = meta.gid().to_string();
+        if !groups.contains(&gid) {
+            groups.push(gid);
+        }
+    }
+    groups
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn gpu_device_groups() -> Vec<String> {
+    Vec::new()
+}
+
+/// Host device paths of `--device` values (`HOST[:CONTAINER[:PERMISSIONS]]`) the
+/// invoking user can't open for reading and writing. A rootless daemon runs as
+/// that user, so it can only pass through devices the user has access to.
+#[cfg(unix)]
+pub fn inaccessible_devices(devices: &[String]) -> Vec<String> {
+    use std::os::unix::ffi::OsStrExt;
+    devices
+        .iter()
+        .map(|d| d.split(':').next().unwrap_or(d).to_string())
+        .filter(|host| {
+            let Ok(path) = std::ffi::CString::new(std::path::Path::new(host).as_os_str().as_bytes()) else {
+                return true;
+            };
+            let accessible = unsafe { libc::access(path.as_ptr(), libc::R_OK | libc::W_OK) } == 0;
+            !accessible
+        })
+        .collect()
+}
+
+#[cfg(not(unix))]
+pub fn inaccessible_devices(_devices: &[String]) -> Vec<String> {
+    Vec::new()
+}
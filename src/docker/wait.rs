@@ -0,0 +1,90 @@
+use anyhow::Result;
+use colored::Colorize;
+use std::time::{Duration, Instant};
+
+use super::client::DockerClient;
+use crate::config::worker::WaitTarget;
+
+/// Default time to wait for a dependency, in seconds
+const DEFAULT_TIMEOUT_SECS: u64 = 60;
+
+/// Delay between readiness checks
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Block until every `runtime.wait_for` target is ready, in order
+pub async fn wait_for_all(targets: &[WaitTarget], client: &DockerClient) -> Result<()> {
+    for target in targets {
+        wait_for(target, client).await?;
+    }
+    Ok(())
+}
+
+async fn wait_for(target: &WaitTarget, client: &DockerClient) -> Result<()> {
+    let description = describe(target)?;
+    let timeout = Duration::from_secs(target.timeout.unwrap_or(DEFAULT_TIMEOUT_SECS));
+    let started = Instant::now();
+
+    eprintln!("{} Waiting for {}...", "=>".blue().bold(), description.cyan());
+    loop {
+        let last_error = match check(target, client).await {
+            Ok(()) => {
+                eprintln!("{} {} is ready", "✓".green().bold(), description);
+                return Ok(());
+            }
+            Err(e) => e,
+        };
+
+        if started.elapsed() >= timeout {
+            anyhow::bail!(
+                "Timed out after {}s waiting for {} (last error: {})",
+                timeout.as_secs(),
+                description,
+                last_error
+            );
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// Human-readable name of a target, validating that exactly one kind is set
+fn describe(target: &WaitTarget) -> Result<String> {
+    match (&target.url, &target.tcp, &target.container) {
+        (Some(url), None, None) => Ok(format!("url {}", url)),
+        (None, Some(addr), None) => Ok(format!("tcp {}", addr)),
+        (None, None, Some(name)) => Ok(format!("container {}", name)),
+        _ => anyhow::bail!("Each runtime.wait_for entry must set exactly one of 'url', 'tcp' or 'container'"),
+    }
+}
+
+/// Run one readiness check, returning why the target is not ready yet
+async fn check(target: &WaitTarget, client: &DockerClient) -> std::result::Result<(), String> {
+    if let Some(url) = &target.url {
+        let response = reqwest::Client::new()
+            .get(url)
+            .timeout(POLL_INTERVAL * 5)
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        if response.status().is_success() || response.status().is_redirection() {
+            return Ok(());
+        }
+        return Err(format!("HTTP {}", response.status()));
+    }
+
+    if let Some(addr) = &target.tcp {
+        return match tokio::time::timeout(POLL_INTERVAL * 5, tokio::net::TcpStream::connect(addr)).await {
+            Ok(Ok(_)) => Ok(()),
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(_) => Err("connection timed out".to_string()),
+        };
+    }
+
+    if let Some(name) = &target.container {
+        return match client.container_ready(name).await {
+            Ok(ready) => ready,
+            Err(e) => Err(format!("{:#}", e)),
+        };
+    }
+
+    Ok(())
+}
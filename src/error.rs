@@ -0,0 +1,181 @@
+use colored::Colorize;
+use serde::Serialize;
+
+/// Failure classes that wrappers can branch on. Each maps to its own process
+/// exit code and, with `--json`, to an `error.code` string. Errors that are not
+/// classified exit with 1 and code `error`.
+///
+/// Raise them like any other error (`Err(GeoEngineError::...)?`) or attach them
+/// as context; context added further up doesn't hide them, since `classify`
+/// searches the whole chain.
+#[derive(Debug, thiserror::Error)]
+pub enum GeoEngineError {
+    /// The Docker daemon can't be reached
+    #[error("{0}")]
+    DockerUnavailable(String),
+
+    /// The worker is not registered or has not been applied
+    #[error("{0}")]
+    WorkerNotFound(String),
+
+    /// geoengine.yaml (or .toml / .json) can't be parsed or is inconsistent
+    #[error("{0}")]
+    ConfigInvalid(String),
+
+    /// A run input or option is missing or malformed
+    #[error("{0}")]
+    InputInvalid(String),
+
+    /// The worker image has not been built or pulled
+    #[error("{0}")]
+    ImageNotFound(String),
+
+    /// A GPU was requested but none (or no free MIG slice) is available
+    #[error("{0}")]
+    GpuUnavailable(String),
+
+    /// A registry rejected the credentials (or their absence)
+    #[error("{0}")]
+    RegistryAuth(String),
+
+    /// The worker container exited with a non-zero code
+    #[error("Worker '{worker}' exited with code {exit_code}")]
+    ContainerFailed { worker: String, exit_code: i64 },
+
+    /// The kernel killed the worker container for exceeding its memory limit
+    #[error("Worker '{worker}' was killed: {detail}")]
+    OutOfMemory { worker: String, detail: String },
+
+    /// The worker exited with code 0 but its outputs failed `outputs.validate`
+    #[error("Outputs of worker '{worker}' failed validation: {}", failures.join("; "))]
+    OutputsInvalid { worker: String, failures: Vec<String> },
+
+    /// Some of `geoengine test`'s test cases failed
+    #[error("{failed} of {total} tests failed")]
+    TestsFailed { failed: usize, total: usize },
+
+    /// Some items of `geoengine run --batch` failed
+    #[error("{failed} of {total} batch items failed")]
+    BatchFailed { failed: usize, total: usize },
+
+    /// Some checks of a diagnostic command (`doctor`, `gpu doctor`) failed
+    #[error("{failed} check(s) failed")]
+    ChecksFailed { failed: usize },
+
+    /// An extension command (geoengine-<name>) exited with a non-zero code
+    #[error("Extension '{name}' exited with code {exit_code}")]
+    ExtensionFailed { name: String, exit_code: i32 },
+
+    /// The user interrupted a run (Ctrl+C)
+    #[error("Run interrupted; container stopped and removed")]
+    Interrupted,
+}
+
+impl GeoEngineError {
+    /// Machine-readable code, reported as `error.code` in `--json` modes
+    pub fn code(&self) -> &'static str {
+        match self {
+            GeoEngineError::DockerUnavailable(_) => "docker_unavailable",
+            GeoEngineError::WorkerNotFound(_) => "worker_not_found",
+            GeoEngineError::ConfigInvalid(_) => "config_invalid",
+            GeoEngineError::InputInvalid(_) => "input_invalid",
+            GeoEngineError::ImageNotFound(_) => "image_not_found",
+            GeoEngineError::GpuUnavailable(_) => "gpu_unavailable",
+            GeoEngineError::RegistryAuth(_) => "registry_auth",
+            GeoEngineError::ContainerFailed { .. } => "container_failed",
+            GeoEngineError::OutOfMemory { .. } => "oom_killed",
+            GeoEngineError::OutputsInvalid { .. } => "outputs_invalid",
+            GeoEngineError::TestsFailed { .. } => "tests_failed",
+            GeoEngineError::BatchFailed { .. } => "batch_failed",
+            GeoEngineError::ChecksFailed { .. } => "checks_failed",
+            GeoEngineError::ExtensionFailed { .. } => "extension_failed",
+            GeoEngineError::Interrupted => "interrupted",
+        }
+    }
+
+    /// Process exit code. 1 is left for unclassified errors and 2 for usage
+    /// errors reported by clap.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GeoEngineError::DockerUnavailable(_) => 3,
+            GeoEngineError::WorkerNotFound(_) => 4,
+            GeoEngineError::ConfigInvalid(_) => 5,
+            GeoEngineError::InputInvalid(_) => 6,
+            GeoEngineError::ImageNotFound(_) => 7,
+            GeoEngineError::GpuUnavailable(_) => 8,
+            GeoEngineError::RegistryAuth(_) => 9,
+            GeoEngineError::ContainerFailed { .. } => 10,
+            GeoEngineError::TestsFailed { .. } => 11,
+            GeoEngineError::BatchFailed { .. } => 12,
+            GeoEngineError::ChecksFailed { .. } => 13,
+            GeoEngineError::OutOfMemory { .. } => 14,
+            GeoEngineError::OutputsInvalid { .. } => 15,
+            // Passed on, so scripts see the extension's own code
+            GeoEngineError::ExtensionFailed { exit_code, .. } => *exit_code,
+            GeoEngineError::Interrupted => 130,
+        }
+    }
+
+    /// Whether the command already printed its JSON result, which carries the error
+    fn reported_in_json(&self) -> bool {
+        matches!(
+            self,
+            GeoEngineError::ContainerFailed { .. }
+                | GeoEngineError::OutOfMemory { .. }
+                | GeoEngineError::OutputsInvalid { .. }
+                | GeoEngineError::TestsFailed { .. }
+                | GeoEngineError::BatchFailed { .. }
+                | GeoEngineError::ChecksFailed { .. }
+                | GeoEngineError::ExtensionFailed { .. }
+        )
+    }
+}
+
+/// `error` object of `--json` output
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct ErrorInfo {
+    pub code: String,
+    pub message: String,
+}
+
+impl ErrorInfo {
+    pub fn new(error: &GeoEngineError) -> Self {
+        ErrorInfo {
+            code: error.code().to_string(),
+            message: error.to_string(),
+        }
+    }
+}
+
+/// The classified error in an error chain, if any
+pub fn classify(err: &anyhow::Error) -> Option<&GeoEngineError> {
+    // anyhow's downcast looks through every context layer, unlike `chain()`
+    err.downcast_ref::<GeoEngineError>()
+}
+
+/// Print a failed command's error (as JSON on stdout when `json`) and return
+/// the exit code to use
+pub fn report(err: &anyhow::Error, json: bool) -> i32 {
+    let classified = classify(err);
+    let code = classified.map(|e| e.code()).unwrap_or("error");
+
+    if json {
+        if !classified.is_some_and(|e| e.reported_in_json()) {
+            let output = serde_json::json!({
+                "status": "error",
+                "error": { "code": code, "message": format!("{:#}", err) },
+            });
+            println!("{}", output);
+        }
+    } else {
+        eprintln!("{} {}", "Error:".red().bold(), err);
+        for cause in err.chain().skip(1) {
+            eprintln!("  {} {}", "caused by:".dimmed(), cause);
+        }
+        if classified.is_some() {
+            eprintln!("  {}", format!("[{}]", code).dimmed());
+        }
+    }
+
+    classified.map(|e| e.exit_code()).unwrap_or(1)
+}
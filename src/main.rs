@@ -1,6 +1,7 @@
 mod cli;
 mod config;
 mod docker;
+mod error;
 mod utils;
 
 use anyhow::Result;
@@ -9,10 +10,22 @@ use cli::Cli;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // Parse CLI arguments first: the settings profile decides where telemetry goes
+    let cli = Cli::parse();
+    cli.select_profile();
+
     // Initialize logging
     utils::logger::init()?;
 
-    // Parse CLI arguments and execute
-    let cli = Cli::parse();
-    cli.execute().await
+    let result = cli.execute().await;
+
+    // Flush any pending trace spans before exiting
+    utils::logger::shutdown();
+
+    if let Err(err) = result {
+        // Arguments after `--` belong to the worker, not to us
+        let json = std::env::args().take_while(|a| a != "--").any(|a| a == "--json");
+        std::process::exit(error::report(&err, json));
+    }
+    Ok(())
 }
@@ -0,0 +1,297 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::PathBuf;
+
+const API: &str = "https://artifactregistry.googleapis.com/v1";
+const SCOPE: &str = "https://www.googleapis.com/auth/cloud-platform";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+
+/// An Artifact Registry Docker repository, accessed through the REST API
+pub struct ArtifactRegistry {
+    project: String,
+    location: String,
+    repository: String,
+    token: String,
+    client: reqwest::Client,
+}
+
+/// One tagged (or untagged) image version
+pub struct ImageTag {
+    pub package: String,
+    /// None for untagged versions
+    pub tag: Option<String>,
+    pub digest: String,
+    pub uploaded_at: String,
+}
+
+impl ArtifactRegistry {
+    /// Authenticate with Application Default Credentials (see `access_token`)
+    pub async fn connect(project: &str, location: &str, repository: &str) -> Result<Self> {
+        let client = reqwest::Client::new();
+        let token = access_token(&client).await?;
+        Ok(ArtifactRegistry {
+            project: project.to_string(),
+            location: location.to_string(),
+            repository: repository.to_string(),
+            token,
+            client,
+        })
+    }
+
+    fn repository_path(&self) -> String {
+        format!(
+            "{}/projects/{}/locations/{}/repositories/{}",
+            API, self.project, self.location, self.repository
+        )
+    }
+
+    /// List every image version in the repository with its tags
+    pub async fn list_images(&self) -> Result<Vec<ImageTag>> {
+        let url = format!("{}/dockerImages", self.repository_path());
+        let mut tags = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self.client.get(&url).bearer_auth(&self.token).query(&[("pageSize", "1000")]);
+            if let Some(token) = &page_token {
+                request = request.query(&[("pageToken", token)]);
+            }
+            let page: DockerImagesPage = check(request.send().await.with_context(|| format!("Failed to query {}", url))?)
+                .await?
+                .json()
+                .await
+                .context("Failed to parse Artifact Registry response")?;
+
+            for image in page.docker_images {
+                // uri: <location>-docker.pkg.dev/<project>/<repository>/<package>@sha256:...
+                let (path, digest) = image.uri.split_once('@').unwrap_or((&image.uri, ""));
+                let package = path.splitn(4, '/').nth(3).unwrap_or(path).to_string();
+                let uploaded_at = image.upload_time.unwrap_or_default();
+                if image.tags.is_empty() {
+                    tags.push(ImageTag {
+                        package,
+                        tag: None,
+                        digest: digest.to_string(),
+                        uploaded_at,
+                    });
+                    continue;
+                }
+                for tag in image.tags {
+                    tags.push(ImageTag {
+                        package: package.clone(),
+                        tag: Some(tag),
+                        digest: digest.to_string(),
+                        uploaded_at: uploaded_at.clone(),
+                    });
+                }
+            }
+
+            page_token = page.next_page_token.filter(|t| !t.is_empty());
+            if page_token.is_none() {
+                break;
+            }
+        }
+        tags.sort_by(|a, b| a.package.cmp(&b.package).then(b.uploaded_at.cmp(&a.uploaded_at)));
+        Ok(tags)
+    }
+
+    /// Delete a tag; the image version it pointed to stays available by digest
+    pub async fn delete_tag(&self, package: &str, tag: &str) -> Result<()> {
+        let url = format!(
+            "{}/packages/{}/tags/{}",
+            self.repository_path(),
+            urlencode(package),
+            urlencode(tag)
+        );
+        let response = self
+            .client
+            .delete(&url)
+            .bearer_auth(&self.token)
+            .send()
+            .await
+            .with_context(|| format!("Failed to query {}", url))?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("Tag not found: {}:{}", package, tag);
+        }
+        check(response).await?;
+        Ok(())
+    }
+}
+
+/// Fail with the API's error message on non-success responses
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().map(String::from))
+        .unwrap_or(body);
+    anyhow::bail!("Artifact Registry request failed ({}): {}", status, message.trim())
+}
+
+/// Get an OAuth access token from Application Default Credentials, in order:
+/// the key file in GOOGLE_APPLICATION_CREDENTIALS, the gcloud ADC file
+/// (`gcloud auth application-default login`), the GCE/GKE metadata server,
+/// and finally `gcloud auth print-access-token`.
+pub async fn access_token(client: &reqwest::Client) -> Result<String> {
+    if let Some(path) = adc_file() {
+        let content = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read credentials: {}", path.display()))?;
+        let credentials: CredentialsFile = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse credentials: {}", path.display()))?;
+        return token_from_file(client, &credentials)
+            .await
+            .with_context(|| format!("Failed to authenticate with {}", path.display()));
+    }
+
+    if let Ok(token) = metadata_token(client).await {
+        return Ok(token);
+    }
+
+    gcloud_token().context(
+        "No Google Cloud credentials found. Set GOOGLE_APPLICATION_CREDENTIALS to a service account key, \
+         run `gcloud auth application-default login`, or install the gcloud CLI.",
+    )
+}
+
+/// GOOGLE_APPLICATION_CREDENTIALS, or the file written by `gcloud auth application-default login`
+fn adc_file() -> Option<PathBuf> {
+    if let Ok(path) = std::env::var("GOOGLE_APPLICATION_CREDENTIALS") {
+        return Some(PathBuf::from(path));
+    }
+    let gcloud_dir = match std::env::var("CLOUDSDK_CONFIG") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) if cfg!(windows) => dirs::config_dir()?.join("gcloud"),
+        Err(_) => dirs::home_dir()?.join(".config").join("gcloud"),
+    };
+    let path = gcloud_dir.join("application_default_credentials.json");
+    path.exists().then_some(path)
+}
+
+#[derive(Deserialize)]
+struct CredentialsFile {
+    #[serde(rename = "type")]
+    kind: String,
+    // service_account
+    client_email: Option<String>,
+    private_key: Option<String>,
+    private_key_id: Option<String>,
+    token_uri: Option<String>,
+    // authorized_user
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    refresh_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+}
+
+#[derive(serde::Serialize)]
+struct JwtClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+async fn token_from_file(client: &reqwest::Client, credentials: &CredentialsFile) -> Result<String> {
+    let token_uri = credentials.token_uri.as_deref().unwrap_or(DEFAULT_TOKEN_URI);
+    let form: Vec<(&str, String)> = match credentials.kind.as_str() {
+        "service_account" => {
+            let email = credentials.client_email.as_deref().context("Key file has no client_email")?;
+            let key = credentials.private_key.as_deref().context("Key file has no private_key")?;
+            let now = chrono::Utc::now().timestamp();
+            let claims = JwtClaims {
+                iss: email,
+                scope: SCOPE,
+                aud: token_uri,
+                iat: now,
+                exp: now + 3600,
+            };
+            let mut header = jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256);
+            header.kid = credentials.private_key_id.clone();
+            let assertion = jsonwebtoken::encode(
+                &header,
+                &claims,
+                &jsonwebtoken::EncodingKey::from_rsa_pem(key.as_bytes()).context("Invalid private_key")?,
+            )?;
+            vec![
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer".to_string()),
+                ("assertion", assertion),
+            ]
+        }
+        "authorized_user" => vec![
+            ("grant_type", "refresh_token".to_string()),
+            ("client_id", credentials.client_id.clone().context("Credentials have no client_id")?),
+            ("client_secret", credentials.client_secret.clone().context("Credentials have no client_secret")?),
+            ("refresh_token", credentials.refresh_token.clone().context("Credentials have no refresh_token")?),
+        ],
+        other => anyhow::bail!("Unsupported credentials type '{}'", other),
+    };
+
+    let response = client.post(token_uri).form(&form).send().await?;
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        anyhow::bail!("Token request failed ({}): {}", status, body.trim());
+    }
+    Ok(response.json::<TokenResponse>().await?.access_token)
+}
+
+/// Token of the attached service account on GCE, GKE and Cloud Run
+async fn metadata_token(client: &reqwest::Client) -> Result<String> {
+    let url = match std::env::var("GCE_METADATA_HOST") {
+        Ok(host) => METADATA_TOKEN_URL.replace("metadata.google.internal", &host),
+        Err(_) => METADATA_TOKEN_URL.to_string(),
+    };
+    let response = client
+        .get(url)
+        .header("Metadata-Flavor", "Google")
+        // Off GCP the name doesn't resolve or nothing answers; don't wait long
+        .timeout(std::time::Duration::from_secs(2))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(response.json::<TokenResponse>().await?.access_token)
+}
+
+fn gcloud_token() -> Result<String> {
+    which::which("gcloud").context("gcloud CLI not found")?;
+    let output = std::process::Command::new("gcloud")
+        .args(["auth", "print-access-token"])
+        .output()
+        .context("Failed to run gcloud auth print-access-token")?;
+    if !output.status.success() {
+        anyhow::bail!("gcloud auth print-access-token failed: {}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DockerImagesPage {
+    #[serde(default)]
+    docker_images: Vec<DockerImage>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DockerImage {
+    uri: String,
+    #[serde(default)]
+    tags: Vec<String>,
+    upload_time: Option<String>,
+}
+
+/// Package names may contain '/', which must be escaped in API paths
+fn urlencode(name: &str) -> String {
+    name.replace('/', "%2F")
+}
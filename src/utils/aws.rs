@@ -0,0 +1,120 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Locate the AWS CLI
+fn aws_bin() -> Result<PathBuf> {
+    which::which("aws").context(
+        "aws CLI not found. Please install it: https://docs.aws.amazon.com/cli/latest/userguide/getting-started-install.html",
+    )
+}
+
+/// Run `aws <service> <operation> ...` in `region` and parse its JSON output.
+/// Credentials come from the CLI's usual chain (environment, profile, SSO, instance role).
+fn run(region: &str, args: &[&str]) -> Result<Value> {
+    let operation = args.iter().take(2).copied().collect::<Vec<_>>().join(" ");
+    let output = Command::new(aws_bin()?)
+        .args(args)
+        .args(["--region", region, "--output", "json"])
+        .output()
+        .with_context(|| format!("Failed to run aws {}", operation))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("aws {} failed: {}", operation, stderr.trim());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    if stdout.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+    serde_json::from_str(&stdout).with_context(|| format!("Unexpected output from aws {}", operation))
+}
+
+/// Region configured for the AWS CLI (`aws configure get region`)
+pub fn configured_region() -> Option<String> {
+    let output = Command::new(aws_bin().ok()?)
+        .args(["configure", "get", "region"])
+        .output()
+        .ok()?;
+    let region = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !region.is_empty()).then_some(region)
+}
+
+/// Account ID of the caller's credentials
+pub fn account_id(region: &str) -> Result<String> {
+    run(region, &["sts", "get-caller-identity"])?
+        .get("Account")
+        .and_then(|a| a.as_str())
+        .map(String::from)
+        .context("aws sts get-caller-identity returned no account")
+}
+
+/// Registry host of the account's ECR in `region`
+pub fn ecr_registry(account: &str, region: &str) -> String {
+    format!("{}.dkr.ecr.{}.amazonaws.com", account, region)
+}
+
+/// Password for `AWS` at the account's ECR registry, valid for 12 hours
+pub fn ecr_password(region: &str) -> Result<String> {
+    let output = Command::new(aws_bin()?)
+        .args(["ecr", "get-login-password", "--region", region])
+        .output()
+        .context("Failed to run aws ecr get-login-password")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("aws ecr get-login-password failed: {}", stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Download an S3 object with `aws s3 cp`, which prints its own progress
+pub fn s3_download(url: &str, dest: &std::path::Path) -> Result<()> {
+    let status = Command::new(aws_bin()?)
+        .args(["s3", "cp", url])
+        .arg(dest)
+        .status()
+        .context("Failed to run aws s3 cp")?;
+    if !status.success() {
+        anyhow::bail!("aws s3 cp {} failed", url);
+    }
+    Ok(())
+}
+
+/// Create the ECR repository unless it exists. Returns whether it was created.
+pub fn ensure_ecr_repository(region: &str, name: &str) -> Result<bool> {
+    if run(region, &["ecr", "describe-repositories", "--repository-names", name]).is_ok() {
+        return Ok(false);
+    }
+    run(region, &["ecr", "create-repository", "--repository-name", name])
+        .with_context(|| format!("Failed to create ECR repository {}", name))?;
+    Ok(true)
+}
+
+/// Register a new revision of a job definition. Returns its ARN.
+pub fn register_job_definition(region: &str, definition: &Value) -> Result<String> {
+    let input = definition.to_string();
+    run(region, &["batch", "register-job-definition", "--cli-input-json", &input])?
+        .get("jobDefinitionArn")
+        .and_then(|a| a.as_str())
+        .map(String::from)
+        .context("aws batch register-job-definition returned no ARN")
+}
+
+/// Submit a job. Returns its ID.
+pub fn submit_job(region: &str, request: &Value) -> Result<String> {
+    let input = request.to_string();
+    run(region, &["batch", "submit-job", "--cli-input-json", &input])?
+        .get("jobId")
+        .and_then(|id| id.as_str())
+        .map(String::from)
+        .context("aws batch submit-job returned no job ID")
+}
+
+/// The description of a job (status, reason, container exit code and log stream)
+pub fn describe_job(region: &str, job_id: &str) -> Result<Value> {
+    run(region, &["batch", "describe-jobs", "--jobs", job_id])?
+        .get("jobs")
+        .and_then(|jobs| jobs.get(0))
+        .cloned()
+        .with_context(|| format!("No AWS Batch job with ID {} in {}", job_id, region))
+}
@@ -0,0 +1,117 @@
+use colored::Colorize;
+use serde::Serialize;
+
+/// Outcome of one diagnostic check
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Pass,
+    Warn,
+    Fail,
+    /// Not applicable here, or depends on a check that failed
+    Skip,
+}
+
+/// Result of a diagnostic check, with the steps that fix it
+#[derive(Debug, Clone, Serialize)]
+pub struct Check {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub fix: Vec<String>,
+}
+
+impl Check {
+    pub fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Pass, detail)
+    }
+
+    pub fn warn(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Warn, detail)
+    }
+
+    pub fn fail(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Fail, detail)
+    }
+
+    pub fn skip(name: &str, detail: impl Into<String>) -> Self {
+        Self::new(name, CheckStatus::Skip, detail)
+    }
+
+    fn new(name: &str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Check {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+            fix: Vec::new(),
+        }
+    }
+
+    /// Add a remediation step (shown for warnings and failures)
+    pub fn fix(mut self, step: impl Into<String>) -> Self {
+        self.fix.push(step.into());
+        self
+    }
+}
+
+/// Checks of a diagnostic run, as printed by `--json`
+#[derive(Debug, Serialize)]
+pub struct Report {
+    pub passed: usize,
+    pub warnings: usize,
+    pub failed: usize,
+    pub checks: Vec<Check>,
+}
+
+impl Report {
+    pub fn new(checks: Vec<Check>) -> Self {
+        let count = |status| checks.iter().filter(|c| c.status == status).count();
+        Report {
+            passed: count(CheckStatus::Pass),
+            warnings: count(CheckStatus::Warn),
+            failed: count(CheckStatus::Fail),
+            checks,
+        }
+    }
+
+    /// Print one line per check, then the fixes of warnings and failures as numbered steps
+    pub fn print(&self) {
+        for check in &self.checks {
+            let symbol = match check.status {
+                CheckStatus::Pass => "✓".green().bold(),
+                CheckStatus::Warn => "!".yellow().bold(),
+                CheckStatus::Fail => "✗".red().bold(),
+                CheckStatus::Skip => "-".dimmed(),
+            };
+            let detail = if check.status == CheckStatus::Skip {
+                check.detail.dimmed()
+            } else {
+                check.detail.normal()
+            };
+            eprintln!("{} {:<28} {}", symbol, check.name, detail);
+        }
+
+        let steps: Vec<&String> = self
+            .checks
+            .iter()
+            .filter(|c| matches!(c.status, CheckStatus::Warn | CheckStatus::Fail))
+            .flat_map(|c| &c.fix)
+            .collect();
+        if !steps.is_empty() {
+            eprintln!();
+            eprintln!("{}", "To fix:".bold());
+            for (i, step) in steps.iter().enumerate() {
+                eprintln!("  {}. {}", i + 1, step);
+            }
+        }
+
+        eprintln!();
+        eprintln!(
+            "{} passed, {} warning(s), {} failed",
+            self.passed.to_string().green(),
+            self.warnings.to_string().yellow(),
+            self.failed.to_string().red()
+        );
+    }
+}
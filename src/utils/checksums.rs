@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
+
+use crate::config::state::compute_file_hash;
+
+/// Hex digest of a `sha256:<hex>` (or bare hex) checksum
+pub fn parse_sha256(checksum: &str) -> Result<String> {
+    let hex = checksum.trim().strip_prefix("sha256:").unwrap_or(checksum.trim());
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("Invalid checksum '{}': expected sha256:<64 hex digits>", checksum);
+    }
+    Ok(hex.to_ascii_lowercase())
+}
+
+/// All files under `dir` with their SHA-256, as `(relative path, hex)` sorted by path.
+/// `on_file` is called with each file's size once it has been hashed.
+pub fn hash_tree(dir: &Path, skip: Option<&Path>, on_file: &mut dyn FnMut(u64)) -> Result<Vec<(String, String)>> {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+    files.sort();
+
+    let skip = skip.and_then(|p| p.canonicalize().ok());
+    let mut entries = Vec::new();
+    for path in files {
+        if skip.is_some() && path.canonicalize().ok() == skip {
+            continue;
+        }
+        let relative = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        entries.push((relative, compute_file_hash(&path)?));
+        on_file(std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0));
+    }
+    Ok(entries)
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = std::fs::read_dir(dir).with_context(|| format!("Failed to read directory: {}", dir.display()))?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if path.is_file() {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// Total size of the files under `dir`, for progress bars
+pub fn tree_size(dir: &Path) -> u64 {
+    let mut files = Vec::new();
+    let _ = collect_files(dir, &mut files);
+    files.iter().filter_map(|f| std::fs::metadata(f).ok()).map(|m| m.len()).sum()
+}
+
+/// Manifest lines in the `sha256sum` format, so `sha256sum -c` can check them too
+pub fn format_manifest(entries: &[(String, String)]) -> String {
+    entries.iter().map(|(path, hex)| format!("{}  {}\n", hex, path)).collect()
+}
+
+/// Read a `sha256sum`-format manifest as `(relative path, hex)` pairs
+pub fn read_manifest(path: &Path) -> Result<Vec<(String, String)>> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("Failed to read manifest: {}", path.display()))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|(i, line)| {
+            // "<hex>  <path>" in text mode, "<hex> *<path>" in binary mode
+            let (hex, file) = line
+                .split_once(' ')
+                .map(|(hex, rest)| (hex, rest.strip_prefix([' ', '*']).unwrap_or(rest)))
+                .filter(|(_, file)| !file.is_empty())
+                .with_context(|| format!("{}:{}: expected '<sha256>  <path>'", path.display(), i + 1))?;
+            let hex = parse_sha256(hex).with_context(|| format!("{}:{}", path.display(), i + 1))?;
+            let file = normalize_relative(file).with_context(|| {
+                format!("{}:{}: '{}' is not a path inside the folder", path.display(), i + 1, file)
+            })?;
+            Ok((file, hex))
+        })
+        .collect()
+}
+
+/// Check the files under `dir` against a manifest. Returns one message per missing,
+/// changed or unlisted file; `skip` (the manifest itself) may be unlisted.
+pub fn verify_tree(
+    dir: &Path,
+    manifest: &[(String, String)],
+    skip: Option<&Path>,
+    on_file: &mut dyn FnMut(u64),
+) -> Result<Vec<String>> {
+    let mut failures = Vec::new();
+    for (relative, expected) in manifest {
+        let path = dir.join(relative);
+        if !path.is_file() {
+            failures.push(format!("{}: missing", relative));
+            continue;
+        }
+        let actual = compute_file_hash(&path)?;
+        if actual != *expected {
+            failures.push(format!("{}: sha256 {} (expected {})", relative, actual, expected));
+        }
+        on_file(std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0));
+    }
+
+    let listed: HashSet<&str> = manifest.iter().map(|(relative, _)| relative.as_str()).collect();
+    let skip = skip.and_then(|p| p.canonicalize().ok());
+    let mut files = Vec::new();
+    collect_files(dir, &mut files)?;
+    files.sort();
+    for path in files {
+        if skip.is_some() && path.canonicalize().ok() == skip {
+            continue;
+        }
+        let relative = path.strip_prefix(dir).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        if !listed.contains(relative.as_str()) {
+            failures.push(format!("{}: not in the manifest", relative));
+        }
+    }
+    Ok(failures)
+}
+
+/// `a/./b` as `a/b`; None for absolute paths and paths with `..`, which could
+/// point outside the folder
+fn normalize_relative(path: &str) -> Option<String> {
+    let mut parts = Vec::new();
+    for component in Path::new(path).components() {
+        match component {
+            Component::Normal(part) => parts.push(part.to_string_lossy().into_owned()),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!parts.is_empty()).then(|| parts.join("/"))
+}
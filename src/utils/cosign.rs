@@ -0,0 +1,117 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::settings::SigningSettings;
+
+/// Locate the cosign binary
+fn cosign_bin() -> Result<PathBuf> {
+    which::which("cosign").context(
+        "cosign not found. Please install it: https://docs.sigstore.dev/cosign/system_config/installation/",
+    )
+}
+
+/// Path of the signature bundle written next to a signed tar file
+pub fn bundle_path(blob: &Path) -> PathBuf {
+    let mut name = blob.as_os_str().to_os_string();
+    name.push(".bundle");
+    PathBuf::from(name)
+}
+
+/// Sign an image in a registry. Uses the given private key, or keyless (OIDC) signing if none.
+/// Annotations are stored in the signature payload, leaving the image itself untouched.
+pub fn sign_image(image: &str, key: Option<&Path>, annotations: &[(String, String)]) -> Result<()> {
+    let mut cmd = Command::new(cosign_bin()?);
+    cmd.args(["sign", "--yes"]);
+    if let Some(key) = key {
+        cmd.arg("--key").arg(key);
+    }
+    for (k, v) in annotations {
+        cmd.arg("-a").arg(format!("{}={}", k, v));
+    }
+    cmd.arg(image);
+
+    // Inherit stdio so cosign can prompt for the key password or open the OIDC flow
+    let status = cmd.status().context("Failed to run cosign sign")?;
+    if !status.success() {
+        anyhow::bail!("cosign failed to sign {}", image);
+    }
+    Ok(())
+}
+
+/// Sign a local file (e.g. an exported image tar), writing a `<file>.bundle` next to it.
+pub fn sign_blob(blob: &Path, key: Option<&Path>) -> Result<PathBuf> {
+    let bundle = bundle_path(blob);
+    let mut cmd = Command::new(cosign_bin()?);
+    cmd.args(["sign-blob", "--yes"]);
+    if let Some(key) = key {
+        cmd.arg("--key").arg(key);
+    }
+    cmd.arg("--bundle").arg(&bundle).arg(blob);
+
+    let status = cmd.status().context("Failed to run cosign sign-blob")?;
+    if !status.success() {
+        anyhow::bail!("cosign failed to sign {}", blob.display());
+    }
+    Ok(bundle)
+}
+
+/// Verify the signature of an image in a registry against the expected signer.
+pub fn verify_image(image: &str, signing: &SigningSettings) -> Result<()> {
+    let mut cmd = Command::new(cosign_bin()?);
+    cmd.arg("verify").args(verification_args(signing)?).arg(image);
+
+    let output = cmd.output().context("Failed to run cosign verify")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Signature verification failed for {}: {}", image, stderr.trim());
+    }
+    Ok(())
+}
+
+/// Verify a local file against its `<file>.bundle` signature and the expected signer.
+pub fn verify_blob(blob: &Path, signing: &SigningSettings) -> Result<()> {
+    let bundle = bundle_path(blob);
+    if !bundle.exists() {
+        anyhow::bail!(
+            "No signature bundle found for {} (expected {})",
+            blob.display(),
+            bundle.display()
+        );
+    }
+
+    let mut cmd = Command::new(cosign_bin()?);
+    cmd.arg("verify-blob")
+        .args(verification_args(signing)?)
+        .arg("--bundle")
+        .arg(&bundle)
+        .arg(blob);
+
+    let output = cmd.output().context("Failed to run cosign verify-blob")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Signature verification failed for {}: {}", blob.display(), stderr.trim());
+    }
+    Ok(())
+}
+
+/// Build the cosign flags describing the expected signer: a public key if configured,
+/// otherwise a keyless certificate identity and OIDC issuer.
+fn verification_args(signing: &SigningSettings) -> Result<Vec<String>> {
+    if let Some(key) = &signing.public_key {
+        return Ok(vec!["--key".to_string(), key.display().to_string()]);
+    }
+
+    match (&signing.identity, &signing.oidc_issuer) {
+        (Some(identity), Some(issuer)) => Ok(vec![
+            "--certificate-identity".to_string(),
+            identity.clone(),
+            "--certificate-oidc-issuer".to_string(),
+            issuer.clone(),
+        ]),
+        _ => anyhow::bail!(
+            "No expected signer configured. Set 'signing.public_key', or 'signing.identity' and \
+             'signing.oidc_issuer', in ~/.geoengine/settings.yaml (or pass --key)."
+        ),
+    }
+}
@@ -0,0 +1,36 @@
+use anyhow::{Context, Result};
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Locate the crane binary
+fn crane_bin() -> Result<PathBuf> {
+    which::which("crane").context(
+        "crane not found. Please install it: https://github.com/google/go-containerregistry/tree/main/cmd/crane",
+    )
+}
+
+/// Resolve the manifest digest (sha256:...) of an image in a registry
+pub fn digest(image: &str) -> Result<String> {
+    let output = Command::new(crane_bin()?)
+        .args(["digest", image])
+        .output()
+        .context("Failed to run crane digest")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to resolve digest of {}: {}", image, stderr.trim());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Copy an image between registries without pulling it or modifying its manifest
+pub fn copy(source: &str, target: &str) -> Result<()> {
+    let output = Command::new(crane_bin()?)
+        .args(["copy", source, target])
+        .output()
+        .context("Failed to run crane copy")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to copy {} to {}: {}", source, target, stderr.trim());
+    }
+    Ok(())
+}
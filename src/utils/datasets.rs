@@ -0,0 +1,295 @@
+use anyhow::{Context, Result};
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::state::compute_file_hash;
+use crate::config::worker::{check_dataset_name, DatasetConfig, Unpack};
+use crate::error::GeoEngineError;
+use crate::utils::{aws, checksums, paths};
+
+/// Written into a fetched dataset's directory; the dataset is current while the
+/// config it records matches the worker's
+const MARKER_FILE: &str = ".geoengine-dataset.json";
+
+/// URI scheme of dataset references in `local_dir_mounts`
+pub const DATA_SCHEME: &str = "data://";
+
+#[derive(Serialize, Deserialize)]
+struct Marker {
+    dataset: DatasetConfig,
+    /// SHA-256 of the downloaded file
+    sha256: String,
+    fetched_at: String,
+}
+
+/// Directory a worker's dataset is fetched into: ~/.geoengine/data/<worker>/<name>
+pub fn dataset_dir(worker: &str, name: &str) -> Result<PathBuf> {
+    Ok(paths::get_data_dir()?.join(worker).join(name))
+}
+
+/// Whether `dir` holds the dataset as currently configured
+pub fn is_current(dir: &Path, dataset: &DatasetConfig) -> bool {
+    std::fs::read_to_string(dir.join(MARKER_FILE))
+        .ok()
+        .and_then(|content| serde_json::from_str::<Marker>(&content).ok())
+        .is_some_and(|marker| marker.dataset == *dataset)
+}
+
+/// Host path of a `data://<name>[/<path>]` reference, or None for other paths
+pub fn resolve_uri(
+    worker: &str,
+    datasets: Option<&BTreeMap<String, DatasetConfig>>,
+    uri: &str,
+) -> Result<Option<PathBuf>> {
+    let Some(rest) = uri.strip_prefix(DATA_SCHEME) else {
+        return Ok(None);
+    };
+    let (name, subpath) = rest.split_once('/').unwrap_or((rest, ""));
+    let dataset = datasets.and_then(|d| d.get(name)).ok_or_else(|| {
+        GeoEngineError::ConfigInvalid(format!(
+            "'{}' refers to dataset '{}', which the data section doesn't define",
+            uri, name
+        ))
+    })?;
+    if Path::new(subpath)
+        .components()
+        .any(|c| !matches!(c, Component::Normal(_) | Component::CurDir))
+    {
+        return Err(GeoEngineError::ConfigInvalid(format!("Invalid path in '{}'", uri)).into());
+    }
+    let dir = dataset_dir(worker, name)?;
+    if !is_current(&dir, dataset) {
+        return Err(GeoEngineError::InputInvalid(format!(
+            "Dataset '{}' hasn't been fetched, or its data entry changed since. Run 'geoengine data fetch {}'.",
+            name, worker
+        ))
+        .into());
+    }
+    Ok(Some(dir.join(subpath)))
+}
+
+/// Download, verify and unpack a dataset into its directory unless it's already
+/// current. Interrupted HTTP downloads resume on the next fetch. Returns whether
+/// anything was downloaded.
+pub async fn fetch(worker: &str, name: &str, dataset: &DatasetConfig, force: bool) -> Result<bool> {
+    check_dataset_name(name)?;
+    let dir = dataset_dir(worker, name)?;
+    if !force && is_current(&dir, dataset) {
+        return Ok(false);
+    }
+    let parent = dir.parent().context("Invalid dataset directory")?.to_path_buf();
+    std::fs::create_dir_all(&parent)?;
+
+    // Kept between attempts so an interrupted download resumes where it stopped
+    let part = parent.join(format!(".{}.part", name));
+    if force {
+        remove_part(&part);
+    }
+    let url = dataset.url.as_str();
+    if url.starts_with("https://") || url.starts_with("http://") {
+        download_http(url, &part).await?;
+    } else if url.starts_with("s3://") {
+        aws::s3_download(url, &part)?;
+    } else if url.starts_with("gs://") {
+        gcs_download(url, &part)?;
+    } else {
+        return Err(GeoEngineError::ConfigInvalid(format!(
+            "Unsupported URL for dataset '{}': {}. Use http(s)://, s3:// or gs://",
+            name, url
+        ))
+        .into());
+    }
+
+    let sha256 = compute_file_hash(&part)?;
+    if let Some(checksum) = &dataset.checksum {
+        let expected = checksums::parse_sha256(checksum)?;
+        if sha256 != expected {
+            remove_part(&part);
+            anyhow::bail!(
+                "Checksum mismatch for dataset '{}': downloaded sha256:{}, expected sha256:{}",
+                name,
+                sha256,
+                expected
+            );
+        }
+    }
+
+    // Unpack next to the final directory and swap it in, so a failed unpack keeps the old copy
+    let staging = parent.join(format!(".{}.staging", name));
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
+    }
+    std::fs::create_dir_all(&staging)?;
+    let file_name = url_file_name(url).unwrap_or(name);
+    let unpacked = unpack(&part, &staging, file_name, dataset.unpack);
+    if let Err(e) = unpacked {
+        std::fs::remove_dir_all(&staging).ok();
+        return Err(e);
+    }
+    remove_part(&part);
+
+    let marker = Marker {
+        dataset: dataset.clone(),
+        sha256,
+        fetched_at: chrono::Utc::now().to_rfc3339(),
+    };
+    std::fs::write(staging.join(MARKER_FILE), serde_json::to_string_pretty(&marker)?)?;
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).with_context(|| format!("Failed to replace {}", dir.display()))?;
+    }
+    std::fs::rename(&staging, &dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    Ok(true)
+}
+
+/// Last path segment of a URL, without its query string; None when it can't be
+/// used as a file name
+fn url_file_name(url: &str) -> Option<&str> {
+    let path = url.split(['?', '#']).next().unwrap_or(url);
+    path.rsplit('/')
+        .next()
+        .filter(|name| !matches!(*name, "" | "." | "..") && !name.contains(['\\', '\0']))
+}
+
+/// Where the ETag or Last-Modified of a partial download is kept, for `If-Range`
+fn validator_path(part: &Path) -> PathBuf {
+    let mut path = part.as_os_str().to_os_string();
+    path.push(".validator");
+    PathBuf::from(path)
+}
+
+fn remove_part(part: &Path) {
+    std::fs::remove_file(part).ok();
+    std::fs::remove_file(validator_path(part)).ok();
+}
+
+fn unpack(archive: &Path, dest: &Path, file_name: &str, unpack: Unpack) -> Result<()> {
+    let lower = file_name.to_lowercase();
+    let unpack = match unpack {
+        Unpack::Auto if lower.ends_with(".zip") => Unpack::Zip,
+        Unpack::Auto
+            if [".tar", ".tar.gz", ".tgz", ".tar.bz2", ".tbz2", ".tar.xz", ".txz"]
+                .iter()
+                .any(|ext| lower.ends_with(ext)) =>
+        {
+            Unpack::Tar
+        }
+        Unpack::Auto => Unpack::None,
+        other => other,
+    };
+    let (program, args): (&str, Vec<&std::ffi::OsStr>) = match unpack {
+        Unpack::Auto | Unpack::None => {
+            std::fs::rename(archive, dest.join(file_name))
+                .with_context(|| format!("Failed to move the download into {}", dest.display()))?;
+            return Ok(());
+        }
+        Unpack::Tar => ("tar", vec!["-xf".as_ref(), archive.as_os_str(), "-C".as_ref(), dest.as_os_str()]),
+        Unpack::Zip => ("unzip", vec!["-q".as_ref(), archive.as_os_str(), "-d".as_ref(), dest.as_os_str()]),
+    };
+    which::which(program).with_context(|| format!("{} not found. Install it to unpack {}.", program, file_name))?;
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        anyhow::bail!("Failed to unpack {} with {}", file_name, program);
+    }
+    Ok(())
+}
+
+/// Download over HTTP(S), continuing a partial file with a Range request when the
+/// server supports it. The request carries `If-Range` with the ETag (or
+/// Last-Modified) the partial file was downloaded with, so a file that changed on
+/// the server comes back whole and replaces it instead of being spliced onto it.
+async fn download_http(url: &str, part: &Path) -> Result<()> {
+    let validator = std::fs::read_to_string(validator_path(part)).ok();
+    // Without a validator there is no telling whether the partial file is still current
+    let offset = match &validator {
+        Some(_) => std::fs::metadata(part).map(|m| m.len()).unwrap_or(0),
+        None => 0,
+    };
+    let mut request = reqwest::Client::new().get(url);
+    if let (true, Some(validator)) = (offset > 0, &validator) {
+        request = request
+            .header(reqwest::header::RANGE, format!("bytes={}-", offset))
+            .header(reqwest::header::IF_RANGE, validator.as_str());
+    }
+    let mut response = request.send().await.with_context(|| format!("Failed to download {}", url))?;
+    // The partial file already holds everything
+    if offset > 0 && response.status() == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+        return Ok(());
+    }
+    if !response.status().is_success() {
+        anyhow::bail!("Failed to download {}: HTTP {}", url, response.status());
+    }
+    let resumed = offset > 0 && response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let start = if resumed { offset } else { 0 };
+    if !resumed {
+        // Weak ETags can't be used with If-Range
+        let headers = response.headers();
+        let validator = headers
+            .get(reqwest::header::ETAG)
+            .filter(|etag| !etag.as_bytes().starts_with(b"W/"))
+            .or_else(|| headers.get(reqwest::header::LAST_MODIFIED))
+            .and_then(|value| value.to_str().ok());
+        if let Some(validator) = validator {
+            std::fs::write(validator_path(part), validator)?;
+        } else {
+            std::fs::remove_file(validator_path(part)).ok();
+        }
+    }
+
+    let pb = match response.content_length() {
+        Some(length) => ProgressBar::new(start + length),
+        None => ProgressBar::new_spinner(),
+    };
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} Downloading [{bar:30.cyan/blue}] {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")?
+            .progress_chars("=> "),
+    );
+    pb.set_position(start);
+    if resumed {
+        pb.println(format!("  {} Resuming at {}", "•".cyan(), indicatif::HumanBytes(offset)));
+    }
+
+    let mut file = if resumed {
+        tokio::fs::OpenOptions::new().append(true).open(part).await?
+    } else {
+        tokio::fs::File::create(part).await?
+    };
+    let result = async {
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .with_context(|| format!("Download of {} interrupted; run the fetch again to resume", url))?
+        {
+            file.write_all(&chunk).await?;
+            pb.inc(chunk.len() as u64);
+        }
+        file.flush().await?;
+        Ok(())
+    }
+    .await;
+    pb.finish_and_clear();
+    result
+}
+
+fn gcs_download(url: &str, part: &Path) -> Result<()> {
+    which::which("gcloud").context(
+        "gcloud CLI not found. Please install the Google Cloud SDK: https://cloud.google.com/sdk/docs/install",
+    )?;
+    let status = Command::new("gcloud")
+        .args(["storage", "cp", url])
+        .arg(part)
+        .status()
+        .context("Failed to run gcloud storage cp")?;
+    if !status.success() {
+        anyhow::bail!("gcloud storage cp {} failed", url);
+    }
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use anyhow::{Context, Result};
+use std::path::Path;
+
+/// Read a dotenv file into ordered KEY/VALUE pairs.
+///
+/// Supports `#` comments, an optional `export ` prefix, unquoted values (with
+/// trailing ` # comments` stripped), single-quoted literal values and
+/// double-quoted values with `\n`, `\t`, `\"`, `\\` escapes. Quoted values may
+/// span multiple lines. Variables are not interpolated.
+pub fn load(path: &Path) -> Result<Vec<(String, String)>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read env file: {}", path.display()))?;
+    parse(&content).with_context(|| format!("Failed to parse env file: {}", path.display()))
+}
+
+/// Parse dotenv content (see [`load`])
+pub fn parse(content: &str) -> Result<Vec<(String, String)>> {
+    let mut vars = Vec::new();
+    let mut lines = content.lines().enumerate();
+
+    while let Some((index, raw)) = lines.next() {
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line).trim_start();
+
+        let (key, rest) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow::anyhow!("line {}: expected KEY=VALUE", index + 1))?;
+        let key = key.trim();
+        if !is_valid_key(key) {
+            anyhow::bail!("line {}: invalid variable name '{}'", index + 1, key);
+        }
+        let rest = rest.trim_start();
+
+        let value = match rest.chars().next() {
+            Some(quote @ ('\'' | '"')) => {
+                // Collect lines until the closing quote
+                let mut text = rest[1..].to_string();
+                loop {
+                    if let Some(end) = closing_quote(&text, quote) {
+                        text.truncate(end);
+                        break;
+                    }
+                    match lines.next() {
+                        Some((_, next)) => {
+                            text.push('\n');
+                            text.push_str(next);
+                        }
+                        None => anyhow::bail!("line {}: unterminated {} quote", index + 1, quote),
+                    }
+                }
+                if quote == '"' {
+                    unescape(&text)
+                } else {
+                    text
+                }
+            }
+            _ => match rest.find(" #") {
+                Some(comment) => rest[..comment].trim_end().to_string(),
+                None => rest.trim_end().to_string(),
+            },
+        };
+
+        vars.push((key.to_string(), value));
+    }
+
+    Ok(vars)
+}
+
+fn is_valid_key(key: &str) -> bool {
+    let mut chars = key.chars();
+    matches!(chars.next(), Some(c) if c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+/// Byte offset of the first unescaped `quote` in `text`
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        if quote == '"' && c == '\\' && !escaped {
+            escaped = true;
+            continue;
+        }
+        if c == quote && !escaped {
+            return Some(i);
+        }
+        escaped = false;
+    }
+    None
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some('r') => out.push('\r'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
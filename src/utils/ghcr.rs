@@ -0,0 +1,159 @@
+use anyhow::{Context, Result};
+use bollard::auth::DockerCredentials;
+use serde::Deserialize;
+
+use crate::config::settings::Settings;
+
+/// GitHub Container Registry host
+pub const REGISTRY: &str = "ghcr.io";
+
+const API: &str = "https://api.github.com";
+
+/// Resolved GHCR owner and access token
+pub struct Ghcr {
+    pub owner: String,
+    token: String,
+}
+
+impl Ghcr {
+    /// Resolve the owner (`--owner`, then `ghcr.owner` in settings) and the token
+    /// (`GHCR_TOKEN`/`GITHUB_TOKEN`, then `ghcr.token` in settings).
+    pub fn resolve(owner: Option<&str>, settings: &Settings) -> Result<Self> {
+        let ghcr = settings.ghcr.clone().unwrap_or_default();
+        let owner = owner
+            .map(|o| o.to_string())
+            .or(ghcr.owner)
+            .context("GHCR owner is required. Pass --owner or set 'ghcr.owner' in ~/.geoengine/settings.yaml.")?;
+        let token = std::env::var("GHCR_TOKEN")
+            .or_else(|_| std::env::var("GITHUB_TOKEN"))
+            .ok()
+            .or(ghcr.token)
+            .context(
+                "No GitHub token found. Set GITHUB_TOKEN, or 'ghcr.token' in ~/.geoengine/settings.yaml, \
+                 to a personal access token with the read:packages/write:packages scopes.",
+            )?;
+        Ok(Ghcr {
+            // GHCR image names must be lowercase
+            owner: owner.to_ascii_lowercase(),
+            token,
+        })
+    }
+
+    /// Full image reference: ghcr.io/<owner>/<name>[:tag]. Only the name is
+    /// lowercased; tags are case-sensitive.
+    pub fn image_ref(&self, image: &str) -> String {
+        let (name, tag) = image.split_at(image.find([':', '@']).unwrap_or(image.len()));
+        format!("{}/{}/{}{}", REGISTRY, self.owner, name.to_ascii_lowercase(), tag)
+    }
+
+    /// Registry credentials for Docker push/pull
+    pub fn credentials(&self) -> DockerCredentials {
+        DockerCredentials {
+            username: Some(self.owner.clone()),
+            password: Some(self.token.clone()),
+            serveraddress: Some(REGISTRY.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// List the owner's container packages and their tagged versions via the GitHub Packages API
+    pub async fn list_images(&self) -> Result<Vec<PackageTag>> {
+        let client = reqwest::Client::new();
+
+        // The owner may be an organization or a user; the API paths differ
+        let mut base = format!("{}/orgs/{}/packages", API, self.owner);
+        let mut packages: Vec<Package> = match self.get_all(&client, &base).await {
+            Ok(packages) => packages,
+            Err(_) => {
+                base = format!("{}/users/{}/packages", API, self.owner);
+                self.get_all(&client, &base).await?
+            }
+        };
+        packages.sort_by(|a, b| a.name.cmp(&b.name));
+
+        let mut tags = Vec::new();
+        for package in packages {
+            let url = format!("{}/container/{}/versions", base, urlencode(&package.name));
+            let versions: Vec<PackageVersion> = self.get_all(&client, &url).await?;
+            for version in versions {
+                for tag in version.metadata.container.tags {
+                    tags.push(PackageTag {
+                        package: package.name.clone(),
+                        tag,
+                        digest: version.name.clone(),
+                        created_at: version.created_at.clone(),
+                    });
+                }
+            }
+        }
+        Ok(tags)
+    }
+
+    /// GET every page of a GitHub list endpoint
+    async fn get_all<T: for<'de> Deserialize<'de>>(&self, client: &reqwest::Client, url: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        for page in 1.. {
+            let response = client
+                .get(url)
+                .query(&[("package_type", "container"), ("per_page", "100"), ("page", &page.to_string())])
+                .bearer_auth(&self.token)
+                .header("Accept", "application/vnd.github+json")
+                .header("X-GitHub-Api-Version", "2022-11-28")
+                .header("User-Agent", concat!("geoengine/", env!("CARGO_PKG_VERSION")))
+                .send()
+                .await
+                .with_context(|| format!("Failed to query {}", url))?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                anyhow::bail!("GitHub API request failed ({}): {}", status, body.trim());
+            }
+            let batch: Vec<T> = response.json().await.context("Failed to parse GitHub API response")?;
+            if batch.is_empty() {
+                break;
+            }
+            let done = batch.len() < 100;
+            items.extend(batch);
+            if done {
+                break;
+            }
+        }
+        Ok(items)
+    }
+}
+
+/// One tag of a container package
+pub struct PackageTag {
+    pub package: String,
+    pub tag: String,
+    pub digest: String,
+    pub created_at: String,
+}
+
+#[derive(Deserialize)]
+struct Package {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct PackageVersion {
+    /// Manifest digest
+    name: String,
+    created_at: String,
+    metadata: VersionMetadata,
+}
+
+#[derive(Deserialize)]
+struct VersionMetadata {
+    container: ContainerMetadata,
+}
+
+#[derive(Deserialize)]
+struct ContainerMetadata {
+    tags: Vec<String>,
+}
+
+/// Package names may contain '/', which must be escaped in API paths
+fn urlencode(name: &str) -> String {
+    name.replace('/', "%2F")
+}
@@ -0,0 +1,96 @@
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Locate the kubectl binary
+fn kubectl_bin() -> Result<PathBuf> {
+    which::which("kubectl").context("kubectl not found. Please install it: https://kubernetes.io/docs/tasks/tools/")
+}
+
+/// A cluster reached through kubectl and the usual kubeconfig (`KUBECONFIG` or ~/.kube/config)
+pub struct Kubectl {
+    /// kubeconfig context (defaults to the current context)
+    pub context: Option<String>,
+
+    /// Namespace (defaults to the context's namespace)
+    pub namespace: Option<String>,
+}
+
+impl Kubectl {
+    fn command(&self) -> Result<Command> {
+        let mut cmd = Command::new(kubectl_bin()?);
+        if let Some(context) = &self.context {
+            cmd.args(["--context", context]);
+        }
+        if let Some(namespace) = &self.namespace {
+            cmd.args(["--namespace", namespace]);
+        }
+        Ok(cmd)
+    }
+
+    /// Run kubectl and parse its `-o json` output
+    fn json(&self, args: &[&str]) -> Result<Value> {
+        let output = self
+            .command()?
+            .args(args)
+            .args(["-o", "json"])
+            .output()
+            .with_context(|| format!("Failed to run kubectl {}", args[0]))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("kubectl {} failed: {}", args[0], stderr.trim());
+        }
+        serde_json::from_slice(&output.stdout).with_context(|| format!("Unexpected output from kubectl {}", args[0]))
+    }
+
+    /// Create the objects in a YAML manifest
+    pub fn create(&self, manifest: &str) -> Result<()> {
+        let mut child = self
+            .command()?
+            .args(["create", "-f", "-"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .context("Failed to run kubectl create")?;
+        child
+            .stdin
+            .take()
+            .context("Failed to open kubectl stdin")?
+            .write_all(manifest.as_bytes())?;
+        let output = child.wait_with_output().context("Failed to run kubectl create")?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            anyhow::bail!("kubectl create failed: {}", stderr.trim());
+        }
+        Ok(())
+    }
+
+    /// The first pod of a Job, if it has been scheduled
+    pub fn job_pod(&self, job: &str) -> Result<Option<Value>> {
+        let selector = format!("job-name={}", job);
+        let pods = self.json(&["get", "pods", "--selector", &selector])?;
+        Ok(pods["items"].get(0).cloned())
+    }
+
+    /// A Job's current status
+    pub fn job(&self, job: &str) -> Result<Value> {
+        self.json(&["get", "job", job])
+    }
+
+    /// Stream a Job's logs to the terminal until its container exits
+    pub fn follow_logs(&self, job: &str) -> Result<()> {
+        let target = format!("job/{}", job);
+        let status = self
+            .command()?
+            .args(["logs", "--follow", &target])
+            .status()
+            .context("Failed to run kubectl logs")?;
+        if !status.success() {
+            anyhow::bail!("kubectl logs exited with {}", status);
+        }
+        Ok(())
+    }
+}
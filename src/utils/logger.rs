@@ -1,11 +1,29 @@
 use anyhow::Result;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry::KeyValue;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use std::sync::OnceLock;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
-/// Initialize the logging system
+use crate::config::settings::{Settings, TelemetrySettings};
+use crate::utils::paths;
+
+/// Tracer provider kept alive for the process so spans can be flushed on exit
+static TRACER_PROVIDER: OnceLock<SdkTracerProvider> = OnceLock::new();
+
+/// Initialize the logging system, plus OTLP trace export when an endpoint is configured
 pub fn init() -> Result<()> {
     let filter = EnvFilter::try_from_default_env()
         .unwrap_or_else(|_| EnvFilter::new("geoengine=info,warn"));
 
+    let otel_layer = init_tracer_provider().map(|provider| {
+        let tracer = provider.tracer("geoengine");
+        let _ = TRACER_PROVIDER.set(provider);
+        tracing_opentelemetry::layer().with_tracer(tracer)
+    });
+
     tracing_subscriber::registry()
         .with(filter)
         .with(
@@ -16,8 +34,60 @@ pub fn init() -> Result<()> {
                 .with_line_number(false)
                 .compact(),
         )
+        .with(otel_layer)
         .init();
 
     Ok(())
 }
 
+/// Flush and stop trace export. Call once before the process exits.
+pub fn shutdown() {
+    if let Some(provider) = TRACER_PROVIDER.get() {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("Failed to flush traces: {}", e);
+        }
+    }
+}
+
+/// Build the OTLP tracer provider, or None if no endpoint is configured.
+/// Export problems must never stop the CLI, so errors are only reported.
+fn init_tracer_provider() -> Option<SdkTracerProvider> {
+    let from_env = std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_some()
+        || std::env::var_os("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT").is_some();
+    // Only read settings.yaml if it exists, so logging never creates it
+    let settings: TelemetrySettings = match paths::get_settings_file() {
+        Ok(path) if path.exists() => Settings::load().ok().and_then(|s| s.telemetry),
+        _ => None,
+    }
+    .unwrap_or_default();
+    if !from_env && settings.otlp_endpoint.is_none() {
+        return None;
+    }
+
+    let mut exporter = SpanExporter::builder().with_http();
+    if !from_env {
+        if let Some(endpoint) = &settings.otlp_endpoint {
+            exporter = exporter.with_endpoint(format!("{}/v1/traces", endpoint.trim_end_matches('/')));
+        }
+    }
+    let exporter = match exporter.build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Failed to set up OTLP trace export: {}", e);
+            return None;
+        }
+    };
+
+    let mut resource = Resource::builder()
+        .with_attribute(KeyValue::new("service.version", env!("CARGO_PKG_VERSION")));
+    if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+        resource = resource.with_service_name(settings.service_name.unwrap_or_else(|| "geoengine".to_string()));
+    }
+
+    Some(
+        SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(resource.build())
+            .build(),
+    )
+}
@@ -1,3 +1,20 @@
+pub mod artifact_registry;
+pub mod aws;
+pub mod checks;
+pub mod checksums;
+pub mod cosign;
+pub mod crane;
+pub mod datasets;
+pub mod disk;
+pub mod dotenv;
+pub mod ghcr;
+pub mod kubectl;
 pub mod logger;
 pub mod paths;
+pub mod process;
+pub mod sbom;
+pub mod sidecars;
+pub mod stac;
 pub mod versioning;
+pub mod vertex;
+pub mod workspace;
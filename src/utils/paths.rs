@@ -1,5 +1,21 @@
 use anyhow::{Context, Result};
 use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::error::GeoEngineError;
+
+/// Name of the profile backed by ~/.geoengine/settings.yaml
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Profile selected with `--profile` or `GEOENGINE_PROFILE` for this invocation
+static PROFILE: OnceLock<String> = OnceLock::new();
+
+/// Select the settings profile for this invocation, overriding `config profile switch`
+pub fn set_profile(profile: Option<String>) {
+    if let Some(profile) = profile.filter(|p| !p.is_empty()) {
+        let _ = PROFILE.set(profile);
+    }
+}
 
 /// Get the GeoEngine configuration directory (~/.geoengine)
 pub fn get_config_dir() -> Result<PathBuf> {
@@ -9,9 +25,84 @@ pub fn get_config_dir() -> Result<PathBuf> {
     Ok(config_dir)
 }
 
-/// Get the settings file path
+/// Get the settings file path of the active profile
 pub fn get_settings_file() -> Result<PathBuf> {
-    Ok(get_config_dir()?.join("settings.yaml"))
+    let profile = active_profile()?;
+    if profile == DEFAULT_PROFILE {
+        return Ok(get_config_dir()?.join("settings.yaml"));
+    }
+    let path = get_profile_file(&profile)?;
+    if !path.exists() {
+        return Err(GeoEngineError::ConfigInvalid(format!(
+            "Settings profile '{}' does not exist. Create it with 'geoengine config profile create {}'.",
+            profile, profile
+        ))
+        .into());
+    }
+    Ok(path)
+}
+
+/// The active settings profile: `--profile`/`GEOENGINE_PROFILE`, then the one
+/// chosen with `config profile switch`, then the default profile
+pub fn active_profile() -> Result<String> {
+    if let Some(profile) = PROFILE.get() {
+        validate_profile_name(profile)?;
+        return Ok(profile.clone());
+    }
+    let switched = std::fs::read_to_string(get_config_dir()?.join("profile")).unwrap_or_default();
+    match switched.trim() {
+        "" => Ok(DEFAULT_PROFILE.to_string()),
+        profile => {
+            validate_profile_name(profile)?;
+            Ok(profile.to_string())
+        }
+    }
+}
+
+/// Remember `profile` as the active one for later invocations
+pub fn switch_profile(profile: &str) -> Result<()> {
+    validate_profile_name(profile)?;
+    let path = get_config_dir()?.join("profile");
+    if profile == DEFAULT_PROFILE {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    std::fs::write(&path, format!("{}\n", profile))?;
+    Ok(())
+}
+
+/// Get the directory holding named settings profiles
+pub fn get_profiles_dir() -> Result<PathBuf> {
+    let profiles_dir = get_config_dir()?.join("profiles");
+    std::fs::create_dir_all(&profiles_dir)?;
+    Ok(profiles_dir)
+}
+
+/// Get the settings file of a named profile (the default profile is settings.yaml)
+pub fn get_profile_file(profile: &str) -> Result<PathBuf> {
+    validate_profile_name(profile)?;
+    if profile == DEFAULT_PROFILE {
+        return Ok(get_config_dir()?.join("settings.yaml"));
+    }
+    Ok(get_profiles_dir()?.join(format!("{}.yaml", profile)))
+}
+
+/// Profile names become file names, so keep them to letters, digits, '-' and '_'
+fn validate_profile_name(profile: &str) -> Result<()> {
+    let valid = !profile.is_empty()
+        && profile
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(GeoEngineError::ConfigInvalid(format!(
+            "Invalid profile name '{}': use letters, digits, '-' and '_'",
+            profile
+        ))
+        .into());
+    }
+    Ok(())
 }
 
 /// Get temporary directory for file transfers
@@ -21,9 +112,30 @@ pub fn get_temp_dir() -> Result<PathBuf> {
     Ok(temp_dir)
 }
 
+/// Get the directory datasets from `data` sections are downloaded into
+pub fn get_data_dir() -> Result<PathBuf> {
+    let data_dir = get_config_dir()?.join("data");
+    std::fs::create_dir_all(&data_dir)?;
+    Ok(data_dir)
+}
+
 /// Get the state directory for worker apply state tracking
 pub fn get_state_dir() -> Result<PathBuf> {
     let state_dir = get_config_dir()?.join("state");
     std::fs::create_dir_all(&state_dir)?;
     Ok(state_dir)
 }
+
+/// Get the directory holding per-run isolated workspaces
+pub fn get_jobs_dir() -> Result<PathBuf> {
+    let jobs_dir = get_config_dir()?.join("jobs");
+    std::fs::create_dir_all(&jobs_dir)?;
+    Ok(jobs_dir)
+}
+
+/// Get the directory holding the outputs and logs of `geoengine test` runs
+pub fn get_tests_dir() -> Result<PathBuf> {
+    let tests_dir = get_config_dir()?.join("tests");
+    std::fs::create_dir_all(&tests_dir)?;
+    Ok(tests_dir)
+}
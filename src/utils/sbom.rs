@@ -0,0 +1,109 @@
+use anyhow::{Context, Result};
+use bollard::auth::DockerCredentials;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Document format of a software bill of materials
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum SbomFormat {
+    /// SPDX 2.3 JSON
+    Spdx,
+    /// CycloneDX JSON
+    Cyclonedx,
+}
+
+impl SbomFormat {
+    /// Name of the format in `syft -o`
+    fn syft_output(self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "spdx-json",
+            SbomFormat::Cyclonedx => "cyclonedx-json",
+        }
+    }
+
+    /// File name suffix of documents in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "spdx.json",
+            SbomFormat::Cyclonedx => "cdx.json",
+        }
+    }
+
+    /// Artifact type of the SBOM when attached to an image in a registry
+    fn media_type(self) -> &'static str {
+        match self {
+            SbomFormat::Spdx => "application/spdx+json",
+            SbomFormat::Cyclonedx => "application/vnd.cyclonedx+json",
+        }
+    }
+}
+
+/// Locate the syft binary
+fn syft_bin() -> Result<PathBuf> {
+    which::which("syft").context("syft not found. Please install it: https://github.com/anchore/syft#installation")
+}
+
+/// Locate the oras binary
+fn oras_bin() -> Result<PathBuf> {
+    which::which("oras").context("oras not found. Please install it: https://oras.land/docs/installation")
+}
+
+/// Catalog the packages of a local image with syft, writing the SBOM to `output`
+/// (stdout if None)
+pub fn generate(image: &str, format: SbomFormat, output: Option<&Path>) -> Result<()> {
+    let target = match output {
+        Some(path) => format!("{}={}", format.syft_output(), path.display()),
+        None => format.syft_output().to_string(),
+    };
+    let status = Command::new(syft_bin()?)
+        .arg(format!("docker:{}", image))
+        .args(["--quiet", "--output", &target])
+        .status()
+        .context("Failed to run syft")?;
+    if !status.success() {
+        anyhow::bail!("syft failed to generate an SBOM for {}", image);
+    }
+    Ok(())
+}
+
+/// Path of the SBOM written next to an exported archive for one of its images
+pub fn sidecar_path(archive: &Path, image: &str, format: SbomFormat) -> PathBuf {
+    let slug: String = image
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let mut name = archive.as_os_str().to_os_string();
+    name.push(format!(".{}.{}", slug, format.extension()));
+    PathBuf::from(name)
+}
+
+/// Attach an SBOM to an image in a registry as an OCI referrer. Without credentials,
+/// oras uses the Docker credential store (e.g. the helper set up by `deploy auth`).
+pub fn attach(image: &str, sbom: &Path, format: SbomFormat, credentials: Option<&DockerCredentials>) -> Result<()> {
+    let dir = sbom.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let file = sbom.file_name().context("Invalid SBOM path")?.to_string_lossy();
+    let mut cmd = Command::new(oras_bin()?);
+    // oras records the file name relative to the working directory
+    cmd.current_dir(dir)
+        .args(["attach", "--artifact-type", format.media_type(), image])
+        .arg(format!("{}:{}", file, format.media_type()));
+    let password = credentials.and_then(|c| c.username.as_ref().zip(c.password.as_ref()));
+    if let Some((username, _)) = password {
+        cmd.args(["--username", username, "--password-stdin"]).stdin(Stdio::piped());
+    }
+    let mut child = cmd
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run oras attach")?;
+    if let (Some((_, password)), Some(mut stdin)) = (password, child.stdin.take()) {
+        stdin.write_all(password.as_bytes())?;
+    }
+    let output = child.wait_with_output().context("Failed to run oras attach")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("Failed to attach the SBOM to {}: {}", image, stderr.trim());
+    }
+    Ok(())
+}
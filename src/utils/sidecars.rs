@@ -0,0 +1,135 @@
+use std::path::{Path, PathBuf};
+
+/// A multi-file dataset format: files sharing the primary file's stem
+/// (`roads.shp` -> `roads.dbf`) that must be mounted alongside it.
+struct Format {
+    /// Primary file extensions (lowercase, without the dot)
+    extensions: &'static [&'static str],
+    /// Sidecar extensions that replace the primary extension
+    sidecars: &'static [&'static str],
+    /// Sidecars without which the dataset cannot be opened
+    required: &'static [&'static str],
+}
+
+const FORMATS: &[Format] = &[
+    // ESRI Shapefile
+    Format {
+        extensions: &["shp"],
+        sidecars: &["shx", "dbf", "prj", "cpg", "sbn", "sbx", "qix", "fix", "atx", "qmd"],
+        required: &["shx", "dbf"],
+    },
+    // MapInfo TAB
+    Format {
+        extensions: &["tab"],
+        sidecars: &["dat", "map", "id", "ind"],
+        required: &["dat", "map", "id"],
+    },
+    // ENVI / generic binary rasters with a header file
+    Format {
+        extensions: &["dat", "bil", "bip", "bsq", "raw", "envi"],
+        sidecars: &["hdr", "prj", "stx", "clr", "blw", "bpw", "bqw"],
+        required: &["hdr"],
+    },
+    // ERDAS Imagine
+    Format {
+        extensions: &["img"],
+        sidecars: &["rrd", "rde", "ige"],
+        required: &[],
+    },
+    // GeoTIFF / TIFF world files
+    Format {
+        extensions: &["tif", "tiff"],
+        sidecars: &["tfw", "tifw", "tiffw", "wld", "prj"],
+        required: &[],
+    },
+    Format {
+        extensions: &["jpg", "jpeg"],
+        sidecars: &["jgw", "jpgw", "wld", "prj"],
+        required: &[],
+    },
+    Format {
+        extensions: &["png"],
+        sidecars: &["pgw", "pngw", "wld", "prj"],
+        required: &[],
+    },
+    // MrSID
+    Format {
+        extensions: &["sid"],
+        sidecars: &["sdw", "sidw", "prj"],
+        required: &[],
+    },
+];
+
+/// Suffixes appended to the full file name by GDAL/ArcGIS (e.g. `scene.tif.aux.xml`)
+const APPENDED_SIDECARS: &[&str] = &["aux.xml", "ovr", "msk", "xml"];
+
+/// Sidecar files found next to a dataset file
+pub struct Sidecars {
+    /// Existing sidecar files to mount alongside the primary file
+    pub files: Vec<PathBuf>,
+    /// Required sidecar extensions that are missing (the dataset is likely broken)
+    pub missing_required: Vec<String>,
+}
+
+/// Find the sidecar files that belong to `path` (an absolute file path), matching names case-insensitively
+pub fn resolve(path: &Path) -> Sidecars {
+    let mut sidecars = Sidecars {
+        files: Vec::new(),
+        missing_required: Vec::new(),
+    };
+    let (Some(dir), Some(file_name), Some(stem)) = (
+        path.parent(),
+        path.file_name().and_then(|n| n.to_str()),
+        path.file_stem().and_then(|s| s.to_str()),
+    ) else {
+        return sidecars;
+    };
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+
+    let format = FORMATS.iter().find(|f| f.extensions.contains(&ext.as_str()));
+
+    // Candidate names, lowercase, in priority order
+    let mut wanted: Vec<String> = Vec::new();
+    if let Some(format) = format {
+        wanted.extend(format.sidecars.iter().map(|s| format!("{}.{}", stem, s).to_ascii_lowercase()));
+    }
+    wanted.extend(APPENDED_SIDECARS.iter().map(|s| format!("{}.{}", file_name, s).to_ascii_lowercase()));
+
+    let siblings: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).filter(|p| p.is_file()).collect())
+        .unwrap_or_default();
+
+    for name in &wanted {
+        if let Some(found) = siblings.iter().find(|p| {
+            p.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.to_ascii_lowercase() == *name)
+                .unwrap_or(false)
+        }) {
+            if !sidecars.files.contains(found) && found.as_path() != path {
+                sidecars.files.push(found.clone());
+            }
+        }
+    }
+
+    if let Some(format) = format {
+        for required in format.required {
+            let name = format!("{}.{}", stem, required).to_ascii_lowercase();
+            let present = sidecars.files.iter().any(|p| {
+                p.file_name()
+                    .and_then(|n| n.to_str())
+                    .map(|n| n.to_ascii_lowercase() == name)
+                    .unwrap_or(false)
+            });
+            if !present {
+                sidecars.missing_required.push(format!(".{}", required));
+            }
+        }
+    }
+
+    sidecars
+}
@@ -0,0 +1,181 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::path::{Path, PathBuf};
+
+use crate::config::state;
+use crate::docker::geometa::GeoMetadata;
+use crate::utils::sidecars;
+
+/// Directory inside an output folder that holds the STAC collection and items
+pub const STAC_DIR: &str = "stac";
+
+const STAC_VERSION: &str = "1.0.0";
+const PROJECTION_EXTENSION: &str = "https://stac-extensions.github.io/projection/v1.1.0/schema.json";
+const FILE_EXTENSION: &str = "https://stac-extensions.github.io/file/v2.1.0/schema.json";
+
+/// A produced file and its georeferencing
+pub struct StacFile<'a> {
+    pub path: &'a Path,
+    pub geo: &'a GeoMetadata,
+}
+
+/// Write `<dir>/stac/collection.json` and one Item per georeferenced file under `dir`.
+/// Asset hrefs are relative, so the folder can be moved or published as a whole.
+pub fn write_collection(
+    dir: &Path,
+    collection_id: &str,
+    description: &str,
+    datetime: &str,
+    files: &[StacFile],
+) -> Result<PathBuf> {
+    let stac_dir = dir.join(STAC_DIR);
+    std::fs::create_dir_all(&stac_dir)
+        .with_context(|| format!("Failed to create {}", stac_dir.display()))?;
+
+    let mut links = vec![
+        json!({"rel": "root", "href": "./collection.json", "type": "application/json"}),
+        json!({"rel": "self", "href": "./collection.json", "type": "application/json"}),
+    ];
+    let mut bboxes: Vec<[f64; 4]> = Vec::new();
+
+    for file in files {
+        let relative = file.path.strip_prefix(dir).unwrap_or(file.path);
+        // The path below `dir` keeps items unique where files in different subfolders share a
+        // name; escaped, so the ID stays a single segment in STAC API URLs
+        let path = relative.to_string_lossy().replace('\\', "/");
+        let id = item_file_stem(&path);
+        let item = build_item(&id, &path, collection_id, relative, file, datetime)?;
+        if let Some(bbox) = file.geo.wgs84_extent {
+            bboxes.push(bbox);
+        }
+
+        let item_file = format!("{}.json", id);
+        std::fs::write(stac_dir.join(&item_file), serde_json::to_string_pretty(&item)?)
+            .with_context(|| format!("Failed to write STAC item {}", item_file))?;
+        links.push(json!({"rel": "item", "href": format!("./{}", item_file), "type": "application/geo+json"}));
+    }
+
+    let spatial = union(&bboxes).unwrap_or([-180.0, -90.0, 180.0, 90.0]);
+    let collection = json!({
+        "type": "Collection",
+        "stac_version": STAC_VERSION,
+        "id": collection_id,
+        "description": description,
+        "license": "other",
+        "extent": {
+            "spatial": {"bbox": [spatial]},
+            "temporal": {"interval": [[datetime, datetime]]},
+        },
+        "links": links,
+    });
+    let path = stac_dir.join("collection.json");
+    std::fs::write(&path, serde_json::to_string_pretty(&collection)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Flatten a relative path into an item ID, escaping the way JSON Pointer does
+/// (`~` -> `~0`, `/` -> `~1`) so distinct paths never share an ID or item file
+fn item_file_stem(path: &str) -> String {
+    path.replace('~', "~0").replace('/', "~1")
+}
+
+fn build_item(id: &str, path: &str, collection_id: &str, relative: &Path, file: &StacFile, datetime: &str) -> Result<Value> {
+    let geo = file.geo;
+    let mut assets = serde_json::Map::new();
+    assets.insert("data".to_string(), asset(file.path, relative, Some(geo.kind.as_str()))?);
+    // Shapefile and world-file sidecars travel with the primary file
+    for sidecar in sidecars::resolve(file.path).files {
+        let sidecar_relative = relative.with_file_name(sidecar.file_name().unwrap_or_default());
+        let key = sidecar_relative
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        assets.insert(key, asset(&sidecar, &sidecar_relative, None)?);
+    }
+
+    let mut properties = serde_json::Map::new();
+    properties.insert("datetime".to_string(), json!(datetime));
+    properties.insert("geoengine:path".to_string(), json!(path));
+    if let Some(epsg) = geo.crs.as_deref().and_then(|c| c.strip_prefix("EPSG:")).and_then(|c| c.parse::<u32>().ok()) {
+        properties.insert("proj:epsg".to_string(), json!(epsg));
+    }
+    if let Some(extent) = geo.extent {
+        properties.insert("proj:bbox".to_string(), json!(extent));
+    }
+    if let Some([width, height]) = geo.size {
+        properties.insert("proj:shape".to_string(), json!([height, width]));
+    }
+
+    let (geometry, bbox) = match geo.wgs84_extent {
+        Some([minx, miny, maxx, maxy]) => (
+            json!({
+                "type": "Polygon",
+                "coordinates": [[[minx, miny], [maxx, miny], [maxx, maxy], [minx, maxy], [minx, miny]]],
+            }),
+            Some(json!([minx, miny, maxx, maxy])),
+        ),
+        None => (Value::Null, None),
+    };
+
+    let mut item = json!({
+        "type": "Feature",
+        "stac_version": STAC_VERSION,
+        "stac_extensions": [PROJECTION_EXTENSION, FILE_EXTENSION],
+        "id": id,
+        "collection": collection_id,
+        "geometry": geometry,
+        "properties": properties,
+        "assets": assets,
+        "links": [
+            {"rel": "root", "href": "./collection.json", "type": "application/json"},
+            {"rel": "parent", "href": "./collection.json", "type": "application/json"},
+            {"rel": "collection", "href": "./collection.json", "type": "application/json"},
+        ],
+    });
+    if let Some(bbox) = bbox {
+        item["bbox"] = bbox;
+    }
+    Ok(item)
+}
+
+fn asset(path: &Path, relative: &Path, role: Option<&str>) -> Result<Value> {
+    let mut asset = json!({
+        // Items live in <dir>/stac/, one level below the outputs
+        "href": format!("../{}", relative.to_string_lossy().replace('\\', "/")),
+        "roles": [if role.is_some() { "data" } else { "metadata" }],
+        // Multihash: 0x12 = sha2-256, 0x20 = 32-byte digest
+        "file:checksum": format!("1220{}", state::compute_file_hash(path)?),
+        "file:size": std::fs::metadata(path)?.len(),
+    });
+    if let Some(media_type) = media_type(path) {
+        asset["type"] = json!(media_type);
+    }
+    Ok(asset)
+}
+
+fn media_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_string_lossy().to_ascii_lowercase();
+    Some(match ext.as_str() {
+        "tif" | "tiff" => "image/tiff; application=geotiff",
+        "jp2" => "image/jp2",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "nc" => "application/netcdf",
+        "h5" | "hdf5" => "application/x-hdf5",
+        "gpkg" => "application/geopackage+sqlite3",
+        "geojson" => "application/geo+json",
+        "json" => "application/json",
+        "fgb" => "application/vnd.flatgeobuf",
+        "parquet" => "application/vnd.apache.parquet",
+        "xml" => "application/xml",
+        _ => return None,
+    })
+}
+
+fn union(bboxes: &[[f64; 4]]) -> Option<[f64; 4]> {
+    let first = *bboxes.first()?;
+    Some(bboxes.iter().fold(first, |acc, b| {
+        [acc[0].min(b[0]), acc[1].min(b[1]), acc[2].max(b[2]), acc[3].max(b[3])]
+    }))
+}
@@ -19,6 +19,8 @@ pub async fn get_latest_worker_version(worker_name: &str, client: &DockerClient)
                 return None;
             }
             let (_, version_str) = tag.rsplit_once(':')?;
+            // Build variants are tagged <version>-<variant>
+            let version_str = version_str.split('-').next()?;
             let parsed = Version::parse(version_str).ok()?;
             Some((parsed, version_str.to_string()))
         })
@@ -60,6 +62,34 @@ pub fn compare_versions(v1: &str, v2: &str) -> Result<Ordering, String> {
     Ok(ver1.cmp(&ver2))
 }
 
+/// Part of a semantic version to increment with `geoengine build --bump`
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum VersionBump {
+    Major,
+    Minor,
+    Patch,
+}
+
+/// Increment one part of a MAJOR.MINOR.PATCH version, resetting the lower parts.
+pub fn bump_version(version: &str, bump: VersionBump) -> Result<String, String> {
+    validate_version(version)?;
+    let mut ver = Version::parse(version)
+        .map_err(|_| format!("Invalid version '{}'. Please ensure your version number follows 'MAJOR.MINOR.PATCH'.", version))?;
+    match bump {
+        VersionBump::Major => {
+            ver.major += 1;
+            ver.minor = 0;
+            ver.patch = 0;
+        }
+        VersionBump::Minor => {
+            ver.minor += 1;
+            ver.patch = 0;
+        }
+        VersionBump::Patch => ver.patch += 1,
+    }
+    Ok(ver.to_string())
+}
+
 /// Compare provided version with worker's built image version, throw an Error if version doesn't follow semantic versioning.
 pub async fn compare_worker_version(worker_name: &str, version: &str, client: &DockerClient) -> Result<Ordering, String> {
     validate_version(version)?;
@@ -0,0 +1,141 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+use crate::utils::artifact_registry::access_token;
+
+const LOGGING_API: &str = "https://logging.googleapis.com/v2";
+
+/// Vertex AI custom jobs of one project and region, accessed through the REST API
+pub struct Vertex {
+    project: String,
+    region: String,
+    /// Replaced when it expires, see `send`
+    token: Mutex<String>,
+    client: reqwest::Client,
+}
+
+/// One line of a job's container output from Cloud Logging
+pub struct LogEntry {
+    pub id: String,
+    pub timestamp: String,
+    pub text: String,
+}
+
+impl Vertex {
+    /// Authenticate with Application Default Credentials (see `access_token`)
+    pub async fn connect(project: &str, region: &str) -> Result<Self> {
+        let client = reqwest::Client::new();
+        let token = access_token(&client).await?;
+        Ok(Vertex {
+            project: project.to_string(),
+            region: region.to_string(),
+            token: Mutex::new(token),
+            client,
+        })
+    }
+
+    /// Send the request `build` makes, with the current access token. ADC tokens
+    /// last about an hour, less than a long job `--wait` follows, so on a 401 a
+    /// fresh token is fetched and the request sent once more.
+    async fn send(&self, url: &str, build: impl Fn() -> reqwest::RequestBuilder) -> Result<reqwest::Response> {
+        let token = self.token.lock().unwrap().clone();
+        let response = build()
+            .bearer_auth(&token)
+            .send()
+            .await
+            .with_context(|| format!("Failed to query {}", url))?;
+        if response.status() != reqwest::StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+        let token = access_token(&self.client).await?;
+        *self.token.lock().unwrap() = token.clone();
+        build()
+            .bearer_auth(&token)
+            .send()
+            .await
+            .with_context(|| format!("Failed to query {}", url))
+    }
+
+    fn api(&self) -> String {
+        format!("https://{}-aiplatform.googleapis.com/v1", self.region)
+    }
+
+    /// Full resource name of a job given by ID or by name
+    pub fn job_name(&self, job: &str) -> String {
+        if job.starts_with("projects/") {
+            job.to_string()
+        } else {
+            format!("projects/{}/locations/{}/customJobs/{}", self.project, self.region, job)
+        }
+    }
+
+    /// Create a CustomJob, returning the created job
+    pub async fn create_custom_job(&self, job: &Value) -> Result<Value> {
+        let url = format!("{}/projects/{}/locations/{}/customJobs", self.api(), self.project, self.region);
+        let response = self.send(&url, || self.client.post(&url).json(job)).await?;
+        check(response).await?.json().await.context("Failed to parse Vertex AI response")
+    }
+
+    pub async fn get_custom_job(&self, job: &str) -> Result<Value> {
+        let url = format!("{}/{}", self.api(), self.job_name(job));
+        let response = self.send(&url, || self.client.get(&url)).await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            anyhow::bail!("Vertex AI job not found: {}", job);
+        }
+        check(response).await?.json().await.context("Failed to parse Vertex AI response")
+    }
+
+    /// Container output of a job logged at or after `since` (RFC 3339), oldest first
+    pub async fn log_entries(&self, job_id: &str, since: Option<&str>) -> Result<Vec<LogEntry>> {
+        let mut filter = format!("resource.type=\"ml_job\" AND resource.labels.job_id=\"{}\"", job_id);
+        if let Some(since) = since {
+            filter.push_str(&format!(" AND timestamp>=\"{}\"", since));
+        }
+        let url = format!("{}/entries:list", LOGGING_API);
+        let mut entries = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut body = json!({
+                "resourceNames": [format!("projects/{}", self.project)],
+                "filter": filter,
+                "orderBy": "timestamp asc",
+                "pageSize": 1000,
+            });
+            if let Some(token) = &page_token {
+                body["pageToken"] = json!(token);
+            }
+            let response = self.send(&url, || self.client.post(&url).json(&body)).await?;
+            let page: Value = check(response).await?.json().await.context("Failed to parse Cloud Logging response")?;
+            for entry in page["entries"].as_array().into_iter().flatten() {
+                let text = entry["textPayload"]
+                    .as_str()
+                    .or_else(|| entry["jsonPayload"]["message"].as_str())
+                    .unwrap_or_default();
+                entries.push(LogEntry {
+                    id: entry["insertId"].as_str().unwrap_or_default().to_string(),
+                    timestamp: entry["timestamp"].as_str().unwrap_or_default().to_string(),
+                    text: text.trim_end().to_string(),
+                });
+            }
+            match page["nextPageToken"].as_str() {
+                Some(token) if !token.is_empty() => page_token = Some(token.to_string()),
+                _ => return Ok(entries),
+            }
+        }
+    }
+}
+
+/// Fail with the API's error message on non-success responses
+async fn check(response: reqwest::Response) -> Result<reqwest::Response> {
+    if response.status().is_success() {
+        return Ok(response);
+    }
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<Value>(&body)
+        .ok()
+        .and_then(|v| v["error"]["message"].as_str().map(String::from))
+        .unwrap_or(body);
+    anyhow::bail!("Google Cloud request failed ({}): {}", status, message.trim())
+}
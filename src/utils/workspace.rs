@@ -0,0 +1,134 @@
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::utils::{paths, process};
+
+/// Isolated per-run directory under ~/.geoengine/jobs. Read-only inputs are
+/// staged into it (hard-linked where possible, copied otherwise) so the container
+/// only sees the workspace instead of arbitrary host paths. Removed on drop.
+pub struct JobWorkspace {
+    root: PathBuf,
+}
+
+/// Host and PID of the geoengine process a workspace belongs to, one per line
+const OWNER_FILE: &str = ".geoengine-owner";
+
+/// Distinguishes workspaces created in the same millisecond (parallel batch items)
+static WORKSPACE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl JobWorkspace {
+    /// Create a fresh workspace for a run of the given worker
+    pub fn create(worker_name: &str) -> Result<Self> {
+        let run_id = format!(
+            "{}-{}-{}-{}",
+            worker_name,
+            chrono::Utc::now().format("%Y%m%d%H%M%S%3f"),
+            std::process::id(),
+            WORKSPACE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let root = paths::get_jobs_dir()?.join(run_id);
+        std::fs::create_dir_all(&root)
+            .with_context(|| format!("Failed to create job workspace: {}", root.display()))?;
+        std::fs::write(
+            root.join(OWNER_FILE),
+            format!("{}\n{}\n", process::hostname(), std::process::id()),
+        )
+        .with_context(|| format!("Failed to create job workspace: {}", root.display()))?;
+        Ok(Self { root })
+    }
+
+    /// Stage a file at `rel` inside the workspace and return its host path
+    pub fn stage_file(&self, src: &Path, rel: &str) -> Result<PathBuf> {
+        let dest = self.root.join(rel);
+        if let Some(parent) = dest.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        link_or_copy(src, &dest)?;
+        Ok(dest)
+    }
+
+    /// Stage a directory tree at `rel` inside the workspace and return its host path
+    pub fn stage_dir(&self, src: &Path, rel: &str) -> Result<PathBuf> {
+        let dest = self.root.join(rel);
+        copy_tree(src, &dest)?;
+        Ok(dest)
+    }
+}
+
+impl Drop for JobWorkspace {
+    fn drop(&mut self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.root) {
+            tracing::warn!("Failed to clean up job workspace {}: {}", self.root.display(), e);
+        }
+    }
+}
+
+/// Workspaces left behind by geoengine processes of this host that no longer
+/// exist. ~/.geoengine may be shared between machines, so workspaces of other
+/// hosts, and those without an owner file, are left alone.
+pub fn orphaned() -> Result<Vec<PathBuf>> {
+    let host = process::hostname();
+    let mut orphans = Vec::new();
+    for entry in std::fs::read_dir(paths::get_jobs_dir()?)?.flatten() {
+        let Ok(owner) = std::fs::read_to_string(entry.path().join(OWNER_FILE)) else {
+            continue;
+        };
+        let mut lines = owner.lines();
+        let owner_host = lines.next().unwrap_or_default();
+        let pid = lines.next().and_then(|p| p.parse::<u32>().ok());
+        if let Some(pid) = pid {
+            if owner_host == host && pid != std::process::id() && !process::pid_alive(pid) {
+                orphans.push(entry.path());
+            }
+        }
+    }
+    Ok(orphans)
+}
+
+/// Hard-link `src` to `dest`, falling back to a copy across filesystems
+fn link_or_copy(src: &Path, dest: &Path) -> Result<()> {
+    if std::fs::hard_link(src, dest).is_err() {
+        std::fs::copy(src, dest).with_context(|| {
+            format!("Failed to stage {} into job workspace", src.display())
+        })?;
+    }
+    Ok(())
+}
+
+/// Stage a directory tree. Symlinks are recreated as links rather than followed,
+/// so a link loop can't recurse forever and a link out of the tree doesn't copy
+/// host files into the workspace.
+fn copy_tree(src: &Path, dest: &Path) -> Result<()> {
+    std::fs::create_dir_all(dest)?;
+    for entry in std::fs::read_dir(src)
+        .with_context(|| format!("Failed to read input directory: {}", src.display()))?
+    {
+        let entry = entry?;
+        let path = entry.path();
+        let target = dest.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            copy_symlink(&path, &target)?;
+        } else if file_type.is_dir() {
+            copy_tree(&path, &target)?;
+        } else {
+            link_or_copy(&path, &target)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn copy_symlink(src: &Path, dest: &Path) -> Result<()> {
+    let link = std::fs::read_link(src)?;
+    std::os::unix::fs::symlink(&link, dest)
+        .with_context(|| format!("Failed to stage {} into job workspace", src.display()))
+}
+
+/// Creating symlinks on Windows needs privileges runs may not have, so they are left out
+#[cfg(not(unix))]
+fn copy_symlink(src: &Path, _dest: &Path) -> Result<()> {
+    tracing::warn!("Skipping symlink {} while staging into job workspace", src.display());
+    Ok(())
+}